@@ -7,6 +7,11 @@ set -euo pipefail
 for file in "$@"; do
     echo "=== $(basename "$file") ==="
 
+    # Run parameters (nodes, processes, algorithm, round time)
+    if grep -q "Run params" "$file"; then
+        grep "Run params" "$file" | sed 's/.*Run params: //'
+    fi
+
     # Monster stats
     if grep -q "Monster stats" "$file"; then
         grep "Monster stats" "$file" | sed 's/.*Monster stats: /Monster: /'
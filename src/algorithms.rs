@@ -1,12 +1,101 @@
-use std::sync::atomic::{AtomicBool};
+use std::sync::atomic::{AtomicBool, Ordering};
+use log::{error, warn};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
-use crate::{GroupView, RepCXLObject};
-use crate::request::{WriteRequest,ReadRequest,ReadReturn};
+use crate::{GroupView, ObjectInfo, RepCXLObject};
+use crate::request::{WriteAck,WriteRequest,ReadRequest,ReadReturn};
 
+pub mod anti_entropy;
+pub mod audit;
 pub mod best_effort;
 pub mod monster;
+pub mod msync;
+
+/// Order of conflicting MONSTER writes to the same object, the
+/// `conflict_order` config
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConflictOrder {
+    /// the later round wins, then the smaller pid
+    Round,
+    /// last writer wins by the client wall clock, then the larger pid
+    Timestamp,
+}
+
+/// Protocol run by an instance and its parameters, resolved from the config.
+/// See `RepCXL::algorithm_info`
+#[derive(Debug, Clone, PartialEq)]
+pub enum AlgorithmInfo {
+    /// `async_best_effort`: writes go to every memory node without conflict
+    /// detection and are acked once written
+    AsyncBestEffort {
+        pipeline: bool,
+        /// queued writes issued together by the write worker
+        write_batch_size: usize,
+        /// retries of a ReadDirty read
+        read_retries: usize,
+    },
+    /// `monster`, or `fmonster` with `fast_wcc`: round-based writes, acked
+    /// after their Replicate round
+    Monster {
+        fast_wcc: bool,
+        round_time: Duration,
+        pipeline: bool,
+        conflict_order: ConflictOrder,
+        /// writes skip the conflict checker
+        single_writer: bool,
+        /// fraction of the round reads wait for, None to read right away
+        read_offset: Option<f64>,
+        /// retries of a ReadDirty read
+        read_retries: usize,
+        /// conflict rate above which writers back off, None if disabled
+        conflict_backoff_threshold: Option<f64>,
+    },
+}
+
+impl AlgorithmInfo {
+    pub(crate) fn from_config(config: &crate::RepCXLConfig) -> Self {
+        match config.algorithm.as_str() {
+            "async_best_effort" => AlgorithmInfo::AsyncBestEffort {
+                pipeline: config.pipeline,
+                write_batch_size: config.write_batch_size,
+                read_retries: config.read_retries,
+            },
+            "monster" | "fmonster" => AlgorithmInfo::Monster {
+                fast_wcc: config.algorithm == "fmonster",
+                round_time: Duration::from_nanos(config.round_time),
+                pipeline: config.pipeline,
+                conflict_order: if config.conflict_order == "timestamp" {
+                    ConflictOrder::Timestamp
+                } else {
+                    ConflictOrder::Round
+                },
+                single_writer: config.single_writer,
+                read_offset: config.read_offset,
+                read_retries: config.read_retries,
+                conflict_backoff_threshold: config.conflict_backoff_threshold,
+            },
+            _ => panic!("Unknown algorithm, check config: {}", config.algorithm),
+        }
+    }
+
+    /// Name of the algorithm in the config
+    pub fn name(&self) -> &'static str {
+        match self {
+            AlgorithmInfo::AsyncBestEffort { .. } => "async_best_effort",
+            AlgorithmInfo::Monster { fast_wcc: false, .. } => "monster",
+            AlgorithmInfo::Monster { fast_wcc: true, .. } => "fmonster",
+        }
+    }
+}
+
+/// Longest a worker blocked on its request queue takes to notice the stop
+/// flag
+pub(crate) const STOP_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// User callback invoked by the write worker after each acknowledged write
+/// with the object id and the write latency (from enqueue to ack)
+pub type WriteCallback = Arc<dyn Fn(usize, Duration) + Send + Sync>;
 
 #[derive(Clone)]
 pub(crate) struct AlgorithmThreadContext {
@@ -16,10 +105,35 @@ pub(crate) struct AlgorithmThreadContext {
     pub read_offset: Option<f64>,
     pub stop_flag: Arc<AtomicBool>,
     pub logger: Option<String>,
+    pub on_write_complete: Option<WriteCallback>,
+    pub backoff_threshold: Option<f64>,
+    pub backoff_max_rounds: u64,
+    pub timestamp_order: bool, // conflict_order = "timestamp"
+    pub single_writer: bool,
+    pub write_batch_size: usize,
+    pub jitter: Option<Arc<crate::timer::JitterRecorder>>, // round delays of the write worker
+    pub ack_policy: crate::request::AckPolicy, // default of the write requests
 }
 
 
 impl AlgorithmThreadContext {
+    /// Context of a thread that issues no client writes (anti-entropy, audit,
+    /// msync...): no logger, callbacks, backoff or jitter recording
+    pub fn background(&self) -> Self {
+        AlgorithmThreadContext {
+            logger: None,
+            on_write_complete: None,
+            backoff_threshold: None,
+            backoff_max_rounds: 0,
+            timestamp_order: false,
+            single_writer: false,
+            write_batch_size: 1,
+            jitter: None,
+            ack_policy: crate::request::AckPolicy::All,
+            ..self.clone()
+        }
+    }
+
     pub fn to_call_context(&self, algorithm: &str, stats: monster::MonsterStats) -> AlgorithmCallContext {
         AlgorithmCallContext {
             algorithm: algorithm.to_string(),
@@ -28,6 +142,10 @@ impl AlgorithmThreadContext {
             read_offset: self.read_offset,
             logger: self.logger.clone(),
             stats: stats,
+            backoff: monster::ConflictBackoff::new(self.backoff_threshold, self.backoff_max_rounds),
+            timestamp_order: self.timestamp_order,
+            single_writer: self.single_writer,
+            jitter: self.jitter.clone(),
         }
     }
 }
@@ -39,6 +157,10 @@ pub(crate) struct AlgorithmCallContext {
     pub read_offset: Option<f64>,
     pub logger: Option<String>,
     pub stats: monster::MonsterStats,
+    pub backoff: monster::ConflictBackoff,
+    pub timestamp_order: bool,
+    pub single_writer: bool,
+    pub jitter: Option<Arc<crate::timer::JitterRecorder>>,
 }
 
 
@@ -48,13 +170,78 @@ pub fn write_thread<T: Copy + PartialEq + std::fmt::Debug>(
     req_queue: kanal::Receiver<WriteRequest<T>>,
 ) {
     match algorithm.as_str() {
-        "async_best_effort" => best_effort::async_best_effort_write_thread(actx.group_view, req_queue, actx.stop_flag),
+        "async_best_effort" => best_effort::async_best_effort_write_thread(
+            actx.group_view,
+            req_queue,
+            actx.stop_flag,
+            actx.on_write_complete,
+            actx.write_batch_size,
+        ),
         "monster" => monster::monster_write_thread(actx, req_queue),
         "fmonster" => monster::fmonster_write_thread(actx, req_queue),
         _ => panic!("Unknown write algorithm, check config: {}", algorithm),
     }
 }
 
+/// Fault injection: the MONSTER write worker panics when it picks a request
+/// for the object with this (scoped) id
+#[cfg(test)]
+pub(crate) static PANIC_ON_OBJECT: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(usize::MAX);
+
+/// Run the write worker and restart it, up to `max_restarts` times, when it
+/// panics on a logic error. The queue is preserved; the request being
+/// processed is dropped, failing its client. A worker that returns (stop
+/// flag, closed queue) is not restarted, nor one that failed with a memory
+/// node down or an unreadable shared state.
+pub fn supervised_write_thread<T: Copy + PartialEq + std::fmt::Debug>(
+    algorithm: &String,
+    actx: AlgorithmThreadContext,
+    req_queue: kanal::Receiver<WriteRequest<T>>,
+    max_restarts: u32,
+) {
+    let mut restarts = 0;
+    loop {
+        let worker_ctx = actx.clone();
+        let worker_queue = req_queue.clone();
+        let panic = match std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            write_thread(algorithm, worker_ctx, worker_queue)
+        })) {
+            Ok(()) => return,
+            Err(panic) => panic,
+        };
+        let reason = panic.downcast_ref::<&str>().map(|s| s.to_string())
+            .or_else(|| panic.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "unknown panic".to_string());
+        error!("Write worker failed: {}", reason);
+
+        if actx.stop_flag.load(Ordering::Relaxed) {
+            return;
+        }
+        if restarts == max_restarts {
+            error!("Write worker not restarted, {} restarts allowed", max_restarts);
+            return;
+        }
+        if let Some(node) = actx.group_view.memory_nodes.iter().find(|n| n.is_failed()) {
+            error!("Write worker not restarted, memory node {} failed", node.id);
+            return;
+        }
+        let Some(state) = actx.group_view.get_master_node().map(|n| n.read_state()) else {
+            error!("Write worker not restarted, no memory node to read the state from");
+            return;
+        };
+        if state.seq() == 0 {
+            error!("Write worker not restarted, the shared state is not initialized");
+            return;
+        }
+
+        restarts += 1;
+        warn!(
+            "Restarting write worker ({}/{}), shared state seq {}, {} queued requests",
+            restarts, max_restarts, state.seq(), req_queue.len()
+        );
+    }
+}
+
 pub fn read_thread<T: Copy + PartialEq + std::fmt::Debug>(
     algorithm: &String,
     actx: AlgorithmThreadContext,
@@ -72,25 +259,43 @@ pub fn read_thread<T: Copy + PartialEq + std::fmt::Debug>(
 pub fn read<T: Copy + PartialEq + std::fmt::Debug>(
     actx: &AlgorithmCallContext,
     view: &GroupView,
-    obj: &RepCXLObject<T>,
+    obj_info: &ObjectInfo,
 ) -> Result<ReadReturn<T>, String> {
-    match actx.algorithm.as_str() {
-        "async_best_effort" => best_effort::async_best_effort_read(&view, &obj.info),
-        "monster" | "fmonster" => monster::monster_read(actx, view, &obj.info),
+    view.read_allocated(obj_info, || match actx.algorithm.as_str() {
+        "async_best_effort" => best_effort::async_best_effort_read(view, obj_info),
+        "monster" | "fmonster" => monster::monster_read(actx, view, obj_info),
         _ => panic!("Unknown read algorithm, check config: {}", actx.algorithm),
+    })
+}
+
+/// Read with the commit version of the value, only kept by the MONSTER
+/// algorithms
+pub fn read_versioned<T: Copy + PartialEq + std::fmt::Debug>(
+    actx: &AlgorithmCallContext,
+    view: &GroupView,
+    obj_info: &ObjectInfo,
+) -> Result<(ReadReturn<T>, u64), String> {
+    match actx.algorithm.as_str() {
+        "monster" | "fmonster" => monster::monster_read_versioned(actx, view, obj_info),
+        _ => Err(format!("versioned reads not supported for algorithm '{}'", actx.algorithm)),
     }
 }
 
+/// Write from the client thread, without the write worker. The write carries
+/// the next sequence number of the handle, see `RepCXLObject::check_continuity`
 pub fn write<T: Copy + PartialEq + std::fmt::Debug>(
     actx: &mut AlgorithmCallContext,
     view: &GroupView,
     obj: &RepCXLObject<T>,
     data: T,
-) -> Result<(), String> {
+    timestamp: u64,
+) -> Result<WriteAck, String> {
+    let mode = monster::WriteMode { seq: obj.next_seq(), ..Default::default() };
     match actx.algorithm.as_str() {
-        "async_best_effort" => best_effort::async_best_effort_write(view, &obj.info, data),
-        "monster"  => monster::monster_write(actx, view, &obj.info, data),
-        "fmonster" => monster::fmonster_write(actx, view, &obj.info, data),
+        "async_best_effort" => best_effort::async_best_effort_write(view, &obj.info, data, mode.seq)
+            .map(|()| WriteAck::uncontended(0)),
+        "monster"  => monster::monster_write(actx, view, &obj.info, data, timestamp, &mode).map(|(ack, _)| ack),
+        "fmonster" => monster::fmonster_write(actx, view, &obj.info, data, &mode).map(|(ack, _)| ack),
         _ => Err(format!("write not supported for algorithm '{}'", actx.algorithm)),
     }
 }
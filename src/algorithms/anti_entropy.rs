@@ -0,0 +1,140 @@
+use std::sync::atomic::Ordering;
+use log::{debug, error, info};
+
+use super::AlgorithmThreadContext;
+use crate::shmem::object_index::ObjectInfo;
+use crate::safe_memio::{ObjectMemoryEntry, mem_readall, mem_repairone, MemoryError};
+use crate::request::ReadReturn;
+use crate::timer;
+
+/// Return the entry whose wid is stored on a strict majority of the nodes,
+/// if any
+pub(crate) fn majority_entry<T: Copy>(omes: &[ObjectMemoryEntry<T>]) -> Option<ObjectMemoryEntry<T>> {
+    omes.iter()
+        .find(|candidate| omes.iter().filter(|ome| ome.wid == candidate.wid).count() * 2 > omes.len())
+        .copied()
+}
+
+/// Copy the majority entry of the object to every node whose wid is behind
+/// it. Nodes ahead of the majority (e.g. a write being replicated) are left
+/// untouched, also if they got ahead since the read (see `mem_repairone`),
+/// and no new wid is ever generated so that foreground conflict resolution
+/// is unaffected.
+///
+/// Returns the number of repaired nodes
+pub(crate) fn repair_object<T: Copy>(
+    obj_info: &ObjectInfo,
+    view: &crate::GroupView,
+) -> Result<usize, MemoryError> {
+    let omes: Vec<ObjectMemoryEntry<T>> = mem_readall(obj_info.offset, &view.memory_nodes)?;
+
+    let Some(majority) = majority_entry(&omes) else {
+        return Ok(0);
+    };
+
+    let mut repaired = 0;
+    for (node, ome) in view.memory_nodes.iter().zip(omes.iter()) {
+        if ome.wid < majority.wid {
+            debug!(
+                "[ANTI-ENTROPY] Repairing object {} on node {}: {:?} -> {:?}",
+                obj_info.id, node.id, ome.wid, majority.wid
+            );
+            if mem_repairone(obj_info.offset, majority, node)? {
+                repaired += 1;
+            }
+        }
+    }
+    Ok(repaired)
+}
+
+/// Read the object from every node and return the majority entry as
+/// ReadSafe, then copy it in the background to the nodes behind it (see
+/// `repair_object`). Each stale node is repaired with `mem_repairone`, so that
+/// a write landing since the read is not overwritten. Without a majority the
+/// newest entry is returned as ReadDirty and nothing is repaired.
+pub(crate) fn read_and_repair<T: Copy + Send + 'static>(
+    obj_info: &ObjectInfo,
+    view: &crate::GroupView,
+) -> Result<ReadReturn<T>, String> {
+    let omes: Vec<ObjectMemoryEntry<T>> = mem_readall(obj_info.offset, &view.memory_nodes)
+        .map_err(|MemoryError(mnid)| format!("Memory node {} failed during read", mnid))?;
+
+    let Some(majority) = majority_entry(&omes) else {
+        let newest = omes.iter().fold(&omes[0], |best, ome| if ome.wid > best.wid { ome } else { best });
+        return Ok(if newest.written { ReadReturn::ReadDirty(newest.value) } else { ReadReturn::Uninitialized });
+    };
+    if !majority.written {
+        return Ok(ReadReturn::Uninitialized);
+    }
+
+    let stale: Vec<usize> = omes.iter().enumerate()
+        .filter(|(_, ome)| ome.wid < majority.wid)
+        .map(|(i, _)| i)
+        .collect();
+    if !stale.is_empty() {
+        let view = view.clone();
+        let offset = obj_info.offset;
+        let id = obj_info.id;
+        std::thread::spawn(move || {
+            let view = view; // capture the whole view, which is Send, not its nodes
+            for node in stale.iter().map(|&i| &view.memory_nodes[i]) {
+                match mem_repairone(offset, majority, node) {
+                    Ok(true) => debug!("[READ-REPAIR] Repaired object {} on node {} to {:?}", id, node.id, majority.wid),
+                    Ok(false) => debug!("[READ-REPAIR] Object {} on node {} got ahead, not repaired", id, node.id),
+                    Err(MemoryError(mnid)) => {
+                        error!("[READ-REPAIR] Memory node {} failed while repairing object {}", mnid, id);
+                    }
+                }
+            }
+        });
+    }
+    Ok(ReadReturn::ReadSafe(majority.value))
+}
+
+/// Background anti-entropy: every `interval` rounds repair the next `batch`
+/// objects of the object index, so that divergent objects that are never
+/// read eventually converge.
+pub fn anti_entropy_thread<T: Copy>(
+    actx: AlgorithmThreadContext,
+    interval: u64,
+    batch: usize,
+) {
+    let mut cursor = 0; // next object to sweep, in index order
+    let mut repaired_total = 0;
+
+    loop {
+        if actx.stop_flag.load(Ordering::Relaxed) {
+            info!("[ANTI-ENTROPY] Stop flag is set, exiting. Repaired {} entries", repaired_total);
+            break;
+        }
+
+        let (round_num, _) = timer::wait_next_round(
+            actx.start_instant,
+            actx.round_time,
+            timer::ROUND_SLEEP_RATIO);
+
+        if round_num % interval != 0 {
+            continue;
+        }
+
+        let Some(master) = actx.group_view.get_master_node() else {
+            error!("[ANTI-ENTROPY] No master node, exiting");
+            break;
+        };
+        let objects: Vec<ObjectInfo> = master.get_state().object_index.objects().collect();
+        if objects.is_empty() {
+            continue;
+        }
+
+        for i in 0..batch.min(objects.len()) {
+            let obj_info = &objects[(cursor + i) % objects.len()];
+            match repair_object::<T>(obj_info, &actx.group_view) {
+                Ok(repaired) => repaired_total += repaired,
+                Err(MemoryError(mnid)) => {
+                    error!("[ANTI-ENTROPY] Memory node {} failed while repairing object {}", mnid, obj_info.id);
+                }
+            }
+        }
+        cursor = (cursor + batch) % objects.len();
+    }
+}
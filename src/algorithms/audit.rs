@@ -0,0 +1,96 @@
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::Ordering;
+use log::{error, info};
+
+use super::AlgorithmThreadContext;
+use crate::fsck::{check_object, AuditState, OrderChecker};
+use crate::safe_memio::{mem_readall, MemoryError, ObjectMemoryEntry};
+use crate::shmem::object_index::ObjectInfo;
+use crate::timer;
+
+/// Background consistency audit: every `interval` rounds check the next
+/// `sample` objects of the object index across the nodes (see
+/// `fsck::check_object`) and record the results in `audit`. Strictly
+/// read-only, divergent objects are reported and never repaired.
+pub fn audit_thread<T: Copy + PartialEq>(
+    actx: AlgorithmThreadContext,
+    interval: u64,
+    sample: usize,
+    audit: Arc<Mutex<AuditState>>,
+) {
+    let mut cursor = 0; // next object to check, in index order
+
+    loop {
+        if actx.stop_flag.load(Ordering::Relaxed) {
+            info!("[AUDIT] Stop flag is set, exiting. {:?}", audit.lock().unwrap().report());
+            break;
+        }
+
+        let (round_num, _) = timer::wait_next_round(
+            actx.start_instant,
+            actx.round_time,
+            timer::ROUND_SLEEP_RATIO);
+
+        if round_num % interval != 0 {
+            continue;
+        }
+
+        let Some(master) = actx.group_view.get_master_node() else {
+            error!("[AUDIT] No master node, exiting");
+            break;
+        };
+        let objects: Vec<ObjectInfo> = master.get_state().object_index.objects().collect();
+        audit.lock().unwrap().retain(&objects);
+        if objects.is_empty() {
+            continue;
+        }
+
+        for i in 0..sample.min(objects.len()) {
+            let obj_info = &objects[(cursor + i) % objects.len()];
+            let report = check_object::<T>(obj_info, &actx.group_view);
+            audit.lock().unwrap().record(obj_info, report);
+        }
+        cursor = (cursor + sample) % objects.len();
+    }
+}
+
+/// Order checker: every round read the newest entry of every object and
+/// check that the writes of each process are observed in the order the
+/// process issued them, see `RepCXL::enable_order_checker`. Read-only.
+pub fn order_check_thread<T: Copy>(actx: AlgorithmThreadContext, checker: Arc<Mutex<OrderChecker>>) {
+    loop {
+        if actx.stop_flag.load(Ordering::Relaxed) {
+            info!("[ORDER] Stop flag is set, exiting. {} violations", checker.lock().unwrap().violations().len());
+            break;
+        }
+
+        timer::wait_next_round(actx.start_instant, actx.round_time, timer::ROUND_SLEEP_RATIO);
+
+        let Some(master) = actx.group_view.get_master_node() else {
+            error!("[ORDER] No master node, exiting");
+            break;
+        };
+        let objects: Vec<ObjectInfo> = master.get_state().object_index.objects().collect();
+        let mut checker = checker.lock().unwrap();
+        checker.retain(&objects);
+
+        for obj_info in &objects {
+            let omes: Vec<ObjectMemoryEntry<T>> = match mem_readall(obj_info.offset, &actx.group_view.memory_nodes) {
+                Ok(omes) => omes,
+                Err(MemoryError(mnid)) => {
+                    error!("[ORDER] Memory node {} failed, skipping object {}", mnid, obj_info.local_id());
+                    continue;
+                }
+            };
+            let Some(newest) = omes.iter().filter(|ome| ome.written).max_by_key(|ome| ome.wid) else {
+                continue;
+            };
+            if let Some(v) = checker.observe(obj_info, newest.wid, newest.seq) {
+                error!(
+                    "[ORDER] Object {} of process {} went back from seq {} ({:?}) to seq {} ({:?})",
+                    v.id, v.process_id, v.prev_seq, v.prev_wid, v.seq, v.wid
+                );
+            }
+        }
+    }
+}
@@ -4,36 +4,71 @@ use std::sync::Arc;
 use log::{info,error,debug};
 use crate::{ObjectMemoryEntry,ReadReturn};
 use crate::utils::ms_logger::MonsterStateLogger;
-use crate::safe_memio::{mem_writeall, mem_readends, MemoryError};
+use crate::safe_memio::{mem_writeall, mem_writebatch, mem_readends, mem_readone, MemoryError, PartialWrite};
 use crate::{GroupView, WriteRequest, ReadRequest};
+use crate::request::WriteAck;
 use crate::timer;
-use super::AlgorithmThreadContext;
+use super::{AlgorithmThreadContext, WriteCallback, STOP_POLL_INTERVAL};
 
 const WRITE_TRACE_SAMPLE_RATE: u64 = 1024;
 
 
 
 /// Client-writer: clients perform write operation directly i.e. no write
-/// thread request handling.
+/// thread request handling. `seq` is the client-side sequence number.
 pub fn async_best_effort_write<T: Copy + PartialEq + std::fmt::Debug>(
     view: &crate::GroupView,
     obj_info: &crate::ObjectInfo,
     data: T,
+    seq: u64,
 ) -> Result<(), String> {
-    let entry = ObjectMemoryEntry::new_nowid(data);
+    write_entry(view, obj_info, ObjectMemoryEntry::new_nowid(data).with_seq(seq))
+}
+
+fn write_entry<T: Copy>(
+    view: &crate::GroupView,
+    obj_info: &crate::ObjectInfo,
+    entry: ObjectMemoryEntry<T>,
+) -> Result<(), String> {
+    view.check_fits(obj_info)?;
+    view.count_client_write::<T>();
     match mem_writeall(obj_info.offset, entry, &view.memory_nodes) {
         Ok(()) => Ok(()),
-        Err(MemoryError(memory_node_id)) => {
+        Err(PartialWrite { failed: memory_node_id, .. }) => {
             Err(format!("Memory node {} failed during write", memory_node_id))
         }
     }
 }
 
+/// Read the current value then write the new one, `update(current, data)`
+/// if set, with the client-side sequence number `seq`. Best effort gives no
+/// guarantee that no other write lands in between.
+pub fn async_best_effort_swap<T: Copy + PartialEq + std::fmt::Debug>(
+    view: &crate::GroupView,
+    obj_info: &crate::ObjectInfo,
+    data: T,
+    update: Option<fn(T, T) -> T>,
+    seq: u64,
+) -> Result<T, String> {
+    let prev = match mem_readends::<T>(obj_info.offset, &view.memory_nodes) {
+        Ok(states) => states[0].value,
+        Err(MemoryError(memory_node_id)) => {
+            return Err(format!("Memory node {} failed during swap read", memory_node_id));
+        }
+    };
+    let data = update.map_or(data, |update| update(prev, data));
+    write_entry(view, obj_info, ObjectMemoryEntry::new_nowid(data).with_seq(seq)).map(|()| prev)
+}
 
+/// Write worker. Up to `batch_size` queued requests are taken at once: the
+/// writes are issued together, node by node, and acked afterwards. A swap
+/// is applied after the writes queued before it.
 pub fn async_best_effort_write_thread<T: Copy + PartialEq + std::fmt::Debug>(
     view: GroupView,
     req_queue_rx: kanal::Receiver<WriteRequest<T>>,
     stop_flag: Arc<AtomicBool>,
+    on_write_complete: Option<WriteCallback>,
+    batch_size: usize,
 ) {
 
     loop {
@@ -41,41 +76,37 @@ pub fn async_best_effort_write_thread<T: Copy + PartialEq + std::fmt::Debug>(
             break;
         }
 
-        match req_queue_rx.recv() {
+        match req_queue_rx.recv_timeout(STOP_POLL_INTERVAL) {
             Ok(req) => {
-                
-                let trace_id = req.trace_id; //debug
-                let queue_wait = req.enqueue_at.elapsed(); //debug
-                let write_start = Instant::now(); //debug
+                let mut writes = Vec::with_capacity(batch_size);
+                for req in recv_batch(&req_queue_rx, req, batch_size) {
+                    // the client gave up on the request while it was queued
+                    if !req.pick() {
+                        debug!("Write request {} cancelled, skipping", req.trace_id);
+                        continue;
+                    }
 
-                // write data to all memory nodes
-                let (oi, data, ack_tx) = req.to_tuple();
-                match async_best_effort_write(&view, &oi, data) {
-                    Ok(()) => {
-                        let replicate_time = write_start.elapsed(); //debug
+                    match &req.prev_tx {
+                        Some(prev_tx) => {
+                            // the swap reads the value of the writes before it
+                            write_batch(&view, &writes, &on_write_complete);
+                            writes.clear();
 
-                        // send ack to client
-                        if let Err(e) = ack_tx.send(true) {
-                            error!("Failed to send ack: {}", e);
+                            let write_start = Instant::now(); //debug
+                            let result = async_best_effort_swap(&view, &req.obj_info, req.data, req.update, req.seq)
+                                .map(|prev| {
+                                    if let Err(e) = prev_tx.send(prev) {
+                                        error!("Failed to send previous value: {}", e);
+                                    }
+                                });
+                            ack_write(&req, &result, write_start, &on_write_complete);
                         }
-
-                        if trace_id % WRITE_TRACE_SAMPLE_RATE == 0 {
-                            debug!(
-                                "[WRITE_TRACE][worker] id={} queue_wait={}ns replicate={}ns",
-                                trace_id,
-                                queue_wait.as_nanos(),
-                                replicate_time.as_nanos(),
-                            );
-                        }
-                    },
-                    Err(e) => {
-                        error!("Failed to write object: {}", e);
-                        continue;
+                        None => writes.push(req),
                     }
                 }
-
-                
+                write_batch(&view, &writes, &on_write_complete);
             },
+            Err(kanal::ReceiveErrorTimeout::Timeout) => continue, // check the stop flag
             Err(e) => {
                 info!("Object queue channel closed: {}", e);
                 break; // exit thread
@@ -84,6 +115,112 @@ pub fn async_best_effort_write_thread<T: Copy + PartialEq + std::fmt::Debug>(
     }
 }
 
+/// `first` and the requests already queued behind it, up to `batch_size`
+/// requests in total
+fn recv_batch<T>(
+    req_queue_rx: &kanal::Receiver<WriteRequest<T>>,
+    first: WriteRequest<T>,
+    batch_size: usize,
+) -> Vec<WriteRequest<T>> {
+    let mut batch = vec![first];
+    while batch.len() < batch_size {
+        match req_queue_rx.try_recv() {
+            Ok(Some(req)) => batch.push(req),
+            _ => break,
+        }
+    }
+    batch
+}
+
+/// Write a batch of picked requests to all memory nodes and ack them
+fn write_batch<T: Copy + PartialEq + std::fmt::Debug>(
+    view: &GroupView,
+    reqs: &[WriteRequest<T>],
+    on_write_complete: &Option<WriteCallback>,
+) {
+    if reqs.is_empty() {
+        return;
+    }
+    let write_start = Instant::now(); //debug
+
+    // objects that do not fit fail on their own
+    let (fits, overruns): (Vec<&WriteRequest<T>>, Vec<&WriteRequest<T>>) =
+        reqs.iter().partition(|req| view.check_fits(&req.obj_info).is_ok());
+    for req in overruns {
+        ack_write(req, &view.check_fits(&req.obj_info), write_start, on_write_complete);
+    }
+
+    // no rounds to serialize concurrent pushes
+    let (pushes, fits): (Vec<&WriteRequest<T>>, Vec<&WriteRequest<T>>) =
+        fits.into_iter().partition(|req| req.ring.is_some());
+    for req in pushes {
+        let result = Err("ring pushes not supported by async_best_effort".to_string());
+        ack_write(req, &result, write_start, on_write_complete);
+    }
+
+    // partial writes are written on their own
+    let (partial, fits): (Vec<&WriteRequest<T>>, Vec<&WriteRequest<T>>) =
+        fits.into_iter().partition(|req| req.nodes.is_some());
+    for req in partial {
+        view.count_client_write::<T>();
+        let entry = ObjectMemoryEntry::new_nowid(req.data).with_seq(req.seq);
+        let result = view.node_subset(req.nodes.as_deref().unwrap_or_default())
+            .and_then(|nodes| mem_writeall(req.obj_info.offset, entry, &nodes)
+                .map_err(|PartialWrite { failed: memory_node_id, .. }| format!("Memory node {} failed during write", memory_node_id)));
+        ack_write(req, &result, write_start, on_write_complete);
+    }
+
+    let entries: Vec<(usize, ObjectMemoryEntry<T>)> = fits.iter()
+        .map(|req| (req.obj_info.offset, ObjectMemoryEntry::new_nowid(req.data).with_seq(req.seq)))
+        .collect();
+    for _ in &entries {
+        view.count_client_write::<T>();
+    }
+    let result = mem_writebatch(&entries, &view.memory_nodes)
+        .map_err(|MemoryError(memory_node_id)| format!("Memory node {} failed during write", memory_node_id));
+    for req in fits {
+        ack_write(req, &result, write_start, on_write_complete);
+    }
+}
+
+fn ack_write<T>(
+    req: &WriteRequest<T>,
+    result: &Result<(), String>,
+    write_start: Instant,
+    on_write_complete: &Option<WriteCallback>,
+) {
+    match result {
+        Ok(()) => {
+            let queue_wait = write_start.duration_since(req.enqueue_at); //debug
+            let replicate_time = write_start.elapsed(); //debug
+
+            // send ack to client
+            let ack = WriteAck::uncontended(0);
+            if let Err(e) = req.ack_tx.send(ack) {
+                error!("Failed to send ack: {}", e);
+            }
+            if let Some(cb) = on_write_complete {
+                cb(req.obj_info.local_id(), queue_wait + replicate_time);
+            }
+
+            if req.trace_id.is_multiple_of(WRITE_TRACE_SAMPLE_RATE) {
+                debug!(
+                    "[WRITE_TRACE][worker] id={} queue_wait={}ns replicate={}ns",
+                    req.trace_id,
+                    queue_wait.as_nanos(),
+                    replicate_time.as_nanos(),
+                );
+            }
+        },
+        Err(e) => {
+            error!("Failed to write object: {}", e);
+            if let Err(e) = req.ack_tx.send(WriteAck::failed()) {
+                error!("Failed to send ack: {}", e);
+            }
+        }
+    }
+}
+
 
 /// Client-reader: clients perform read operation directly i.e. no read thread
 /// processing requests
@@ -98,7 +235,9 @@ pub fn async_best_effort_read<T: Copy + PartialEq + std::fmt::Debug>(
 
             let consistent = states.iter().all(|s: &ObjectMemoryEntry<T>| s.value == states[0].value);
             // return based on consistency
-            let result = if consistent {
+            let result = if states.iter().all(|s| !s.written) {
+                ReadReturn::Uninitialized
+            } else if consistent {
                 ReadReturn::ReadSafe(states[0].value)
             } else {
                 ReadReturn::ReadDirty(states[0].value)
@@ -112,6 +251,23 @@ pub fn async_best_effort_read<T: Copy + PartialEq + std::fmt::Debug>(
 }
 
 
+/// Best-effort read from the nearest live memory node only, see
+/// `safe_memio::nearest_node`. The value is not compared with the other
+/// nodes, so a written value is returned as ReadDirty.
+pub fn best_effort_read_nearest<T: Copy + PartialEq + std::fmt::Debug>(
+    view: &crate::GroupView,
+    obj_info: &crate::ObjectInfo,
+) -> Result<ReadReturn<T>, String> {
+    match mem_readone(obj_info.offset, &view.memory_nodes) {
+        Ok(ome) if !ome.written => Ok(ReadReturn::Uninitialized),
+        Ok(ome) => Ok(ReadReturn::ReadDirty(ome.value)),
+        Err(MemoryError(memory_node_id)) => {
+            Err(format!("Memory node {} failed during read", memory_node_id))
+        }
+    }
+}
+
+
 /// Thread-reader: process read requests from repCXL object channels and sends
 /// ReadReturn. inter-thread communication might lead to overhead, prefer 
 /// _client version for better latency  
@@ -124,19 +280,26 @@ pub fn async_best_effort_read_thread<T: Copy + PartialEq + std::fmt::Debug>(
         if actx.stop_flag.load(Ordering::Relaxed) {
             break;
         }
-        match req_queue.recv() {
+        match req_queue.recv_timeout(STOP_POLL_INTERVAL) {
             Ok(req) => {
-                match async_best_effort_read(&view, &req.obj_info) {
-                    Ok(result) => {
-                        if let Err(e) = req.ack_tx.send(result) {
-                            error!("Failed to send read response: {}", e);
-                        }
-                    }
-                    Err(e) => {
+                for obj_info in req.objects() {
+                    // no write ids to tell the age of a value
+                    let res = view.read_allocated(obj_info, || match req.max_stale_rounds {
+                        Some(_) => Err("freshness bounded reads not supported by async_best_effort".to_string()),
+                        None => async_best_effort_read(&view, obj_info),
+                    });
+                    if let Err(e) = &res {
                         error!("Failed to read object: {}", e);
                     }
-                }             
+                    // a failed read is answered too, the other objects of
+                    // the request are still read
+                    if let Err(e) = req.ack_tx.send(res) {
+                        error!("Failed to send read response: {}", e);
+                        break;
+                    }
+                }
             },
+            Err(kanal::ReceiveErrorTimeout::Timeout) => continue, // check the stop flag
             Err(e) => {
                 log::info!("[READ] Read request channel closed: {}", e);
                 break; // exit thread
@@ -174,19 +337,22 @@ pub fn sync_best_effort<T: Copy + PartialEq + std::fmt::Debug>(
         );
 
         match req_queue_rx.try_recv() {
+            Ok(Some(req)) if !req.pick() => {
+                debug!("Write request {} cancelled, skipping", req.trace_id);
+            },
             Ok(Some(req)) => {
                 // write data to all memory nodes
-                let (oi, data, ack_tx) = req.to_tuple();
-                let ome = ObjectMemoryEntry::new_nowid(data);
+                let ome = ObjectMemoryEntry::new_nowid(req.data).with_seq(req.seq);
                 
-                match mem_writeall(oi.offset, ome, &view.memory_nodes) {
+                match mem_writeall(req.obj_info.offset, ome, &view.memory_nodes) {
                     Ok(()) => {
                         // send ack to client
-                        if let Err(_) = ack_tx.send(true) {
+                        let ack = WriteAck::uncontended(1);
+                        if let Err(_) = req.ack_tx.send(ack) {
                             error!("Failed to send ack");
                         }
                     },
-                    Err(MemoryError(memory_node_id)) => {
+                    Err(PartialWrite { failed: memory_node_id, .. }) => {
                         error!("Memory node {} failed during write replication", memory_node_id);
                         break;
                     }
@@ -214,6 +380,34 @@ pub fn sync_best_effort<T: Copy + PartialEq + std::fmt::Debug>(
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ObjectInfo;
+    use std::sync::atomic::AtomicUsize;
 
+    #[test]
+    fn test_recv_batch_drains_queue() {
+        let (tx, rx) = kanal::unbounded();
+        let (ack_tx, _ack_rx) = kanal::unbounded();
+        let pending = Arc::new(AtomicUsize::new(0));
+        for i in 0..5u64 {
+            tx.send(WriteRequest::new(ObjectInfo::new(1, 0, 64), i, ack_tx.clone(), &pending)).unwrap();
+        }
 
+        // all queued requests in one batch
+        let first = rx.recv().unwrap();
+        let batch = recv_batch(&rx, first, 8);
+        assert_eq!(batch.iter().map(|req| req.data).collect::<Vec<_>>(), vec![0, 1, 2, 3, 4]);
 
+        // capped to the batch size
+        for i in 0..5u64 {
+            tx.send(WriteRequest::new(ObjectInfo::new(1, 0, 64), i, ack_tx.clone(), &pending)).unwrap();
+        }
+        let first = rx.recv().unwrap();
+        assert_eq!(recv_batch(&rx, first, 3).len(), 3);
+        let first = rx.recv().unwrap();
+        assert_eq!(recv_batch(&rx, first, 3).len(), 2);
+        assert!(rx.try_recv().unwrap().is_none());
+    }
+}
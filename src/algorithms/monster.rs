@@ -1,12 +1,16 @@
+use std::collections::HashMap;
 use std::sync::atomic::Ordering;
 use std::time::{Instant, Duration};
-use log::{error, debug};
+use log::{error, debug, warn};
+use rand::Rng;
 
 use super::{AlgorithmThreadContext, AlgorithmCallContext};
 use crate::timer;
-use crate::request::{Wid, WriteRequest, ReadRequest, ReadReturn};
-use crate::safe_memio::{ObjectMemoryEntry, mem_writeall, mem_readall, mem_readends, MemoryError};
+use crate::request::{AckPolicy, ConflictInfo, Wid, WriteAck, WriteRequest, ReadRequest, ReadReturn};
+use crate::safe_memio::{ObjectMemoryEntry, mem_writeall, mem_writeall_quorum, mem_readall, mem_readends, MemoryError};
+use crate::shmem::wcc::{OWCC_ENTRY_SIZE, FWCC_ENTRY_SIZE};
 use crate::utils::ms_logger;
+use crate::ring::{self, RingLayout};
 
 
 
@@ -18,6 +22,7 @@ pub enum MonsterState {
     Replicate,
     Wait,
     PostConflictCheck,
+    Backoff,
 }
 
 impl std::fmt::Display for MonsterState {
@@ -29,6 +34,7 @@ impl std::fmt::Display for MonsterState {
             MonsterState::Replicate => write!(f, "Replicate"),
             MonsterState::Wait => write!(f, "Wait"),
             MonsterState::PostConflictCheck => write!(f, "PostConflictCheck"),
+            MonsterState::Backoff => write!(f, "Backoff"),
         }
     } 
 }
@@ -49,6 +55,8 @@ macro_rules! monster_error {
 /// Collect statistics for MONSTER algorithm
 pub struct MonsterStats {
     conflicts: u64,
+    retries: u64,
+    backoff_rounds: u64,
     sync_failures: u64,
     empty_requests: u64,
     prev_round: u64,
@@ -60,6 +68,8 @@ impl MonsterStats {
     pub fn new() -> Self {
         Self {
             conflicts: 0,
+            retries: 0,
+            backoff_rounds: 0,
             sync_failures: 0,
             empty_requests: 0,
             prev_round: 1,
@@ -82,8 +92,10 @@ impl MonsterStats {
     }
 
     pub fn print(&self) {
-        log::info!("Monster stats: conflicts={}, sync_failures={}, empty_requests={}, try_overtime={}, check_overtime={}, replicate_overtime={}", 
+        log::info!("Monster stats: conflicts={}, retries={}, backoff_rounds={}, sync_failures={}, empty_requests={}, try_overtime={}, check_overtime={}, replicate_overtime={}", 
             self.conflicts, 
+            self.retries,
+            self.backoff_rounds,
             self.sync_failures, 
             self.empty_requests, 
             self.try_overtime,
@@ -92,16 +104,229 @@ impl MonsterStats {
     }
 }
 
+/// weight of the last write in the rolling conflict rate of an object
+const CONFLICT_RATE_WEIGHT: f64 = 0.25;
+
+#[derive(Debug, Default, Clone, Copy)]
+struct ObjectBackoff {
+    conflict_rate: f64,
+    window: u64,
+}
+
+/// Per-object retry backoff. When the rolling conflict rate of an object
+/// exceeds the threshold, a write losing a conflict on it skips a random
+/// number of rounds before retrying, so that the contenders do not collide
+/// again in the same round. The backoff window doubles up to `max_rounds`
+/// while conflicts persist.
+pub struct ConflictBackoff {
+    threshold: Option<f64>,
+    max_rounds: u64,
+    objects: HashMap<usize, ObjectBackoff>,
+}
+
+impl ConflictBackoff {
+    /// Disabled if `threshold` is None
+    pub fn new(threshold: Option<f64>, max_rounds: u64) -> Self {
+        Self {
+            threshold,
+            max_rounds,
+            objects: HashMap::new(),
+        }
+    }
+
+    /// Record a write attempt on the object. Returns the number of rounds to
+    /// skip before retrying, 0 if the attempt did not conflict or the conflict
+    /// rate is below the threshold
+    fn record(&mut self, obj_id: usize, conflict: bool) -> u64 {
+        let Some(threshold) = self.threshold else { return 0 };
+        let ob = self.objects.entry(obj_id).or_default();
+
+        let sample = if conflict { 1.0 } else { 0.0 };
+        ob.conflict_rate += CONFLICT_RATE_WEIGHT * (sample - ob.conflict_rate);
+
+        if ob.conflict_rate <= threshold {
+            ob.window = 0;
+            return 0;
+        }
+        if !conflict {
+            ob.window /= 2;
+            return 0;
+        }
+        ob.window = (ob.window * 2).clamp(1, self.max_rounds);
+        rand::rng().random_range(1..=ob.window)
+    }
+}
+
+/// The newest entry of another process after a lost conflict, i.e. the
+/// write that won it
+fn conflict_winner<T: Copy>(omes: &[ObjectMemoryEntry<T>], self_id: usize) -> Option<ConflictInfo> {
+    omes.iter()
+        .filter(|ome| ome.written)
+        .map(|ome| ome.wid)
+        .max()
+        .filter(|wid| wid.process_id != self_id)
+        .map(|wid| ConflictInfo { winner_pid: wid.process_id, winner_wid: wid })
+}
+
+/// State to retry a write from, after backing off if needed
+fn retry_state(backoff_rounds: u64) -> MonsterState {
+    if backoff_rounds > 0 {
+        MonsterState::Backoff
+    } else {
+        MonsterState::Retry
+    }
+}
+
 fn is_overtime(round_start: Instant, round_time: Duration) -> bool {
     Instant::now().duration_since(round_start) > round_time
 }
 
+/// Read the current value of the object, i.e. the entry with the largest wid
+/// across all memory nodes
+fn latest_value<T: Copy>(omes: &[ObjectMemoryEntry<T>]) -> Result<T, String> {
+    let latest = omes.iter().max_by_key(|ome| ome.wid)
+        .ok_or("No memory nodes to read from")?;
+    Ok(latest.value)
+}
+
+/// Entries of the object on every node before a replicate. The write is
+/// committed with the next version
+fn read_before_replicate<T: Copy>(
+    obj_info: &crate::ObjectInfo,
+    view: &crate::GroupView,
+) -> Result<(Vec<ObjectMemoryEntry<T>>, u64), String> {
+    let omes: Vec<ObjectMemoryEntry<T>> = mem_readall(obj_info.offset, &view.memory_nodes)
+        .map_err(|MemoryError(mnid)| format!("Memory node {} failed during version read", mnid))?;
+    let version = omes.iter().map(|ome| ome.version).max().unwrap_or(0) + 1;
+    Ok((omes, version))
+}
+
+/// How a MONSTER write is applied, see `monster_write`
+#[derive(Clone, Copy)]
+pub struct WriteMode<'a, T> {
+    /// return the replaced value
+    pub swap: bool,
+    /// swaps only: write `update(previous, data)` instead of `data`
+    pub update: Option<fn(T, T) -> T>,
+    /// ids of the only memory nodes to write, see `RepCXLObject::write_to`
+    pub nodes: Option<&'a [usize]>,
+    /// push to a ring instead of writing the object, see `RingObject`
+    pub(crate) ring: Option<RingLayout>,
+    /// client-side sequence number stored with the value
+    pub seq: u64,
+    /// set by the write worker for the `Quorum` ack policy: called with the
+    /// ack once a majority of the nodes hold the write
+    pub(crate) on_quorum: Option<&'a dyn Fn(WriteAck)>,
+    /// abandon the write if it is not replicated by then, see
+    /// `RepCXLObject::write_deadline`
+    pub deadline: Option<Instant>,
+}
 
+impl<T> Default for WriteMode<'_, T> {
+    fn default() -> Self {
+        WriteMode { swap: false, update: None, nodes: None, ring: None, seq: 0, on_quorum: None, deadline: None }
+    }
+}
+
+impl<'a, T> WriteMode<'a, T> {
+    pub(crate) fn of(req: &'a WriteRequest<T>) -> Self {
+        WriteMode {
+            swap: req.prev_tx.is_some(),
+            update: req.update,
+            nodes: req.nodes.as_deref(),
+            ring: req.ring,
+            seq: req.seq,
+            on_quorum: None,
+            deadline: req.deadline,
+        }
+    }
+
+    // a write losing a conflict cannot be ordered before the winning one
+    fn retries_conflicts(&self) -> bool {
+        self.swap || self.ring.is_some()
+    }
+}
+
+/// Replicate phase of MONSTER and fMONSTER: write the entry to the nodes of
+/// the write and return `ack`, late if the round ran `overtime`. With
+/// `mode.on_quorum`, the ack is handed over once a majority of the nodes hold
+/// the entry
+fn replicate<T: Copy>(
+    view: &crate::GroupView,
+    offset: usize,
+    ome: ObjectMemoryEntry<T>,
+    mode: &WriteMode<T>,
+    ack: WriteAck,
+    overtime: impl Fn() -> bool,
+    stats: &mut MonsterStats,
+) -> Result<WriteAck, String> {
+    let subset;
+    let targets = match mode.nodes {
+        Some(ids) => {
+            subset = view.node_subset(ids)?;
+            &subset
+        },
+        None => &view.memory_nodes,
+    };
+    let result = match mode.on_quorum {
+        Some(on_quorum) => mem_writeall_quorum(offset, ome, targets, || {
+            on_quorum(WriteAck { on_time: ack.on_time && !overtime(), ..ack })
+        }),
+        None => mem_writeall(offset, ome, targets),
+    };
+
+    let on_time = if overtime() {
+        stats.replicate_overtime += 1;
+        false
+    } else {
+        ack.on_time
+    };
+    result
+        .map(|()| WriteAck { on_time, ..ack })
+        .map_err(|e| format!("Memory node {} failed during write replication ({}), written to nodes {:?}", e.failed, e.cause, e.succeeded))
+}
+
+/// Value written by a swap replacing `prev`
+fn swap_value<T: Copy>(mode: &WriteMode<T>, prev: T, data: T) -> T {
+    mode.update.map_or(data, |update| update(prev, data))
+}
+
+/// Offset and entry of a ring push: the entry after the newest push, which
+/// is the oldest one once the ring is full
+fn ring_entry<T: Copy>(
+    obj_info: &crate::ObjectInfo,
+    layout: RingLayout,
+    view: &crate::GroupView,
+    wid: Wid,
+    data: T,
+) -> Result<(usize, ObjectMemoryEntry<T>), String> {
+    let seq = ring::next_seq::<T>(obj_info, layout, view)
+        .map_err(|MemoryError(mnid)| format!("Memory node {} failed during ring read", mnid))?;
+    Ok((layout.slot_offset(obj_info, seq), ObjectMemoryEntry::new(wid, data).with_version(seq)))
+}
+
+/// MONSTER write, returns the ack for the client. If `mode.swap` is set, the
+/// value replaced by the write is returned as well, and a write losing a
+/// conflict is retried instead of being ordered before the winning one, since
+/// the replaced value would be unknown. Same for ring pushes, whose entry
+/// depends on the pushes ordered before.
+///
+/// With the "timestamp" conflict order, `timestamp` is part of the wid and
+/// orders the write before any other write of the object: a write older than
+/// the stored one is not replicated (except for swaps).
+///
+/// With `single_writer`, the write skips the WCC and goes from Try straight
+/// to Replicate. Concurrent writers of the same object are then not ordered.
 pub fn monster_write<T: Copy + PartialEq + std::fmt::Debug>(
         actx: &mut super::AlgorithmCallContext, 
         view: &crate::GroupView,
         obj_info: &crate::ObjectInfo,
-        data: T) -> Result<(), String> {
+        data: T,
+        timestamp: u64,
+        mode: &WriteMode<T>) -> Result<(WriteAck, Option<T>), String> {
+
+    view.check_fits(obj_info)?;
+    view.count_client_write::<T>();
 
     let mut monster_state = MonsterState::Try;
     let stats = &mut actx.stats;
@@ -117,13 +342,19 @@ pub fn monster_write<T: Copy + PartialEq + std::fmt::Debug>(
 
     // MONSTER loop vars
     let mut wid = Wid::new(0,0); // write request id
+    let mut backoff_left = 0; // rounds to skip before retrying
+    let mut conflict = None; // winner of the last lost conflict
+    let mut conflicted = false; // another process tried the object in one of our rounds
+    let mut retries = 0;
     // let mut oid = 0; // object id
     // let mut stats = MonsterStats::new();
 
 
-    // get reference to shared write conflict checker (wcc)
-    let mnode_state = view.get_master_node().unwrap().get_state();
-    let owcc = mnode_state.get_owcc();
+    // no conflict possible, or the user guarantees a single writer per object
+    // (single_writer), skip the WCC and Check phase
+    let fast_path = view.is_conflict_free() || actx.single_writer;
+
+    let timestamp = if actx.timestamp_order { timestamp } else { 0 };
 
     let round_zero = actx.start_instant;
 
@@ -132,8 +363,13 @@ pub fn monster_write<T: Copy + PartialEq + std::fmt::Debug>(
             round_zero, 
             actx.round_time, 
             timer::ROUND_SLEEP_RATIO);
+    let first_round = round_num;
+    let mut on_time = true;
 
     loop {
+        if let Some(jitter) = &actx.jitter {
+            jitter.record(round_start);
+        }
         monster_info!(monster_state,
             "Round #{round_num}, delay {:?}, obj id: {}",
             Instant::now().duration_since(round_start),
@@ -142,41 +378,67 @@ pub fn monster_write<T: Copy + PartialEq + std::fmt::Debug>(
 
         let _ = stats.update_sync_failure(round_num);
 
+        // the deadline passed before the write was replicated, possibly while
+        // waiting for a retry: give up. A WCC entry left behind is outdated
+        // in the next round
+        if mode.deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            monster_info!(monster_state, "Deadline of the write of object {} exceeded, abandoning it", obj_info.id);
+            let rounds_taken = round_num - first_round;
+            return Ok((WriteAck { deadline_exceeded: true, rounds_taken, conflict, conflicted, retries, ..WriteAck::failed() }, None));
+        }
+
 
         // Log state transition if logging is enabled
         if let Some(ref mut logger) = mslog {
-            logger.log_monster(round_num, monster_state, obj_info.id);
+            logger.log_monster(round_num, monster_state, obj_info.local_id());
         }
 
         match monster_state {
             MonsterState::Try => {
-                wid = Wid::new(round_num, view.self_id);
+                wid = Wid::with_timestamp(round_num, view.self_id, timestamp);
                 // oid = obj_info.id;
-                owcc.write(obj_info.id, round_num, view.self_id);
-                monster_state = MonsterState::Check;
+                if fast_path {
+                    monster_state = MonsterState::Replicate;
+                } else if view.quorum_read(|state| state.get_owcc().is_pending(obj_info.id, round_num, view.self_id)) {
+                    // our WCC entry of this shard may still be checked for
+                    // another object: try again next round
+                    monster_info!(monster_state, "WCC shard of object {} busy in round {}", obj_info.id, round_num);
+                } else {
+                    view.update_states_counted(OWCC_ENTRY_SIZE, |state| state.get_owcc().write(obj_info.id, round_num, view.self_id, timestamp));
+                    monster_state = MonsterState::Check;
+                }
                         
                 if is_overtime(round_start, actx.round_time) {
                     stats.try_overtime += 1;
+                    on_time = false;
                 }
             },
 
             // Same as Try but don't fetch new request, use the pending one
             MonsterState::Retry => {
-                
-                wid = Wid::new(round_num, view.self_id);
-                // oid = obj_info.id;
-                owcc.write(obj_info.id, round_num, view.self_id);
+                if view.quorum_read(|state| state.get_owcc().is_pending(obj_info.id, round_num, view.self_id)) {
+                    // see Try
+                    monster_info!(monster_state, "WCC shard of object {} busy in round {}", obj_info.id, round_num);
+                } else {
+                    stats.retries += 1;
+                    retries += 1;
+                    wid = Wid::with_timestamp(round_num, view.self_id, timestamp);
+                    view.update_states_counted(OWCC_ENTRY_SIZE, |state| state.get_owcc().write(obj_info.id, round_num, view.self_id, timestamp));
+                    monster_state = MonsterState::Check;
+                }
 
-                monster_state = MonsterState::Check;
                 if is_overtime(round_start, actx.round_time) {
                     stats.try_overtime += 1;
+                    on_time = false;
                 }
             },
             
             MonsterState::Check => {
-                if owcc.is_last(obj_info.id, round_num, wid.round_num, wid.process_id) {
+                // shared write conflict checker (wcc), replicated on every node
+                if view.quorum_read(|state| state.get_owcc().is_last(obj_info.id, round_num, wid.round_num, wid.process_id, wid.timestamp)) {
                     // current process is the last writer
                     monster_info!(monster_state, "Process {} is the last writer for object {} in round {}", view.self_id, obj_info.id, round_num);
+                    conflicted |= view.quorum_read(|state| state.get_owcc().has_contender(obj_info.id, wid.round_num, view.self_id));
                     monster_state = MonsterState::Replicate;
 
                 }
@@ -186,26 +448,55 @@ pub fn monster_write<T: Copy + PartialEq + std::fmt::Debug>(
                 }
                 if is_overtime(round_start, actx.round_time) {
                     stats.check_overtime += 1;
+                    on_time = false;
                 }
             },
 
             MonsterState::Replicate => {
-                let ome = ObjectMemoryEntry::new(wid, data);
+                actx.backoff.record(obj_info.id, false);
 
-                let result = mem_writeall(obj_info.offset, ome, &view.memory_nodes)
-                    .map_err(|MemoryError(mnid)| format!("Memory node {} failed during write replication", mnid));
+                let (offset, ome, prev) = match mode.ring {
+                    Some(layout) => {
+                        let (offset, ome) = ring_entry(obj_info, layout, view, wid, data)?;
+                        (offset, ome, None)
+                    },
+                    None => {
+                        let (omes, version) = read_before_replicate::<T>(obj_info, view)?;
 
-                if is_overtime(round_start, actx.round_time) {
-                    stats.replicate_overtime += 1;
-                }
+                        // last writer wins: a later write is already stored
+                        if actx.timestamp_order && !mode.swap && omes.iter().any(|ome| ome.wid > wid) {
+                            monster_info!(monster_state, "A later write of object {} is stored, skipping {:?}", obj_info.id, wid);
+                            conflict = conflict_winner(&omes, view.self_id).or(conflict);
+                            return Ok((WriteAck::succeeded(on_time, round_num - first_round + 1, conflict, conflicted, retries, wid), None));
+                        }
 
-                return result;
+                        let prev = if mode.swap { Some(latest_value(&omes)?) } else { None };
+                        let value = prev.map_or(data, |prev| swap_value(mode, prev, data));
+                        (obj_info.offset, ObjectMemoryEntry::new(wid, value).with_version(version).with_seq(mode.seq), prev)
+                    },
+                };
+
+                let ack = WriteAck::succeeded(on_time, round_num - first_round + 1, conflict, conflicted, retries, wid);
+                let overtime = || is_overtime(round_start, actx.round_time);
+                return replicate(view, offset, ome, mode, ack, overtime, stats).map(|ack| (ack, prev));
             },
 
             // wait for the replicate phase of the conflicting process to finish
             MonsterState::Wait => {
+                conflicted = true;
                 monster_state = MonsterState::PostConflictCheck;
                 stats.conflicts += 1;
+                view.update_states(|state| state.get_conflict_stats().record(view.self_id, obj_info.id, round_num));
+                backoff_left = actx.backoff.record(obj_info.id, true);
+            },
+
+            // hot object, skip rounds before retrying
+            MonsterState::Backoff => {
+                stats.backoff_rounds += 1;
+                backoff_left -= 1;
+                if backoff_left == 0 {
+                    monster_state = MonsterState::Retry;
+                }
             },
 
             // check if the conflicting write has been fully replicated, otherwise
@@ -216,17 +507,21 @@ pub fn monster_write<T: Copy + PartialEq + std::fmt::Debug>(
                         // Check if any wid in omes is smaller than the current
                         // wid
                         let any_smaller = omes.iter().any(|ome: &ObjectMemoryEntry<T>| ome.wid < wid);
+                        conflict = conflict_winner(&omes, view.self_id).or(conflict);
 
                         if any_smaller {                            
                             monster_info!(monster_state,
                                 "Found wid smaller than current wid={:?} for object {}, retrying to write",
                                 wid, obj_info.id
                             );
-                            monster_state = MonsterState::Retry; 
+                            monster_state = retry_state(backoff_left);
                         
+                        } else if mode.retries_conflicts() {
+                            monster_info!(monster_state, "Lost the conflict for object {}, retrying", obj_info.id);
+                            monster_state = retry_state(backoff_left);
                         } else {
                             monster_info!(monster_state, "State up to date");
-                            return Ok(());
+                            return Ok((WriteAck::succeeded(on_time, round_num - first_round + 1, conflict, conflicted, retries, wid), None));
                         }
                     },
                     Err(MemoryError(memory_node_id)) => {
@@ -245,6 +540,52 @@ pub fn monster_write<T: Copy + PartialEq + std::fmt::Debug>(
 }
 
 
+/// Run `write` for a request of a write worker and ack it: with the quorum
+/// ack policy as soon as a majority of the nodes hold the write, otherwise
+/// once it returns
+fn write_and_ack<T: Copy>(
+    actx: &AlgorithmThreadContext,
+    req: &WriteRequest<T>,
+    queue_wait_rounds: u64,
+    write: impl FnOnce(&WriteMode<T>) -> Result<(WriteAck, Option<T>), String>,
+) {
+    let acked = std::cell::Cell::new(false);
+    let ack_quorum = |ack: WriteAck| {
+        if req.ack_tx.send(WriteAck { queue_wait_rounds, ..ack }).is_err() {
+            error!("Failed to send ack");
+        }
+        acked.set(true);
+    };
+    let mode = WriteMode {
+        on_quorum: (req.ack_policy.unwrap_or(actx.ack_policy) == AckPolicy::Quorum)
+            .then_some(&ack_quorum as &dyn Fn(WriteAck)),
+        ..WriteMode::of(req)
+    };
+    match write(&mode) {
+        Ok((ack, prev)) => {
+            if let (Some(prev_tx), Some(prev)) = (&req.prev_tx, prev) {
+                if let Err(e) = prev_tx.send(prev) {
+                    error!("Failed to send previous value: {}", e);
+                }
+            }
+            if !acked.get() && req.ack_tx.send(WriteAck { queue_wait_rounds, ..ack }).is_err() {
+                error!("Failed to send ack");
+            }
+            if let Some(cb) = &actx.on_write_complete {
+                cb(req.obj_info.local_id(), req.enqueue_at.elapsed());
+            }
+        }
+        Err(e) => {
+            error!("Failed to write object: {}", e);
+            if acked.get() {
+                error!("Write was already acknowledged by a quorum of the nodes");
+            } else if let Err(e) = req.ack_tx.send(WriteAck::failed()) {
+                error!("Failed to send ack: {}", e);
+            }
+        }
+    }
+}
+
 pub fn monster_write_thread<T: Copy + PartialEq + std::fmt::Debug>(
     actx: AlgorithmThreadContext, 
     req_queue: kanal::Receiver<WriteRequest<T>>) {
@@ -260,16 +601,26 @@ pub fn monster_write_thread<T: Copy + PartialEq + std::fmt::Debug>(
 
         match req_queue.try_recv() {
             Ok(Some(req)) => {
-                match monster_write(&mut actx_call, &actx.group_view, &req.obj_info, req.data) { 
-                    Ok(()) => {
-                        if let Err(_) = req.ack_tx.send(true) {
-                            error!("Failed to send ack");
-                        }
-                    }
-                    Err(e) => {
-                        error!("Failed to write object: {}", e);
-                    }
+                // the client gave up on the request while it was queued
+                if !req.pick() {
+                    debug!("Write request {} cancelled, skipping", req.trace_id);
+                    continue;
                 }
+                let enqueue_round = timer::round_at(actx.start_instant, actx.round_time, req.enqueue_at);
+                let queue_wait_rounds = timer::current_round(actx.start_instant, actx.round_time)
+                    .saturating_sub(enqueue_round);
+
+                #[cfg(test)]
+                if super::PANIC_ON_OBJECT
+                    .compare_exchange(req.obj_info.id, usize::MAX, Ordering::Relaxed, Ordering::Relaxed)
+                    .is_ok()
+                {
+                    panic!("Injected write worker failure on object {}", req.obj_info.id);
+                }
+
+                write_and_ack(&actx, &req, queue_wait_rounds, |mode| {
+                    monster_write(&mut actx_call, &actx.group_view, &req.obj_info, req.data, req.timestamp, mode)
+                });
             },
             Ok(None) => {
                 // no request, stay in Try state
@@ -293,12 +644,17 @@ pub fn monster_write_thread<T: Copy + PartialEq + std::fmt::Debug>(
     }
 }
 
+/// fMONSTER write, `mode` has the same semantics as in `monster_write`
 pub fn fmonster_write<T: Copy + PartialEq + std::fmt::Debug>(
     actx: &mut super::AlgorithmCallContext,
     view: &crate::GroupView,
     obj_info: &crate::ObjectInfo,
     data: T,
-) -> Result<(), String> {
+    mode: &WriteMode<T>,
+) -> Result<(WriteAck, Option<T>), String> {
+
+    view.check_fits(obj_info)?;
+    view.count_client_write::<T>();
 
     let mut monster_state = MonsterState::Try;
     let stats = &mut actx.stats;
@@ -314,10 +670,18 @@ pub fn fmonster_write<T: Copy + PartialEq + std::fmt::Debug>(
     // MONSTER loop vars
     let mut wid = Wid::new(0, 0); // write request id
     let mut last_writer_pid = 0;
+    let mut backoff_left = 0; // rounds to skip before retrying
+    let mut conflict = None; // winner of the last lost conflict
+    let mut conflicted = false; // another process tried the object in one of our rounds
+    let mut retries = 0;
 
-    // get shared write conflict checker
-    let mnode_state = view.get_master_node().unwrap().get_state();
-    let fwcc = mnode_state.get_fwcc();
+    // the shared write conflict checker is indexed by the id without
+    // namespace: objects with the same id in different namespaces share a
+    // slot, which can only cause spurious conflicts
+
+    // no conflict possible, or the user guarantees a single writer per object
+    // (single_writer), skip the WCC and Check phase
+    let fast_path = view.is_conflict_free() || actx.single_writer;
 
     let round_zero = actx.start_instant;
 
@@ -327,8 +691,13 @@ pub fn fmonster_write<T: Copy + PartialEq + std::fmt::Debug>(
         actx.round_time,
         timer::ROUND_SLEEP_RATIO,
     );
+    let first_round = round_num;
+    let mut on_time = true;
 
     loop {
+        if let Some(jitter) = &actx.jitter {
+            jitter.record(round_start);
+        }
         monster_info!(
             monster_state,
             "Round #{round_num}, delay {:?}, obj id: {}",
@@ -338,39 +707,57 @@ pub fn fmonster_write<T: Copy + PartialEq + std::fmt::Debug>(
 
         let _ = stats.update_sync_failure(round_num);
 
+        // the deadline passed before the write was replicated, give up and
+        // withdraw from the WCC so that other writers do not wait for it
+        if mode.deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            monster_info!(monster_state, "Deadline of the write of object {} exceeded, abandoning it", obj_info.id);
+            view.update_states(|state| state.get_fwcc().clear(obj_info.local_id(), view.self_id));
+            let rounds_taken = round_num - first_round;
+            return Ok((WriteAck { deadline_exceeded: true, rounds_taken, conflict, conflicted, retries, ..WriteAck::failed() }, None));
+        }
+
         // Log state transition if logging is enabled
         if let Some(ref mut logger) = mslog {
-            logger.log_monster(round_num, monster_state, obj_info.id);
+            logger.log_monster(round_num, monster_state, obj_info.local_id());
         }
 
         match monster_state {
             MonsterState::Try => {
                 wid = Wid::new(round_num, view.self_id);
-                fwcc.write(obj_info.id, view.self_id);
-                monster_state = MonsterState::Check;
+                if fast_path {
+                    monster_state = MonsterState::Replicate;
+                } else {
+                    view.update_states_counted(FWCC_ENTRY_SIZE, |state| state.get_fwcc().write(obj_info.local_id(), view.self_id));
+                    monster_state = MonsterState::Check;
+                }
 
                 if is_overtime(round_start, actx.round_time) {
                     stats.try_overtime += 1;
+                    on_time = false;
                 }
             }
 
             // Same as Try but don't fetch new request, use the pending one
             MonsterState::Retry => {
+                stats.retries += 1;
+                retries += 1;
                 wid = Wid::new(round_num, view.self_id);
 
                 // last writer did not complete replication due to failure
                 // (process or sync). current process steps in unsetting the
                 // last writer to avoid indefinite conflict loss
-                fwcc.replace(obj_info.id, view.self_id, last_writer_pid);
+                view.update_states_counted(FWCC_ENTRY_SIZE, |state| state.get_fwcc().replace(obj_info.local_id(), view.self_id, last_writer_pid));
 
                 monster_state = MonsterState::Check;
                 if is_overtime(round_start, actx.round_time) {
                     stats.try_overtime += 1;
+                    on_time = false;
                 }
             }
 
             MonsterState::Check => {
-                match fwcc.last(obj_info.id) {
+                // shared write conflict checker, replicated on every node
+                match view.quorum_read(|state| state.get_fwcc().last(obj_info.local_id())) {
                     Some(last_writer) => {
                         if last_writer == view.self_id {
                             monster_info!(
@@ -381,8 +768,9 @@ pub fn fmonster_write<T: Copy + PartialEq + std::fmt::Debug>(
                                 round_num
                             );
 
+                            conflicted |= view.quorum_read(|state| state.get_fwcc().writers(obj_info.local_id())) > 1;
                             monster_state = MonsterState::Replicate;
-                            fwcc.clear(obj_info.id, view.self_id);
+                            view.update_states(|state| state.get_fwcc().clear(obj_info.local_id(), view.self_id));
                         } else {
                             // not the last writer
                             last_writer_pid = last_writer;
@@ -406,28 +794,46 @@ pub fn fmonster_write<T: Copy + PartialEq + std::fmt::Debug>(
 
                 if is_overtime(round_start, actx.round_time) {
                     stats.check_overtime += 1;
+                    on_time = false;
                 }
             }
 
             MonsterState::Replicate => {
-                let ome = ObjectMemoryEntry::new(wid, data);
-
-                let result = mem_writeall(obj_info.offset, ome, &view.memory_nodes)
-                    .map_err(|MemoryError(mnid)| {
-                        format!("Memory node {} failed during write replication", mnid)
-                    });
-
-                if is_overtime(round_start, actx.round_time) {
-                    stats.replicate_overtime += 1;
-                }
+                actx.backoff.record(obj_info.id, false);
+                let (offset, ome, prev) = match mode.ring {
+                    Some(layout) => {
+                        let (offset, ome) = ring_entry(obj_info, layout, view, wid, data)?;
+                        (offset, ome, None)
+                    },
+                    None => {
+                        let (omes, version) = read_before_replicate::<T>(obj_info, view)?;
+                        let prev = if mode.swap { Some(latest_value(&omes)?) } else { None };
+                        let value = prev.map_or(data, |prev| swap_value(mode, prev, data));
+                        (obj_info.offset, ObjectMemoryEntry::new(wid, value).with_version(version).with_seq(mode.seq), prev)
+                    },
+                };
 
-                return result;
+                let ack = WriteAck::succeeded(on_time, round_num - first_round + 1, conflict, conflicted, retries, wid);
+                let overtime = || is_overtime(round_start, actx.round_time);
+                return replicate(view, offset, ome, mode, ack, overtime, stats).map(|ack| (ack, prev));
             }
 
             // wait for the replicate phase of the conflicting process to finish
             MonsterState::Wait => {
+                conflicted = true;
                 monster_state = MonsterState::PostConflictCheck;
                 stats.conflicts += 1;
+                view.update_states(|state| state.get_conflict_stats().record(view.self_id, obj_info.id, round_num));
+                backoff_left = actx.backoff.record(obj_info.id, true);
+            }
+
+            // hot object, skip rounds before retrying
+            MonsterState::Backoff => {
+                stats.backoff_rounds += 1;
+                backoff_left -= 1;
+                if backoff_left == 0 {
+                    monster_state = MonsterState::Retry;
+                }
             }
 
             // check if the conflicting write has been fully replicated, otherwise
@@ -440,6 +846,7 @@ pub fn fmonster_write<T: Copy + PartialEq + std::fmt::Debug>(
                         let any_smaller = omes.iter().any(|ome: &ObjectMemoryEntry<T>| {
                             ome.wid < wid
                         });
+                        conflict = conflict_winner(&omes, view.self_id).or(conflict);
 
                         if any_smaller {
                             monster_info!(
@@ -449,10 +856,17 @@ pub fn fmonster_write<T: Copy + PartialEq + std::fmt::Debug>(
                                 obj_info.id
                             );
 
-                            monster_state = MonsterState::Retry;
+                            monster_state = retry_state(backoff_left);
+                        } else if mode.retries_conflicts() {
+                            monster_info!(
+                                monster_state,
+                                "Lost the conflict for object {}, retrying",
+                                obj_info.id
+                            );
+                            monster_state = retry_state(backoff_left);
                         } else {
                             monster_info!(monster_state, "State up to date");
-                            return Ok(());
+                            return Ok((WriteAck::succeeded(on_time, round_num - first_round + 1, conflict, conflicted, retries, wid), None));
                         }
                     }
                     Err(MemoryError(memory_node_id)) => {
@@ -493,16 +907,18 @@ pub fn fmonster_write_thread<T: Copy + PartialEq + std::fmt::Debug>(
 
         match req_queue.try_recv() {
             Ok(Some(req)) => {
-                match fmonster_write(&mut actx_call, &actx.group_view, &req.obj_info, req.data) {
-                    Ok(()) => {
-                        if let Err(_) = req.ack_tx.send(true) {
-                            error!("Failed to send ack");
-                        }
-                    }
-                    Err(e) => {
-                        error!("Failed to write object: {}", e);
-                    }
+                // the client gave up on the request while it was queued
+                if !req.pick() {
+                    debug!("Write request {} cancelled, skipping", req.trace_id);
+                    continue;
                 }
+                let enqueue_round = timer::round_at(actx.start_instant, actx.round_time, req.enqueue_at);
+                let queue_wait_rounds = timer::current_round(actx.start_instant, actx.round_time)
+                    .saturating_sub(enqueue_round);
+
+                write_and_ack(&actx, &req, queue_wait_rounds, |mode| {
+                    fmonster_write(&mut actx_call, &actx.group_view, &req.obj_info, req.data, mode)
+                });
             }
             Ok(None) => {
                 // no request, stay in Try state
@@ -534,6 +950,25 @@ pub fn monster_read<T: Copy + PartialEq + std::fmt::Debug>(
     view: &crate::GroupView,
     obj_info: &crate::ObjectInfo,
 ) -> Result<ReadReturn<T>, String> {
+    monster_read_versioned(actx, view, obj_info).map(|(result, _)| result)
+}
+
+/// An entry reads as all zeros while another node holds a write whose
+/// Replicate round is over: the node lost the object, e.g. a truncated file
+/// or a reset DAX device, rather than missing a write in progress
+fn is_zeroed<T>(ome: &ObjectMemoryEntry<T>, latest: &ObjectMemoryEntry<T>, current_round: u64) -> bool {
+    !ome.written && ome.wid.round_num == 0 && ome.version == 0
+        && latest.written && latest.version > 0
+        && current_round > latest.wid.round_num + 2
+}
+
+/// Same as `monster_read`, also returns the commit version of the returned
+/// value (0 if uninitialized)
+pub fn monster_read_versioned<T: Copy + PartialEq + std::fmt::Debug>(
+    actx: &AlgorithmCallContext,
+    view: &crate::GroupView,
+    obj_info: &crate::ObjectInfo,
+) -> Result<(ReadReturn<T>, u64), String> {
 
     if let Some(offset) = actx.read_offset {
         timer::wait_round_progress(offset, 
@@ -544,15 +979,30 @@ pub fn monster_read<T: Copy + PartialEq + std::fmt::Debug>(
 
     match mem_readends(obj_info.offset, &view.memory_nodes) {
         Ok(states) => {
+            let latest = states.iter().skip(1).fold(&states[0], |best, s| if s.wid > best.wid { s } else { best });
+
+            // leave out the nodes that lost the object
+            let round = timer::current_round(actx.start_instant, actx.round_time);
+            let ends = [view.memory_nodes.first(), view.memory_nodes.last()];
+            let valid: Vec<&ObjectMemoryEntry<T>> = states.iter().zip(ends)
+                .filter(|(s, node)| {
+                    if !is_zeroed(s, latest, round) {
+                        return true;
+                    }
+                    if let Some(node) = node {
+                        warn!("Memory node {} returned a zeroed entry for written object {}", node.id, obj_info.local_id());
+                        node.flag_zeroed();
+                    }
+                    false
+                })
+                .map(|(s, _)| s)
+                .collect();
             // check if all states are consistent (have the same wid (i.e. value))
-            // and get the latest wid with one pass
-            // println!("{:?}", states);
-            let (consistent, latest) = states.iter().skip(1).fold(
-                (true, &states[0]),
-                |(cons, best), s| (cons && s.wid == states[0].wid, if s.wid > best.wid { s } else { best }),
-            );
+            let consistent = valid.iter().all(|s| s.wid == latest.wid);
             // return based on consistency
-            let result = if consistent {
+            let result = if !latest.written {
+                ReadReturn::Uninitialized
+            } else if consistent {
                 ReadReturn::ReadSafe(latest.value)
             } else {
                 if states[0].wid.round_num > states[1].wid.round_num {
@@ -563,7 +1013,8 @@ pub fn monster_read<T: Copy + PartialEq + std::fmt::Debug>(
                 }
                 ReadReturn::ReadDirty(latest.value)
             };
-            Ok(result)
+            let version = if latest.written { latest.version } else { 0 };
+            Ok((result, version))
         },
         Err(MemoryError(memory_node_id)) => {
             Err(format!("Memory node {} failed during read", memory_node_id))
@@ -571,6 +1022,44 @@ pub fn monster_read<T: Copy + PartialEq + std::fmt::Debug>(
     }
 }
 
+/// MONSTER read bounded in staleness: the value is returned only if it was
+/// written at most `max_stale_rounds` rounds before the current one. If the
+/// end nodes only hold an older value, all nodes are read in case a newer
+/// write reached the others, e.g. a partial or in-flight write. Returns
+/// `TooStale` if no node holds a fresh enough value.
+pub fn monster_read_fresh<T: Copy + PartialEq + std::fmt::Debug>(
+    actx: &AlgorithmCallContext,
+    view: &crate::GroupView,
+    obj_info: &crate::ObjectInfo,
+    max_stale_rounds: u64,
+) -> Result<ReadReturn<T>, String> {
+    let is_fresh = |wid: Wid| {
+        timer::current_round(actx.start_instant, actx.round_time).saturating_sub(wid.round_num) <= max_stale_rounds
+    };
+
+    let states: [ObjectMemoryEntry<T>; 2] = mem_readends(obj_info.offset, &view.memory_nodes)
+        .map_err(|MemoryError(mnid)| format!("Memory node {} failed during read", mnid))?;
+    let latest = states.iter().max_by_key(|ome| ome.wid).ok_or("No memory nodes to read from")?;
+    if !latest.written {
+        return Ok(ReadReturn::Uninitialized);
+    }
+    if is_fresh(latest.wid) {
+        let consistent = states.iter().all(|s| s.wid == latest.wid);
+        return Ok(if consistent { ReadReturn::ReadSafe(latest.value) } else { ReadReturn::ReadDirty(latest.value) });
+    }
+
+    // retry on every node
+    let omes: Vec<ObjectMemoryEntry<T>> = mem_readall(obj_info.offset, &view.memory_nodes)
+        .map_err(|MemoryError(mnid)| format!("Memory node {} failed during read", mnid))?;
+    let newest = omes.iter().max_by_key(|ome| ome.wid).ok_or("No memory nodes to read from")?;
+    if newest.wid > latest.wid && is_fresh(newest.wid) {
+        debug!("Fresh read of object {} from the middle nodes", obj_info.local_id());
+        Ok(ReadReturn::ReadDirty(newest.value))
+    } else {
+        Ok(ReadReturn::TooStale)
+    }
+}
+
 /// Thread-reader:
 /// - pull read requests from queue (blocking) 
 /// - call monster_read and return result to client
@@ -584,23 +1073,26 @@ pub fn monster_read_thread<T: Copy + PartialEq + std::fmt::Debug>(
             break;
         }
 
-        match req_queue.recv() {
+        match req_queue.recv_timeout(super::STOP_POLL_INTERVAL) {
             Ok(req) => {
-                match monster_read(
-                    &actx.to_call_context("monster", MonsterStats::new()), 
-                    &actx.group_view, 
-                    &req.obj_info) {
-
-                    Ok(result) => {
-                        if let Err(e) = req.ack_tx.send(result) {
-                            error!("Failed to send read response: {}", e);
-                        }
-                    },
-                    Err(e) => {
+                let actx_call = actx.to_call_context("monster", MonsterStats::new());
+                for obj_info in req.objects() {
+                    let res = actx.group_view.read_allocated(obj_info, || match req.max_stale_rounds {
+                        Some(max_stale_rounds) => monster_read_fresh(&actx_call, &actx.group_view, obj_info, max_stale_rounds),
+                        None => monster_read(&actx_call, &actx.group_view, obj_info),
+                    });
+                    if let Err(e) = &res {
                         error!("read error: {}", e);
                     }
+                    // a failed read is answered too, the other objects of
+                    // the request are still read
+                    if let Err(e) = req.ack_tx.send(res) {
+                        error!("Failed to send read response: {}", e);
+                        break;
+                    }
                 }
             },
+            Err(kanal::ReceiveErrorTimeout::Timeout) => continue, // check the stop flag
             Err(e) => { // the repcxl instance keeps the original sender, so this should occur when the instance is dropped 
                 log::info!("[READ] Read request channel closed: {}", e);
                 break; 
@@ -611,3 +1103,49 @@ pub fn monster_read_thread<T: Copy + PartialEq + std::fmt::Debug>(
 }
 
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Retries of `num` contenders swapping `writes` times the same object,
+    /// following the MONSTER phases: an attempt in round t is checked in
+    /// t+1, the winner (smallest pid among the attempts of the round)
+    /// replicates in t+2 and tries its next write in t+3, the losers wait,
+    /// check in t+3, back off and retry.
+    fn conflict_storm_retries(num: usize, writes: u64, threshold: Option<f64>) -> u64 {
+        let mut backoffs: Vec<ConflictBackoff> = (0..num).map(|_| ConflictBackoff::new(threshold, 8)).collect();
+        let mut next_attempt = vec![0u64; num];
+        let mut left = vec![writes; num];
+        let mut retries = 0;
+        let mut round = 0;
+
+        while left.iter().any(|&l| l > 0) {
+            let attempts: Vec<usize> = (0..num)
+                .filter(|&pid| left[pid] > 0 && next_attempt[pid] == round)
+                .collect();
+            if let Some(&winner) = attempts.first() {
+                backoffs[winner].record(0, false);
+                left[winner] -= 1;
+                next_attempt[winner] = round + 3;
+                for &pid in &attempts[1..] {
+                    let skip = backoffs[pid].record(0, true);
+                    retries += 1;
+                    next_attempt[pid] = round + 4 + skip;
+                }
+            }
+            round += 1;
+        }
+        retries
+    }
+
+    #[test]
+    fn test_conflict_backoff_reduces_retries() {
+        let no_backoff = conflict_storm_retries(4, 100, None);
+        let backoff = conflict_storm_retries(4, 100, Some(0.2));
+        assert!(
+            backoff < no_backoff,
+            "Backoff should reduce retries: {} with backoff, {} without",
+            backoff, no_backoff
+        );
+    }
+}
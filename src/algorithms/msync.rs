@@ -0,0 +1,56 @@
+use std::sync::atomic::Ordering;
+use log::{error, info};
+
+use super::AlgorithmThreadContext;
+use crate::shmem::object_index::ObjectInfo;
+use crate::timer;
+
+/// Write the shared state and the allocated objects of every memory node back
+/// to its backing file. Clean pages are skipped by the kernel, so in practice
+/// only the ranges written since the previous sync are flushed.
+///
+/// Returns the number of nodes that failed to sync
+pub(crate) fn sync_nodes(view: &crate::GroupView) -> usize {
+    let Some(master) = view.get_master_node() else {
+        return 0;
+    };
+    let objects: Vec<ObjectInfo> = master.read_state().object_index.objects().collect();
+
+    let mut failed = 0;
+    for node in &view.memory_nodes {
+        let result = node.sync_state().and_then(|()| {
+            objects.iter().try_for_each(|oi| node.sync_range(oi.offset, oi.size))
+        });
+        if let Err(e) = result {
+            error!("[MSYNC] {}", e);
+            failed += 1;
+        }
+    }
+    failed
+}
+
+/// Background durability for file-backed memory nodes: every `interval`
+/// rounds sync the nodes to their backing files, and once more when stopped.
+pub fn msync_thread(actx: AlgorithmThreadContext, interval: u64) {
+    let mut syncs = 0;
+
+    loop {
+        if actx.stop_flag.load(Ordering::Relaxed) {
+            sync_nodes(&actx.group_view);
+            info!("[MSYNC] Stop flag is set, exiting after {} syncs", syncs + 1);
+            break;
+        }
+
+        let (round_num, _) = timer::wait_next_round(
+            actx.start_instant,
+            actx.round_time,
+            timer::ROUND_SLEEP_RATIO);
+
+        if round_num % interval != 0 {
+            continue;
+        }
+
+        sync_nodes(&actx.group_view);
+        syncs += 1;
+    }
+}
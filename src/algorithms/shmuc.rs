@@ -89,9 +89,6 @@ pub fn shmuc<T: Copy + PartialEq + std::fmt::Debug>(
     let mut round_num = 0;
 
     let mut shmuc_sm = ShmucStateMachine::new();
-    // get shared write conflict referee
-    let mstate = view.get_master_node().unwrap().get_state();
-    let wcc = mstate.get_wcc();
     let mut pending_write_req = None;
 
     // wait to start
@@ -112,7 +109,7 @@ pub fn shmuc<T: Copy + PartialEq + std::fmt::Debug>(
                 match req_queue_rx.try_recv() {
                     Ok((offset, data, ack_tx)) => {
                         // WCC check. We use the offset as request ID
-                        wcc.push_request(offset, view.self_id);
+                        view.update_states(|state| state.get_wcc().push_request(offset, view.self_id));
                         // write data to all memory nodes
                         for node in &view.memory_nodes {
                             let addr = node.addr_at(offset) as *mut T;
@@ -158,7 +155,7 @@ pub fn shmuc<T: Copy + PartialEq + std::fmt::Debug>(
 
                     // WCC check
                     // @TODO: time check the operation!
-                    if let Some(conflicting_pids) = wcc.check_conflicts(*offset) {
+                    if let Some(conflicting_pids) = view.quorum_read(|state| state.get_wcc().check_conflicts(*offset)) {
                         // handle write conflict
                         info!("conflict detected with pids: {:?}", conflicting_pids);
                         // min process ID wins
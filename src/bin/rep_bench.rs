@@ -1,11 +1,10 @@
 // evaluate raw replication performance
 use clap::{value_parser, Arg};
 use log::{debug, error};
-use rand::Rng;
 use rep_cxl::{RepCXL, utils};
 use simple_logger;
 use std::sync::Arc;
-use std::time::{Duration, Instant};
+use std::time::Duration;
 use rep_cxl::utils::arg_parser::ArgParser;
 
 const OBJ_VAL: u64 = 124; // use this value for all objects. Change size or type
@@ -14,6 +13,15 @@ const OBJ_VAL: u64 = 124; // use this value for all objects. Change size or type
 const DEFAULT_ATTEMPTS: &str = "100000";
 const DEFAULT_CLIENTS: &str = "1";
 const DEFAULT_OBJECTS: &str = "100";
+const DEFAULT_MODE: &str = MODE_THROUGHPUT;
+const DEFAULT_SOAK_CSV: &str = "soak.csv";
+
+// BENCHMARK MODES
+const MODE_THROUGHPUT: &str = "throughput";
+const MODE_REPLICATION_FLOOR: &str = "replication-floor";
+
+// how long a replica waits for the coordinator to create an object
+const OBJECT_WAIT_TIMEOUT: Duration = Duration::from_secs(60);
 
 pub fn percentile(latencies: &Vec<u128>, p: f32) -> u128 {
     if latencies.is_empty() {
@@ -50,6 +58,26 @@ fn main() {
             .help("Number of objects to create")
             .default_value(DEFAULT_OBJECTS)
             .value_parser(value_parser!(usize)),
+        Arg::new("mode")
+            .short('m')
+            .long("mode")
+            .help("'throughput': concurrent clients. 'replication-floor': single client with one write at a time, measures the raw replication latency")
+            .default_value(DEFAULT_MODE)
+            .value_parser([MODE_THROUGHPUT, MODE_REPLICATION_FLOOR]),
+        Arg::new("duration")
+            .short('d')
+            .long("duration")
+            .help("Soak test: clients write continuously for this duration (e.g. '60s') instead of --attempts times, throughput mode only")
+            .value_parser(rep_cxl::config::parse_duration_ns),
+        Arg::new("csv")
+            .long("csv")
+            .help("Output file of the per-second soak time series")
+            .default_value(DEFAULT_SOAK_CSV)
+            .value_parser(value_parser!(String)),
+        Arg::new("rate")
+            .long("rate")
+            .help("Offered load: maximum writes per second of the process, overrides write_rate_limit of the config")
+            .value_parser(value_parser!(f64)),
     ]);
 
     let matches = ap.parse();
@@ -57,8 +85,14 @@ fn main() {
     let attempts = matches.get_one::<u32>("attempts").unwrap().clone();
     let clients = matches.get_one::<u32>("clients").unwrap().clone();
     let num_of_objects = matches.get_one::<usize>("objects").unwrap().clone();
+    let mode = matches.get_one::<String>("mode").unwrap().clone();
+    let duration = matches.get_one::<u64>("duration").map(|&ns| Duration::from_nanos(ns));
+    let csv_path = matches.get_one::<String>("csv").unwrap().clone();
 
-    let config = ap.config;
+    let mut config = ap.config;
+    if let Some(&rate) = matches.get_one::<f64>("rate") {
+        config.write_rate_limit = Some(rate);
+    }
 
     // start repCXL process
     debug!("Starting RepCXL instance with id {}", config.id);
@@ -71,7 +105,7 @@ fn main() {
     // only the coordinator manages the state
     if rcxl.is_coordinator() {
         debug!("Starting as coordinator with id {}", rcxl.config.id);
-        rcxl.init_state();
+        rcxl.init_state().expect("failed to initialize state");
 
         for i in 0..num_of_objects {
             debug!("Creating object {}", i);
@@ -84,91 +118,62 @@ fn main() {
         debug!("Starting as replica with id {}", rcxl.config.id);
 
         for i in 0..num_of_objects {
-            // try until the coordinator creates the object
-            let obj = loop {
-                match rcxl.get_object(i) {
-                    Some(obj) => break obj,
-                    None => std::thread::sleep(Duration::from_millis(100)),
-                }
-            };
+            // wait until the coordinator creates the object
+            let obj = rcxl.get_object_blocking(i, OBJECT_WAIT_TIMEOUT).unwrap_or_else(|e| {
+                error!("{}", e);
+                std::process::exit(1);
+            });
             objects.push(obj);
         }
     }
 
-    rcxl.sync_start();
+    rcxl.sync_start().unwrap_or_else(|e| {
+        error!("{}", e);
+        std::process::exit(1);
+    });
 
     // wait for all processes to start up before starting benchmark
     std::thread::sleep(Duration::from_nanos(rcxl.config.startup_delay));
-    
-    // start benchmark
-    let objects = Arc::new(objects);
-    let mut client_handles = Vec::new();
-
-    // init metrics vectors
-    let (lats_tx, lats_rx) = std::sync::mpsc::channel();
-    let (tput_tx, tput_rx) = std::sync::mpsc::channel();
-
-    for c in 0..clients {
-        let lats_tx = lats_tx.clone();
-        let tput_tx = tput_tx.clone();
-
-        let objects = Arc::clone(&objects);
-        let handle = std::thread::spawn(move || {
-            debug!("Starting client thread {}", c);
-
-            let mut lats = Vec::new();
-            let mut rng = rand::rng();
-            
-            
-            let total_start = Instant::now();
-            for _ in 0..attempts {
-                let id = rng.random_range(0..num_of_objects);
-                let obj = objects.get(id).unwrap();
-
-                let start = Instant::now();
-                // write is blocking
-                match obj.write(OBJ_VAL) {
-                    Ok(()) => (),
-                    Err(e) => error!("{e}"),
-                }
-                lats.push(start.elapsed());
-            }
-            let total_elapsed_s = total_start.elapsed().as_secs_f64();
-            lats_tx.send(lats).unwrap();
-            tput_tx.send(attempts as f64 / total_elapsed_s).unwrap();
-        }); // end of thread body
-
-        client_handles.push(handle);
-    }
 
-    // collect and print stats (probably dumb way of doing it)
-    let mut lats_ns = Vec::new();
-    let mut total_tputs = Vec::new();
+    match mode.as_str() {
+        MODE_REPLICATION_FLOOR => {
+            // single client, one outstanding write at a time
+            let lats = utils::bench::replication_floor(&mut rcxl, &objects, attempts, OBJ_VAL);
 
-    // drop extra senders to make the recv loop below exit later
-    drop(lats_tx);
-    drop(tput_tx);
+            rcxl.stop();
 
-    loop {
-        match (lats_rx.recv(), tput_rx.recv()) {
-            (Ok(mut lvec), Ok(t)) => {
-                lats_ns.append(&mut lvec);
-                total_tputs.push(t);
+            println!("Replication floor ({} memory nodes)", rcxl.config.mem_nodes.len());
+            println!("{}", utils::bench::run_summary(&rcxl.config));
+            println!("{}", utils::bench::run_params(&rcxl.config));
+            utils::print_latency_stats(&lats);
+        }
+        _ if duration.is_some() => {
+            let duration = duration.unwrap();
+            let objects = Arc::new(objects);
+            let (buckets, tput) = utils::bench::soak_clients(objects, clients, duration, OBJ_VAL);
+
+            rcxl.stop();
+
+            println!("Sustained throughput: {:.2} ops/sec over {:?}", tput, duration);
+            println!("{}", utils::bench::run_summary(&rcxl.config));
+            println!("{}", utils::bench::run_params(&rcxl.config));
+            if let Err(e) = std::fs::write(&csv_path, utils::bench::soak_csv(&buckets)) {
+                error!("Failed to write {}: {}", csv_path, e);
+            } else {
+                println!("Time series written to {}", csv_path);
             }
-            _ => break,
         }
-    }
-
-    for handle in client_handles {
-        handle.join().unwrap();
-    }
+        _ => {
+            // start benchmark
+            let objects = Arc::new(objects);
+            let (lats_ns, tput) = utils::bench::write_clients(objects, clients, attempts, OBJ_VAL);
 
-    rcxl.stop();
+            rcxl.stop();
 
-    println!(
-        "Throughput: {:.2} ops/sec",
-        total_tputs.iter().sum::<f64>() / total_tputs.len() as f64
-    );
-    // let lats_ns: Vec<u128> = lats.into_iter().map(|d| d.as_nanos()).collect();
-    utils::print_latency_stats(&lats_ns);
+            println!("Throughput: {:.2} ops/sec", tput);
+            println!("{}", utils::bench::run_summary(&rcxl.config));
+            println!("{}", utils::bench::run_params(&rcxl.config));
+            utils::print_latency_stats(&lats_ns);
+        }
+    }
 }
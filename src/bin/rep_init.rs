@@ -0,0 +1,49 @@
+// prepare the memory nodes of a config before any process starts, e.g. as a
+// separate init job: provision the node files, zero their object regions and
+// write a fresh shared state
+use log::{error, info};
+use rep_cxl::RepCXL;
+use rep_cxl::utils::arg_parser::ArgParser;
+
+fn main() {
+    simple_logger::init().unwrap();
+
+    let mut ap = ArgParser::new(
+        "rep_init",
+        "Provision the memory nodes of a config and write a fresh shared state, then exit",
+    );
+    ap.parse();
+    let mut config = ap.config;
+
+    if let Err(e) = RepCXL::<u64>::provision(&config) {
+        error!("Failed to provision the memory nodes: {}", e);
+        std::process::exit(1);
+    }
+
+    // the state is initialized on behalf of the coordinator, which then
+    // starts without init_state
+    let Some(&coordinator) = config.processes.iter().min() else {
+        error!("No processes in config");
+        std::process::exit(1);
+    };
+    config.id = coordinator as i32;
+    let mut rcxl = RepCXL::<u64>::new(config);
+
+    if let Err(e) = rcxl.init_state() {
+        error!("Failed to initialize the shared state: {}", e);
+        std::process::exit(1);
+    }
+    if let Err(e) = rcxl.zero_object_regions() {
+        error!("Failed to zero the memory nodes: {}", e);
+        std::process::exit(1);
+    }
+
+    for report in rcxl.states_report() {
+        info!(
+            "Memory node {}: state seq {}, {} bytes free for objects",
+            report.node_id,
+            report.seq,
+            report.memory.total_size - report.memory.allocated_size
+        );
+    }
+}
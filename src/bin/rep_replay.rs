@@ -0,0 +1,60 @@
+// reconstruct the history of an object from the MONSTER state logs of
+// several processes
+use clap::{value_parser, Arg, ArgAction, Command};
+use rep_cxl::utils::ms_logger::{self, MonsterStateLogger};
+
+fn main() {
+    let matches = Command::new("rep_replay")
+        .about("Print the timeline of writes, conflicts and retries of an object from repCXL state logs")
+        .arg(
+            Arg::new("log")
+                .short('l')
+                .long("log")
+                .help("State log of a process as <process_id>=<path>. Repeat for every process")
+                .required(true)
+                .action(ArgAction::Append),
+        )
+        .arg(
+            Arg::new("object")
+                .short('o')
+                .long("object")
+                .help("Object id to replay")
+                .required(true)
+                .value_parser(value_parser!(usize)),
+        )
+        .get_matches();
+
+    let mut logs = Vec::new();
+    for spec in matches.get_many::<String>("log").unwrap() {
+        match spec.split_once('=').map(|(pid, path)| (pid.parse::<usize>(), path)) {
+            Some((Ok(pid), path)) if std::path::Path::new(path).is_file() => logs.push((pid, path)),
+            Some((Ok(_), path)) => {
+                eprintln!("Log file '{}' not found", path);
+                std::process::exit(1);
+            }
+            _ => {
+                eprintln!("Invalid log '{}', expected <process_id>=<path>", spec);
+                std::process::exit(1);
+            }
+        }
+    }
+    let object_id = *matches.get_one::<usize>("object").unwrap();
+
+    let merged = MonsterStateLogger::merge_logs(&logs);
+    let timeline = ms_logger::object_timeline(&merged, object_id);
+    if timeline.is_empty() {
+        println!("No entries for object {}", object_id);
+        return;
+    }
+
+    println!("Timeline of object {}:", object_id);
+    for e in timeline {
+        println!(
+            "  round {:>8}  process {:>3}  {:<18} {}",
+            e.round_num,
+            e.process_id,
+            e.state,
+            ms_logger::describe_state(&e.state)
+        );
+    }
+}
@@ -1,12 +1,28 @@
 use rep_cxl::RepCXL;
+use rep_cxl::request::ReadReturn;
 use rep_cxl::utils::arg_parser::ArgParser;
 use clap::Arg;
 
-// const ID: i32 = 1;
-// const MEMORY_SIZE: usize = 1024 * 1024; // 1 MiB
-// const CHUNK_SIZE: usize = 64; // 64 bytes
-// const SHMEM_PATH: &str = "/sys/bus/pci/devices/0000:00:03.0/resource2";
-// const ROUND_INTERVAL_NS: u64 = 1_000_000; // 1 ms
+fn check_read(rcxl: &RepCXL<[u8; 14]>, obj: &rep_cxl::RepCXLObject<[u8; 14]>, msg: [u8; 14], role: &str) {
+    match rcxl.read_object(obj).expect("failed to read from object") {
+        ReadReturn::ReadSafe(buf) => {
+            assert_eq!(buf, msg, "{} read incorrect data", role);
+            println!("{} successfully read: {}", role, String::from_utf8_lossy(&buf));
+        },
+        ReadReturn::ReadDirty(_) => {
+            println!("Read dirty detected, something went wrong");
+        },
+        ReadReturn::Uninitialized => {
+            println!("Object never written, something went wrong");
+        },
+        ReadReturn::TooStale => {
+            println!("Read too stale, something went wrong");
+        },
+        ReadReturn::ObjectRemoved => {
+            println!("Object removed, something went wrong");
+        },
+    }
+}
 
 fn main() {
 
@@ -16,7 +32,7 @@ fn main() {
         .init()
         .unwrap();
 
-    let mut ap = ArgParser::new("shmem_obj_test_leader", 
+    let mut ap = ArgParser::new("shmem_obj_test", 
     "test creating and removing objects with shmem backend");
 
     ap.add_args(&[
@@ -29,7 +45,6 @@ fn main() {
 
     let matches = ap.parse();
     let msg = *b"Hello, RepCXL!";
-    let _msg = 10; // convert to fixed-size array for simplicity
 
     ap.config.algorithm = "async_best_effort".to_string();
 
@@ -38,34 +53,23 @@ fn main() {
     match matches.get_one::<String>("role").map(|s| s.as_str()) {
         Some("c") | Some("coordinator") => {
 
-            rcxl.init_state(); // coordinator inits state
+            rcxl.init_state().expect("failed to initialize state"); // coordinator inits state
 
             rcxl.start(); // start protocol threads
 
             std::thread::sleep(std::time::Duration::from_millis(10)); // wait for protocol to start
 
             let obj100 = rcxl.new_object(100).expect("failed to create object");
-            obj100.write(msg).expect("failed to write to object");
-            // obj100.write(*b"repcxl nocach1").expect("failed to write to object");
-            // obj100.write(*b"repcxl nocach2").expect("failed to write to object");
-            // obj100.write(*b"repcxl nocach3").expect("failed to write to object");
+            // follows the pipeline setting, unlike obj100.write which needs the worker
+            rcxl.write_object(&obj100, msg).expect("failed to write to object");
+            check_read(&rcxl, &obj100, msg, "Coordinator");
         },
         Some("r") | Some("replica") => {
             rcxl.start(); // start protocol threads
             std::thread::sleep(std::time::Duration::from_millis(10)); // wait for protocol to start
 
             let obj100 = rcxl.get_object(100).expect("failed to get object");
-            match rcxl.read_object(&obj100).expect("failed to read from object") {
-                rep_cxl::request::ReadReturn::ReadSafe(buf) => {
-                    assert_eq!(buf, msg, "replica read incorrect data");
-                    println!("Replica successfully read: {}", String::from_utf8_lossy(&buf));
-                },
-                rep_cxl::request::ReadReturn::ReadDirty(_) => {
-                    println!("Read dirty detected, something went wrong");
-
-                },
-            }
-                        
+            check_read(&rcxl, &obj100, msg, "Replica");
         },
         _ => println!("Usage: shmem_obj_test <role>\nrole: 'c'/'coordinator' or 'r'/'replica'"),
     }
@@ -4,20 +4,15 @@
 use core::panic;
 use rep_cxl::utils::ycsb::load_ycsb_workload;
 use rep_cxl::utils::arg_parser::ArgParser;
-use rep_cxl::{RepCXL};
+use rep_cxl::{Blob, RepCXL};
 use rep_cxl::request::ReadReturn;
 use rep_cxl::utils;
 use clap::{Arg, value_parser};
 use log::{debug, info, error};
 use std::time::Duration;
 
-/// Convert Vec<u8> to fixed-size array, truncating or padding with zeros as needed
-fn vec_to_array<const N: usize>(vec: &Vec<u8>) -> [u8; N] {
-    let mut arr = [0u8; N];
-    let copy_len = vec.len().min(N);
-    arr[..copy_len].copy_from_slice(&vec[..copy_len]);
-    arr
-}
+// how long a replica waits for the coordinator to create an object
+const OBJECT_WAIT_TIMEOUT: Duration = Duration::from_secs(60);
 
 fn main() {
 
@@ -60,25 +55,24 @@ fn main() {
     }
 
     // Initialize RepCXL client and local index
-    let mut rcxl = RepCXL::<[u8; 64]>::new(ap.config);
+    let mut rcxl = RepCXL::<Blob<64>>::new(ap.config);
     let mut index = std::collections::HashMap::new();
 
     // LOAD PHASE: populate index and memory nodes
     if rcxl.is_coordinator() {
         info!("This process is the coordinator. Executing YCSB load phase...");
-        rcxl.init_state(); // only coordinator initializes the state
+        rcxl.init_state().expect("failed to initialize state"); // only coordinator initializes the state
 
-        let mut oid = 0;
         for op in workload.load_ops {
             match op.op_type {
                 rep_cxl::utils::ycsb::OpType::Insert => {
                     
                     // truncate/pad to fixed-size
-                    let value: [u8; 64] = vec_to_array(&op.fields[0].1);
+                    let value = Blob::<64>::from_slice(&op.fields[0].1);
 
-                    if let Some(obj) = rcxl.new_object_with_val(oid, value) {
+                    // ids are allocated in order from 0, like the replicas expect
+                    if let Some((_, obj)) = rcxl.new_object_auto(value) {
                         index.insert(op.key, obj);
-                        oid += 1;
                     }
                     else {
                         panic!("Failed to create object for key {}", op.key);
@@ -95,14 +89,13 @@ fn main() {
         for op in workload.load_ops {
             match op.op_type {
                 rep_cxl::utils::ycsb::OpType::Insert => {
-                    if let Some(obj) = rcxl.get_object(oid) {
-                        index.insert(op.key, obj);
-                        oid += 1;
-                    }
-                    else {
-                        // wait for the coordinator to create the object
-                        std::thread::sleep(std::time::Duration::from_millis(10));
-                    }
+                    // wait for the coordinator to create the object
+                    let obj = rcxl.get_object_blocking(oid, OBJECT_WAIT_TIMEOUT).unwrap_or_else(|e| {
+                        error!("{}", e);
+                        std::process::exit(1);
+                    });
+                    index.insert(op.key, obj);
+                    oid += 1;
                 },
                 _ => panic!("Unexpected operation type in load phase: {:?}", op.op_type),
             }
@@ -110,7 +103,10 @@ fn main() {
     }
     
     // start repcxl
-    rcxl.sync_start();
+    rcxl.sync_start().unwrap_or_else(|e| {
+        error!("{}", e);
+        std::process::exit(1);
+    });
 
     // wait for all processes to start up before starting benchmark
     std::thread::sleep(Duration::from_nanos(rcxl.config.startup_delay));
@@ -164,7 +160,12 @@ fn main() {
                     Ok(rr) => {
                         match rr {
                             ReadReturn::ReadDirty(_) => dirty_reads += 1,
-                            ReadReturn::ReadSafe(_) => safe_reads += 1,
+                            ReadReturn::ReadSafe(_) | ReadReturn::Uninitialized => safe_reads += 1,
+                            ReadReturn::TooStale => unreachable!("read without freshness bound"),
+                            ReadReturn::ObjectRemoved => {
+                                error!("object {} was removed", op.key);
+                                read_errors += 1;
+                            }
                         }
                         read_latencies.push(start.elapsed());
                     },
@@ -175,7 +176,7 @@ fn main() {
                 }
             },
             rep_cxl::utils::ycsb::OpType::Update => {
-                let value: [u8; 64] = vec_to_array(&op.fields[0].1);
+                let value = Blob::<64>::from_slice(&op.fields[0].1);
 
                 if let Some(obj) = index.get(&op.key) {
                     let start = std::time::Instant::now();
@@ -204,6 +205,8 @@ fn main() {
     let tput = workload.run_ops.len() as f64 / total_elapsed.as_secs_f64();
     
     println!("YCSB run phase complete.");
+    println!("{}", utils::bench::run_summary(&rcxl.config));
+    println!("  {}", utils::bench::run_params(&rcxl.config));
     println!("  Total operations: {}", workload.run_ops.len());
     println!("  Total time: {:.2}s", total_elapsed.as_secs_f64());
     println!("  Throughput: {:.2} ops/sec", tput);
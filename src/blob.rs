@@ -0,0 +1,55 @@
+/// Fixed-size byte value, e.g. `RepCXL<Blob<128>>`, for payloads of variable
+/// length up to `N` bytes. Shorter payloads are zero-padded and longer ones
+/// truncated, so the layout in memory stays fixed.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Blob<const N: usize>([u8; N]);
+
+impl<const N: usize> Blob<N> {
+    /// Copy up to `N` bytes of `data`, padding with zeros
+    pub fn from_slice(data: &[u8]) -> Self {
+        let mut bytes = [0u8; N];
+        let len = data.len().min(N);
+        bytes[..len].copy_from_slice(&data[..len]);
+        Blob(bytes)
+    }
+
+    /// All `N` bytes, padding included
+    pub fn as_slice(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl<const N: usize> Default for Blob<N> {
+    fn default() -> Self {
+        Blob([0; N])
+    }
+}
+
+/// Value types that can be copied from and to their bytes, e.g. to export
+/// objects with `RepCXL::export_kv`.
+///
+/// # Safety
+///
+/// The type must have no padding bytes, and every bit pattern of its size
+/// must be a valid value (not the case of e.g. `bool` or `char`).
+pub unsafe trait Pod: Copy + 'static {}
+
+macro_rules! impl_pod {
+    ($($t:ty),*) => { $(unsafe impl Pod for $t {})* };
+}
+impl_pod!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize, f32, f64);
+unsafe impl<T: Pod, const N: usize> Pod for [T; N] {}
+unsafe impl<const N: usize> Pod for Blob<N> {}
+
+/// Bytes of a value
+pub(crate) fn pod_bytes<T: Pod>(value: &T) -> &[u8] {
+    // SAFETY: Pod types have no padding, every byte is initialized
+    unsafe { std::slice::from_raw_parts(value as *const T as *const u8, std::mem::size_of::<T>()) }
+}
+
+/// Value of the given bytes, None if their length is not the size of T
+pub(crate) fn pod_from_bytes<T: Pod>(bytes: &[u8]) -> Option<T> {
+    // SAFETY: the length is checked, any bit pattern is a valid Pod value
+    (bytes.len() == std::mem::size_of::<T>()).then(|| unsafe { std::ptr::read_unaligned(bytes.as_ptr() as *const T) })
+}
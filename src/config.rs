@@ -5,10 +5,11 @@
 
 use std::fs;
 use crate::shmem;
-use serde::{Deserialize, Deserializer};
+use serde::{Deserialize, Deserializer, Serialize};
 
 // default values for config parameters
 const DEFAULT_MEM_SIZE: usize = 1024 * 1024; // 1 MiB
+const DEFAULT_MAX_MEM_SIZE: usize = 1024 * 1024 * 1024; // 1 GiB
 const DEFAULT_CHUNK_SIZE: usize = 64; // 64 bytes
 const DEFAULT_STARTUP_DELAY: u64 = 1000000000; // 1s
 const DEFAULT_ROUND_TIME_NS: u64 = 100000; //1ms
@@ -18,6 +19,32 @@ const DEFAULT_PIPELINE: bool = false;
 const DEFAULT_READ_RETRIES: usize = 0;
 const DEFAULT_CORE_AFFINITY: Option<usize> = None;
 const DEFAULT_READ_OFFSET: Option<f64> = None;
+const DEFAULT_STATE_CHECKSUM: bool = false;
+const DEFAULT_ANTI_ENTROPY_INTERVAL: Option<u64> = None;
+const DEFAULT_ANTI_ENTROPY_BATCH: usize = 16;
+const DEFAULT_AUDIT_INTERVAL: Option<u64> = None; // disabled
+const DEFAULT_AUDIT_SAMPLE: usize = 16;
+const DEFAULT_NAMESPACE: u32 = 0;
+const DEFAULT_MIN_READY: Option<usize> = None; // all processes
+const DEFAULT_READY_TIMEOUT: u64 = 5_000_000_000; // 5s
+const DEFAULT_SYNC_TIMEOUT: u64 = 60_000_000_000; // 60s
+const DEFAULT_CONFLICT_BACKOFF_THRESHOLD: Option<f64> = None; // disabled
+const DEFAULT_CONFLICT_BACKOFF_MAX: u64 = 8; // rounds
+const DEFAULT_FORCE_INIT: bool = false;
+const DEFAULT_MSYNC_INTERVAL_ROUNDS: Option<u64> = None; // disabled
+const DEFAULT_TWO_PHASE_COMMIT: bool = false;
+const DEFAULT_CONFLICT_ORDER: &str = "round";
+const DEFAULT_SINGLE_WRITER: bool = false;
+const DEFAULT_WRITE_BATCH_SIZE: usize = 1;
+const DEFAULT_REPLICATION_THREADS: usize = 1; // sequential
+const DEFAULT_WRITE_RATE_LIMIT: Option<f64> = None; // unlimited
+const DEFAULT_WORKER_RESTARTS: u32 = 0;
+const DEFAULT_MAX_INFLIGHT_WRITES: usize = 1; // per-object order
+const DEFAULT_ACK_POLICY: &str = "all";
+const DEFAULT_STARTUP_JITTER: u64 = 0; // disabled
+const DEFAULT_LOCK_MEMORY: bool = false;
+const DEFAULT_DEFERRED_NODES: bool = false;
+const DEFAULT_FAULT_MODE: &str = "both";
 
 
 
@@ -73,16 +100,79 @@ where
     }
 }
 
+/// Parse a duration into nanoseconds. Accepts raw nanoseconds ("1000000")
+/// or an integer with a unit suffix: "ns", "us", "ms" or "s" (e.g. "1ms").
+pub fn parse_duration_ns(s: &str) -> Result<u64, String> {
+    let s = s.trim();
+    let (num, multiplier) = if let Some(n) = s.strip_suffix("ns") {
+        (n, 1)
+    } else if let Some(n) = s.strip_suffix("us") {
+        (n, 1_000)
+    } else if let Some(n) = s.strip_suffix("ms") {
+        (n, 1_000_000)
+    } else if let Some(n) = s.strip_suffix('s') {
+        (n, 1_000_000_000)
+    } else {
+        (s, 1)
+    };
+
+    let n: u64 = num.trim().parse()
+        .map_err(|_| format!("Invalid duration '{}', expected e.g. 1000000, 500us, 1ms or 2s", s))?;
+    n.checked_mul(multiplier)
+        .ok_or_else(|| format!("Duration '{}' overflows u64 nanoseconds", s))
+}
+
+/// Parse a duration field which can be a number of nanoseconds or a string
+/// with a unit suffix
+fn parse_duration<'de, D>(deserializer: D) -> Result<u64, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    use serde::de::Error;
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum DurationSpec {
+        Nanos(u64),
+        Suffixed(String),
+    }
+
+    match DurationSpec::deserialize(deserializer)? {
+        DurationSpec::Nanos(n) => Ok(n),
+        DurationSpec::Suffixed(s) => parse_duration_ns(&s).map_err(Error::custom),
+    }
+}
+
+fn default_fault_mode() -> String {
+    DEFAULT_FAULT_MODE.to_string()
+}
+
+/// Scheduled failure of a memory node for chaos testing, a `[[fault]]` table
+/// of the config file. Reads and/or writes to the node fail from round
+/// `start_round` (counted from the start of the rounds) for `fail_for_rounds`
+/// rounds. `node` is the id of the node: its index in `mem_nodes`, then the
+/// nodes added after `RepCXL::new` in order
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct FaultSpec {
+    pub node: usize,
+    pub start_round: u64,
+    pub fail_for_rounds: u64,
+    #[serde(default = "default_fault_mode")]
+    pub mode: String, // "read", "write" or "both"
+}
+
 /// Raw deserialized representation of the TOML config file.
 /// All fields are optional during deserialization  missing fields keep their 
 /// Can be checked with validate() 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
 #[serde(default)]
 pub struct RepCXLConfig {
     pub mem_nodes: Vec<String>,
     pub mem_size: usize,
+    pub max_mem_size: usize,
     pub chunk_size: usize,
     pub startup_delay: u64,
+    #[serde(deserialize_with = "parse_duration")]
     pub round_time: u64,
     pub id: i32,
     #[serde(deserialize_with = "parse_processes")]
@@ -92,6 +182,36 @@ pub struct RepCXLConfig {
     pub read_retries: usize,
     pub read_offset: Option<f64>,
     pub core_affinity: Option<usize>,
+    pub state_checksum: bool,
+    pub anti_entropy_interval: Option<u64>,
+    pub anti_entropy_batch: usize,
+    pub audit_interval: Option<u64>,
+    pub audit_sample: usize,
+    pub namespace: u32,
+    pub min_ready: Option<usize>,
+    pub ready_timeout: u64,
+    pub sync_timeout: u64,
+    pub conflict_backoff_threshold: Option<f64>,
+    pub conflict_backoff_max: u64,
+    pub force_init: bool,
+    pub msync_interval_rounds: Option<u64>,
+    pub two_phase_commit: bool,
+    pub conflict_order: String,
+    pub single_writer: bool,
+    pub mem_node_latency: Vec<u64>,
+    pub write_batch_size: usize,
+    pub replication_threads: usize,
+    pub write_rate_limit: Option<f64>,
+    pub worker_restarts: u32,
+    pub max_inflight_writes: usize,
+    pub ack_policy: String,
+    #[serde(deserialize_with = "parse_duration")]
+    pub startup_jitter: u64,
+    pub lock_memory: bool,
+    // memory nodes are added after `RepCXL::new`, e.g. heap nodes with
+    // `add_memory_node_heap`, and `mem_nodes` may be empty
+    pub deferred_nodes: bool,
+    pub fault: Vec<FaultSpec>,
 }
 
 impl Default for RepCXLConfig {
@@ -99,6 +219,7 @@ impl Default for RepCXLConfig {
         Self {
             mem_nodes: Vec::new(),
             mem_size: DEFAULT_MEM_SIZE,
+            max_mem_size: DEFAULT_MAX_MEM_SIZE,
             chunk_size: DEFAULT_CHUNK_SIZE,
             startup_delay: DEFAULT_STARTUP_DELAY,
             round_time: DEFAULT_ROUND_TIME_NS,
@@ -109,6 +230,33 @@ impl Default for RepCXLConfig {
             read_retries: DEFAULT_READ_RETRIES,
             read_offset: DEFAULT_READ_OFFSET,
             core_affinity: DEFAULT_CORE_AFFINITY,
+            state_checksum: DEFAULT_STATE_CHECKSUM,
+            anti_entropy_interval: DEFAULT_ANTI_ENTROPY_INTERVAL,
+            anti_entropy_batch: DEFAULT_ANTI_ENTROPY_BATCH,
+            audit_interval: DEFAULT_AUDIT_INTERVAL,
+            audit_sample: DEFAULT_AUDIT_SAMPLE,
+            namespace: DEFAULT_NAMESPACE,
+            min_ready: DEFAULT_MIN_READY,
+            ready_timeout: DEFAULT_READY_TIMEOUT,
+            sync_timeout: DEFAULT_SYNC_TIMEOUT,
+            conflict_backoff_threshold: DEFAULT_CONFLICT_BACKOFF_THRESHOLD,
+            conflict_backoff_max: DEFAULT_CONFLICT_BACKOFF_MAX,
+            force_init: DEFAULT_FORCE_INIT,
+            msync_interval_rounds: DEFAULT_MSYNC_INTERVAL_ROUNDS,
+            two_phase_commit: DEFAULT_TWO_PHASE_COMMIT,
+            conflict_order: DEFAULT_CONFLICT_ORDER.to_string(),
+            single_writer: DEFAULT_SINGLE_WRITER,
+            mem_node_latency: Vec::new(),
+            write_batch_size: DEFAULT_WRITE_BATCH_SIZE,
+            replication_threads: DEFAULT_REPLICATION_THREADS,
+            write_rate_limit: DEFAULT_WRITE_RATE_LIMIT,
+            worker_restarts: DEFAULT_WORKER_RESTARTS,
+            max_inflight_writes: DEFAULT_MAX_INFLIGHT_WRITES,
+            ack_policy: DEFAULT_ACK_POLICY.to_string(),
+            startup_jitter: DEFAULT_STARTUP_JITTER,
+            lock_memory: DEFAULT_LOCK_MEMORY,
+            deferred_nodes: DEFAULT_DEFERRED_NODES,
+            fault: Vec::new(),
         }
     }
 }
@@ -126,6 +274,13 @@ impl RepCXLConfig {
             .map_err(|e| format!("Failed to parse config file '{}': {}", path, e))
     }
 
+    /// Serialize the effective config back to TOML, e.g. to store it with
+    /// the results of a run. `processes` is written as an explicit array and
+    /// durations in nanoseconds; unset optional fields are omitted.
+    pub fn to_toml(&self) -> String {
+        toml::to_string(self).expect("Config values are always serializable to TOML")
+    }
+
     /// Validate the config values. Exits if any value is invalid.
     pub fn validate(&self) -> Result<(), String> {
 
@@ -140,14 +295,25 @@ impl RepCXLConfig {
             return Err(format!("{} id {} must be in the processes list {:?}", err_prefix, self.id, self.processes));
         }
 
+        // a typo in mem_size would map (and fill tmpfs with) a huge region
+        if self.mem_size > self.max_mem_size {
+            return Err(format!(
+                "{} mem_size {} exceeds max_mem_size {} bytes, raise max_mem_size for larger memory nodes",
+                err_prefix, self.mem_size, self.max_mem_size
+            ));
+        }
+
         // must have less than MAX_PROCESSES
         if self.processes.len() > shmem::MAX_PROCESSES as usize {
             return Err(format!("{} Maximum number of processes: {}", err_prefix, shmem::MAX_PROCESSES));
         }
 
-        // must specify at least one node
-        if self.mem_nodes.is_empty() {
-            return Err(format!("{} at least one memory node must be specified in the config", err_prefix));
+        // must specify at least one node, unless they are added later
+        if self.mem_nodes.is_empty() && !self.deferred_nodes {
+            return Err(format!(
+                "{} at least one memory node must be specified in the config, or set deferred_nodes to add them later",
+                err_prefix
+            ));
         }
 
         // core affinity should not use core 0 (reserved for system tasks)
@@ -157,7 +323,214 @@ impl RepCXLConfig {
             }
         }
 
+        // the quorum includes at least the coordinator
+        if let Some(min_ready) = self.min_ready {
+            if min_ready == 0 || min_ready > self.processes.len() {
+                return Err(format!("{} min_ready must be between 1 and the number of processes ({})", err_prefix, self.processes.len()));
+            }
+        }
+
+        // a quorum can only form before giving up on the sync
+        if self.sync_timeout < self.ready_timeout {
+            return Err(format!("{} sync_timeout must be at least ready_timeout", err_prefix));
+        }
+
+        // conflict rate is a fraction of the writes
+        if let Some(threshold) = self.conflict_backoff_threshold {
+            if !(0.0..1.0).contains(&threshold) {
+                return Err(format!("{} conflict_backoff_threshold must be between 0 and 1", err_prefix));
+            }
+            if self.conflict_backoff_max == 0 {
+                return Err(format!("{} conflict_backoff_max must be at least 1 round", err_prefix));
+            }
+        }
+
+        // anti-entropy sweeps need a positive rate and batch
+        if self.anti_entropy_interval == Some(0) {
+            return Err(format!("{} anti_entropy_interval must be at least 1 round", err_prefix));
+        }
+        if self.anti_entropy_interval.is_some() && self.anti_entropy_batch == 0 {
+            return Err(format!("{} anti_entropy_batch must be at least 1", err_prefix));
+        }
+
+        if self.audit_interval == Some(0) {
+            return Err(format!("{} audit_interval must be at least 1 round", err_prefix));
+        }
+        if self.audit_interval.is_some() && self.audit_sample == 0 {
+            return Err(format!("{} audit_sample must be at least 1", err_prefix));
+        }
+
+        if self.msync_interval_rounds == Some(0) {
+            return Err(format!("{} msync_interval_rounds must be at least 1 round", err_prefix));
+        }
+
+        // only the MONSTER WCC stores the write timestamps
+        match self.conflict_order.as_str() {
+            "round" => (),
+            "timestamp" if self.algorithm == "monster" => (),
+            "timestamp" => {
+                return Err(format!("{} conflict_order \"timestamp\" requires the monster algorithm", err_prefix));
+            }
+            other => {
+                return Err(format!("{} conflict_order must be \"round\" or \"timestamp\", got \"{}\"", err_prefix, other));
+            }
+        }
+
+        if self.write_batch_size == 0 {
+            return Err(format!("{} write_batch_size must be at least 1", err_prefix));
+        }
+
+        if self.replication_threads == 0 {
+            return Err(format!("{} replication_threads must be at least 1", err_prefix));
+        }
+
+        if let Some(rate) = self.write_rate_limit {
+            if !rate.is_finite() || rate <= 0.0 {
+                return Err(format!("{} write_rate_limit must be a positive number of writes per second", err_prefix));
+            }
+        }
+
+        if self.max_inflight_writes == 0 {
+            return Err(format!("{} max_inflight_writes must be at least 1", err_prefix));
+        }
+
+        // only the MONSTER write workers ack before the write is complete
+        match self.ack_policy.as_str() {
+            "all" => (),
+            "quorum" if self.algorithm == "monster" || self.algorithm == "fmonster" => (),
+            "quorum" => {
+                return Err(format!("{} ack_policy \"quorum\" requires the monster or fmonster algorithm", err_prefix));
+            }
+            other => {
+                return Err(format!("{} ack_policy must be \"all\" or \"quorum\", got \"{}\"", err_prefix, other));
+            }
+        }
+
+        // one hint per memory node, the extra ones are for the nodes added
+        // after `RepCXL::new`, e.g. with `add_memory_node_heap`
+        if !self.mem_node_latency.is_empty() && self.mem_node_latency.len() < self.mem_nodes.len() {
+            return Err(format!(
+                "{} mem_node_latency needs one value per memory node, got {} for {} nodes",
+                err_prefix, self.mem_node_latency.len(), self.mem_nodes.len()
+            ));
+        }
+
+        if self.single_writer && self.algorithm != "monster" && self.algorithm != "fmonster" {
+            return Err(format!("{} single_writer requires the monster or fmonster algorithm", err_prefix));
+        }
+
+        // faults may target the nodes added after `RepCXL::new`, by id
+        for fault in &self.fault {
+            if fault.fail_for_rounds == 0 {
+                return Err(format!("{} fault on node {} must last at least one round", err_prefix, fault.node));
+            }
+            if !["read", "write", "both"].contains(&fault.mode.as_str()) {
+                return Err(format!("{} fault mode must be read, write or both, got {}", err_prefix, fault.mode));
+            }
+        }
+
         Ok(())
     }
 
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_duration_units() {
+        assert_eq!(parse_duration_ns("1ms"), Ok(1_000_000));
+        assert_eq!(parse_duration_ns("1000000"), Ok(1_000_000));
+        assert_eq!(parse_duration_ns("1000us"), Ok(1_000_000));
+        assert_eq!(parse_duration_ns("2s"), Ok(2_000_000_000));
+        assert_eq!(parse_duration_ns("500ns"), Ok(500));
+        assert!(parse_duration_ns("1h").is_err());
+        assert!(parse_duration_ns("ms").is_err());
+    }
+
+    #[test]
+    fn test_round_time_toml() {
+        let suffixed: RepCXLConfig = toml::from_str("round_time = \"1ms\"").unwrap();
+        let raw: RepCXLConfig = toml::from_str("round_time = 1_000_000").unwrap();
+        assert_eq!(suffixed.round_time, 1_000_000);
+        assert_eq!(raw.round_time, 1_000_000);
+    }
+
+    #[test]
+    fn test_to_toml_round_trip() {
+        let config: RepCXLConfig = toml::from_str(
+            "mem_nodes = [\"/dev/shm/n0\", \"/dev/shm/n1\"]\n\
+             processes = \"0..3\"\n\
+             round_time = \"500us\"\n\
+             read_offset = 0.5\n\
+             min_ready = 3\n",
+        ).unwrap();
+
+        let dumped = config.to_toml();
+        assert!(dumped.contains("processes = [0, 1, 2, 3]"), "processes not canonical:\n{}", dumped);
+        let parsed: RepCXLConfig = toml::from_str(&dumped).unwrap();
+        assert_eq!(parsed, config);
+
+        let default: RepCXLConfig = toml::from_str(&RepCXLConfig::default().to_toml()).unwrap();
+        assert_eq!(default, RepCXLConfig::default());
+    }
+
+    #[test]
+    fn test_fault_schedule_toml() {
+        let config: RepCXLConfig = toml::from_str(
+            "mem_nodes = [\"/dev/shm/n0\", \"/dev/shm/n1\"]\n\
+             processes = [0]\n\
+             id = 0\n\
+             [[fault]]\n\
+             node = 1\n\
+             start_round = 50\n\
+             fail_for_rounds = 10\n\
+             mode = \"write\"\n\
+             [[fault]]\n\
+             node = 0\n\
+             start_round = 100\n\
+             fail_for_rounds = 5\n",
+        ).unwrap();
+        assert_eq!(config.fault.len(), 2);
+        assert_eq!(config.fault[0], FaultSpec { node: 1, start_round: 50, fail_for_rounds: 10, mode: "write".into() });
+        assert_eq!(config.fault[1].mode, "both");
+        assert_eq!(config.validate(), Ok(()));
+
+        let parsed: RepCXLConfig = toml::from_str(&config.to_toml()).unwrap();
+        assert_eq!(parsed, config);
+
+        let mut bad = RepCXLConfig { fault: vec![config.fault[0].clone()], ..config };
+        bad.fault[0].fail_for_rounds = 0;
+        assert!(bad.validate().unwrap_err().contains("at least one round"));
+        bad.fault[0].fail_for_rounds = 10;
+        bad.fault[0].mode = "sometimes".into();
+        assert!(bad.validate().is_err());
+    }
+
+    #[test]
+    fn test_max_mem_size() {
+        let mut config = RepCXLConfig {
+            id: 0,
+            processes: vec![0],
+            mem_nodes: vec!["/dev/shm/node0".into()],
+            mem_size: 100 * 1024 * 1024 * 1024, // 100 GiB, extra zeros
+            ..Default::default()
+        };
+        let err = config.validate().unwrap_err();
+        assert!(err.contains("exceeds max_mem_size"), "Unexpected error: {}", err);
+
+        config.max_mem_size = config.mem_size;
+        assert_eq!(config.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_memory_nodes_required() {
+        let mut config = RepCXLConfig { id: 0, processes: vec![0], ..Default::default() };
+        let err = config.validate().unwrap_err();
+        assert!(err.contains("at least one memory node"), "Unexpected error: {}", err);
+
+        config.deferred_nodes = true;
+        assert_eq!(config.validate(), Ok(()));
+    }
+}
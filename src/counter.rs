@@ -0,0 +1,55 @@
+//! Replicated counters, see `RepCXLCounter`
+
+use crate::request::ReadReturn;
+use crate::{RepCXL, RepCXLObject};
+
+/// Counter shared by all processes, backed by a `u64` object. Increments are
+/// read-modify-writes of the write worker (see `RepCXLObject::fetch_update`):
+/// under the MONSTER algorithms concurrent increments from any process all
+/// take effect.
+///
+/// Counts wrap around: adding past `u64::MAX` continues from 0, i.e.
+/// arithmetic is modulo 2^64 like `u64::wrapping_add`. Decrement with
+/// `add(n.wrapping_neg())`.
+#[derive(Debug)]
+pub struct RepCXLCounter {
+    obj: RepCXLObject<u64>,
+}
+
+impl RepCXLCounter {
+    pub fn id(&self) -> usize {
+        self.obj.info.local_id()
+    }
+
+    /// Add 1, returns the new count
+    pub fn increment(&self) -> Result<u64, String> {
+        self.add(1)
+    }
+
+    /// Add `n`, returns the new count
+    pub fn add(&self, n: u64) -> Result<u64, String> {
+        self.obj.fetch_update(n, u64::wrapping_add).map(|prev| prev.wrapping_add(n))
+    }
+
+    /// Current count, possibly missing an increment still being replicated
+    pub fn get(&self) -> Result<u64, String> {
+        match self.obj.read()? {
+            ReadReturn::ReadSafe(count) | ReadReturn::ReadDirty(count) => Ok(count),
+            ReadReturn::Uninitialized => Ok(0),
+            ReadReturn::TooStale => unreachable!("read without freshness bound"),
+            ReadReturn::ObjectRemoved => Err("Counter was removed".into()),
+        }
+    }
+}
+
+impl RepCXL<u64> {
+    /// Create a counter starting at 0
+    pub fn new_counter(&mut self, id: usize) -> Option<RepCXLCounter> {
+        self.new_object_with_val(id, 0).map(|obj| RepCXLCounter { obj })
+    }
+
+    /// Look up a counter created by `new_counter`
+    pub fn get_counter(&mut self, id: usize) -> Option<RepCXLCounter> {
+        self.get_object(id).map(|obj| RepCXLCounter { obj })
+    }
+}
@@ -0,0 +1,250 @@
+//! C interface, e.g. for C-based CXL test harnesses. Values are byte buffers
+//! of up to `REPCXL_VALUE_SIZE` bytes, stored as `Blob<REPCXL_VALUE_SIZE>`.
+//!
+//! The header is generated with cbindgen:
+//!
+//! ```sh
+//! cbindgen --lang c --crate rep_cxl --output repcxl.h
+//! ```
+//!
+//! and the program linked against `target/release/librep_cxl.so`. Handles are
+//! not thread-safe: a handle and its objects must be used by one thread at a
+//! time. All functions fail on NULL handles.
+
+use std::ffi::{c_char, CStr};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+use log::error;
+
+use crate::{Blob, ReadReturn, RepCXL, RepCXLConfig, RepCXLObject};
+
+/// Maximum size in bytes of a value
+pub const REPCXL_VALUE_SIZE: usize = 64;
+
+/// `repcxl_read` return codes
+pub const REPCXL_READ_SAFE: i32 = 0;
+pub const REPCXL_READ_DIRTY: i32 = 1;
+pub const REPCXL_UNINITIALIZED: i32 = 2;
+pub const REPCXL_OBJECT_REMOVED: i32 = 3;
+pub const REPCXL_ERROR: i32 = -1;
+
+type Value = Blob<REPCXL_VALUE_SIZE>;
+
+/// Opaque handle on a repCXL instance
+pub struct RepCXLHandle {
+    rcxl: RepCXL<Value>,
+}
+
+/// Opaque handle on an object of a repCXL instance
+pub struct RepCXLObjectHandle {
+    rcxl: *mut RepCXLHandle,
+    obj: RepCXLObject<Value>,
+}
+
+/// Start a repCXL instance from a TOML config file: the coordinator
+/// initializes the shared state, then the processes synchronize. Blocks until
+/// the rounds start. Returns NULL on failure.
+///
+/// # Safety
+/// `config_path` must be a valid NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn repcxl_open(config_path: *const c_char) -> *mut RepCXLHandle {
+    if config_path.is_null() {
+        return std::ptr::null_mut();
+    }
+    let path = match CStr::from_ptr(config_path).to_str() {
+        Ok(path) => path,
+        Err(e) => {
+            error!("Invalid config path: {}", e);
+            return std::ptr::null_mut();
+        }
+    };
+
+    // no unwinding into C, e.g. on an invalid config
+    let opened = catch_unwind(|| -> Result<RepCXL<Value>, String> {
+        let config = RepCXLConfig::from_file(path)?;
+        config.validate()?;
+        let mut rcxl = RepCXL::new(config);
+        if rcxl.is_coordinator() {
+            rcxl.init_state()?;
+        }
+        rcxl.sync_start()?;
+        Ok(rcxl)
+    });
+    match opened {
+        Ok(Ok(rcxl)) => Box::into_raw(Box::new(RepCXLHandle { rcxl })),
+        Ok(Err(e)) => {
+            error!("Failed to open repCXL: {}", e);
+            std::ptr::null_mut()
+        }
+        Err(_) => {
+            error!("Failed to open repCXL: panicked");
+            std::ptr::null_mut()
+        }
+    }
+}
+
+/// Get object `id`. The coordinator creates it if it does not exist yet.
+/// Returns NULL if the object does not exist or cannot be created.
+///
+/// # Safety
+/// `handle` must come from `repcxl_open` and not be closed.
+#[no_mangle]
+pub unsafe extern "C" fn repcxl_get_object(handle: *mut RepCXLHandle, id: usize) -> *mut RepCXLObjectHandle {
+    let Some(h) = handle.as_mut() else {
+        return std::ptr::null_mut();
+    };
+    let obj = match h.rcxl.get_object(id) {
+        Some(obj) => Some(obj),
+        None if h.rcxl.is_coordinator() => h.rcxl.new_object(id).ok(),
+        None => None,
+    };
+    match obj {
+        Some(obj) => Box::into_raw(Box::new(RepCXLObjectHandle { rcxl: handle, obj })),
+        None => std::ptr::null_mut(),
+    }
+}
+
+/// Write `len` bytes from `data` to the object. Longer values are truncated
+/// to `REPCXL_VALUE_SIZE` bytes, shorter ones zero-padded. Returns 0 on
+/// success, `REPCXL_ERROR` otherwise.
+///
+/// # Safety
+/// `obj` must come from `repcxl_get_object` of an open handle, and `data`
+/// point to at least `len` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn repcxl_write(obj: *mut RepCXLObjectHandle, data: *const u8, len: usize) -> i32 {
+    let Some(o) = obj.as_ref() else {
+        return REPCXL_ERROR;
+    };
+    if data.is_null() && len > 0 {
+        return REPCXL_ERROR;
+    }
+    let bytes = if len == 0 { &[][..] } else { std::slice::from_raw_parts(data, len) };
+    let rcxl = &mut (*o.rcxl).rcxl;
+
+    match catch_unwind(AssertUnwindSafe(|| rcxl.write_object(&o.obj, Blob::from_slice(bytes)))) {
+        Ok(Ok(())) => 0,
+        Ok(Err(e)) => {
+            error!("Failed to write object: {}", e);
+            REPCXL_ERROR
+        }
+        Err(_) => REPCXL_ERROR,
+    }
+}
+
+/// Read the object into `out`, copying up to `len` bytes of the value.
+/// Returns `REPCXL_READ_SAFE`, `REPCXL_READ_DIRTY`, `REPCXL_UNINITIALIZED`
+/// or `REPCXL_OBJECT_REMOVED` (`out` untouched), or `REPCXL_ERROR`.
+///
+/// # Safety
+/// `obj` must come from `repcxl_get_object` of an open handle, and `out`
+/// point to at least `len` writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn repcxl_read(obj: *mut RepCXLObjectHandle, out: *mut u8, len: usize) -> i32 {
+    let Some(o) = obj.as_ref() else {
+        return REPCXL_ERROR;
+    };
+    if out.is_null() && len > 0 {
+        return REPCXL_ERROR;
+    }
+    let rcxl = &(*o.rcxl).rcxl;
+
+    let (code, value) = match catch_unwind(AssertUnwindSafe(|| rcxl.read_object(&o.obj))) {
+        Ok(Ok(ReadReturn::ReadSafe(v))) => (REPCXL_READ_SAFE, v),
+        Ok(Ok(ReadReturn::ReadDirty(v))) => (REPCXL_READ_DIRTY, v),
+        Ok(Ok(ReadReturn::Uninitialized)) => return REPCXL_UNINITIALIZED,
+        Ok(Ok(ReadReturn::ObjectRemoved)) => return REPCXL_OBJECT_REMOVED,
+        // plain reads have no freshness bound
+        Ok(Ok(ReadReturn::TooStale)) => return REPCXL_ERROR,
+        Ok(Err(e)) => {
+            error!("Failed to read object: {}", e);
+            return REPCXL_ERROR;
+        }
+        Err(_) => return REPCXL_ERROR,
+    };
+    let n = len.min(REPCXL_VALUE_SIZE);
+    std::ptr::copy_nonoverlapping(value.as_slice().as_ptr(), out, n);
+    code
+}
+
+/// Release an object handle
+///
+/// # Safety
+/// `obj` must come from `repcxl_get_object` and not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn repcxl_free_object(obj: *mut RepCXLObjectHandle) {
+    if !obj.is_null() {
+        drop(Box::from_raw(obj));
+    }
+}
+
+/// Stop the instance and release its handle. The object handles of the
+/// instance must be freed before.
+///
+/// # Safety
+/// `handle` must come from `repcxl_open` and not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn repcxl_close(handle: *mut RepCXLHandle) {
+    if !handle.is_null() {
+        let h = Box::from_raw(handle);
+        h.rcxl.stop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CString;
+
+    #[test]
+    fn test_ffi_write_read() {
+        let node_path = "/dev/shm/repCXL_test_ffi";
+        let config_path = "/tmp/repcxl_test_ffi.toml";
+        let size: usize = 2 * 1024 * 1024;
+        let file = std::fs::File::create(node_path).expect("Failed to create tmpfs file");
+        file.set_len(size as u64).expect("Failed to set file length");
+        std::fs::write(config_path, format!(
+            "mem_nodes = [\"{}\"]\nmem_size = {}\nid = 0\nprocesses = [0]\nround_time = \"1ms\"\nstartup_delay = 0\nforce_init = true\n",
+            node_path, size
+        )).expect("Failed to write config file");
+
+        unsafe {
+            let path = CString::new(config_path).unwrap();
+            let handle = repcxl_open(path.as_ptr());
+            assert!(!handle.is_null(), "repcxl_open failed");
+
+            let obj = repcxl_get_object(handle, 1);
+            assert!(!obj.is_null(), "repcxl_get_object failed");
+
+            let mut out = [0xffu8; 8];
+            assert_eq!(repcxl_read(obj, out.as_mut_ptr(), out.len()), REPCXL_UNINITIALIZED);
+
+            let data = b"cxl";
+            assert_eq!(repcxl_write(obj, data.as_ptr(), data.len()), 0);
+            assert_eq!(repcxl_read(obj, out.as_mut_ptr(), out.len()), REPCXL_READ_SAFE);
+            assert_eq!(&out, b"cxl\0\0\0\0\0");
+
+            // a second handle on the same object sees the write
+            let same = repcxl_get_object(handle, 1);
+            let mut out = [0u8; REPCXL_VALUE_SIZE];
+            assert_eq!(repcxl_read(same, out.as_mut_ptr(), out.len()), REPCXL_READ_SAFE);
+            assert_eq!(&out[..3], data);
+
+            assert_eq!(repcxl_write(std::ptr::null_mut(), data.as_ptr(), data.len()), REPCXL_ERROR);
+            assert_eq!(repcxl_write(obj, std::ptr::null(), 4), REPCXL_ERROR);
+
+            repcxl_free_object(same);
+            repcxl_free_object(obj);
+            repcxl_close(handle);
+        }
+
+        // invalid config
+        let missing = CString::new("/tmp/repcxl_test_ffi_missing.toml").unwrap();
+        assert!(unsafe { repcxl_open(missing.as_ptr()) }.is_null());
+        assert!(unsafe { repcxl_open(std::ptr::null()) }.is_null());
+
+        std::fs::remove_file(node_path).expect("Failed to remove tmpfs file");
+        std::fs::remove_file(config_path).expect("Failed to remove config file");
+    }
+}
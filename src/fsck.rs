@@ -0,0 +1,349 @@
+//! Read-only consistency check of the shared region, see `RepCXL::fsck`,
+//! the background audit (`RepCXL::audit_report`) and the order checker
+//! (`RepCXL::enable_order_checker`)
+
+use std::collections::{HashMap, VecDeque};
+
+use crate::safe_memio::{mem_readall, MemoryError, ObjectMemoryEntry};
+use crate::shmem::object_index::{MemoryStats, ObjectInfo};
+use crate::request::Wid;
+use crate::GroupView;
+
+/// State of an object across the memory nodes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ObjectStatus {
+    /// Every node holds the same entry
+    Consistent,
+    /// Nodes hold different writes of the object, e.g. a write that did not
+    /// reach every node. Anti-entropy or the next write repairs it
+    Divergent,
+    /// Nodes hold the same write id with different contents, which no write
+    /// can produce
+    Corrupt,
+    /// The object could not be read from every node
+    Unreadable,
+}
+
+/// Shared state as stored on one memory node
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NodeStateReport {
+    pub node_id: usize,
+    pub seq: u64,
+    pub checksum_ok: bool,
+    /// Allocator occupancy according to this node
+    pub memory: MemoryStats,
+    /// (namespace, id) of the objects in the index of this node, sorted
+    pub objects: Vec<(u32, usize)>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ObjectReport {
+    pub id: usize,
+    pub namespace: u32,
+    pub status: ObjectStatus,
+    /// Nodes whose entry differs from the one held by the other nodes
+    pub disagreeing_nodes: Vec<usize>,
+}
+
+/// Result of `RepCXL::fsck`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FsckReport {
+    pub node_states: Vec<NodeStateReport>,
+    /// Ids of the pairs of objects whose regions overlap, or of objects that
+    /// exceed the object region (paired with themselves)
+    pub overlapping_objects: Vec<(usize, usize)>,
+    pub objects: Vec<ObjectReport>,
+}
+
+impl FsckReport {
+    /// All nodes hold a valid shared state with the same sequence number
+    pub fn state_consistent(&self) -> bool {
+        self.node_states.iter().all(|s| s.checksum_ok && s.seq == self.node_states[0].seq)
+    }
+
+    /// Nodes that disagree with the others on the state or on any object
+    pub fn disagreeing_nodes(&self) -> Vec<usize> {
+        let newest = self.node_states.iter().filter(|s| s.checksum_ok).map(|s| s.seq).max();
+        let mut nodes: Vec<usize> = self.node_states.iter()
+            .filter(|s| !s.checksum_ok || Some(s.seq) != newest)
+            .map(|s| s.node_id)
+            .chain(self.objects.iter().flat_map(|o| o.disagreeing_nodes.iter().copied()))
+            .collect();
+        nodes.sort_unstable();
+        nodes.dedup();
+        nodes
+    }
+
+    /// No inconsistency was found
+    pub fn is_clean(&self) -> bool {
+        self.state_consistent()
+            && self.overlapping_objects.is_empty()
+            && self.objects.iter().all(|o| o.status == ObjectStatus::Consistent)
+    }
+}
+
+/// Number of latest object checks the audit computes its inconsistency rate
+/// over
+pub const AUDIT_WINDOW: usize = 1024;
+
+/// Result of `RepCXL::audit_report`
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuditReport {
+    /// Objects checked since the audit started
+    pub checked: u64,
+    /// Share of the latest `AUDIT_WINDOW` checks that found the object
+    /// inconsistent, 0 before the first check
+    pub inconsistency_rate: f64,
+    /// Objects found inconsistent by their latest check, sorted by
+    /// (namespace, id)
+    pub divergent: Vec<ObjectReport>,
+}
+
+/// Rolling results of the background audit, see `audit_interval`
+#[derive(Debug, Default)]
+pub(crate) struct AuditState {
+    checked: u64,
+    // latest checks, true if inconsistent
+    window: VecDeque<bool>,
+    // by scoped object id
+    divergent: HashMap<usize, ObjectReport>,
+}
+
+impl AuditState {
+    pub(crate) fn record(&mut self, obj_info: &ObjectInfo, report: ObjectReport) {
+        let inconsistent = report.status != ObjectStatus::Consistent;
+        self.checked += 1;
+        if self.window.len() == AUDIT_WINDOW {
+            self.window.pop_front();
+        }
+        self.window.push_back(inconsistent);
+        if inconsistent {
+            self.divergent.insert(obj_info.id, report);
+        } else {
+            self.divergent.remove(&obj_info.id);
+        }
+    }
+
+    /// Forget the objects that are not in `objects` anymore
+    pub(crate) fn retain(&mut self, objects: &[ObjectInfo]) {
+        self.divergent.retain(|id, _| objects.iter().any(|oi| oi.id == *id));
+    }
+
+    pub(crate) fn report(&self) -> AuditReport {
+        let inconsistent = self.window.iter().filter(|&&i| i).count();
+        let mut divergent: Vec<ObjectReport> = self.divergent.values().cloned().collect();
+        divergent.sort_by_key(|o| (o.namespace, o.id));
+        AuditReport {
+            checked: self.checked,
+            inconsistency_rate: if self.window.is_empty() { 0.0 } else { inconsistent as f64 / self.window.len() as f64 },
+            divergent,
+        }
+    }
+}
+
+/// Write of a process observed after a later write of the same process, see
+/// `RepCXL::enable_order_checker`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OrderViolation {
+    pub id: usize,
+    pub namespace: u32,
+    pub process_id: usize,
+    /// write observed first, issued later by the process
+    pub prev_wid: Wid,
+    pub prev_seq: u64,
+    /// write observed afterwards
+    pub wid: Wid,
+    pub seq: u64,
+}
+
+/// Latest write of each process observed on each object by the order checker
+#[derive(Debug, Default)]
+pub(crate) struct OrderChecker {
+    // (wid, seq) by scoped object id and process id
+    last: HashMap<(usize, usize), (Wid, u64)>,
+    violations: Vec<OrderViolation>,
+}
+
+impl OrderChecker {
+    /// Record the newest entry of an object. Entries without a sequence
+    /// number (e.g. the initial value) are not ordered and are skipped
+    pub(crate) fn observe(&mut self, obj_info: &ObjectInfo, wid: Wid, seq: u64) -> Option<OrderViolation> {
+        if seq == 0 {
+            return None;
+        }
+        let prev = self.last.insert((obj_info.id, wid.process_id), (wid, seq));
+        let (prev_wid, prev_seq) = prev.filter(|&(_, prev_seq)| seq < prev_seq)?;
+        let violation = OrderViolation {
+            id: obj_info.local_id(),
+            namespace: obj_info.namespace(),
+            process_id: wid.process_id,
+            prev_wid,
+            prev_seq,
+            wid,
+            seq,
+        };
+        self.violations.push(violation);
+        Some(violation)
+    }
+
+    /// Forget the objects that are not in `objects` anymore, a new object
+    /// can reuse their ids
+    pub(crate) fn retain(&mut self, objects: &[ObjectInfo]) {
+        self.last.retain(|(id, _), _| objects.iter().any(|oi| oi.id == *id));
+    }
+
+    pub(crate) fn violations(&self) -> Vec<OrderViolation> {
+        self.violations.clone()
+    }
+}
+
+/// Check the shared state of every node
+pub(crate) fn check_states(view: &GroupView) -> Vec<NodeStateReport> {
+    view.memory_nodes.iter().map(|node| {
+        let state = node.read_state();
+        let mut objects: Vec<(u32, usize)> = state.object_index.objects()
+            .map(|oi| (oi.namespace(), oi.local_id()))
+            .collect();
+        objects.sort_unstable();
+        NodeStateReport {
+            node_id: node.id,
+            seq: state.seq(),
+            checksum_ok: state.checksum_ok(),
+            memory: state.object_index.stats(),
+            objects,
+        }
+    }).collect()
+}
+
+/// Pairs of objects whose regions overlap or exceed `region_size`
+pub(crate) fn check_regions(objects: &[ObjectInfo], region_size: usize) -> Vec<(usize, usize)> {
+    let mut sorted = objects.to_vec();
+    sorted.sort_by_key(|o| o.offset);
+
+    let mut overlaps = Vec::new();
+    for (i, obj) in sorted.iter().enumerate() {
+        if obj.offset + obj.size > region_size {
+            overlaps.push((obj.local_id(), obj.local_id()));
+        }
+        for next in &sorted[i + 1..] {
+            if next.offset >= obj.offset + obj.size {
+                break;
+            }
+            overlaps.push((obj.local_id(), next.local_id()));
+        }
+    }
+    overlaps
+}
+
+/// Compare the entries of an object across the nodes. The reference entry is
+/// the one with the newest wid
+pub(crate) fn check_object<T: Copy + PartialEq>(obj_info: &ObjectInfo, view: &GroupView) -> ObjectReport {
+    let mut report = ObjectReport {
+        id: obj_info.local_id(),
+        namespace: obj_info.namespace(),
+        status: ObjectStatus::Consistent,
+        disagreeing_nodes: Vec::new(),
+    };
+
+    let omes: Vec<ObjectMemoryEntry<T>> = match mem_readall(obj_info.offset, &view.memory_nodes) {
+        Ok(omes) => omes,
+        Err(MemoryError(mnid)) => {
+            report.status = ObjectStatus::Unreadable;
+            report.disagreeing_nodes.push(mnid);
+            return report;
+        }
+    };
+
+    // no memory node to read, e.g. before heap nodes are added
+    let Some(first) = omes.first() else {
+        report.status = ObjectStatus::Unreadable;
+        return report;
+    };
+    let newest = omes.iter().fold(first, |best, ome| if ome.wid > best.wid { ome } else { best });
+    for (node, ome) in view.memory_nodes.iter().zip(omes.iter()) {
+        if ome.wid != newest.wid {
+            report.disagreeing_nodes.push(node.id);
+            if report.status == ObjectStatus::Consistent {
+                report.status = ObjectStatus::Divergent;
+            }
+        } else if ome.written != newest.written || ome.value != newest.value {
+            report.disagreeing_nodes.push(node.id);
+            report.status = ObjectStatus::Corrupt;
+        }
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_order_checker() {
+        let obj = ObjectInfo::new(4, 0, 64);
+        let mut checker = OrderChecker::default();
+        assert_eq!(checker.observe(&obj, Wid::new(0, 0), 0), None);
+        assert_eq!(checker.observe(&obj, Wid::new(3, 1), 1), None);
+        assert_eq!(checker.observe(&obj, Wid::new(5, 1), 2), None);
+        // another process has its own sequence
+        assert_eq!(checker.observe(&obj, Wid::new(6, 2), 1), None);
+        assert_eq!(checker.observe(&obj, Wid::new(5, 1), 2), None);
+
+        let violation = checker.observe(&obj, Wid::new(3, 1), 1).expect("Stale write not flagged");
+        assert_eq!((violation.id, violation.process_id), (4, 1));
+        assert_eq!((violation.prev_wid, violation.prev_seq), (Wid::new(5, 1), 2));
+        assert_eq!((violation.wid, violation.seq), (Wid::new(3, 1), 1));
+        assert_eq!(checker.violations(), vec![violation]);
+
+        checker.retain(&[]);
+        assert_eq!(checker.observe(&obj, Wid::new(9, 1), 1), None);
+    }
+
+    #[test]
+    fn test_check_regions() {
+        let objects = [
+            ObjectInfo::new(1, 0, 64),
+            ObjectInfo::new(3, 128, 64),
+            ObjectInfo::new(2, 64, 64),
+        ];
+        assert!(check_regions(&objects, 192).is_empty());
+        // the last object exceeds the region
+        assert_eq!(check_regions(&objects, 160), vec![(3, 3)]);
+
+        let objects = [
+            ObjectInfo::new(1, 0, 128),
+            ObjectInfo::new(2, 64, 64),
+            ObjectInfo::new(3, 120, 64),
+        ];
+        assert_eq!(check_regions(&objects, 1024), vec![(1, 2), (1, 3), (2, 3)]);
+    }
+
+    #[test]
+    fn test_audit_state() {
+        let report = |id: usize, status: ObjectStatus| ObjectReport { id, namespace: 0, status, disagreeing_nodes: Vec::new() };
+        let objects = [ObjectInfo::new(1, 0, 64), ObjectInfo::new(2, 64, 64)];
+        let mut audit = AuditState::default();
+        assert_eq!(audit.report().inconsistency_rate, 0.0);
+
+        audit.record(&objects[0], report(1, ObjectStatus::Divergent));
+        audit.record(&objects[1], report(2, ObjectStatus::Consistent));
+        let r = audit.report();
+        assert_eq!(r.checked, 2);
+        assert_eq!(r.inconsistency_rate, 0.5);
+        assert_eq!(r.divergent, vec![report(1, ObjectStatus::Divergent)]);
+
+        // healed, then removed
+        audit.record(&objects[0], report(1, ObjectStatus::Consistent));
+        assert!(audit.report().divergent.is_empty());
+        audit.record(&objects[1], report(2, ObjectStatus::Corrupt));
+        audit.retain(&objects[..1]);
+        assert!(audit.report().divergent.is_empty());
+
+        // the rate only covers the latest checks
+        for _ in 0..AUDIT_WINDOW {
+            audit.record(&objects[0], report(1, ObjectStatus::Consistent));
+        }
+        assert_eq!(audit.report().inconsistency_rate, 0.0);
+        assert_eq!(audit.report().checked, AUDIT_WINDOW as u64 + 4);
+    }
+}
@@ -3,22 +3,39 @@
 /// all machines.
 use log::{debug, error, info, warn};
 
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, SystemTime, Instant};
 
 mod algorithms;
+pub use algorithms::{AlgorithmInfo, ConflictOrder};
 mod safe_memio;
 use safe_memio::ObjectMemoryEntry;
 pub mod shmem;
 mod timer;
+pub use timer::{DelayStats, JitterHistogram};
 pub mod utils;
 pub mod request;
-use request::{WriteRequest, ReadRequest, ReadReturn};
-use shmem::object_index::ObjectInfo;
-use shmem::{MemoryNode, SharedState};
+use request::{WriteAck, WriteRequest, WriteToken, ReadRequest, ReadReturn};
+use shmem::object_index::{scoped_id, MemoryStats, ObjectInfo};
+pub use shmem::object_index::{AllocError, LayoutSegment};
+use shmem::{MemoryNode, SharedState, StateLock};
+pub use shmem::HeapMemory;
+pub use shmem::conflict_stats::{ConflictReport, ObjectConflicts};
 pub mod config;
 pub use config::RepCXLConfig;
+pub mod blob;
+pub use blob::{Blob, Pod};
+pub mod fsck;
+pub use fsck::{AuditReport, FsckReport, NodeStateReport, ObjectStatus, OrderViolation};
+pub mod versioned;
+pub use versioned::VersionedObject;
+pub mod ring;
+pub use ring::RingObject;
+pub mod counter;
+pub use counter::RepCXLCounter;
+pub mod ffi;
 
 
 /// The current membership of the group. Stores both the
@@ -28,19 +45,51 @@ pub struct GroupView {
     self_id: usize, // process ID of this instance
     pub processes: Vec<u32>,
     memory_nodes: Vec<MemoryNode>,
+    // value bytes of the write requests, shared by the clones of the view
+    client_bytes: Arc<AtomicU64>,
+    // id of the master memory node, shared by the clones of the view so that
+    // a failover is seen by every thread, see `get_master_node`
+    master: Arc<AtomicUsize>,
+    // allocations of the shared state, shared by the clones of the view, see
+    // `is_allocated`
+    alloc_cache: Arc<std::sync::RwLock<AllocCache>>,
+}
+
+/// Allocated objects by id, as of the shared state with sequence number `seq`
+#[derive(Debug, Default)]
+struct AllocCache {
+    seq: Option<u64>,
+    objects: HashMap<usize, ObjectInfo>,
 }
 
 unsafe impl Send for GroupView {} // required because MemoryNode contains raw pointers
 unsafe impl Sync for GroupView {}
+impl std::fmt::Debug for GroupView {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("GroupView")
+            .field("self_id", &self.self_id)
+            .field("processes", &self.processes)
+            .field("memory_nodes", &self.memory_nodes.iter().map(|n| n.id).collect::<Vec<_>>())
+            .finish()
+    }
+}
 impl GroupView {
     fn new(self_id: usize) -> Self {
         GroupView {
             self_id,
             processes: Vec::new(),
             memory_nodes: Vec::new(),
+            client_bytes: Arc::new(AtomicU64::new(0)),
+            master: Arc::new(AtomicUsize::new(0)),
+            alloc_cache: Arc::new(std::sync::RwLock::new(AllocCache::default())),
         }
     }
 
+    /// Count a write request of a `T` value, see `RepCXL::write_amplification`
+    fn count_client_write<T>(&self) {
+        self.client_bytes.fetch_add(size_of::<T>() as u64, Ordering::Relaxed);
+    }
+
     fn add_process(&mut self, pid: u32) {
         if !self.processes.contains(&pid) {
             self.processes.push(pid);
@@ -54,9 +103,166 @@ impl GroupView {
         self.processes.iter().min().cloned()
     }
 
-    // Returns the memory node with the lowest ID as the master node
+    // Returns the master node, which holds the coordination state read by
+    // the processes (starting block), the lowest ID unless set with
+    // `RepCXL::set_master_node`. The coordination state is replicated to
+    // every live node, so if the master failed the next live node in ID
+    // order is promoted. Returns the failed master if no node is live.
     fn get_master_node(&self) -> Option<&MemoryNode> {
-        self.memory_nodes.iter().min_by_key(|n| n.id)
+        let master = self.master.load(Ordering::Acquire);
+        let node = self.memory_nodes.iter().find(|n| n.id == master)
+            .or_else(|| self.memory_nodes.iter().min_by_key(|n| n.id))?;
+        if !node.is_failed() {
+            return Some(node);
+        }
+        let Some(next) = self.memory_nodes.iter()
+            .filter(|n| !n.is_failed())
+            .min_by_key(|n| (n.id <= master, n.id))
+        else {
+            return Some(node);
+        };
+        if self.master.compare_exchange(master, next.id, Ordering::AcqRel, Ordering::Acquire).is_ok() {
+            warn!("Master memory node {} failed, promoting memory node {}", master, next.id);
+        }
+        Some(next)
+    }
+
+    /// Apply an in-place update of the coordination state (WCCs, starting
+    /// block) to every live node, so that any of them can take over as master
+    fn update_states(&self, update: impl Fn(&mut SharedState)) {
+        for node in self.memory_nodes.iter().filter(|n| !n.is_failed()) {
+            update(node.get_state());
+        }
+    }
+
+    /// Same as `update_states` for an update writing `bytes` to each node on
+    /// behalf of a client write, e.g. a WCC entry, counted in the write
+    /// amplification
+    fn update_states_counted(&self, bytes: usize, update: impl Fn(&mut SharedState)) {
+        for node in self.memory_nodes.iter().filter(|n| !n.is_failed()) {
+            update(node.get_state());
+            node.count_written(bytes);
+        }
+    }
+
+    /// Read the coordination state (e.g. a WCC check) of every live node and
+    /// return the answer of a majority of the memory nodes, so that a failed
+    /// or lagging node cannot decide alone. Without a majority, e.g. if most
+    /// nodes failed, the master answers.
+    fn quorum_read<R: PartialEq>(&self, read: impl Fn(&mut SharedState) -> R) -> R {
+        let quorum = self.memory_nodes.len() / 2 + 1;
+        let mut answers: Vec<(R, usize)> = Vec::new();
+        for node in self.memory_nodes.iter().filter(|n| !n.is_failed()) {
+            let answer = read(node.get_state());
+            let votes = match answers.iter_mut().find(|(a, _)| *a == answer) {
+                Some((_, votes)) => {
+                    *votes += 1;
+                    *votes
+                }
+                None => 1,
+            };
+            if votes >= quorum {
+                return answer;
+            }
+            if votes == 1 {
+                answers.push((answer, 1));
+            }
+        }
+        read(self.get_master_node().expect("No memory node").get_state())
+    }
+
+    /// Lock the shared state for a read-modify-write, e.g. an allocation.
+    /// The lock word of the master node guards the state of every node.
+    fn lock_state(&self) -> Result<StateLock<'_>, String> {
+        self.get_master_node().ok_or("No memory node to lock the state on")?.lock_state()
+    }
+
+    // Size of the smallest object region: offsets must be valid on every node
+    fn min_region_size(&self) -> usize {
+        self.memory_nodes.iter().map(|n| n.region_size()).min().unwrap_or(0)
+    }
+
+    /// Check that the object fits in the object region of every memory node
+    fn check_fits(&self, obj_info: &ObjectInfo) -> Result<(), String> {
+        let min_size = self.min_region_size();
+        if obj_info.offset + obj_info.size > min_size {
+            return Err(format!(
+                "Object {} at offset {} (size {}) overruns the smallest memory node region ({} bytes)",
+                obj_info.local_id(), obj_info.offset, obj_info.size, min_size
+            ));
+        }
+        Ok(())
+    }
+
+    /// Check that the object is still allocated in the shared state, i.e. it
+    /// was not removed since the handle was created. Otherwise its region
+    /// might hold another object. Handles on part of an object (versions,
+    /// ring slots) are checked against the whole allocation.
+    ///
+    /// The allocations are read with `read_state` and cached until the
+    /// sequence number of the state changes, i.e. until the next state update.
+    /// A cache hit only takes the cache lock shared, so concurrent readers of
+    /// the process do not wait on each other.
+    fn is_allocated(&self, obj_info: &ObjectInfo) -> Result<bool, String> {
+        let Some(master) = self.get_master_node() else {
+            return Err("No memory node to read the state from".into());
+        };
+        let seq = Some(master.get_state().seq());
+        let contains = |cache: &AllocCache| match cache.objects.get(&obj_info.id) {
+            Some(alloc) => alloc.offset <= obj_info.offset
+                && obj_info.offset + obj_info.size <= alloc.offset + alloc.size,
+            None => false,
+        };
+
+        {
+            let cache = self.alloc_cache.read().unwrap();
+            if cache.seq == seq {
+                return Ok(contains(&cache));
+            }
+        }
+
+        let mut cache = self.alloc_cache.write().unwrap();
+        // another reader may have refreshed it meanwhile
+        if cache.seq != seq {
+            let state = master.read_state();
+            cache.objects = state.object_index.objects().map(|oi| (oi.id, oi)).collect();
+            cache.seq = Some(state.seq());
+        }
+        Ok(contains(&cache))
+    }
+
+    /// Read the object with `read` if it is still allocated, see
+    /// `is_allocated`, `ReadReturn::ObjectRemoved` otherwise
+    fn read_allocated<T>(
+        &self,
+        obj_info: &ObjectInfo,
+        read: impl FnOnce() -> Result<ReadReturn<T>, String>,
+    ) -> Result<ReadReturn<T>, String> {
+        if !self.is_allocated(obj_info)? {
+            return Ok(ReadReturn::ObjectRemoved);
+        }
+        read()
+    }
+
+    /// Memory nodes with the given ids, e.g. the targets of a partial write
+    fn node_subset(&self, ids: &[usize]) -> Result<Vec<MemoryNode>, String> {
+        if ids.is_empty() {
+            return Err("No memory node to write to".into());
+        }
+        if let Some(id) = ids.iter().find(|&&id| !self.memory_nodes.iter().any(|n| n.id == id)) {
+            return Err(format!("Unknown memory node {}", id));
+        }
+        Ok(self.memory_nodes.iter().filter(|n| ids.contains(&n.id)).cloned().collect())
+    }
+
+    // A single process on a single memory node cannot have write conflicts
+    fn is_conflict_free(&self) -> bool {
+        self.processes.len() == 1 && self.memory_nodes.len() == 1
+    }
+
+    /// Some memory nodes are nearer than others, see `mem_node_latency`
+    fn has_locality(&self) -> bool {
+        self.memory_nodes.iter().any(|n| n.latency() != self.memory_nodes[0].latency())
     }
 }
 impl PartialEq for GroupView {
@@ -72,16 +278,65 @@ impl PartialEq for GroupView {
         self_procs == other_procs && self_nodes == other_nodes
     }
 }
+/// Value bytes requested by the clients of an instance vs value bytes
+/// written to the memory nodes, see `RepCXL::write_amplification`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WriteAmplification {
+    pub client_bytes: u64,
+    pub node_bytes: u64,
+}
+
+impl WriteAmplification {
+    /// `node_bytes / client_bytes`, 0 before the first write
+    pub fn ratio(&self) -> f64 {
+        if self.client_bytes == 0 {
+            return 0.0;
+        }
+        self.node_bytes as f64 / self.client_bytes as f64
+    }
+}
+
+/// Client-side sequence numbers of the writes of a handle, see
+/// `RepCXLObject::check_continuity`
+#[derive(Debug, Default)]
+struct WriteSeq {
+    // last sequence number given to a write
+    issued: AtomicU64,
+    // writes acked successfully, shared with the write tokens
+    acked: Arc<request::AckCounter>,
+    // stored sequence number and acked writes at the last check
+    checked: std::sync::Mutex<(u64, u64)>,
+}
+
 /// Shared replicated object across memory nodes
 #[derive(Debug)]
 pub struct RepCXLObject<T: Copy> {
     wreq_queue_tx: kanal::Sender<WriteRequest<T>>,
     rreq_queue_tx: kanal::Sender<ReadRequest<T>>,
+    pending_writes: Arc<AtomicUsize>,
     info: ObjectInfo,
+    // memory nodes for `read_direct`, only set for best-effort algorithms
+    direct_view: Option<GroupView>,
+    // last value written through this handle and not read back yet, see
+    // `with_local_fence`. Boxed, values can be large
+    local_fence: Option<Box<std::sync::Mutex<Option<T>>>>,
+    // paces the writes of the process, shared by all its handles
+    rate_limiter: Option<Arc<timer::RateLimiter>>,
+    // caps the `try_write`s in flight per object, shared by all the handles
+    // of the process
+    inflight_writes: Option<Arc<request::InflightWrites>>,
+    // memory nodes of the group when the object was created or looked up,
+    // read by `check_continuity`
+    group_view: Option<GroupView>,
+    write_seq: WriteSeq,
 }
 
 impl<T: Copy> RepCXLObject<T> {
     const WRITE_TRACE_SAMPLE_RATE: u64 = 1024;
+    /// Default timeout of `flush`
+    pub const FLUSH_TIMEOUT: Duration = Duration::from_secs(1);
+    // interval between two reads of the memory nodes by `flush`
+    const FLUSH_POLL: Duration = Duration::from_micros(100);
 
     pub fn new(
         id: usize,
@@ -89,19 +344,185 @@ impl<T: Copy> RepCXLObject<T> {
         size: usize,
         wreq_queue_tx: kanal::Sender<WriteRequest<T>>,
         rreq_queue_tx: kanal::Sender<ReadRequest<T>>,
+        pending_writes: Arc<AtomicUsize>,
     ) -> Self {
         RepCXLObject {
             wreq_queue_tx,
             rreq_queue_tx,
+            pending_writes,
             info: ObjectInfo::new(id, offset, size),
+            direct_view: None,
+            local_fence: None,
+            rate_limiter: None,
+            inflight_writes: None,
+            group_view: None,
+            write_seq: WriteSeq::default(),
+        }
+    }
+
+    /// Offset of the object in the object region of the memory nodes
+    pub fn offset(&self) -> usize {
+        self.info.offset
+    }
+
+    fn with_direct_view(mut self, view: Option<GroupView>) -> Self {
+        self.direct_view = view;
+        self
+    }
+
+    fn with_rate_limiter(mut self, rate_limiter: Option<Arc<timer::RateLimiter>>) -> Self {
+        self.rate_limiter = rate_limiter;
+        self
+    }
+
+    fn with_inflight_writes(mut self, inflight_writes: Arc<request::InflightWrites>) -> Self {
+        self.inflight_writes = Some(inflight_writes);
+        self
+    }
+
+    fn with_group_view(mut self, view: GroupView) -> Self {
+        self.group_view = Some(view);
+        self
+    }
+
+    // sequence number of the next write of the handle
+    fn next_seq(&self) -> u64 {
+        self.write_seq.issued.fetch_add(1, Ordering::AcqRel) + 1
+    }
+
+    // count a successfully acked write, see `check_continuity` and `flush`
+    fn count_ack(&self, ack: &WriteAck) {
+        self.write_seq.acked.record(ack);
+    }
+
+    // wait for the rate limiter, if any, before queuing a write
+    fn pace(&self) {
+        if let Some(limiter) = &self.rate_limiter {
+            limiter.acquire();
+        }
+    }
+
+    // handle on another region with the queues of this object, e.g. a
+    // version of a versioned object
+    fn with_info(&self, info: ObjectInfo) -> Self {
+        RepCXLObject {
+            wreq_queue_tx: self.wreq_queue_tx.clone(),
+            rreq_queue_tx: self.rreq_queue_tx.clone(),
+            pending_writes: self.pending_writes.clone(),
+            info,
+            direct_view: self.direct_view.clone(),
+            local_fence: None,
+            rate_limiter: self.rate_limiter.clone(),
+            inflight_writes: self.inflight_writes.clone(),
+            group_view: self.group_view.clone(),
+            write_seq: WriteSeq::default(),
+        }
+    }
+
+    /// Read-your-writes for best-effort algorithms, whose reads can race a
+    /// write acked by the worker: the handle keeps the last value it wrote
+    /// successfully, and its reads return it (as ReadDirty) until the memory
+    /// nodes return it as ReadSafe once. Only the writes and reads through
+    /// this handle, including `RepCXL::write_object` and `read_object` with
+    /// it, are fenced.
+    pub fn with_local_fence(mut self) -> Self {
+        self.local_fence = Some(Box::new(std::sync::Mutex::new(None)));
+        self
+    }
+
+    // record a successful write for the local fence
+    fn fence_write(&self, data: T) {
+        if let Some(fence) = &self.local_fence {
+            *fence.lock().unwrap() = Some(data);
         }
     }
 
     pub fn write(&self, data: T) -> Result<(), String> {
-        let client_start = Instant::now();
+        match self.write_detailed(data)? {
+            ack if ack.success => Ok(()),
+            _ => Err("Failed write operation".into()),
+        }
+    }
+
+    /// Same as `write` with the timestamp given by the client, see
+    /// `RepCXL::write_object_at`
+    pub fn write_at(&self, data: T, timestamp: u64) -> Result<(), String> {
+        let (ack_tx, ack_rx) = kanal::unbounded();
+        let req = WriteRequest::new(self.info, data, ack_tx, &self.pending_writes)
+            .with_timestamp(timestamp)
+            .with_seq(self.next_seq());
+        match self.send_write(req, ack_rx)? {
+            ack if ack.success => Ok(()),
+            _ => Err("Failed write operation".into()),
+        }
+    }
+
+    /// Same as `write`, replicated to the memory nodes with the given ids
+    /// only, e.g. rack-local nodes, the other nodes keep their entry. The
+    /// write still gets a new wid, so anti-entropy (or the next full write)
+    /// reconciles the other nodes. Until then reads are ReadDirty if they
+    /// see both the new and the old entry.
+    pub fn write_to(&self, data: T, nodes: &[usize]) -> Result<(), String> {
+        let (ack_tx, ack_rx) = kanal::unbounded();
+        let req = WriteRequest::new(self.info, data, ack_tx, &self.pending_writes)
+            .with_nodes(nodes)
+            .with_seq(self.next_seq());
+        match self.send_write(req, ack_rx)? {
+            ack if ack.success => Ok(()),
+            _ => Err("Failed write operation".into()),
+        }
+    }
+
+    /// Same as `write`, acknowledged with `policy` instead of the
+    /// `ack_policy` of the instance, e.g. to wait for every node on a
+    /// critical write. Only the MONSTER write workers ack on a quorum, the
+    /// other algorithms always ack once every node holds the write.
+    pub fn write_with_ack(&self, data: T, policy: request::AckPolicy) -> Result<(), String> {
+        let (ack_tx, ack_rx) = kanal::unbounded();
+        let req = WriteRequest::new(self.info, data, ack_tx, &self.pending_writes)
+            .with_ack_policy(policy)
+            .with_seq(self.next_seq());
+        match self.send_write(req, ack_rx)? {
+            ack if ack.success => Ok(()),
+            _ => Err("Failed write operation".into()),
+        }
+    }
+
+    /// Same as `write`, abandoned by the write worker if it is not
+    /// replicated by `deadline`, e.g. for a latency SLA: the worker checks the
+    /// deadline every round, also while waiting to retry after a conflict, and
+    /// acks the write with `deadline_exceeded`. An abandoned write is never
+    /// replicated. Only the MONSTER write workers enforce the deadline.
+    pub fn write_deadline(&self, data: T, deadline: Instant) -> Result<(), String> {
+        let (ack_tx, ack_rx) = kanal::unbounded();
+        let req = WriteRequest::new(self.info, data, ack_tx, &self.pending_writes)
+            .with_deadline(deadline)
+            .with_seq(self.next_seq());
+        match self.send_write(req, ack_rx)? {
+            ack if ack.success => Ok(()),
+            ack if ack.deadline_exceeded => Err(format!(
+                "DeadlineExceeded: write not replicated after {} rounds", ack.rounds_taken
+            )),
+            _ => Err("Failed write operation".into()),
+        }
+    }
+
+    /// Same as `write` but returns the worker ack, i.e. whether the write
+    /// succeeded, met its round deadlines, how many rounds it took and
+    /// whether it was in a conflict, e.g. to back off hot objects. The rounds
+    /// it waited in the queue behind other writes are reported apart from
+    /// the rounds of its replication
+    pub fn write_detailed(&self, data: T) -> Result<WriteAck, String> {
         let (ack_tx, ack_rx) = kanal::unbounded();
-        let req = WriteRequest::new(self.info, data, ack_tx);
+        let req = WriteRequest::new(self.info, data, ack_tx, &self.pending_writes).with_seq(self.next_seq());
+        self.send_write(req, ack_rx)
+    }
+
+    fn send_write(&self, req: WriteRequest<T>, ack_rx: kanal::Receiver<WriteAck>) -> Result<WriteAck, String> {
+        self.pace();
+        let client_start = Instant::now();
         let trace_id = req.trace_id;
+        let data = req.data;
 
         self.wreq_queue_tx
             .send(req)
@@ -111,13 +532,14 @@ impl<T: Copy> RepCXLObject<T> {
 
         // std::thread::sleep(Duration::from_millis(10));
         // wait for ack
-        let result = match ack_rx.recv() {
-            Ok(true) => Ok(()),
-            Ok(false) => Err("Failed write operation".into()),
-            Err(e) => Err(format!("Failed to receive ack: {}", e)),
-        };
+        let result = ack_rx.recv()
+            .map_err(|e| format!("Failed to receive ack: {}", e));
+        if let Ok(ack @ WriteAck { success: true, .. }) = &result {
+            self.fence_write(data);
+            self.count_ack(ack);
+        }
 
-        if trace_id % Self::WRITE_TRACE_SAMPLE_RATE == 0 {
+        if trace_id.is_multiple_of(Self::WRITE_TRACE_SAMPLE_RATE) {
             debug!(
                 "[WRITE_TRACE][client] id={} send_to_worker={}ns ack_wait={}ns total={}ns",
                 trace_id,
@@ -130,6 +552,143 @@ impl<T: Copy> RepCXLObject<T> {
         result
     }
 
+    /// Enqueue a write without waiting for its ack. The returned token can be
+    /// used to wait for the write or to cancel it while it is still queued.
+    ///
+    /// Blocks while the object has `max_inflight_writes` writes issued with
+    /// `try_write` that the write worker is not done with.
+    pub fn try_write(&self, data: T) -> Result<WriteToken, String> {
+        let (ack_tx, ack_rx) = kanal::unbounded();
+        let mut req = WriteRequest::new(self.info, data, ack_tx, &self.pending_writes).with_seq(self.next_seq());
+        if let Some(inflight_writes) = &self.inflight_writes {
+            req = req.with_inflight_slot(inflight_writes.acquire(self.info.id));
+        }
+        let token = WriteToken::new(&req, ack_rx).counting_acks(self.write_seq.acked.clone());
+
+        self.pace();
+        self.wreq_queue_tx
+            .send(req)
+            .map_err(|e| format!("Failed to send to object queue: {}", e))?;
+
+        Ok(token)
+    }
+
+    /// Replace the value of the object and return the previous one. The read
+    /// and the write are a single operation of the write worker: concurrent
+    /// swaps on the same object each return the value written by the one
+    /// ordered before it.
+    pub fn swap(&self, data: T) -> Result<T, String> {
+        self.send_swap(data, None)
+    }
+
+    /// Replace the value of the object with `update(current, data)` and
+    /// return the previous value, e.g. a fetch-and-add with
+    /// `obj.fetch_update(n, u64::wrapping_add)`. Applied like a swap: under
+    /// the MONSTER algorithms an update losing a conflict is retried, so
+    /// concurrent updates from all processes take effect. Best-effort
+    /// algorithms give no such guarantee.
+    pub fn fetch_update(&self, data: T, update: fn(T, T) -> T) -> Result<T, String> {
+        self.send_swap(data, Some(update))
+    }
+
+    fn send_swap(&self, data: T, update: Option<fn(T, T) -> T>) -> Result<T, String> {
+        let (ack_tx, ack_rx) = kanal::unbounded();
+        let (prev_tx, prev_rx) = kanal::unbounded();
+        let req = WriteRequest::new_swap(self.info, data, ack_tx, prev_tx, &self.pending_writes)
+            .with_update(update)
+            .with_seq(self.next_seq());
+
+        self.pace();
+        self.wreq_queue_tx
+            .send(req)
+            .map_err(|e| format!("Failed to send to object queue: {}", e))?;
+
+        let ack = match ack_rx.recv() {
+            Ok(ack) if ack.success => ack,
+            Ok(_) => return Err("Failed swap operation".into()),
+            Err(e) => return Err(format!("Failed to receive ack: {}", e)),
+        };
+        let prev = prev_rx.recv().map_err(|e| format!("Failed to receive previous value: {}", e))?;
+        self.fence_write(update.map_or(data, |update| update(prev, data)));
+        self.count_ack(&ack);
+        Ok(prev)
+    }
+
+    /// Number of writes of this handle lost since the last check, or since
+    /// the first write for the first check. Each write carries the next
+    /// sequence number of the handle, stored with the value, so the stored
+    /// number advances by exactly one per acked write unless writes were
+    /// dropped, e.g. by a restart of the write worker. A lost write is
+    /// detected once a later write of the handle is stored.
+    ///
+    /// Assumes the handle is the only writer of the object and none of its
+    /// writes is in flight.
+    pub fn check_continuity(&self) -> Result<u64, String> {
+        let Some(view) = &self.group_view else {
+            return Err("Object handle has no group view".into());
+        };
+        let stored = safe_memio::mem_readall::<T>(self.info.offset, &view.memory_nodes)
+            .map_err(|safe_memio::MemoryError(mnid)| format!("Memory node {} failed during continuity check", mnid))?
+            .iter()
+            .filter(|ome| ome.written)
+            .map(|ome| ome.seq)
+            .max()
+            .unwrap_or(0);
+        let acked = self.write_seq.acked.count();
+
+        let mut checked = self.write_seq.checked.lock().unwrap();
+        let (last_stored, last_acked) = *checked;
+        *checked = (stored, acked);
+        let gaps = stored.saturating_sub(last_stored).saturating_sub(acked - last_acked);
+        if gaps > 0 {
+            warn!("{} lost writes detected on object {}", gaps, self.info.id);
+        }
+        Ok(gaps)
+    }
+
+    /// Durability barrier: block until every memory node holds the last
+    /// write acked through this handle, or a later one. With the "quorum"
+    /// ack policy a write is acked once a majority of the nodes holds it,
+    /// e.g. flush at a checkpoint to wait for the other nodes.
+    ///
+    /// Fails if a memory node cannot be read, or still misses the write
+    /// after `FLUSH_TIMEOUT`, e.g. if the writer crashed while replicating.
+    pub fn flush(&self) -> Result<(), String> {
+        self.flush_timeout(Self::FLUSH_TIMEOUT)
+    }
+
+    /// Same as `flush`, failing after `timeout`
+    pub fn flush_timeout(&self, timeout: Duration) -> Result<(), String> {
+        // algorithms without write ids ack once every node holds the write
+        let Some(target) = self.write_seq.acked.last_wid() else {
+            return Ok(());
+        };
+        let Some(view) = &self.group_view else {
+            return Err("Object handle has no group view".into());
+        };
+
+        let deadline = Instant::now() + timeout;
+        loop {
+            let omes = safe_memio::mem_readall::<T>(self.info.offset, &view.memory_nodes)
+                .map_err(|safe_memio::MemoryError(mnid)| format!("Memory node {} failed during flush", mnid))?;
+            let behind: Vec<usize> = omes.iter().zip(&view.memory_nodes)
+                .filter(|(ome, _)| !ome.written || ome.wid < target)
+                .map(|(_, node)| node.id)
+                .collect();
+            if behind.is_empty() {
+                return Ok(());
+            }
+            if Instant::now() >= deadline {
+                return Err(format!("Memory nodes {:?} miss write {:?} of object {} after {:?}",
+                    behind, target, self.info.local_id(), timeout));
+            }
+            debug!("Object {} not yet on memory nodes {:?}, waiting", self.info.local_id(), behind);
+            std::thread::sleep(Self::FLUSH_POLL);
+        }
+    }
+}
+
+impl<T: Copy + PartialEq + std::fmt::Debug> RepCXLObject<T> {
     pub fn read(&self) -> Result<ReadReturn<T>, String> {
         let (ack_tx, ack_rx) = kanal::unbounded();
         let req = ReadRequest::new(self.info, ack_tx);
@@ -139,11 +698,70 @@ impl<T: Copy> RepCXLObject<T> {
             .unwrap();
 
         // wait for ack
+        let res = match ack_rx.recv() {
+            Ok(ack) => ack,
+            Err(e) => Err(format!("Failed to receive read ack: {}", e)),
+        };
+        self.fence_read(res)
+    }
+
+    /// Read a value written at most `max_stale_rounds` rounds ago. If the
+    /// end nodes hold an older value, every node is read again for a newer
+    /// write, and `ReadReturn::TooStale` is returned if none is found. The
+    /// age of a write counts from its Try round, two rounds before it
+    /// commits. Only supported by the MONSTER algorithms.
+    pub fn read_fresh(&self, max_stale_rounds: u64) -> Result<ReadReturn<T>, String> {
+        let (ack_tx, ack_rx) = kanal::unbounded();
+        let req = ReadRequest::new(self.info, ack_tx).with_max_stale_rounds(max_stale_rounds);
+        self.rreq_queue_tx
+            .send(req)
+            .map_err(|e| format!("Failed to send to object queue: {}", e))?;
+
         match ack_rx.recv() {
-            Ok(return_val) => Ok(return_val),
+            Ok(ack) => ack,
             Err(e) => Err(format!("Failed to receive read ack: {}", e)),
         }
     }
+
+    // apply the local fence to a read, see `with_local_fence`
+    fn fence_read(&self, res: Result<ReadReturn<T>, String>) -> Result<ReadReturn<T>, String> {
+        let Some(fence) = &self.local_fence else {
+            return res;
+        };
+        let mut fence = fence.lock().unwrap();
+        let Some(written) = *fence else {
+            return res;
+        };
+        match res {
+            Ok(ReadReturn::ReadSafe(value)) if value == written => {
+                // the write landed, later values come from other writers
+                *fence = None;
+                res
+            }
+            Ok(_) => Ok(ReadReturn::ReadDirty(written)),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Read the object from the memory nodes in the calling thread, without
+    /// going through the read worker. Only valid for best-effort algorithms,
+    /// whose reads do not depend on the round state. Uses the memory nodes
+    /// of the group when the object was created or looked up.
+    ///
+    /// If the memory nodes have different latency hints (`mem_node_latency`),
+    /// only the nearest live node is read, see `best_effort_read_nearest`.
+    pub fn read_direct(&self) -> Result<ReadReturn<T>, String> {
+        let res = match &self.direct_view {
+            Some(view) if view.has_locality() => view.read_allocated(&self.info, || {
+                algorithms::best_effort::best_effort_read_nearest(view, &self.info)
+            }),
+            Some(view) => view.read_allocated(&self.info, || {
+                algorithms::best_effort::async_best_effort_read(view, &self.info)
+            }),
+            None => return Err("read_direct is only supported by best-effort algorithms".into()),
+        };
+        self.fence_read(res)
+    }
 }
 
 
@@ -158,11 +776,31 @@ pub struct RepCXL<T> {
     wreq_queue_rx: Option<kanal::Receiver<WriteRequest<T>>>,
     rreq_queue_tx: kanal::Sender<ReadRequest<T>>,
     rreq_queue_rx: Option<kanal::Receiver<ReadRequest<T>>>,
+    // receivers handed to the workers by `start`, kept to restart them, see
+    // `switch_algorithm`
+    worker_wreq_queue: Option<kanal::Receiver<WriteRequest<T>>>,
+    worker_rreq_queue: Option<kanal::Receiver<ReadRequest<T>>>,
+    pending_writes: Arc<AtomicUsize>, // write requests not yet processed by the worker
+    // set with `write_rate_limit`, shared with the object handles
+    write_limiter: Option<Arc<timer::RateLimiter>>,
+    inflight_writes: Arc<request::InflightWrites>,
+    on_write_complete: Option<algorithms::WriteCallback>,
     stop_flag: Arc<AtomicBool>,
     algorithm_ctx: algorithms::AlgorithmCallContext,
+    instance_token: u64, // random, tells apart instances configured with the same id
+    // threads started by `sync_start`, joined by `stop`
+    workers: std::sync::Mutex<Vec<std::thread::JoinHandle<()>>>,
+    // highest commit version read per object id, see `read_object_monotonic`
+    observed_versions: std::sync::Mutex<HashMap<usize, (u64, ReadReturn<T>)>>,
+    // results of the audit thread, see `audit_interval`
+    audit: Arc<std::sync::Mutex<fsck::AuditState>>,
+    // observed write order, see `enable_order_checker`
+    order_checker: Option<Arc<std::sync::Mutex<fsck::OrderChecker>>>,
 }
 
 impl<T: Send + Copy + PartialEq + std::fmt::Debug + 'static> RepCXL<T> {
+    /// How long `clear_objects` waits for the queued writes of the process
+    pub const DRAIN_TIMEOUT: Duration = Duration::from_secs(5);
 
     /// Create a new empty repCXL instance
     pub fn new(config: RepCXLConfig) -> Self {
@@ -175,10 +813,19 @@ impl<T: Send + Copy + PartialEq + std::fmt::Debug + 'static> RepCXL<T> {
         let mut view = GroupView::new(config.id as usize);
         view.processes = config.processes.clone(); // add all processes to group view
 
+        let replication_pool = (config.replication_threads > 1)
+            .then(|| Arc::new(safe_memio::ReplicationPool::new(config.replication_threads)));
+        let write_limiter = config.write_rate_limit.map(|rate| Arc::new(timer::RateLimiter::new(rate)));
+        let inflight_writes = Arc::new(request::InflightWrites::new(config.max_inflight_writes));
+
         // open memory nodes
         for path in config.mem_nodes.iter() {
+            if let Err(e) = MemoryNode::check_file_size(path, 0, config.mem_size) {
+                panic!("{}", e);
+            }
             let mnid = view.memory_nodes.len();
-            let node = MemoryNode::from_file(mnid, path, config.mem_size);
+            let mut node = MemoryNode::from_file(mnid, path, config.mem_size);
+            Self::configure_node(&config, &mut node, replication_pool.clone());
             view.memory_nodes.push(node);
         }
 
@@ -193,6 +840,13 @@ impl<T: Send + Copy + PartialEq + std::fmt::Debug + 'static> RepCXL<T> {
             read_offset: config.read_offset,
             logger: None, // will be set if file logging is enabled
             stats: algorithms::monster::MonsterStats::new(),
+            backoff: algorithms::monster::ConflictBackoff::new(
+                config.conflict_backoff_threshold,
+                config.conflict_backoff_max,
+            ),
+            timestamp_order: config.conflict_order == "timestamp",
+            single_writer: config.single_writer,
+            jitter: Some(Arc::new(timer::JitterRecorder::new())),
         };
 
         RepCXL {
@@ -203,19 +857,49 @@ impl<T: Send + Copy + PartialEq + std::fmt::Debug + 'static> RepCXL<T> {
             wreq_queue_rx: Some(wrx),
             rreq_queue_tx: rtx,
             rreq_queue_rx: Some(rrx),
+            worker_wreq_queue: None,
+            worker_rreq_queue: None,
+            pending_writes: Arc::new(AtomicUsize::new(0)),
+            write_limiter,
+            inflight_writes,
+            on_write_complete: None,
             stop_flag: Arc::new(AtomicBool::new(false)),
             algorithm_ctx: acfg,
+            instance_token: rand::random::<u64>().max(1),
+            observed_versions: std::sync::Mutex::new(HashMap::new()),
+            workers: std::sync::Mutex::new(Vec::new()),
+            audit: Arc::new(std::sync::Mutex::new(fsck::AuditState::default())),
+            order_checker: None,
         }
     }
 
-    /// Enable state logging to a file. Clears any existing log at the path.
-    /// The algorithm thread will append state transitions to this file.
+    /// Enable state logging to a file. Clears any existing log at the path,
+    /// e.g. left by a previous run. The algorithm thread will append state
+    /// transitions to this file.
     pub fn enable_file_log(&mut self, path: &str) {
+        self.enable_file_log_with(path, true);
+    }
+
+    /// Same as `enable_file_log`, keeping the existing log at the path unless
+    /// `truncate` is set
+    pub fn enable_file_log_with(&mut self, path: &str, truncate: bool) {
         let mut log = utils::ms_logger::MonsterStateLogger::new(path);
-        log.clear();
+        if truncate {
+            log.clear();
+        }
         self.algorithm_ctx.logger = Some(path.to_string());
     }
 
+    /// Register a callback invoked after each acknowledged write with the
+    /// object id and the write latency, e.g. to feed an external metrics
+    /// system. Must be set before starting the protocol threads.
+    ///
+    /// The callback runs on the write worker between two requests: it must
+    /// be cheap and never block, otherwise it stalls the protocol rounds.
+    pub fn on_write_complete(&mut self, callback: impl Fn(usize, Duration) + Send + Sync + 'static) {
+        self.on_write_complete = Some(Arc::new(callback));
+    }
+
     pub fn register_process(&mut self, pid: u32) {
         self.view.add_process(pid);
     }
@@ -224,21 +908,187 @@ impl<T: Send + Copy + PartialEq + std::fmt::Debug + 'static> RepCXL<T> {
         self.view.get_coordinator() == Some(self.config.id as u32)
     }
 
-    pub fn add_memory_node_from_file(&mut self, path: &str) {
+    /// Size of the file backing a memory node of `config`, header and shared
+    /// state included
+    pub fn required_node_size(config: &RepCXLConfig) -> usize {
+        config.mem_size
+    }
+
+    /// Set up the memory node files of `config`: create the missing ones and
+    /// grow them to `required_node_size`. Idempotent, so any process can call
+    /// it before `new`; existing contents are kept.
+    pub fn provision(config: &RepCXLConfig) -> Result<(), String> {
+        let size = Self::required_node_size(config);
+        for path in &config.mem_nodes {
+            MemoryNode::provision_file(path, size)?;
+        }
+        Ok(())
+    }
+
+    /// Add a memory node to the group. Fails if the node is smaller than the
+    /// configured region, since object offsets must be valid on every node.
+    pub fn add_memory_node_from_file(&mut self, path: &str) -> Result<(), String> {
+        self.add_memory_node_from_file_at(path, 0)
+    }
+
+    /// Add a memory node mapping the configured region starting at
+    /// `file_offset` in the file, e.g. one of several regions of a large DAX
+    /// device. The offset must be 2 MiB aligned and the region must fit in
+    /// the file.
+    pub fn add_memory_node_from_file_at(&mut self, path: &str, file_offset: usize) -> Result<(), String> {
+        MemoryNode::check_file_size(path, file_offset, self.config.mem_size)?;
+        let id = self.view.memory_nodes.len();
+        let mut node = MemoryNode::from_file_at(id, path, self.config.mem_size, file_offset);
+        Self::configure_node(&self.config, &mut node, self.replication_pool());
+        self.view.memory_nodes.push(node);
+        Ok(())
+    }
+
+    /// Add a memory node backed by a zeroed heap region of `mem_size` bytes
+    /// instead of a file. Returns the region so that other instances in the
+    /// same process can share the node with `add_shared_memory_node_heap`.
+    pub fn add_memory_node_heap(&mut self) -> Arc<HeapMemory> {
+        let heap = HeapMemory::new(self.config.mem_size);
+        self.add_shared_memory_node_heap(heap.clone())
+            .expect("Heap memory node has the configured size");
+        heap
+    }
+
+    /// Add a memory node backed by a heap region created by another instance.
+    /// Fails if the region is smaller than the configured one.
+    pub fn add_shared_memory_node_heap(&mut self, heap: Arc<HeapMemory>) -> Result<(), String> {
+        if heap.size() < self.config.mem_size {
+            return Err(format!(
+                "Heap memory node is smaller ({} bytes) than the configured region ({} bytes)",
+                heap.size(), self.config.mem_size
+            ));
+        }
         let id = self.view.memory_nodes.len();
-        let node = MemoryNode::from_file(id, path, self.config.mem_size);
+        let mut node = MemoryNode::from_heap(id, heap);
+        Self::configure_node(&self.config, &mut node, self.replication_pool());
         self.view.memory_nodes.push(node);
+        Ok(())
+    }
+
+    /// Apply the per-node settings of the config to a new node, however it
+    /// is added: two-phase commit, replication pool, memory locking, latency
+    /// hint and scheduled faults (by node id)
+    fn configure_node(config: &RepCXLConfig, node: &mut MemoryNode, replication_pool: Option<Arc<safe_memio::ReplicationPool>>) {
+        node.set_two_phase_commit(config.two_phase_commit);
+        node.set_replication_pool(replication_pool);
+        if config.lock_memory {
+            if let Err(e) = node.lock_memory() {
+                warn!("{}, continuing with an unlocked node", e);
+            }
+        }
+        if let Some(&latency) = config.mem_node_latency.get(node.id) {
+            node.set_latency(latency);
+        }
+        let faults: Vec<safe_memio::FaultWindow> = config.fault.iter()
+            .filter(|f| f.node == node.id)
+            .map(|f| safe_memio::FaultWindow {
+                start_round: f.start_round,
+                end_round: f.start_round.saturating_add(f.fail_for_rounds),
+                reads: f.mode != "write",
+                writes: f.mode != "read",
+            })
+            .collect();
+        if !faults.is_empty() {
+            node.set_fault_schedule(faults);
+        }
+    }
+
+    /// Make memory node `node_id` the master node, whose copy of the
+    /// coordination state (WCCs, starting block) the processes read. The
+    /// lowest ID by default. All the processes of the group must pick the
+    /// same master. If the master fails, the next live node in ID order takes
+    /// over automatically.
+    pub fn set_master_node(&mut self, node_id: usize) -> Result<(), String> {
+        let node = self.view.memory_nodes.iter().find(|n| n.id == node_id)
+            .ok_or(format!("No memory node {}", node_id))?;
+        if node.is_failed() {
+            return Err(format!("Memory node {} failed", node_id));
+        }
+        self.view.master.store(node_id, Ordering::Release);
+        info!("Memory node {} is the master node", node_id);
+        Ok(())
+    }
+
+    /// Id of the current master node, see `set_master_node`
+    pub fn master_node(&self) -> Option<usize> {
+        self.view.get_master_node().map(|n| n.id)
     }
 
-    pub fn init_state(&mut self) {
+    /// Write a fresh shared state to every memory node. Fails if a node
+    /// already holds a valid state, e.g. one to recover from, unless
+    /// `force_init` is set. The written state is read back from every node.
+    pub fn init_state(&mut self) -> Result<(), String> {
+        // a replica would wipe the objects of the coordinator
         if !self.is_coordinator() {
-            warn!("Only the coordinator should initialize state");
-            return;
+            warn!("Only the coordinator can initialize the state");
+            return Err("Only the coordinator can initialize the state".into());
+        }
+
+        if self.view.memory_nodes.is_empty() {
+            return Err("No memory nodes in group, state not initialized".into());
+        }
+
+        // a process that crashed during a state update left the lock taken
+        if self.config.force_init {
+            for node in &self.view.memory_nodes {
+                node.reset_header();
+            }
+        }
+
+        let _lock = self.view.lock_state()?;
+        for node in &self.view.memory_nodes {
+            let existing = node.read_state();
+            // zeroed memory does not pass the checksum
+            if existing.checksum_ok() && existing.seq() > 0 {
+                if !self.config.force_init {
+                    return Err(format!(
+                        "Memory node {} already holds a shared state, set force_init to overwrite it",
+                        node.id
+                    ));
+                }
+                warn!("Overwriting the shared state of memory node {}", node.id);
+            }
+        }
+
+        let mut state = SharedState::new(self.config.mem_size, self.config.chunk_size);
+        state.set_algorithm(&self.config.algorithm);
+        state.set_round_time(self.config.round_time);
+        self.write_state_all(&mut state);
+
+        for node in &self.view.memory_nodes {
+            let written = node.read_state();
+            if !written.checksum_ok() || written.seq() != state.seq() {
+                return Err(format!("State read-back failed on memory node {}", node.id));
+            }
+        }
+        Ok(())
+    }
+
+    /// Zero the object region of every memory node, e.g. to prepare nodes
+    /// holding the objects of a previous run. Only the coordinator can zero
+    /// the nodes, and no process must be running rounds on them.
+    pub fn zero_object_regions(&mut self) -> Result<(), String> {
+        if !self.is_coordinator() {
+            warn!("Only the coordinator can zero the memory nodes");
+            return Err("Only the coordinator can zero the memory nodes".into());
         }
 
-        let state = SharedState::new(self.config.mem_size, self.config.chunk_size);
+        let _lock = self.view.lock_state()?;
+        for node in &self.view.memory_nodes {
+            node.zero_at(0, node.region_size());
+        }
+        Ok(())
+    }
 
-        // Write the shared state to each memory node
+    /// Write an updated shared state to every memory node, bumping its
+    /// sequence number so readers can tell the newest copy apart.
+    fn write_state_all(&self, state: &mut SharedState) {
+        state.bump_seq();
         for node in &self.view.memory_nodes {
             node.write_state(state);
         }
@@ -248,12 +1098,24 @@ impl<T: Send + Copy + PartialEq + std::fmt::Debug + 'static> RepCXL<T> {
         self.view.clone()
     }
 
-    fn read_state_from_any(&self) -> Result<SharedState, &str> {
+    /// Read the newest shared state, i.e. the one with the highest sequence
+    /// number across the memory nodes. A previous update might not have
+    /// reached every node.
+    /// If `state_checksum` is enabled, copies that fail the checksum
+    /// verification (e.g. torn by a crash during `write_state`) are skipped.
+    fn read_state_from_any(&self) -> Result<Box<SharedState>, &str> {
+        let mut newest: Option<Box<SharedState>> = None;
         for node in &self.view.memory_nodes {
             let state = node.read_state();
-            return Ok(state);
+            if self.config.state_checksum && !state.checksum_ok() {
+                warn!("State checksum mismatch on memory node {}, trying next node", node.id);
+                continue;
+            }
+            if newest.as_ref().is_none_or(|n| state.seq() > n.seq()) {
+                newest = Some(state);
+            }
         }
-        Err("Could not read state from any memory node!")
+        newest.ok_or("Could not read state from any memory node!")
     }
 
     // Get a mutable reference to the starting block from the master memory node
@@ -265,11 +1127,35 @@ impl<T: Send + Copy + PartialEq + std::fmt::Debug + 'static> RepCXL<T> {
         Err("Could not read state from master node!")
     }
 
-    pub fn dump_states(&mut self) {
+    /// Conflicts lost by the MONSTER writers of every process of the group,
+    /// as published in the shared state, e.g. to find the conflict hotspots
+    /// from a monitoring process. Counts since `init_state`.
+    pub fn conflict_report(&self) -> Result<ConflictReport, String> {
+        let master = self.view.get_master_node().ok_or("No memory node to read the state from")?;
+        Ok(master.get_state().get_conflict_stats().report())
+    }
+
+    /// Shared state as stored on each memory node, read-only
+    pub fn states_report(&self) -> Vec<NodeStateReport> {
+        fsck::check_states(&self.view)
+    }
+
+    /// Print `states_report`
+    pub fn dump_states(&self) {
         println!("#### state dump ####");
-        for node in &self.view.memory_nodes {
-            let state = node.read_state();
-            println!("Memory node {}:\n{:?}", node.id, state);
+        for report in self.states_report() {
+            println!(
+                "Memory node {}: seq {}{}, {}/{} bytes allocated, {} objects",
+                report.node_id,
+                report.seq,
+                if report.checksum_ok { "" } else { " (bad checksum)" },
+                report.memory.allocated_size,
+                report.memory.total_size,
+                report.memory.num_objects,
+            );
+            for (namespace, id) in &report.objects {
+                println!("  object {} (namespace {})", id, namespace);
+            }
         }
     }
 
@@ -278,63 +1164,157 @@ impl<T: Send + Copy + PartialEq + std::fmt::Debug + 'static> RepCXL<T> {
     ///
     /// # Arguments
     /// * `id` - Unique identifier for the object.
-    pub fn new_object(&mut self, id: usize) -> Option<RepCXLObject<T>> {
+    pub fn new_object(&mut self, id: usize) -> Result<RepCXLObject<T>, AllocError> {
+        // the whole entry, value and wid, padded and aligned
+        self.alloc_object(id, self.object_size())
+    }
+
+    // allocate `size` bytes for object `id` in the shared state
+    fn alloc_object(&mut self, id: usize, size: usize) -> Result<RepCXLObject<T>, AllocError> {
         if self.num_of_objects >= shmem::MAX_OBJECTS {
             warn!("Maximum number of objects reached");
-            return None;
+            return Err(AllocError::TooManyObjects);
         }
 
 
         // TODO: do it more cleanly
         if id >= shmem::MAX_OBJECTS {
             warn!("Allowed IDs: 0-{}", shmem::MAX_OBJECTS - 1);
-            return None;
+            return Err(AllocError::InvalidId);
         }
 
         if !self.is_coordinator() {
             warn!("Only the coordinator can create new objects");
-            return None;
+            return Err(AllocError::NotCoordinator);
         }
 
-        let size = std::mem::size_of::<ObjectMemoryEntry<T>>(); // padded and aligned
+        if let Err(e) = self.check_object_size(size) {
+            warn!("Object {} does not fit any memory node: {:?}", id, e);
+            return Err(e);
+        }
 
+        let _lock = match self.view.lock_state() {
+            Ok(lock) => lock,
+            Err(e) => {
+                warn!("{}", e);
+                return Err(AllocError::StateLocked);
+            }
+        };
         let mut state = self.read_state_from_any().unwrap();
 
         // try to alloc object
-        match state.object_index.alloc_object(id, size) {
-            Some(offset) => {
-                for node in &self.view.memory_nodes {
-                    // write state to every memory node
-                    node.write_state(state);
+        match state.object_index.alloc_object(self.scoped_id(id), size) {
+            Ok(offset) => {
+                // the offset must be valid on the smallest node
+                if let Err(e) = self.view.check_fits(&ObjectInfo::new(self.scoped_id(id), offset, size)) {
+                    warn!("{}", e);
+                    return Err(AllocError::OutOfSpace);
                 }
 
+                // write state to every memory node
+                self.write_state_all(&mut state);
+
                 // clone the request queues
                 let wtx = self.wreq_queue_tx.clone();
                 let rtx = self.rreq_queue_tx.clone();
                 // create the new RepCXLObject
-                let obj = RepCXLObject::new(id, offset, size, wtx, rtx);
+                let obj = RepCXLObject::new(self.scoped_id(id), offset, size, wtx, rtx, self.pending_writes.clone())
+                    .with_direct_view(self.direct_view())
+                    .with_rate_limiter(self.write_limiter.clone())
+                    .with_inflight_writes(self.inflight_writes.clone())
+                    .with_group_view(self.view.clone());
 
                 self.num_of_objects += 1;
-                return Some(obj);
+                return Ok(obj);
             }
-            None => {
-                info!("Failed to allocate object with id {} of size {}", id, size);
-                return None;
+            Err(e) => {
+                info!("Failed to allocate object with id {} of size {}: {:?}", id, size, e);
+                return Err(e);
             }
         }
     }
 
-    /// Creates a new object and initalizes it with a given value
-    pub fn new_object_with_val(&mut self, id: usize, value: T) -> Option<RepCXLObject<T>> {
-        if let Some(obj) = self.new_object(id) {
-            
-            // no write request ID for initialization
-            let entry = ObjectMemoryEntry::new_nowid(value);
+    /// Creates a versioned object keeping the last `versions` writes, see
+    /// `VersionedObject`. Takes `versions` times the space of an object.
+    pub fn new_versioned_object(&mut self, id: usize, versions: usize) -> Result<VersionedObject<T>, AllocError> {
+        assert!(versions > 0, "A versioned object needs at least one version");
+        let obj = self.alloc_object(id, versions * self.object_size())?;
+        Ok(VersionedObject::new(obj, versions, self.object_size()))
+    }
 
-            // write to all memory nodes
-            match safe_memio::mem_writeall(obj.info.offset, entry, &self.view.memory_nodes) {
+    /// Look up a versioned object created by `new_versioned_object` with the
+    /// same number of versions
+    pub fn get_versioned_object(&mut self, id: usize, versions: usize) -> Option<VersionedObject<T>> {
+        let obj = self.get_object(id)?;
+        // allocations are rounded up to whole chunks
+        if obj.info.size != (versions * self.object_size()).next_multiple_of(self.config.chunk_size) {
+            warn!("Object {} does not hold {} versions", id, versions);
+            return None;
+        }
+        Some(VersionedObject::new(obj, versions, self.object_size()))
+    }
+
+    /// Creates a ring of the last `capacity` pushed values, see
+    /// `RingObject`. Takes `capacity` times the space of an object.
+    pub fn new_ring(&mut self, id: usize, capacity: usize) -> Result<RingObject<T>, AllocError> {
+        assert!(capacity > 0, "A ring needs at least one entry");
+        let obj = self.alloc_object(id, capacity * self.object_size())?;
+        Ok(RingObject::new(obj, capacity, self.object_size(), self.view.clone()))
+    }
+
+    /// Look up a ring created by `new_ring` with the same capacity
+    pub fn get_ring(&mut self, id: usize, capacity: usize) -> Option<RingObject<T>> {
+        let obj = self.get_object(id)?;
+        // allocations are rounded up to whole chunks
+        if obj.info.size != (capacity * self.object_size()).next_multiple_of(self.config.chunk_size) {
+            warn!("Object {} does not hold a ring of {} entries", id, capacity);
+            return None;
+        }
+        Some(RingObject::new(obj, capacity, self.object_size(), self.view.clone()))
+    }
+
+    /// Write a new version of a versioned object, replacing its oldest
+    /// version. Concurrent writers may replace the same version, in which
+    /// case only the write ordered last is kept.
+    pub fn write_version(&mut self, obj: &VersionedObject<T>, data: T) -> Result<(), String> {
+        let slots = versioned::slots_by_age::<T>(obj, &self.view)
+            .map_err(|safe_memio::MemoryError(mnid)| format!("Memory node {} failed during read", mnid))?;
+        self.write_object(&obj.slots[*slots.last().unwrap()], data)
+    }
+
+    /// Read the `k`-th newest version of a versioned object, 0 being the
+    /// latest write. Uninitialized if fewer than `k + 1` versions were
+    /// written.
+    pub fn read_version(&self, obj: &VersionedObject<T>, k: usize) -> Result<ReadReturn<T>, String> {
+        if k >= obj.versions() {
+            return Err(format!("Object {} keeps {} versions, no version {}", obj.id(), obj.versions(), k));
+        }
+        let slots = versioned::slots_by_age::<T>(obj, &self.view)
+            .map_err(|safe_memio::MemoryError(mnid)| format!("Memory node {} failed during read", mnid))?;
+        self.read_object(&obj.slots[slots[k]])
+    }
+
+    /// Read the object from every memory node and return the value held by a
+    /// strict majority of them as ReadSafe, repairing the minority in the
+    /// background (see `anti_entropy::read_and_repair`). Bypasses the rounds
+    /// of the configured algorithm; without a majority the newest value is
+    /// returned as ReadDirty.
+    pub fn read_and_repair(&self, obj: &RepCXLObject<T>) -> Result<ReadReturn<T>, String> {
+        algorithms::anti_entropy::read_and_repair(&obj.info, &self.view)
+    }
+
+    /// Creates a new object and initalizes it with a given value
+    pub fn new_object_with_val(&mut self, id: usize, value: T) -> Option<RepCXLObject<T>> {
+        if let Ok(obj) = self.new_object(id) {
+            
+            // no write request ID for initialization
+            let entry = ObjectMemoryEntry::new_nowid(value);
+
+            // write to all memory nodes
+            self.view.count_client_write::<T>();
+            match safe_memio::mem_writeall(obj.info.offset, entry, &self.view.memory_nodes) {
                 Ok(_) => Some(obj),
-                Err(safe_memio::MemoryError(memory_node_id)) => {
+                Err(safe_memio::PartialWrite { failed: memory_node_id, .. }) => {
                     error!("Failed to write object {} to memory node {}", id, memory_node_id);
                     None
                 }
@@ -344,19 +1324,310 @@ impl<T: Send + Copy + PartialEq + std::fmt::Debug + 'static> RepCXL<T> {
         }
     }
 
+    /// Create an object with the smallest id not used in this instance's
+    /// namespace and write its initial value, so that callers do not track
+    /// ids themselves. Ids freed by `remove_object` are reused. Returns the id
+    /// with the object, None if no id is left or the object could not be
+    /// created. The ids follow the creation order, so replicas can get the
+    /// objects with `get_object` in the same order.
+    pub fn new_object_auto(&mut self, value: T) -> Option<(usize, RepCXLObject<T>)> {
+        let used: std::collections::HashSet<usize> = self.list_objects().into_iter().collect();
+        let Some(id) = (0..shmem::MAX_OBJECTS).find(|id| !used.contains(id)) else {
+            warn!("Maximum number of objects reached");
+            return None;
+        };
+        self.new_object_with_val(id, value).map(|obj| (id, obj))
+    }
+
     pub fn remove_object(&mut self, id: usize) {
         if !self.is_coordinator() {
             error!("Only the coordinator can remove objects");
             return;
         }
 
+        let _lock = match self.view.lock_state() {
+            Ok(lock) => lock,
+            Err(e) => {
+                error!("Object {} not removed: {}", id, e);
+                return;
+            }
+        };
         let mut state = self.read_state_from_any().unwrap();
-        state.object_index.dealloc_object(id);
+        state.object_index.dealloc_object(self.scoped_id(id));
 
         // Update the shared state in each memory node
-        for node in &mut self.view.memory_nodes {
-            node.write_state(state);
+        self.write_state_all(&mut state);
+    }
+
+    /// Convert object `id` to a value of type U, e.g. to migrate stored
+    /// objects to a new schema: the latest value is read from the memory
+    /// nodes, converted with `convert` and written to a new region sized for
+    /// U on every node. The shared state then points `id` to the new region
+    /// and frees the old one, so the object is never missing or half
+    /// converted. An object that was never written stays unwritten.
+    ///
+    /// Only the coordinator can migrate objects. The object must not be
+    /// written during the migration, and the `RepCXLObject<T>` handles of it
+    /// are invalid afterwards: look it up again with a `RepCXL<U>`.
+    pub fn migrate_object<U: Copy>(&mut self, id: usize, convert: impl Fn(T) -> U) -> Result<(), String> {
+        if !self.is_coordinator() {
+            error!("Only the coordinator can migrate objects");
+            return Err("Only the coordinator can migrate objects".into());
+        }
+        let size = safe_memio::object_size::<U>(self.config.two_phase_commit);
+        self.check_object_size(size)
+            .map_err(|e| format!("Object {} does not fit any memory node as the new type: {:?}", id, e))?;
+
+        let _lock = self.view.lock_state()?;
+        let mut state = self.read_state_from_any()?;
+        let old = state.object_index.lookup_object(self.scoped_id(id))
+            .ok_or_else(|| format!("Object {} not found", id))?;
+
+        let latest = safe_memio::mem_readall::<T>(old.offset, &self.view.memory_nodes)
+            .map_err(|safe_memio::MemoryError(mnid)| format!("Memory node {} failed during migration read", mnid))?
+            .into_iter()
+            .filter(|ome| ome.written)
+            .max_by_key(|ome| (ome.version, ome.wid));
+
+        // the old region stays allocated until the state is switched
+        let mut index = state.object_index;
+        let offset = index.realloc_object(old.id, size)
+            .map_err(|e| format!("Failed to allocate object {} as the new type: {:?}", id, e))?;
+        self.view.check_fits(&ObjectInfo::new(old.id, offset, size))?;
+
+        if let Some(ome) = latest {
+            let entry = ObjectMemoryEntry::new_nowid(convert(ome.value));
+            self.view.count_client_write::<U>();
+            safe_memio::mem_writeall(offset, entry, &self.view.memory_nodes)
+                .map_err(|safe_memio::PartialWrite { failed: mnid, .. }| format!("Memory node {} failed during migration write", mnid))?;
+        }
+
+        state.object_index = index;
+        self.write_state_all(&mut state);
+        info!("Object {} migrated from offset {} to {} ({} bytes)", id, old.offset, offset, size);
+        Ok(())
+    }
+
+    /// Remove all objects and reset the allocation table on every memory node.
+    /// Pending writes are drained first if the write worker is running.
+    /// Fails if they are not done within `DRAIN_TIMEOUT`, e.g. if the worker
+    /// stopped, or if the state cannot be locked.
+    ///
+    /// # Arguments
+    /// * `zero_data` - also zero the memory of the removed objects
+    pub fn clear_objects(&mut self, zero_data: bool) -> Result<(), String> {
+        if !self.is_coordinator() {
+            error!("Only the coordinator can clear objects");
+            return Err("Only the coordinator can clear objects".into());
+        }
+
+        self.drain_pending_writes(Instant::now() + Self::DRAIN_TIMEOUT)
+            .map_err(|e| format!("Objects not cleared: {}", e))?;
+
+        let _lock = self.view.lock_state()?;
+        let mut state = self.read_state_from_any().unwrap();
+
+        if zero_data {
+            for oi in state.object_index.objects() {
+                for node in &self.view.memory_nodes {
+                    node.zero_at(oi.offset, oi.size);
+                }
+            }
+        }
+
+        state.object_index.clear();
+        self.write_state_all(&mut state);
+        self.num_of_objects = 0;
+        Ok(())
+    }
+
+    /// IDs of the objects of this instance's namespace currently allocated in
+    /// the shared state
+    pub fn list_objects(&self) -> Vec<usize> {
+        let state = self.read_state_from_any().unwrap();
+        state.object_index.objects()
+            .filter(|oi| oi.namespace() == self.config.namespace)
+            .map(|oi| oi.local_id())
+            .collect()
+    }
+
+    /// Memory nodes handed to the objects for `read_direct`
+    fn direct_view(&self) -> Option<GroupView> {
+        (self.config.algorithm == "async_best_effort").then(|| self.view.clone())
+    }
+
+    /// Replication pool shared by the nodes, see `replication_threads`. Nodes
+    /// added after the first one reuse its pool
+    fn replication_pool(&self) -> Option<Arc<safe_memio::ReplicationPool>> {
+        match self.view.memory_nodes.first() {
+            Some(node) => node.shared_replication_pool(),
+            None => (self.config.replication_threads > 1)
+                .then(|| Arc::new(safe_memio::ReplicationPool::new(self.config.replication_threads))),
+        }
+    }
+
+    /// Object ids are scoped by the configured namespace
+    fn scoped_id(&self, id: usize) -> usize {
+        scoped_id(self.config.namespace, id)
+    }
+
+    /// Protocol run by this instance and its parameters, e.g. for benchmark
+    /// metadata. The round time is the one adopted from the coordinator once
+    /// the rounds started.
+    pub fn algorithm_info(&self) -> AlgorithmInfo {
+        AlgorithmInfo::from_config(&self.config)
+    }
+
+    /// Allocation statistics of the object region
+    pub fn memory_stats(&self) -> MemoryStats {
+        let state = self.read_state_from_any().unwrap();
+        state.object_index.stats()
+    }
+
+    /// Allocation layout of the object region: ordered segments covering the
+    /// whole region, one per object (of any namespace) and one per free gap.
+    /// Meant for plotting fragmentation, see `dump_states` for the full state
+    pub fn layout_map(&self) -> Vec<LayoutSegment> {
+        let state = self.read_state_from_any().unwrap();
+        state.object_index.layout()
+    }
+
+    /// Delays of the round starts of the MONSTER write path (worker or
+    /// caller thread). Empty for best-effort writes,
+    /// which do not follow rounds.
+    pub fn jitter_histogram(&self) -> JitterHistogram {
+        self.algorithm_ctx.jitter.as_ref().map(|j| j.snapshot()).unwrap_or_default()
+    }
+
+    /// Write amplification of this instance: bytes written to the memory
+    /// nodes over the value bytes of the writes requested by its clients. The
+    /// node bytes are the values (replication, read repair and anti-entropy
+    /// included) and the WCC entries of the MONSTER conflict checks, written
+    /// again on every retry. The number of nodes when writes replicate once
+    /// and skip the WCC, e.g. with `single_writer`.
+    pub fn write_amplification(&self) -> WriteAmplification {
+        WriteAmplification {
+            client_bytes: self.view.client_bytes.load(Ordering::Relaxed),
+            node_bytes: self.view.memory_nodes.iter().map(|n| n.bytes_written()).sum(),
+        }
+    }
+
+    /// Ids of the memory nodes that returned a zeroed entry for an object
+    /// written on the other nodes, e.g. after their backing file was
+    /// truncated or their device reset. MONSTER reads leave such entries out
+    /// of the consistency check.
+    pub fn zeroed_nodes(&self) -> Vec<usize> {
+        self.view.memory_nodes.iter()
+            .filter(|node| node.zeroed_reads() > 0)
+            .map(|node| node.id)
+            .collect()
+    }
+
+    /// Cross-check the memory nodes: shared state of every node, overlapping
+    /// object regions and the entries of every object in the index (read as
+    /// `T`, including the objects of other namespaces). Read-only and safe
+    /// to run on a live system, where a write being replicated shows up as
+    /// a divergent object.
+    pub fn fsck(&self) -> Result<FsckReport, String> {
+        let state = self.read_state_from_any()?;
+        let objects: Vec<ObjectInfo> = state.object_index.objects().collect();
+        let region_size = self.view.memory_nodes.iter()
+            .map(|node| node.region_size())
+            .min()
+            .unwrap_or(0);
+
+        Ok(FsckReport {
+            node_states: fsck::check_states(&self.view),
+            overlapping_objects: fsck::check_regions(&objects, region_size),
+            objects: objects.iter().map(|oi| fsck::check_object::<T>(oi, &self.view)).collect(),
+        })
+    }
+
+    /// Latest results of the background audit (see `audit_interval`): the
+    /// objects found inconsistent by their latest check and the rolling
+    /// inconsistency rate. Like `fsck`, a write being replicated can show up
+    /// as a divergent object.
+    pub fn audit_report(&self) -> Result<AuditReport, String> {
+        if self.config.audit_interval.is_none() {
+            return Err("Audit is not enabled, see audit_interval".into());
+        }
+        Ok(self.audit.lock().unwrap().report())
+    }
+
+    /// Correctness testing: check in the background, every round, that the
+    /// writes of each process to an object are observed in the order the
+    /// process issued them, using the sequence number every write handle
+    /// stores with its writes (see `RepCXLObject::check_continuity`). A
+    /// write observed after a later one of the same process, e.g. a stale
+    /// entry, is logged with both write ids and returned by
+    /// `order_violations`.
+    ///
+    /// Writes are told apart by their write id, so only the monster and
+    /// fmonster algorithms are supported. Must be called before `sync_start`.
+    pub fn enable_order_checker(&mut self) -> Result<(), String> {
+        if self.config.algorithm != "monster" && self.config.algorithm != "fmonster" {
+            return Err(format!("Order checker is not supported by algorithm {}", self.config.algorithm));
+        }
+        self.order_checker.get_or_insert_with(Default::default);
+        Ok(())
+    }
+
+    /// Ordering violations found since `enable_order_checker`
+    pub fn order_violations(&self) -> Result<Vec<OrderViolation>, String> {
+        let checker = self.order_checker.as_ref().ok_or("Order checker is not enabled")?;
+        Ok(checker.lock().unwrap().violations())
+    }
+
+    /// High-water mark of an object: the highest write round found across the
+    /// memory nodes, i.e. the round of its latest write (0 if never written).
+    /// Compared against `current_round`, gives the replication lag of the
+    /// object. None if the object does not exist or a memory node cannot be
+    /// read.
+    pub fn object_hwm(&self, id: usize) -> Option<u64> {
+        let state = self.read_state_from_any().ok()?;
+        let oi = state.object_index.lookup_object(self.scoped_id(id))?;
+        let omes: Vec<ObjectMemoryEntry<T>> = safe_memio::mem_readall(oi.offset, &self.view.memory_nodes).ok()?;
+        omes.iter().map(|ome| ome.wid.round_num).max()
+    }
+
+    // an object must fit the object region of the smallest memory node
+    fn check_object_size(&self, size: usize) -> Result<(), AllocError> {
+        let max = self.view.min_region_size();
+        if size > max {
+            return Err(AllocError::ObjectTooLarge { requested: size, max });
         }
+        Ok(())
+    }
+
+    /// Offsets that `(id, size)` objects would get if they were created in
+    /// order, given the current allocations, e.g. to check that a working set
+    /// fits before loading it. Does not change the shared state. Sizes are in
+    /// bytes, see `object_size` for the size of an object of type T.
+    pub fn plan_objects(&self, objects: &[(usize, usize)]) -> Result<Vec<Result<usize, AllocError>>, String> {
+        let state = self.read_state_from_any()?;
+        // objects rejected by new_object do not take space
+        let rejected = |id: usize, size: usize| {
+            if id >= shmem::MAX_OBJECTS {
+                return Some(AllocError::InvalidId);
+            }
+            self.check_object_size(size).err()
+        };
+        let valid: Vec<(usize, usize)> = objects.iter()
+            .filter(|&&(id, size)| rejected(id, size).is_none())
+            .map(|&(id, size)| (self.scoped_id(id), size))
+            .collect();
+        let mut planned = state.object_index.plan(&valid).into_iter();
+        Ok(objects.iter()
+            .map(|&(id, size)| match rejected(id, size) {
+                Some(e) => Err(e),
+                None => planned.next().unwrap(),
+            })
+            .collect())
+    }
+
+    /// Size in bytes of the region of an object of type T
+    pub fn object_size(&self) -> usize {
+        safe_memio::object_size::<T>(self.config.two_phase_commit)
     }
 
     /// Attempt to get an object reference by its ID first in the local cache
@@ -365,14 +1636,7 @@ impl<T: Send + Copy + PartialEq + std::fmt::Debug + 'static> RepCXL<T> {
 
         let state = self.read_state_from_any().unwrap();
 
-        if let Some(oi) = state.object_index.lookup_object(id) {
-            let obj = RepCXLObject::new(
-                id,
-                oi.offset,
-                oi.size,
-                self.wreq_queue_tx.clone(),
-                self.rreq_queue_tx.clone(),
-            );
+        if let Some(obj) = self.lookup_object(&state, id) {
             return Some(obj);
         }
         info!("Object {} not found in shared state", id);
@@ -380,13 +1644,51 @@ impl<T: Send + Copy + PartialEq + std::fmt::Debug + 'static> RepCXL<T> {
         None
     }
 
+    /// Same as `get_object`, waits until the coordinator creates the object,
+    /// e.g. for replicas started along with the coordinator. The shared state
+    /// is re-read every 10 ms, also while no node holds a valid state yet.
+    /// Fails once `timeout` has elapsed.
+    pub fn get_object_blocking(&mut self, id: usize, timeout: Duration) -> Result<RepCXLObject<T>, String> {
+        let start = Instant::now();
+        loop {
+            if let Ok(state) = self.read_state_from_any() {
+                if let Some(obj) = self.lookup_object(&state, id) {
+                    return Ok(obj);
+                }
+            }
+            if start.elapsed() >= timeout {
+                return Err(format!("Object {} not created within {:?}", id, timeout));
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+    }
 
-    fn write_threaded(&self, obj: &RepCXLObject<T>, data: T) -> Result<(), String> {
+    fn lookup_object(&self, state: &SharedState, id: usize) -> Option<RepCXLObject<T>> {
+        let oi = state.object_index.lookup_object(self.scoped_id(id))?;
+        Some(RepCXLObject::new(
+            oi.id,
+            oi.offset,
+            oi.size,
+            self.wreq_queue_tx.clone(),
+            self.rreq_queue_tx.clone(),
+            self.pending_writes.clone(),
+        )
+        .with_direct_view(self.direct_view())
+        .with_rate_limiter(self.write_limiter.clone())
+        .with_inflight_writes(self.inflight_writes.clone())
+        .with_group_view(self.view.clone()))
+    }
+
+
+    fn write_threaded(&self, obj: &RepCXLObject<T>, data: T, timestamp: u64) -> Result<(), String> {
         // let client_start = Instant::now();
         let (ack_tx, ack_rx) = kanal::unbounded();
-        let req = WriteRequest::new(obj.info, data, ack_tx);
+        let req = WriteRequest::new(obj.info, data, ack_tx, &self.pending_writes)
+            .with_timestamp(timestamp)
+            .with_seq(obj.next_seq());
         // let trace_id = req.trace_id;
 
+        obj.pace();
         self.wreq_queue_tx
             .send(req)
             .map_err(|e| format!("Failed to send to object queue: {}", e))?;
@@ -396,10 +1698,14 @@ impl<T: Send + Copy + PartialEq + std::fmt::Debug + 'static> RepCXL<T> {
         // std::thread::sleep(Duration::from_millis(10));
         // wait for ack
         let result = match ack_rx.recv() {
-            Ok(true) => Ok(()),
-            Ok(false) => Err("Failed write operation".into()),
+            Ok(ack) if ack.success => Ok(ack),
+            Ok(_) => Err("Failed write operation".into()),
             Err(e) => Err(format!("Failed to receive ack: {}", e)),
         };
+        let result = result.map(|ack| {
+            obj.fence_write(data);
+            obj.count_ack(&ack);
+        });
 
         // if trace_id % Self::WRITE_TRACE_SAMPLE_RATE == 0 {
         //     debug!(
@@ -424,7 +1730,7 @@ impl<T: Send + Copy + PartialEq + std::fmt::Debug + 'static> RepCXL<T> {
 
         // wait for ack
         match ack_rx.recv() {
-            Ok(return_val) => Ok(return_val),
+            Ok(ack) => ack,
             Err(e) => Err(format!("Failed to receive read ack: {}", e)),
         }
     }
@@ -435,12 +1741,29 @@ impl<T: Send + Copy + PartialEq + std::fmt::Debug + 'static> RepCXL<T> {
     /// - pipeline: whether to use the pipelined read/write threads. @TODO currently
     /// pipeline mode is still blocking, move to kanal::async_channel or similar
     pub fn write_object(&mut self, obj: &RepCXLObject<T>, data: T) -> Result<(), String> {
-        
+        self.write_object_at(obj, data, timer::wall_clock_ns())
+    }
+
+    /// Same as `write_object` with the timestamp (e.g. ns since the epoch)
+    /// given by the client instead of the wall clock. With the "timestamp"
+    /// conflict order the write with the largest timestamp wins.
+    pub fn write_object_at(&mut self, obj: &RepCXLObject<T>, data: T, timestamp: u64) -> Result<(), String> {
         if self.config.pipeline {
-            self.write_threaded(obj, data)
+            self.write_threaded(obj, data, timestamp)
         }
         else {
-            algorithms::write(&mut self.algorithm_ctx, &self.view, obj, data)
+            obj.pace();
+            let start = Instant::now();
+            let result = algorithms::write(&mut self.algorithm_ctx, &self.view, obj, data, timestamp)
+                .and_then(|ack| if ack.success { Ok(ack) } else { Err("Failed write operation".into()) });
+            if let (Ok(_), Some(cb)) = (&result, &self.on_write_complete) {
+                cb(obj.info.local_id(), start.elapsed());
+            }
+            if let Ok(ack) = &result {
+                obj.fence_write(data);
+                obj.count_ack(ack);
+            }
+            result.map(|_| ())
         }
     }
 
@@ -465,7 +1788,7 @@ impl<T: Send + Copy + PartialEq + std::fmt::Debug + 'static> RepCXL<T> {
             if self.config.pipeline {
                 self.read_threaded(obj)
             } else {
-                algorithms::read(&self.algorithm_ctx, &self.view, obj)
+                algorithms::read(&self.algorithm_ctx, &self.view, &obj.info)
             }
         };
 
@@ -481,10 +1804,134 @@ impl<T: Send + Copy + PartialEq + std::fmt::Debug + 'static> RepCXL<T> {
             }
         }
 
-        res
+        obj.fence_read(res)
+    }
+
+    /// Read an object without ever going back in time: the commit version of
+    /// the value is compared to the highest version this instance read for
+    /// the object. A lower version (e.g. read from nodes a write has not
+    /// reached yet) is retried up to `read_retries` times, then the value of
+    /// the highest version is returned. Returns the version along the value.
+    ///
+    /// Reads in the calling thread, MONSTER algorithms only.
+    pub fn read_object_monotonic(&self, obj: &RepCXLObject<T>) -> Result<(ReadReturn<T>, u64), String> {
+        let mut attempt = 0;
+        loop {
+            let (res, version) = algorithms::read_versioned(&self.algorithm_ctx, &self.view, &obj.info)?;
+            let mut observed = self.observed_versions.lock().unwrap();
+            match observed.get(&obj.info.id) {
+                Some(&(highest, ref cached)) if version < highest => {
+                    if attempt < self.config.read_retries {
+                        attempt += 1;
+                        continue;
+                    }
+                    debug!("Read version {} of object {} behind {}, returning cached value", version, obj.info.id, highest);
+                    return Ok((cached.clone(), highest));
+                }
+                _ => {
+                    observed.insert(obj.info.id, (version, res.clone()));
+                    return Ok((res, version));
+                }
+            }
+        }
+    }
+
+
+
+    /// Read multiple objects with a single request, i.e. the read worker reads
+    /// them in one sweep. Each object is read independently (no guarantee that
+    /// the values belong to the same round) and read retries are not applied.
+    ///
+    /// Objects that do not exist or could not be read are missing from the
+    /// returned map, a failed read does not stop the reads of the others.
+    pub fn read_many(&self, ids: &[usize]) -> HashMap<usize, ReadReturn<T>> {
+        let mut results = HashMap::with_capacity(ids.len());
+
+        let state = match self.read_state_from_any() {
+            Ok(state) => state,
+            Err(e) => {
+                error!("{}", e);
+                return results;
+            }
+        };
+
+        let obj_infos: Vec<ObjectInfo> = ids
+            .iter()
+            .filter_map(|&id| {
+                let oi = state.object_index.lookup_object(self.scoped_id(id));
+                if oi.is_none() {
+                    info!("Object {} not found in shared state", id);
+                }
+                oi
+            })
+            .collect();
+
+        if self.config.pipeline {
+            let (ack_tx, ack_rx) = kanal::unbounded();
+            let req = ReadRequest::new_batch(obj_infos.clone(), ack_tx);
+            if let Err(e) = self.rreq_queue_tx.send(req) {
+                error!("Failed to send to object queue: {}", e);
+                return results;
+            }
+
+            // results come back in request order
+            for oi in obj_infos {
+                match ack_rx.recv() {
+                    Ok(Ok(rr)) => {
+                        results.insert(oi.local_id(), rr);
+                    }
+                    Ok(Err(e)) => {
+                        error!("Failed to read object {}: {}", oi.local_id(), e);
+                    }
+                    Err(_) => break,
+                }
+            }
+        } else {
+            for oi in obj_infos {
+                match algorithms::read(&self.algorithm_ctx, &self.view, &oi) {
+                    Ok(rr) => {
+                        results.insert(oi.local_id(), rr);
+                    }
+                    Err(e) => {
+                        error!("Failed to read object {}: {}", oi.local_id(), e);
+                    }
+                }
+            }
+        }
+
+        results
     }
 
+    /// Durability barrier, see `RepCXLObject::flush`. The writes of the
+    /// process still queued are drained first, both within
+    /// `RepCXLObject::FLUSH_TIMEOUT`.
+    pub fn flush_object(&self, obj: &RepCXLObject<T>) -> Result<(), String> {
+        let deadline = Instant::now() + RepCXLObject::<T>::FLUSH_TIMEOUT;
+        self.drain_pending_writes(deadline)?;
+        obj.flush_timeout(deadline.saturating_duration_since(Instant::now()))
+    }
 
+    /// Wait until the write worker is done with the queued writes of the
+    /// process. Fails at `deadline`, e.g. if the worker gave up restarting
+    /// and the writes are never done.
+    fn drain_pending_writes(&self, deadline: Instant) -> Result<(), String> {
+        // the worker owns the receiver once started
+        if !self.config.pipeline || self.wreq_queue_rx.is_some() {
+            return Ok(());
+        }
+        let round_time = Duration::from_nanos(self.config.round_time);
+        loop {
+            let pending = self.pending_writes.load(Ordering::Acquire);
+            if pending == 0 {
+                return Ok(());
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(format!("{} queued writes still pending, the write worker may have stopped", pending));
+            }
+            std::thread::sleep(round_time.min(deadline - now));
+        }
+    }
 
     /// Start the repCXL protocol threads without initial synchronization (for async protocols)
     pub fn start(&mut self) {
@@ -497,67 +1944,223 @@ impl<T: Send + Copy + PartialEq + std::fmt::Debug + 'static> RepCXL<T> {
         }
 
 
+        // context of the write worker, the other threads derive theirs from it
+        let base = algorithms::AlgorithmThreadContext {
+            group_view: self.view.clone(),
+            start_instant: self.algorithm_ctx.start_instant,
+            round_time: Duration::from_nanos(self.config.round_time),
+            read_offset: self.config.read_offset,
+            stop_flag: self.stop_flag.clone(),
+            logger: self.algorithm_ctx.logger.clone(),
+            on_write_complete: self.on_write_complete.clone(),
+            backoff_threshold: self.config.conflict_backoff_threshold,
+            backoff_max_rounds: self.config.conflict_backoff_max,
+            timestamp_order: self.config.conflict_order == "timestamp",
+            single_writer: self.config.single_writer,
+            write_batch_size: self.config.write_batch_size,
+            jitter: self.algorithm_ctx.jitter.clone(),
+            ack_policy: request::AckPolicy::from_name(&self.config.ack_policy).unwrap_or(request::AckPolicy::All),
+        };
+
         // pipeline mode uses threads and requests queues
         if self.config.pipeline {
             // for both read and write threads move the rx queue to the thread
             // and keep the tx queue in main state
 
-            // build thread context
-            let wactx = algorithms::AlgorithmThreadContext {
-                group_view: self.view.clone(),
-                start_instant: self.algorithm_ctx.start_instant,
-                round_time: Duration::from_nanos(self.config.round_time),
-                read_offset: self.config.read_offset,
-                stop_flag: self.stop_flag.clone(),
-                logger: self.algorithm_ctx.logger.clone(),
-            };
-
+            let wactx = base.clone();
             let ractx = wactx.clone();
 
             // WRITE thread
             let wreq_queue = self.wreq_queue_rx.take().expect("Receiver already taken");
+            let rreq_queue = self.rreq_queue_rx.take().expect("Receiver already taken");
+            self.worker_wreq_queue = Some(wreq_queue.clone());
+            self.worker_rreq_queue = Some(rreq_queue.clone());
 
             let core_affinity = self.config.core_affinity;
-            std::thread::spawn(move || {
+            let worker_restarts = self.config.worker_restarts;
+            let writer = std::thread::spawn(move || {
                 if let Some(core) = core_affinity {
                         core_affinity::set_for_current(core_affinity::CoreId { id: core });
                 }
-                algorithms::write_thread(&algorithm, wactx, wreq_queue);
+                algorithms::supervised_write_thread(&algorithm, wactx, wreq_queue, worker_restarts);
             });
 
             // READ thread
             let algo = self.config.algorithm.clone();
-            let rreq_queue = self.rreq_queue_rx.take().expect("Receiver already taken");
 
-            std::thread::spawn(move || {
+            let reader = std::thread::spawn(move || {
                 // @TODO: pin thread for read?
                 algorithms::read_thread(&algo, ractx, rreq_queue);
             });
+            self.workers.lock().unwrap().extend([writer, reader]);
         }
 
+        // ANTI-ENTROPY thread, needs wids to find the nodes that are behind
+        if let Some(interval) = self.config.anti_entropy_interval {
+            if self.config.algorithm == "monster" || self.config.algorithm == "fmonster" {
+                let actx = base.background();
+                let batch = self.config.anti_entropy_batch;
+                info!("Starting anti-entropy thread every {} rounds, batch {}", interval, batch);
+                let worker = std::thread::spawn(move || {
+                    algorithms::anti_entropy::anti_entropy_thread::<T>(actx, interval, batch);
+                });
+                self.workers.lock().unwrap().push(worker);
+            } else {
+                warn!("Anti-entropy is not supported by algorithm {}", self.config.algorithm);
+            }
+        }
+
+        // AUDIT thread
+        if let Some(interval) = self.config.audit_interval {
+            let actx = algorithms::AlgorithmThreadContext { read_offset: None, ..base.background() };
+            let (sample, audit) = (self.config.audit_sample, self.audit.clone());
+            info!("Starting audit thread every {} rounds, sample {}", interval, sample);
+            let worker = std::thread::spawn(move || {
+                algorithms::audit::audit_thread::<T>(actx, interval, sample, audit);
+            });
+            self.workers.lock().unwrap().push(worker);
+        }
+
+        // ORDER CHECKER thread, see enable_order_checker
+        if let Some(checker) = &self.order_checker {
+            let actx = algorithms::AlgorithmThreadContext { read_offset: None, ..base.background() };
+            let checker = checker.clone();
+            info!("Starting order checker thread");
+            let worker = std::thread::spawn(move || {
+                algorithms::audit::order_check_thread::<T>(actx, checker);
+            });
+            self.workers.lock().unwrap().push(worker);
+        }
+
+        // MSYNC thread, durability of file-backed memory nodes
+        if let Some(interval) = self.config.msync_interval_rounds {
+            let actx = algorithms::AlgorithmThreadContext { read_offset: None, ..base.background() };
+            info!("Starting msync thread every {} rounds", interval);
+            let worker = std::thread::spawn(move || {
+                algorithms::msync::msync_thread(actx, interval);
+            });
+            self.workers.lock().unwrap().push(worker);
+        }
+
+    }
+
+    /// Number of the current protocol round, as seen by the worker. Only
+    /// meaningful after `sync_start`.
+    pub fn current_round(&self) -> u64 {
+        timer::current_round(self.algorithm_ctx.start_instant, self.algorithm_ctx.round_time)
+    }
 
+    /// Block until the protocol reaches round `round`, e.g. to align actions
+    /// to round boundaries. Returns immediately if the round has passed.
+    pub fn wait_for_round(&self, round: u64) {
+        timer::wait_round(
+            self.algorithm_ctx.start_instant,
+            self.algorithm_ctx.round_time,
+            round,
+            timer::WAIT_ROUND_SLEEP_RATIO,
+        );
     }
 
-    /// Synchronize processes in the group and start repCXL rounds.
+    /// Switch the group to `algorithm` at round `at_round`, e.g. to compare
+    /// algorithms on the same objects without restarting the instances.
+    /// Every process of the group must call it with the same round: each
+    /// waits for the round, lets its workers finish the write in progress
+    /// and restarts them with the new algorithm, keeping the queued requests.
+    /// The coordinator publishes the new algorithm for the processes that
+    /// join later.
+    pub fn switch_algorithm(&mut self, algorithm: &str, at_round: u64) -> Result<(), String> {
+        if !matches!(algorithm, "async_best_effort" | "monster" | "fmonster") {
+            return Err(format!("Unknown algorithm {}", algorithm));
+        }
+        let previous = std::mem::replace(&mut self.config.algorithm, algorithm.to_string());
+        if let Err(e) = self.config.validate() {
+            self.config.algorithm = previous;
+            return Err(e);
+        }
+        if self.current_round() >= at_round {
+            self.config.algorithm = previous;
+            return Err(format!("Round {} already started, cannot switch algorithm", at_round));
+        }
+
+        self.wait_for_round(at_round);
+        info!("Switching from {} to {} at round {}", previous, algorithm, at_round);
+        self.stop_workers();
+        self.stop_flag = Arc::new(AtomicBool::new(false));
+        if self.wreq_queue_rx.is_none() {
+            self.wreq_queue_rx = self.worker_wreq_queue.take();
+            self.rreq_queue_rx = self.worker_rreq_queue.take();
+        }
+        self.algorithm_ctx.algorithm = algorithm.to_string();
+
+        if self.is_coordinator() {
+            let _lock = self.view.lock_state()?;
+            let mut state = self.read_state_from_any()?;
+            state.set_algorithm(algorithm);
+            self.write_state_all(&mut state);
+        }
+        self.start();
+        Ok(())
+    }
+
+    /// Synchronize processes in the group and start repCXL rounds, running
+    /// the algorithm and round time of the config.
     /// **assumes sync'ed clocks**
     /// All processes must call this function with the same group view to
     /// ensure consistency.
-    pub fn sync_start(&mut self) {
+    ///
+    /// The coordinator waits for all processes to be ready, or for
+    /// `min_ready` of them once `ready_timeout` has elapsed.
+    ///
+    /// Fails after `sync_timeout` if the rounds did not start, reporting the
+    /// processes that were not ready.
+    ///
+    /// Fails if the configured algorithm differs from the one published by
+    /// the coordinator in the shared state. The round time published by the
+    /// coordinator replaces the configured one.
+    ///
+    /// Fails if this process does not compute the same shared state offsets
+    /// as the coordinator, or if the shared state holds an absolute address.
+    pub fn sync_start(&mut self) -> Result<(), String> {
+        if self.view.memory_nodes.is_empty() {
+            error!("FATAL: No memory nodes in group");
+            return Err("No memory nodes in group, add at least one before sync_start".into());
+        }
+
         if let Some(_coord) = self.view.get_coordinator() {
-            let mstate = self.get_state_from_master().unwrap();
-            let sblock = mstate.get_starting_block();
+            self.check_group_params()?;
+            self.check_layout()?;
+
             let start_time;
-            // mark self as ready
-            sblock.mark_ready(self.config.id as usize);
+            // mark self as ready, on every live node in case the master fails
+            for node in self.view.memory_nodes.iter().filter(|n| !n.is_failed()) {
+                if let Err(e) = node.get_state().get_starting_block().mark_ready(self.config.id as usize, self.instance_token) {
+                    error!("FATAL: {}", e);
+                    return Err(e);
+                }
+            }
             info!("Process {} ready and waiting to start", self.config.id);
 
+            let min_ready = self.config.min_ready.unwrap_or(self.view.processes.len());
+            let ready_timeout = Duration::from_nanos(self.config.ready_timeout);
+            let sync_timeout = Duration::from_nanos(self.config.sync_timeout);
+            let wait_start = Instant::now();
+
             loop {
+                self.check_id_collisions()?;
+                let sblock = self.get_state_from_master()?.get_starting_block();
+
                 if self.is_coordinator() {
 
-                    // check if all processes are ready
-                    if sblock.all_ready(self.view.processes.clone()) {
+                    // start when all processes are ready, or a quorum after
+                    // the timeout. Late processes see the start time set
+                    let ready = sblock.ready_count(&self.view.processes);
+                    let quorum = ready >= min_ready && wait_start.elapsed() >= ready_timeout;
+                    if sblock.all_ready(self.view.processes.clone()) || quorum {
+                        if ready < self.view.processes.len() {
+                            warn!("Starting with {}/{} processes ready", ready, self.view.processes.len());
+                        }
                         start_time = SystemTime::now() + Duration::from_nanos(self.config.startup_delay);
-                        sblock.start_at(start_time);
+                        self.view.update_states(|state| state.get_starting_block().start_at(start_time));
                         info!("Rounds starting at {:?}", start_time);
 
                         break;
@@ -570,23 +2173,143 @@ impl<T: Send + Copy + PartialEq + std::fmt::Debug + 'static> RepCXL<T> {
                     );
                     break;
                 }
+
+                if wait_start.elapsed() >= sync_timeout {
+                    let missing = sblock.not_ready(&self.view.processes);
+                    error!("Process {} timed out waiting for processes {:?}", self.config.id, missing);
+                    return Err(format!(
+                        "Sync timeout after {:?}: processes {:?} not ready",
+                        sync_timeout, missing
+                    ));
+                }
                 std::thread::sleep(Duration::from_millis(100));
                 debug!("Process {} waiting for start...", self.config.id);
             }
 
             let start_instant = timer::system_time_to_instant(start_time);
             self.algorithm_ctx.start_instant = start_instant;
+            for node in &self.view.memory_nodes {
+                node.fault_schedule().start(start_instant, self.algorithm_ctx.round_time);
+            }
 
             timer::wait_start_time(start_instant, timer::ROUND_SLEEP_RATIO);
+            // a colliding instance might have marked itself after the start
+            // was scheduled
+            self.check_id_collisions()?;
+
+            // spread the first writes of the processes, see `startup_jitter`
+            let jitter = timer::startup_jitter(
+                self.config.id as u32,
+                start_time,
+                Duration::from_nanos(self.config.startup_jitter));
+            if !jitter.is_zero() {
+                debug!("Process {} joining the rounds {:?} late", self.config.id, jitter);
+                std::thread::sleep(jitter);
+            }
 
             self.start();
+            info!("{}", self.topology_summary());
+            Ok(())
 
         } else {
             error!("FATAL: No coordinator found in group");
-            return;
+            Err("No coordinator found in group".into())
+        }
+    }
+
+    /// Fail if several instances use the id of a process of the group, they
+    /// would share its WCC entries
+    fn check_id_collisions(&self) -> Result<(), String> {
+        let sblock = self.get_state_from_master()?.get_starting_block();
+        let collisions = sblock.collisions(&self.view.processes);
+        if !collisions.is_empty() {
+            error!("FATAL: process ids {:?} are used by several instances", collisions);
+            return Err(format!("Process id collision: ids {:?} are used by several instances", collisions));
         }
+        Ok(())
     }
 
+    /// Operational summary of the group as resolved by this process: members,
+    /// memory nodes, protocol parameters and region usage
+    fn topology_summary(&self) -> String {
+        let role = if self.is_coordinator() { "coordinator" } else { "replica" };
+        let mut summary = format!("Topology of process {} ({}):\n", self.config.id, role);
+        summary += &format!(
+            "  processes: {:?}, coordinator: {}\n",
+            self.view.processes,
+            self.view.get_coordinator().unwrap()
+        );
+        summary += &format!("  {} memory nodes:\n", self.view.memory_nodes.len());
+        for node in &self.view.memory_nodes {
+            summary += &format!("    node {}: {}\n", node.id, node.describe());
+        }
+        summary += &format!(
+            "  algorithm: {}, round_time: {}ns, pipeline: {}\n",
+            self.config.algorithm, self.config.round_time, self.config.pipeline
+        );
+        let stats = self.memory_stats();
+        summary += &format!(
+            "  objects: {}, allocated {}/{} bytes",
+            stats.num_objects, stats.allocated_size, stats.total_size
+        );
+        summary
+    }
+
+    /// Verify that this process runs the same algorithm as the coordinator
+    /// and adopt the round time of the coordinator. Processes running
+    /// different algorithms on the same memory nodes break the conflict
+    /// resolution semantics, and processes with different round times
+    /// disagree on the current round.
+    fn check_group_params(&mut self) -> Result<(), String> {
+        let state = self.read_state_from_any()?;
+        let published = state.algorithm();
+
+        if published.is_empty() {
+            return Err("Shared state not initialized by the coordinator: no algorithm published".into());
+        }
+        if published != self.config.algorithm {
+            return Err(format!(
+                "Algorithm mismatch: process {} is configured with '{}' but the coordinator runs '{}'",
+                self.config.id, self.config.algorithm, published
+            ));
+        }
+
+        let round_time = state.round_time();
+        if round_time != self.config.round_time {
+            warn!(
+                "Process {} is configured with round_time {}ns, adopting {}ns from the coordinator",
+                self.config.id, self.config.round_time, round_time
+            );
+            self.config.round_time = round_time;
+            self.algorithm_ctx.round_time = Duration::from_nanos(round_time);
+        }
+        Ok(())
+    }
+
+    /// Check that this process lays out the shared state like the process
+    /// that created it, and that the state holds no address of a memory node
+    /// mapping, which would be meaningless in the other processes.
+    pub(crate) fn check_layout(&self) -> Result<(), String> {
+        let state = self.read_state_from_any()?;
+        if !state.layout_ok() {
+            return Err(format!(
+                "Shared state layout mismatch: process {} computes other offsets than the \
+                 process that created the state (different version or build?)",
+                self.config.id
+            ));
+        }
+
+        let ranges: Vec<_> = self.view.memory_nodes.iter().map(|n| n.mapped_range()).collect();
+        for node in &self.view.memory_nodes {
+            if let Some(offset) = node.find_pointer(&ranges) {
+                return Err(format!(
+                    "Absolute address in the shared state of memory node {} at offset {}",
+                    node.id, offset
+                ));
+            }
+        }
+        Ok(())
+    }
 
     // stop pipeline threads and exit process
     pub fn stop(&self) {
@@ -594,12 +2317,1304 @@ impl<T: Send + Copy + PartialEq + std::fmt::Debug + 'static> RepCXL<T> {
 
         if self.config.pipeline {
             info!("Stopping pipelined threads...");
-            self.stop_flag.store(true, Ordering::Relaxed);
         }
         else { // if pipelined, the write thread prints stats
             if self.config.algorithm == "monster" || self.config.algorithm == "fmonster" {
                 self.algorithm_ctx.stats.print();
             }
         }
+        self.stop_workers();
+    }
+}
+
+/// Export and import of the values as bytes, for `Pod` value types
+impl<T: Pod + Send + PartialEq + std::fmt::Debug> RepCXL<T> {
+    /// Export the objects of this instance's namespace as (id, bytes of the
+    /// value), e.g. to dump the dataset to disk or to another store. The
+    /// value of an object is the one held by a majority of the memory nodes;
+    /// objects never written are left out. Fails if an object has no
+    /// majority, e.g. while a write is being replicated. See `import_kv`.
+    pub fn export_kv(&self) -> Result<Vec<(usize, Vec<u8>)>, String> {
+        let state = self.read_state_from_any()?;
+        let mut dump = Vec::new();
+        for info in state.object_index.objects().filter(|oi| oi.namespace() == self.config.namespace) {
+            let omes = safe_memio::mem_readall::<T>(info.offset, &self.view.memory_nodes)
+                .map_err(|safe_memio::MemoryError(mnid)| format!("Memory node {} failed during export", mnid))?;
+            let ome = algorithms::anti_entropy::majority_entry(&omes)
+                .ok_or_else(|| format!("Object {} has no value on a majority of the memory nodes", info.local_id()))?;
+            if !ome.written {
+                continue;
+            }
+            dump.push((info.local_id(), blob::pod_bytes(&ome.value).to_vec()));
+        }
+        Ok(dump)
+    }
+
+    /// Create the objects of a dump made with `export_kv`, with their value,
+    /// e.g. on a fresh region. Coordinator only. Fails on the first object
+    /// whose size does not match T or that cannot be created, e.g. because
+    /// its id is already used; the objects before it are kept.
+    pub fn import_kv(&mut self, dump: &[(usize, Vec<u8>)]) -> Result<(), String> {
+        for (id, bytes) in dump {
+            let value = blob::pod_from_bytes::<T>(bytes).ok_or_else(|| format!(
+                "Object {} has {} bytes, expected {}", id, bytes.len(), std::mem::size_of::<T>()
+            ))?;
+            self.new_object_with_val(*id, value)
+                .ok_or_else(|| format!("Failed to import object {}", id))?;
+        }
+        Ok(())
+    }
+}
+
+impl<T> RepCXL<T> {
+    /// How long `stop` waits for the workers to finish their current round,
+    /// e.g. a write retried after conflicts
+    const STOP_TIMEOUT: Duration = Duration::from_secs(5);
+
+    /// Set the stop flag and wait until every worker has exited, so that no
+    /// worker touches the memory nodes while they are dropped. A worker that
+    /// does not exit within `STOP_TIMEOUT` is left behind with a warning, its
+    /// clones of the nodes keep the memory mapped.
+    fn stop_workers(&self) {
+        self.stop_flag.store(true, Ordering::Relaxed);
+
+        let workers = std::mem::take(&mut *self.workers.lock().unwrap());
+        let deadline = Instant::now() + Self::STOP_TIMEOUT;
+        for worker in workers {
+            while !worker.is_finished() && Instant::now() < deadline {
+                std::thread::sleep(algorithms::STOP_POLL_INTERVAL);
+            }
+            if !worker.is_finished() {
+                warn!("Worker thread still running after {:?}, not waiting for it", Self::STOP_TIMEOUT);
+                continue;
+            }
+            if worker.join().is_err() {
+                error!("Worker thread panicked");
+            }
+        }
+    }
+}
+
+impl<T> Drop for RepCXL<T> {
+    fn drop(&mut self) {
+        self.stop_workers();
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST_NODE_SIZE: usize = 2 * 1024 * 1024;
+
+    /// Memory node files of a test, removed when dropped, also if the test
+    /// fails
+    struct TestNodes {
+        paths: Vec<String>,
+    }
+
+    impl TestNodes {
+        /// `count` fresh node files named after the test
+        fn new(name: &str, count: usize) -> Self {
+            let nodes = TestNodes {
+                paths: (0..count).map(|i| format!("/dev/shm/repCXL_test_{}{}", name, i)).collect(),
+            };
+            // provisioning keeps existing contents, e.g. of an aborted run
+            for path in &nodes.paths {
+                let _ = std::fs::remove_file(path);
+            }
+            RepCXL::<u64>::provision(&nodes.config()).expect("Failed to provision the memory nodes");
+            nodes
+        }
+
+        /// Config of a single process over all the nodes
+        fn config(&self) -> RepCXLConfig {
+            RepCXLConfig {
+                id: 0,
+                processes: vec![0],
+                mem_nodes: self.paths.clone(),
+                mem_size: TEST_NODE_SIZE,
+                ..Default::default()
+            }
+        }
+    }
+
+    impl Drop for TestNodes {
+        fn drop(&mut self) {
+            for path in &self.paths {
+                let _ = std::fs::remove_file(path);
+            }
+        }
+    }
+
+    #[test]
+    fn test_added_nodes_get_the_node_settings() {
+        let nodes = TestNodes::new("addcfg", 2);
+
+        let config = RepCXLConfig {
+            mem_nodes: nodes.paths[..1].to_vec(),
+            mem_node_latency: vec![0, 5, 7],
+            // never ends, the end round saturates
+            fault: vec![config::FaultSpec { node: 2, start_round: 1, fail_for_rounds: u64::MAX, mode: "read".into() }],
+            ..nodes.config()
+        };
+        let mut rcxl = RepCXL::<u64>::new(config);
+        rcxl.add_memory_node_from_file(&nodes.paths[1]).expect("Failed to add node");
+        rcxl.add_memory_node_heap();
+
+        let latencies: Vec<u64> = rcxl.view.memory_nodes.iter().map(|n| n.latency()).collect();
+        assert_eq!(latencies, vec![0, 5, 7]);
+        let schedule = rcxl.view.memory_nodes[2].fault_schedule();
+        schedule.start(Instant::now() - Duration::from_millis(5), Duration::from_millis(1));
+        assert!(schedule.fails(false), "Fault window of the heap node is not active");
+        assert!(!schedule.fails(true));
+    }
+
+    #[test]
+    fn test_sync_start_without_memory_nodes() {
+        let nodes = TestNodes::new("no_nodes", 1);
+
+        let config = nodes.config();
+        let mut rcxl = RepCXL::<u64>::new(config);
+        rcxl.view.memory_nodes.clear();
+
+        let err = rcxl.init_state().expect_err("init_state should fail without memory nodes");
+        assert!(err.contains("No memory nodes"), "Unexpected error: {}", err);
+        let err = rcxl.sync_start().expect_err("sync_start should fail without memory nodes");
+        assert!(err.contains("No memory nodes"), "Unexpected error: {}", err);
+    }
+
+    #[test]
+    fn test_anti_entropy_heals_divergent_node() {
+        let nodes = TestNodes::new("ae", 3);
+
+        let config = RepCXLConfig {
+            round_time: 10_000_000, // 10 ms
+            anti_entropy_interval: Some(2),
+            anti_entropy_batch: 1,
+            ..nodes.config()
+        };
+        let mut rcxl = RepCXL::<u64>::new(config);
+        rcxl.init_state().expect("init_state failed");
+        let obj = rcxl.new_object(1).expect("failed to create object");
+        let _other = rcxl.new_object(2).expect("failed to create object");
+        rcxl.sync_start().expect("sync_start failed");
+
+        rcxl.write_object(&obj, 5).expect("Write should succeed");
+
+        // the last node misses the write, and the object is never read
+        let stale = ObjectMemoryEntry::new(request::Wid::new(0, 0), 0u64);
+        safe_memio::mem_writeone(obj.info.offset, stale, &rcxl.view.memory_nodes[2])
+            .expect("Failed to write stale entry");
+
+        // one object per sweep, every 2 rounds
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let omes = loop {
+            let omes: Vec<ObjectMemoryEntry<u64>> =
+                safe_memio::mem_readall(obj.info.offset, &rcxl.view.memory_nodes).expect("Read should succeed");
+            if omes.iter().all(|ome| ome.value == 5) || Instant::now() >= deadline {
+                break omes;
+            }
+            rcxl.wait_for_round(rcxl.current_round() + 1);
+        };
+        rcxl.stop();
+
+        for ome in &omes {
+            assert_eq!(ome.value, 5, "Node was not repaired: {:?}", omes);
+            assert_eq!(ome.wid, omes[0].wid);
+        }
+    }
+
+    #[test]
+    fn test_repair_keeps_newer_entry() {
+        let nodes = TestNodes::new("repair_newer", 3);
+        let mut rcxl = RepCXL::<u64>::new(nodes.config());
+        rcxl.init_state().expect("init_state failed");
+        let obj = rcxl.new_object(1).expect("failed to create object");
+        let offset = obj.info.offset;
+        let view = &rcxl.view;
+
+        let majority = ObjectMemoryEntry::new(request::Wid::new(5, 0), 5u64);
+        safe_memio::mem_writeall(offset, majority, &view.memory_nodes).expect("Write should succeed");
+        let stale = ObjectMemoryEntry::new(request::Wid::new(1, 0), 1u64);
+        safe_memio::mem_writeone(offset, stale, &view.memory_nodes[2]).expect("Write should succeed");
+
+        // a newer write reaches the stale node after the repair read it
+        let newer = ObjectMemoryEntry::new(request::Wid::new(9, 0), 9u64);
+        safe_memio::mem_writeone(offset, newer, &view.memory_nodes[2]).expect("Write should succeed");
+        assert!(!safe_memio::mem_repairone(offset, majority, &view.memory_nodes[2]).expect("Repair should succeed"));
+        assert_eq!(algorithms::anti_entropy::repair_object::<u64>(&obj.info, view).expect("Repair should succeed"), 0);
+        let ome: ObjectMemoryEntry<u64> = safe_memio::mem_readone(offset, &view.memory_nodes[2..]).expect("Read should succeed");
+        assert_eq!(ome.value, 9, "Newer entry was overwritten");
+
+        // a stale node is still repaired
+        safe_memio::mem_writeone(offset, stale, &view.memory_nodes[2]).expect("Write should succeed");
+        assert_eq!(algorithms::anti_entropy::repair_object::<u64>(&obj.info, view).expect("Repair should succeed"), 1);
+        let ome: ObjectMemoryEntry<u64> = safe_memio::mem_readone(offset, &view.memory_nodes[2..]).expect("Read should succeed");
+        assert_eq!(ome.value, 5);
+    }
+
+    #[test]
+    fn test_audit_reports_divergent_object() {
+        let nodes = TestNodes::new("audit", 3);
+
+        let config = RepCXLConfig {
+            round_time: 10_000_000, // 10 ms
+            audit_interval: Some(2),
+            audit_sample: 2,
+            ..nodes.config()
+        };
+        let mut rcxl = RepCXL::<u64>::new(config);
+        rcxl.init_state().expect("init_state failed");
+        let obj = rcxl.new_object(1).expect("failed to create object");
+        let _other = rcxl.new_object(2).expect("failed to create object");
+        rcxl.sync_start().expect("sync_start failed");
+
+        rcxl.write_object(&obj, 5).expect("Write should succeed");
+        // poll the report every round until `done`, bounded by a deadline
+        let poll_report = |done: fn(&AuditReport) -> bool| {
+            let deadline = Instant::now() + Duration::from_secs(5);
+            loop {
+                let report = rcxl.audit_report().expect("Audit should be enabled");
+                if done(&report) || Instant::now() >= deadline {
+                    return report;
+                }
+                rcxl.wait_for_round(rcxl.current_round() + 1);
+            }
+        };
+        let report = poll_report(|report| report.checked > 0);
+        assert!(report.checked > 0, "Nothing audited: {:?}", report);
+        assert!(report.divergent.is_empty(), "Unexpected divergence: {:?}", report);
+        assert_eq!(report.inconsistency_rate, 0.0);
+
+        // the last node misses a write
+        let stale = ObjectMemoryEntry::new(request::Wid::new(0, 0), 0u64);
+        safe_memio::mem_writeone(obj.info.offset, stale, &rcxl.view.memory_nodes[2])
+            .expect("Failed to write stale entry");
+
+        // both objects are sampled every 2 rounds
+        let report = poll_report(|report| !report.divergent.is_empty());
+        assert_eq!(report.divergent.len(), 1, "Divergence not reported: {:?}", report);
+        assert_eq!(report.divergent[0].id, 1);
+        assert_eq!(report.divergent[0].status, ObjectStatus::Divergent);
+        assert_eq!(report.divergent[0].disagreeing_nodes, vec![2]);
+        assert!(report.inconsistency_rate > 0.0);
+        rcxl.stop();
+
+        // read-only, the node is left behind
+        let omes: Vec<ObjectMemoryEntry<u64>> =
+            safe_memio::mem_readall(obj.info.offset, &rcxl.view.memory_nodes).expect("Read should succeed");
+        assert_eq!(omes[2].value, 0, "Audit repaired the node: {:?}", omes);
+    }
+
+    #[test]
+    fn test_check_layout_rejects_absolute_address() {
+        let nodes = TestNodes::new("layout", 1);
+
+        let config = nodes.config();
+        let mut rcxl = RepCXL::<u64>::new(config);
+        rcxl.init_state().expect("init_state failed");
+        rcxl.new_object(1).expect("failed to create object");
+        rcxl.check_layout().expect("Fresh state should pass the layout check");
+
+        // a pointer into the mapping, meaningless in the other processes
+        {
+            let _lock = rcxl.view.lock_state().expect("Failed to lock state");
+            let mut state = rcxl.read_state_from_any().expect("Failed to read state");
+            state.set_round_time(rcxl.view.memory_nodes[0].addr_at(8) as u64);
+            rcxl.write_state_all(&mut state);
+        }
+        let err = rcxl.check_layout().expect_err("Absolute address not detected");
+        assert!(err.contains("Absolute address"), "Unexpected error: {}", err);
+    }
+
+    #[test]
+    fn test_force_init_releases_stale_state_lock() {
+        let nodes = TestNodes::new("stale_lock", 1);
+
+        let mut rcxl = RepCXL::<u64>::new(nodes.config());
+        rcxl.init_state().expect("init_state failed");
+
+        // a holder that crashed never releases the lock
+        std::mem::forget(rcxl.view.memory_nodes[0].lock_state().expect("Failed to lock state"));
+        let err = rcxl.view.memory_nodes[0]
+            .lock_state_timeout(Duration::from_millis(10))
+            .err()
+            .expect("Stale lock acquired");
+        assert!(err.contains(&format!("pid {}", std::process::id())), "Unexpected error: {}", err);
+
+        rcxl.config.force_init = true;
+        rcxl.init_state().expect("force_init should reset the state lock");
+        rcxl.new_object(1).expect("Allocation blocked by the stale lock");
+    }
+
+    #[test]
+    fn test_lock_memory_keeps_nodes_resident() {
+        let nodes = TestNodes::new("mlock", 2);
+        let config = |path: &str, lock_memory| RepCXLConfig {
+            mem_nodes: vec![path.to_string()],
+            lock_memory,
+            ..nodes.config()
+        };
+
+        // the pages of a fresh node are only faulted in when touched
+        let unlocked = RepCXL::<u64>::new(config(&nodes.paths[0], false));
+        assert!(unlocked.view.memory_nodes[0].non_resident_pages() > 0);
+
+        let locked = RepCXL::<u64>::new(config(&nodes.paths[1], true));
+        let node = &locked.view.memory_nodes[0];
+        let missing = node.non_resident_pages();
+        // locking fails without CAP_IPC_LOCK and a large enough RLIMIT_MEMLOCK
+        if node.lock_memory().is_ok() {
+            assert_eq!(missing, 0, "Locked node not resident");
+            std::thread::sleep(Duration::from_millis(50));
+            assert_eq!(node.non_resident_pages(), 0);
+        }
+    }
+
+    #[test]
+    fn test_order_checker_flags_stale_entry() {
+        let nodes = TestNodes::new("order", 2);
+
+        let config = RepCXLConfig {
+            round_time: 10_000_000, // 10 ms
+            pipeline: true,
+            ..nodes.config()
+        };
+        let mut rcxl = RepCXL::<u64>::new(config);
+        rcxl.init_state().expect("init_state failed");
+        let obj = rcxl.new_object(1).expect("failed to create object");
+        rcxl.enable_order_checker().expect("Order checker should be supported");
+        rcxl.sync_start().expect("sync_start failed");
+
+        for val in 1..=3 {
+            obj.write(val).expect("Write should succeed");
+            std::thread::sleep(Duration::from_millis(30));
+        }
+        assert_eq!(rcxl.order_violations(), Ok(vec![]));
+
+        // every node goes back to the first write
+        let stale = ObjectMemoryEntry::new(request::Wid::new(1, 0), 1u64).with_seq(1);
+        safe_memio::mem_writeall(obj.info.offset, stale, &rcxl.view.memory_nodes)
+            .expect("Failed to write stale entry");
+        std::thread::sleep(Duration::from_millis(50));
+        let violations = rcxl.order_violations().expect("Order checker should be enabled");
+        assert_eq!(violations.len(), 1, "Unexpected violations: {:?}", violations);
+        assert_eq!((violations[0].id, violations[0].process_id), (1, 0));
+        assert_eq!((violations[0].prev_seq, violations[0].seq), (3, 1));
+        assert_eq!(violations[0].wid, request::Wid::new(1, 0));
+        rcxl.stop();
+    }
+
+    #[test]
+    fn test_master_node_failover() {
+        let nodes = TestNodes::new("failover", 2);
+        let instance = |id: i32| RepCXL::<u64>::new(RepCXLConfig {
+            id,
+            processes: vec![0, 1],
+            round_time: 50_000_000, // 50 ms
+            pipeline: true,
+            startup_delay: 0,
+            ..nodes.config()
+        });
+        let mut rcxl0 = instance(0);
+        rcxl0.init_state().expect("init_state failed");
+        let obj0 = rcxl0.new_object(1).expect("failed to create object");
+        let mut rcxl1 = instance(1);
+        let obj1 = rcxl1.get_object(1).expect("failed to get object");
+        assert!(rcxl0.set_master_node(2).is_err());
+        assert_eq!(rcxl0.master_node(), Some(0));
+
+        // both processes detect the failure of the master
+        for rcxl in [&rcxl0, &rcxl1] {
+            rcxl.view.memory_nodes[0].set_failed(true);
+            assert_eq!(rcxl.master_node(), Some(1));
+        }
+        // writes still go to every node, bring the node back for them. The
+        // master stays on the promoted node
+        for rcxl in [&rcxl0, &rcxl1] {
+            rcxl.view.memory_nodes[0].set_failed(false);
+            assert_eq!(rcxl.master_node(), Some(1));
+        }
+
+        // the processes start with the starting block of the new master, and
+        // writes enqueued before the start conflict in the first round: the
+        // replicated WCCs resolve the conflict
+        let (ack0, ack1) = std::thread::scope(|s| {
+            let h0 = s.spawn(|| obj0.write_detailed(1).expect("Write should succeed"));
+            let h1 = s.spawn(|| obj1.write_detailed(2).expect("Write should succeed"));
+            s.spawn(|| rcxl0.sync_start().expect("sync_start failed"));
+            s.spawn(|| rcxl1.sync_start().expect("sync_start failed"));
+            (h0.join().unwrap(), h1.join().unwrap())
+        });
+        assert!(ack0.success && ack1.success);
+        assert!(ack0.conflicted || ack1.conflicted, "No conflict: {:?} {:?}", ack0, ack1);
+        let report = rcxl0.fsck().expect("fsck failed");
+        assert!(report.objects.iter().all(|o| o.status == ObjectStatus::Consistent), "{:?}", report);
+        rcxl0.stop();
+        rcxl1.stop();
+    }
+
+    #[test]
+    fn test_wcc_quorum_survives_master_failure() {
+        let nodes = TestNodes::new("wcc_quorum", 3);
+        let mut rcxl = RepCXL::<u64>::new(RepCXLConfig {
+            processes: vec![0, 1, 2],
+            ..nodes.config()
+        });
+        rcxl.init_state().expect("init_state failed");
+        let view = &rcxl.view;
+
+        // processes 0 and 1 write object 7 in round 5, the larger pid wins
+        view.update_states(|state| state.get_owcc().write(7, 5, 0, 0));
+        view.update_states(|state| state.get_owcc().write(7, 5, 1, 0));
+        // the master diverged, a later timestamp of process 0 made it only there
+        view.memory_nodes[0].get_state().get_owcc().write(7, 5, 0, 9);
+        let is_last = |pid: usize| view.quorum_read(|state| state.get_owcc().is_last(7, 6, 5, pid, 0));
+        assert!(is_last(1), "The master alone should not decide");
+        assert!(!is_last(0));
+
+        // the master fails, the remaining replicas still form a quorum
+        view.memory_nodes[0].set_failed(true);
+        assert_eq!(rcxl.master_node(), Some(1));
+        assert!(is_last(1));
+        assert!(!is_last(0));
+        assert!(view.quorum_read(|state| state.get_owcc().has_contender(7, 5, 1)));
+    }
+
+    #[test]
+    fn test_read_and_repair() {
+        let nodes = TestNodes::new("rr", 3);
+
+        let config = RepCXLConfig {
+            round_time: 10_000_000, // 10 ms
+            ..nodes.config()
+        };
+        let mut rcxl = RepCXL::<u64>::new(config);
+        rcxl.init_state().expect("init_state failed");
+        let obj = rcxl.new_object(1).expect("failed to create object");
+        rcxl.sync_start().expect("sync_start failed");
+
+        rcxl.write_object(&obj, 5).expect("Write should succeed");
+
+        // the last node misses the write
+        let stale = ObjectMemoryEntry::new(request::Wid::new(0, 0), 0u64);
+        safe_memio::mem_writeone(obj.info.offset, stale, &rcxl.view.memory_nodes[2])
+            .expect("Failed to write stale entry");
+
+        assert!(matches!(rcxl.read_and_repair(&obj), Ok(ReadReturn::ReadSafe(5))));
+
+        // the repair is asynchronous
+        let stale_node = std::slice::from_ref(&rcxl.view.memory_nodes[2]);
+        let mut healed = None;
+        for _ in 0..100 {
+            let ome: ObjectMemoryEntry<u64> = safe_memio::mem_readone(obj.info.offset, stale_node)
+                .expect("Read should succeed");
+            if ome.value == 5 {
+                healed = Some(ome);
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        let healed = healed.expect("Stale node was not repaired");
+        let first: ObjectMemoryEntry<u64> = safe_memio::mem_readone(obj.info.offset, &rcxl.view.memory_nodes[..1])
+            .expect("Read should succeed");
+        assert_eq!(healed.wid, first.wid);
+        rcxl.stop();
+    }
+
+    #[test]
+    fn test_monotonic_reads_across_divergent_nodes() {
+        let nodes = TestNodes::new("monotonic", 2);
+
+        let config = RepCXLConfig {
+            round_time: 10_000_000, // 10 ms
+            algorithm: "monster".to_string(),
+            pipeline: false,
+            read_retries: 2,
+            ..nodes.config()
+        };
+        let mut rcxl = RepCXL::<u64>::new(config);
+        rcxl.init_state().expect("init_state failed");
+        let obj = rcxl.new_object(1).expect("failed to create object");
+        rcxl.sync_start().expect("sync_start failed");
+
+        assert!(matches!(rcxl.read_object_monotonic(&obj), Ok((ReadReturn::Uninitialized, 0))));
+        // every replicate bumps the version
+        for (i, value) in [10u64, 20].into_iter().enumerate() {
+            rcxl.write_object(&obj, value).expect("Write should succeed");
+            let (res, version) = rcxl.read_object_monotonic(&obj).expect("Read should succeed");
+            assert!(matches!(res, ReadReturn::ReadSafe(v) if v == value));
+            assert_eq!(version, i as u64 + 1);
+        }
+
+        // a newer write reached node 0 only, then nodes go back to the older
+        // write, e.g. restored from a stale copy
+        let newer = ObjectMemoryEntry::new(request::Wid::new(u64::MAX, 0), 30u64).with_version(3);
+        let older = ObjectMemoryEntry::new(request::Wid::new(1, 0), 20u64).with_version(2);
+        let mut highest = 2;
+        for i in 0..6 {
+            if i % 2 == 0 {
+                safe_memio::mem_writeone(obj.info.offset, newer, &rcxl.view.memory_nodes[0]).expect("Failed to write entry");
+            } else {
+                safe_memio::mem_writeall(obj.info.offset, older, &rcxl.view.memory_nodes).expect("Failed to write entry");
+            }
+            let (res, version) = rcxl.read_object_monotonic(&obj).expect("Read should succeed");
+            assert!(version >= highest, "read version {} after {}", version, highest);
+            highest = version;
+            assert!(matches!(res, ReadReturn::ReadDirty(30)));
+        }
+        assert_eq!(highest, 3);
+        // plain reads see the nodes as they are
+        assert!(matches!(rcxl.read_object(&obj), Ok(ReadReturn::ReadSafe(20))));
+        rcxl.stop();
+    }
+
+    #[test]
+    fn test_write_amplification() {
+        let nodes = TestNodes::new("amp", 3);
+
+        let config = RepCXLConfig {
+            round_time: 10_000_000, // 10 ms
+            algorithm: "monster".to_string(),
+            pipeline: false,
+            single_writer: true, // no conflict check, only the values are written
+            ..nodes.config()
+        };
+        let mut rcxl = RepCXL::<u64>::new(config);
+        rcxl.init_state().expect("init_state failed");
+        let obj = rcxl.new_object(1).expect("failed to create object");
+        rcxl.sync_start().expect("sync_start failed");
+        assert_eq!(rcxl.write_amplification().ratio(), 0.0);
+
+        // no conflicts: every write replicates once to each node
+        for value in 0..5u64 {
+            rcxl.write_object(&obj, value).expect("Write should succeed");
+        }
+        let amp = rcxl.write_amplification();
+        assert_eq!(amp.client_bytes, 5 * 8);
+        assert_eq!(amp.ratio(), 3.0);
+
+        // repairing a stale node writes without a client request
+        let stale = ObjectMemoryEntry::new(request::Wid::new(0, 0), 0u64);
+        safe_memio::mem_writeone(obj.info.offset, stale, &rcxl.view.memory_nodes[2])
+            .expect("Failed to write stale entry");
+        let before = rcxl.write_amplification();
+        assert!(matches!(rcxl.read_and_repair(&obj), Ok(ReadReturn::ReadSafe(4))));
+        for _ in 0..100 {
+            if rcxl.write_amplification().node_bytes > before.node_bytes {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        let after = rcxl.write_amplification();
+        assert_eq!(after.client_bytes, before.client_bytes);
+        assert_eq!(after.node_bytes, before.node_bytes + 8);
+        assert!(after.ratio() > 3.0);
+        rcxl.stop();
+
+        // two writers race on the object in the same round. The node writes
+        // are slower than a round, so the loser finds the winner's write
+        // incomplete and retries, writing its WCC entries again
+        let race_nodes = TestNodes::new("amp_race", 3);
+        let config = |id| RepCXLConfig {
+            id,
+            processes: vec![0, 1],
+            round_time: 10_000_000, // 10 ms
+            algorithm: "monster".to_string(),
+            pipeline: true,
+            force_init: true,
+            ..race_nodes.config()
+        };
+        let mut writers = [RepCXL::<u64>::new(config(0)), RepCXL::<u64>::new(config(1))];
+        writers[0].init_state().expect("init_state failed");
+        for rcxl in writers.iter_mut() {
+            for node in rcxl.view.memory_nodes.iter_mut() {
+                node.set_write_delay(Duration::from_millis(15));
+            }
+        }
+        let objs = [
+            writers[0].new_object(1).expect("failed to create object"),
+            writers[1].get_object(1).expect("failed to get object"),
+        ];
+        let [w0, w1] = &mut writers;
+        let acks = std::thread::scope(|s| {
+            let h0 = s.spawn(|| objs[0].write_detailed(1).expect("Write should succeed"));
+            let h1 = s.spawn(|| objs[1].write_detailed(2).expect("Write should succeed"));
+            s.spawn(|| w0.sync_start().expect("sync_start failed"));
+            s.spawn(|| w1.sync_start().expect("sync_start failed"));
+            [h0.join().unwrap(), h1.join().unwrap()]
+        });
+        let loser = acks.iter().position(|ack| ack.retries > 0).expect("No write retried");
+        let (loser_amp, winner_amp) = (writers[loser].write_amplification(), writers[1 - loser].write_amplification());
+        assert!(winner_amp.ratio() > 3.0, "Winner amplification {:?}", winner_amp);
+        assert!(loser_amp.ratio() > winner_amp.ratio(), "Loser {:?}, winner {:?}", loser_amp, winner_amp);
+        for rcxl in writers.iter_mut() {
+            rcxl.stop();
+        }
+    }
+
+    #[test]
+    fn test_write_to_subset() {
+        let nodes = TestNodes::new("subset", 3);
+
+        let config = RepCXLConfig {
+            round_time: 10_000_000, // 10 ms
+            algorithm: "monster".to_string(),
+            pipeline: true,
+            ..nodes.config()
+        };
+        let mut rcxl = RepCXL::<u64>::new(config);
+        rcxl.init_state().expect("init_state failed");
+        let obj = rcxl.new_object(1).expect("failed to create object");
+        rcxl.sync_start().expect("sync_start failed");
+
+        obj.write(5).expect("Write should succeed");
+        obj.write_to(9, &[0, 1]).expect("Partial write should succeed");
+
+        let entries: Vec<ObjectMemoryEntry<u64>> = safe_memio::mem_readall(obj.info.offset, &rcxl.view.memory_nodes)
+            .expect("Read should succeed");
+        assert_eq!(entries.iter().map(|e| e.value).collect::<Vec<_>>(), vec![9, 9, 5]);
+        assert!(entries[0].wid > entries[2].wid, "The partial write should get a newer wid");
+        assert!(matches!(obj.read(), Ok(ReadReturn::ReadDirty(9))));
+
+        assert!(obj.write_to(3, &[7]).is_err(), "Unknown nodes should be rejected");
+        assert!(obj.write_to(3, &[]).is_err());
+
+        // a full write reconciles the nodes
+        obj.write(11).expect("Write should succeed");
+        assert!(matches!(obj.read(), Ok(ReadReturn::ReadSafe(11))));
+        rcxl.stop();
+    }
+
+    #[test]
+    fn test_read_skips_zeroed_node() {
+        let nodes = TestNodes::new("zeroed", 2);
+
+        let config = RepCXLConfig {
+            round_time: 10_000_000, // 10 ms
+            algorithm: "monster".to_string(),
+            pipeline: true,
+            ..nodes.config()
+        };
+        let mut rcxl = RepCXL::<u64>::new(config);
+        rcxl.init_state().expect("init_state failed");
+        let obj = rcxl.new_object(1).expect("failed to create object");
+        rcxl.sync_start().expect("sync_start failed");
+
+        obj.write(5).expect("Write should succeed");
+        std::thread::sleep(Duration::from_millis(50));
+        assert!(matches!(obj.read(), Ok(ReadReturn::ReadSafe(5))));
+        assert!(rcxl.zeroed_nodes().is_empty());
+
+        // node 0 loses its content
+        let node = &rcxl.view.memory_nodes[0];
+        unsafe { std::ptr::write_bytes(node.addr_at(obj.info.offset), 0, obj.info.size) };
+
+        assert!(matches!(obj.read(), Ok(ReadReturn::ReadSafe(5))));
+        assert_eq!(rcxl.zeroed_nodes(), vec![0]);
+        rcxl.stop();
+    }
+
+    #[test]
+    fn test_parallel_replication_latency() {
+        let nodes = TestNodes::new("pool", 4);
+
+        // a different write delay per node: replicating in parallel takes
+        // about the largest one, sequentially their sum
+        let delays: Vec<Duration> = (1..=4).map(|i| Duration::from_millis(10 * i)).collect();
+        let max_delay = *delays.iter().max().unwrap();
+        let sum_delay: Duration = delays.iter().sum();
+
+        // mean write latency with the node writes delayed
+        let write_latency = |replication_threads: usize| {
+            let config = RepCXLConfig {
+                round_time: 10_000_000, // 10 ms
+                algorithm: "async_best_effort".to_string(),
+                pipeline: false,
+                force_init: true,
+                replication_threads,
+                ..nodes.config()
+            };
+            let mut rcxl = RepCXL::<u64>::new(config);
+            rcxl.init_state().expect("init_state failed");
+            let obj = rcxl.new_object(1).expect("failed to create object");
+            for (node, delay) in rcxl.view.memory_nodes.iter_mut().zip(&delays) {
+                node.set_write_delay(*delay);
+            }
+
+            let start = Instant::now();
+            for value in 0..5u64 {
+                rcxl.write_object(&obj, value).expect("Write should succeed");
+            }
+            let latency = start.elapsed() / 5;
+            assert!(matches!(rcxl.read_object(&obj), Ok(ReadReturn::ReadSafe(4))));
+            rcxl.stop();
+            latency
+        };
+
+        let sequential = write_latency(1);
+        assert!(sequential >= sum_delay, "Sequential write {:?}, delays sum to {:?}", sequential, sum_delay);
+
+        // closer to the largest delay than to the sum
+        let parallel = write_latency(4);
+        let midpoint = (max_delay + sum_delay) / 2;
+        assert!(parallel >= max_delay, "Parallel write {:?} under the largest delay {:?}", parallel, max_delay);
+        assert!(
+            parallel < midpoint,
+            "Parallel write {:?} not closer to the largest delay {:?} than to the sum {:?}",
+            parallel, max_delay, sum_delay
+        );
+    }
+
+    #[test]
+    fn test_write_with_ack_overrides_quorum() {
+        let nodes = TestNodes::new("ackp", 3);
+        let slow = Duration::from_millis(300);
+
+        let config = RepCXLConfig {
+            round_time: 10_000_000, // 10 ms
+            pipeline: true,
+            ack_policy: "quorum".to_string(),
+            force_init: true,
+            ..nodes.config()
+        };
+        let mut rcxl = RepCXL::<u64>::new(config);
+        rcxl.init_state().expect("init_state failed");
+        let obj = rcxl.new_object(1).expect("failed to create object");
+        // the last node is out of the quorum
+        rcxl.view.memory_nodes[2].set_write_delay(slow);
+        rcxl.sync_start().expect("sync_start failed");
+
+        let start = Instant::now();
+        obj.write(1).expect("Write should succeed");
+        let quorum = start.elapsed();
+        assert!(quorum < slow / 2, "Quorum write waited for the slow node: {:?}", quorum);
+
+        // queued behind the replication of the previous write to the slow node
+        let start = Instant::now();
+        obj.write_with_ack(2, request::AckPolicy::All).expect("Write should succeed");
+        let all = start.elapsed();
+        assert!(all >= slow, "All write did not wait for the slow node: {:?}", all);
+        let omes: Vec<ObjectMemoryEntry<u64>> =
+            safe_memio::mem_readall(obj.info.offset, &rcxl.view.memory_nodes).expect("Read should succeed");
+        assert!(omes.iter().all(|ome| ome.value == 2), "Acked before every node was written: {:?}", omes);
+        rcxl.stop();
+    }
+
+    #[test]
+    fn test_local_fence_masks_stale_read() {
+        let nodes = TestNodes::new("fence", 2);
+
+        let config = RepCXLConfig {
+            round_time: 1_000_000, // 1 ms
+            algorithm: "async_best_effort".to_string(),
+            pipeline: true,
+            ..nodes.config()
+        };
+        let mut rcxl = RepCXL::<u64>::new(config);
+        rcxl.init_state().expect("init_state failed");
+        let obj = rcxl.new_object(1).expect("failed to create object").with_local_fence();
+        rcxl.sync_start().expect("sync_start failed");
+
+        obj.write(5).expect("Write should succeed");
+        // the nodes still hold the previous value when read back
+        let old = ObjectMemoryEntry::new_nowid(1u64);
+        safe_memio::mem_writeall(obj.info.offset, old, &rcxl.view.memory_nodes).expect("Failed to write old entry");
+        assert!(matches!(obj.read_direct(), Ok(ReadReturn::ReadDirty(5))));
+        assert!(matches!(rcxl.read_object(&obj), Ok(ReadReturn::ReadDirty(5))));
+
+        // the write lands, then another process overwrites it
+        let landed = ObjectMemoryEntry::new_nowid(5u64);
+        safe_memio::mem_writeall(obj.info.offset, landed, &rcxl.view.memory_nodes).expect("Failed to write entry");
+        assert!(matches!(obj.read(), Ok(ReadReturn::ReadSafe(5))));
+        let other = ObjectMemoryEntry::new_nowid(7u64);
+        safe_memio::mem_writeall(obj.info.offset, other, &rcxl.view.memory_nodes).expect("Failed to write entry");
+        assert!(matches!(obj.read_direct(), Ok(ReadReturn::ReadSafe(7))));
+
+        // handles without the fence read the nodes as they are
+        let plain = rcxl.get_object(1).expect("Object should exist");
+        obj.write(8).expect("Write should succeed");
+        safe_memio::mem_writeall(obj.info.offset, old, &rcxl.view.memory_nodes).expect("Failed to write old entry");
+        assert!(matches!(plain.read_direct(), Ok(ReadReturn::ReadSafe(1))));
+        assert!(matches!(obj.read_direct(), Ok(ReadReturn::ReadDirty(8))));
+        rcxl.stop();
+    }
+
+    #[test]
+    fn test_flush_waits_for_all_nodes() {
+        let nodes = TestNodes::new("flush", 3);
+        let slow = Duration::from_millis(300);
+
+        let config = RepCXLConfig {
+            round_time: 10_000_000, // 10 ms
+            pipeline: true,
+            ack_policy: "quorum".to_string(),
+            force_init: true,
+            ..nodes.config()
+        };
+        let mut rcxl = RepCXL::<u64>::new(config);
+        rcxl.init_state().expect("init_state failed");
+        let obj = rcxl.new_object(1).expect("failed to create object");
+        // the last node is out of the quorum
+        rcxl.view.memory_nodes[2].set_write_delay(slow);
+        rcxl.sync_start().expect("sync_start failed");
+
+        let start = Instant::now();
+        obj.write(1).expect("Write should succeed");
+        rcxl.flush_object(&obj).expect("Flush should succeed");
+        assert!(start.elapsed() >= slow, "Flush returned before the slow node got the value");
+        let omes: Vec<ObjectMemoryEntry<u64>> =
+            safe_memio::mem_readall(obj.info.offset, &rcxl.view.memory_nodes).expect("Read should succeed");
+        assert!(omes.iter().all(|ome| ome.value == 1), "Flushed before every node was written: {:?}", omes);
+
+        // the slow node misses the next write for longer than the timeout
+        obj.write(2).expect("Write should succeed");
+        let err = obj.flush_timeout(Duration::from_millis(10)).expect_err("Flush should time out");
+        assert!(err.contains("[2]"), "Unexpected error: {}", err);
+        obj.flush().expect("Flush should succeed");
+
+        // a queued write the worker never gets done with, e.g. it died
+        rcxl.pending_writes.fetch_add(1, Ordering::AcqRel);
+        let start = Instant::now();
+        let err = rcxl.flush_object(&obj).expect_err("Flush should time out");
+        assert!(err.contains("pending"), "Unexpected error: {}", err);
+        assert!(start.elapsed() < RepCXLObject::<u64>::FLUSH_TIMEOUT * 2, "Flush took {:?}", start.elapsed());
+        rcxl.pending_writes.fetch_sub(1, Ordering::AcqRel);
+        rcxl.stop();
+    }
+
+    #[test]
+    fn test_clear_objects_fails_on_stuck_writes() {
+        let nodes = TestNodes::new("clear_stuck", 1);
+
+        let config = RepCXLConfig {
+            round_time: 1_000_000, // 1 ms
+            pipeline: true,
+            force_init: true,
+            ..nodes.config()
+        };
+        let mut rcxl = RepCXL::<u64>::new(config);
+        rcxl.init_state().expect("init_state failed");
+        rcxl.new_object(1).expect("failed to create object");
+        rcxl.sync_start().expect("sync_start failed");
+
+        // a queued write the worker never gets done with, e.g. it died
+        rcxl.pending_writes.fetch_add(1, Ordering::AcqRel);
+        let err = rcxl.clear_objects(false).expect_err("Clear should time out");
+        assert!(err.contains("pending"), "Unexpected error: {}", err);
+        assert_eq!(rcxl.list_objects().len(), 1, "Objects cleared despite the error");
+
+        rcxl.pending_writes.fetch_sub(1, Ordering::AcqRel);
+        rcxl.clear_objects(false).expect("Clear should succeed");
+        assert!(rcxl.list_objects().is_empty());
+        rcxl.stop();
+    }
+
+    #[test]
+    fn test_alloc_cache_hit_takes_shared_lock() {
+        let nodes = TestNodes::new("alloc_cache", 1);
+
+        let mut rcxl = RepCXL::<u64>::new(RepCXLConfig { force_init: true, ..nodes.config() });
+        rcxl.init_state().expect("init_state failed");
+        let obj = rcxl.new_object(1).expect("failed to create object");
+        assert_eq!(rcxl.view.is_allocated(&obj.info), Ok(true));
+
+        // another reader holding the cache does not block a hit
+        {
+            let _reader = rcxl.view.alloc_cache.read().unwrap();
+            assert_eq!(rcxl.view.is_allocated(&obj.info), Ok(true));
+        }
+
+        // a state update refreshes the cache
+        rcxl.remove_object(1);
+        assert_eq!(rcxl.view.is_allocated(&obj.info), Ok(false));
+    }
+
+    #[test]
+    fn test_flush_without_pipeline() {
+        let nodes = TestNodes::new("flush_direct", 3);
+
+        let config = RepCXLConfig {
+            round_time: 1_000_000, // 1 ms
+            pipeline: false,
+            force_init: true,
+            ..nodes.config()
+        };
+        let mut rcxl = RepCXL::<u64>::new(config);
+        rcxl.init_state().expect("init_state failed");
+        let obj = rcxl.new_object(1).expect("failed to create object");
+        rcxl.sync_start().expect("sync_start failed");
+
+        rcxl.write_object(&obj, 1).expect("Write should succeed");
+        rcxl.flush_object(&obj).expect("Flush should succeed");
+
+        // the flush checks the nodes against the acked write
+        rcxl.view.memory_nodes[2].zero_at(obj.info.offset, obj.info.size);
+        let err = obj.flush_timeout(Duration::from_millis(10)).expect_err("Flush should time out");
+        assert!(err.contains("[2]"), "Unexpected error: {}", err);
+        rcxl.stop();
+    }
+
+    #[test]
+    fn test_read_worker_reports_node_failure() {
+        let nodes = TestNodes::new("rfail", 2);
+
+        for algorithm in ["monster", "async_best_effort"] {
+            let config = RepCXLConfig {
+                round_time: 1_000_000, // 1 ms
+                algorithm: algorithm.to_string(),
+                pipeline: true,
+                force_init: true,
+                ..nodes.config()
+            };
+            let mut rcxl = RepCXL::<u64>::new(config);
+            rcxl.init_state().expect("init_state failed");
+            let obj = rcxl.new_object_with_val(1, 5).expect("failed to create object");
+            let other = rcxl.new_object_with_val(2, 6).expect("failed to create object");
+            rcxl.sync_start().expect("sync_start failed");
+
+            assert!(matches!(rcxl.read_object(&obj), Ok(ReadReturn::ReadSafe(5))));
+
+            // the last node fails, the worker must answer instead of
+            // leaving the client waiting
+            rcxl.view.memory_nodes[1].set_failed(true);
+            let err = rcxl.read_object(&obj).expect_err("Read from a failed node should fail");
+            assert!(err.contains("Memory node 1 failed"), "{}: unexpected error: {}", algorithm, err);
+            let err = obj.read().expect_err("Read from a failed node should fail");
+            assert!(err.contains("Memory node 1 failed"), "{}: unexpected error: {}", algorithm, err);
+            assert!(rcxl.read_many(&[1, 2]).is_empty());
+
+            // every object of a batch is answered, also after a failed read
+            let (ack_tx, ack_rx) = kanal::unbounded();
+            rcxl.rreq_queue_tx.send(ReadRequest::new_batch(vec![obj.info, other.info], ack_tx))
+                .expect("Failed to send read request");
+            let acks: Vec<_> = std::iter::from_fn(|| ack_rx.recv().ok()).collect();
+            assert_eq!(acks.len(), 2, "{}: one response per object expected: {:?}", algorithm, acks);
+            assert!(acks.iter().all(|ack| ack.is_err()), "{}: {:?}", algorithm, acks);
+
+            // the worker keeps serving requests once the node is back
+            rcxl.view.memory_nodes[1].set_failed(false);
+            assert!(matches!(rcxl.read_object(&other), Ok(ReadReturn::ReadSafe(6))));
+            rcxl.stop();
+        }
+    }
+
+    #[test]
+    fn test_partial_write_reports_succeeded_nodes() {
+        // (replication threads, two-phase commit, nodes holding the write)
+        for (replication_threads, two_phase_commit, succeeded) in [(1, false, vec![0]), (3, false, vec![0, 2]), (1, true, vec![])] {
+            let nodes = TestNodes::new("pwrite", 3);
+            let config = RepCXLConfig {
+                round_time: 1_000_000, // 1 ms
+                replication_threads,
+                two_phase_commit,
+                fault: vec![config::FaultSpec { node: 1, start_round: 0, fail_for_rounds: u64::MAX / 2, mode: "write".into() }],
+                force_init: true,
+                ..nodes.config()
+            };
+            let mut rcxl = RepCXL::<u64>::new(config);
+            rcxl.init_state().expect("init_state failed");
+            let obj = rcxl.new_object_with_val(1, 5).expect("failed to create object");
+
+            // writes to the middle node fail from now on
+            for node in &rcxl.view.memory_nodes {
+                node.fault_schedule().start(Instant::now(), Duration::from_millis(1));
+            }
+            let ome = ObjectMemoryEntry::new_nowid(6u64);
+            let err = safe_memio::mem_writeall(obj.info.offset, ome, &rcxl.view.memory_nodes)
+                .expect_err("Write to a failed node should fail");
+            assert_eq!(err.failed, 1);
+            assert_eq!(err.succeeded, succeeded, "threads {} 2pc {}", replication_threads, two_phase_commit);
+
+            let values: Vec<u64> = safe_memio::mem_readall::<u64>(obj.info.offset, &rcxl.view.memory_nodes)
+                .expect("Read should succeed")
+                .iter().map(|ome| ome.value).collect();
+            for (mnid, &value) in values.iter().enumerate() {
+                assert_eq!(value, if succeeded.contains(&mnid) { 6 } else { 5 }, "node {}", mnid);
+            }
+        }
+    }
+
+    // keeps the topology summaries logged by the tests of this module
+    struct TopologyLogger(std::sync::Mutex<Vec<String>>);
+
+    impl log::Log for TopologyLogger {
+        fn enabled(&self, metadata: &log::Metadata) -> bool {
+            metadata.level() <= log::Level::Info
+        }
+
+        fn log(&self, record: &log::Record) {
+            let msg = record.args().to_string();
+            if msg.starts_with("Topology") {
+                self.0.lock().unwrap().push(msg);
+            }
+        }
+
+        fn flush(&self) {}
+    }
+
+    static TOPOLOGY_LOGGER: TopologyLogger = TopologyLogger(std::sync::Mutex::new(Vec::new()));
+
+    #[test]
+    fn test_sync_start_logs_topology() {
+        let nodes = TestNodes::new("topology", 2);
+        let _ = log::set_logger(&TOPOLOGY_LOGGER);
+        log::set_max_level(log::LevelFilter::Info);
+
+        let config = RepCXLConfig {
+            round_time: 1_000_000, // 1 ms
+            force_init: true,
+            ..nodes.config()
+        };
+        let mut rcxl = RepCXL::<u64>::new(config);
+        rcxl.init_state().expect("init_state failed");
+        rcxl.new_object(1).expect("failed to create object");
+        rcxl.sync_start().expect("sync_start failed");
+        rcxl.stop();
+
+        // other tests may log their own topology
+        let logged = TOPOLOGY_LOGGER.0.lock().unwrap();
+        let summary = logged.iter().find(|msg| msg.contains(&nodes.paths[0])).expect("Topology not logged");
+        assert!(summary.contains("(coordinator)"), "{}", summary);
+        assert!(summary.contains("coordinator: 0"), "{}", summary);
+        assert!(summary.contains("2 memory nodes"), "{}", summary);
+        assert!(summary.contains(&nodes.paths[1]), "{}", summary);
+        assert!(summary.contains("objects: 1"), "{}", summary);
+    }
+
+    #[test]
+    fn test_write_worker_restarts_after_panic() {
+        let nodes = TestNodes::new("worker_restart", 1);
+
+        let config = RepCXLConfig {
+            round_time: 1_000_000, // 1 ms
+            pipeline: true,
+            force_init: true,
+            worker_restarts: 1,
+            namespace: 77, // the fault injection is global, keep ids unique
+            ..nodes.config()
+        };
+        let mut rcxl = RepCXL::<u64>::new(config);
+        rcxl.init_state().expect("init_state failed");
+        let faulty = rcxl.new_object(1).expect("failed to create object");
+        let other = rcxl.new_object(2).expect("failed to create object");
+        rcxl.sync_start().expect("sync_start failed");
+
+        // the request that crashed the worker fails, the queue is kept
+        algorithms::PANIC_ON_OBJECT.store(faulty.info.id, Ordering::Relaxed);
+        let crashing = faulty.try_write(5).expect("Failed to enqueue write");
+        let queued = other.try_write(6).expect("Failed to enqueue write");
+        crashing.wait().expect_err("The write crashing the worker should fail");
+        queued.wait().expect("Queued write should be applied by the restarted worker");
+
+        // the restarted worker keeps serving requests
+        faulty.write(7).expect("Write should succeed after the restart");
+        assert!(matches!(rcxl.read_object(&faulty), Ok(ReadReturn::ReadSafe(7))));
+        assert!(matches!(rcxl.read_object(&other), Ok(ReadReturn::ReadSafe(6))));
+        rcxl.stop();
+    }
+
+    #[test]
+    fn test_check_continuity_detects_dropped_write() {
+        let nodes = TestNodes::new("continuity", 1);
+
+        let config = RepCXLConfig {
+            round_time: 1_000_000, // 1 ms
+            pipeline: true,
+            force_init: true,
+            worker_restarts: 1,
+            namespace: 78, // the fault injection is global, keep ids unique
+            ..nodes.config()
+        };
+        let mut rcxl = RepCXL::<u64>::new(config);
+        rcxl.init_state().expect("init_state failed");
+        let obj = rcxl.new_object(1).expect("failed to create object");
+        rcxl.sync_start().expect("sync_start failed");
+
+        obj.write(1).expect("Write should succeed");
+        obj.write(2).expect("Write should succeed");
+        assert_eq!(obj.check_continuity().expect("Check should succeed"), 0);
+
+        // the restart drops the write being processed
+        algorithms::PANIC_ON_OBJECT.store(obj.info.id, Ordering::Relaxed);
+        obj.write(3).expect_err("The write crashing the worker should fail");
+        obj.write(4).expect("Write should succeed after the restart");
+        assert_eq!(obj.check_continuity().expect("Check should succeed"), 1);
+
+        // counted once
+        obj.write(5).expect("Write should succeed");
+        assert_eq!(obj.check_continuity().expect("Check should succeed"), 0);
+        rcxl.stop();
+    }
+
+    #[test]
+    fn test_check_continuity_without_pipeline() {
+        let nodes = TestNodes::new("continuity_direct", 2);
+
+        let config = RepCXLConfig {
+            round_time: 1_000_000, // 1 ms
+            pipeline: false,
+            force_init: true,
+            ..nodes.config()
+        };
+        let mut rcxl = RepCXL::<u64>::new(config);
+        rcxl.init_state().expect("init_state failed");
+        let obj = rcxl.new_object(1).expect("failed to create object");
+        rcxl.sync_start().expect("sync_start failed");
+
+        rcxl.write_object(&obj, 1).expect("Write should succeed");
+        rcxl.write_object(&obj, 2).expect("Write should succeed");
+        assert_eq!(obj.check_continuity().expect("Check should succeed"), 0);
+
+        // a write that got a sequence number but never reached the nodes
+        obj.next_seq();
+        rcxl.write_object(&obj, 4).expect("Write should succeed");
+        assert_eq!(obj.check_continuity().expect("Check should succeed"), 1);
+        rcxl.stop();
+    }
+
+    #[test]
+    fn test_read_direct_prefers_nearest_node() {
+        let nodes = TestNodes::new("nearest", 2);
+
+        // node 1 is local, node 0 remote
+        let config = RepCXLConfig {
+            algorithm: "async_best_effort".to_string(),
+            pipeline: true,
+            force_init: true,
+            mem_node_latency: vec![500, 100],
+            ..nodes.config()
+        };
+        let mut rcxl = RepCXL::<u64>::new(config);
+        rcxl.init_state().expect("init_state failed");
+        let obj = rcxl.new_object(1).expect("failed to create object");
+        rcxl.start();
+
+        assert!(matches!(obj.read_direct(), Ok(ReadReturn::Uninitialized)));
+        obj.write(5).expect("Write should succeed");
+        assert!(matches!(obj.read_direct(), Ok(ReadReturn::ReadDirty(5))));
+
+        // tell the nodes apart by their value
+        let entry = ObjectMemoryEntry::new(request::Wid::new(0, 0), 6u64);
+        safe_memio::mem_writeone(obj.info.offset, entry, &rcxl.view.memory_nodes[1])
+            .expect("Failed to write entry");
+        for _ in 0..10 {
+            assert!(matches!(obj.read_direct(), Ok(ReadReturn::ReadDirty(6))), "Read from the remote node");
+        }
+
+        // the local node fails, fall back to the remote one
+        rcxl.view.memory_nodes[1].set_failed(true);
+        assert!(matches!(obj.read_direct(), Ok(ReadReturn::ReadDirty(5))));
+        rcxl.view.memory_nodes[0].set_failed(true);
+        assert!(obj.read_direct().is_err());
+        rcxl.stop();
+    }
+
+    #[test]
+    fn test_fsck_flags_divergent_object() {
+        let nodes = TestNodes::new("fsck", 3);
+
+        let config = RepCXLConfig {
+            round_time: 1_000_000, // 1 ms
+            force_init: true,
+            ..nodes.config()
+        };
+        let mut rcxl = RepCXL::<u64>::new(config);
+        rcxl.init_state().expect("init_state failed");
+        let divergent = rcxl.new_object(1).expect("failed to create object");
+        let corrupt = rcxl.new_object(2).expect("failed to create object");
+        let _clean = rcxl.new_object(3).expect("failed to create object");
+        rcxl.sync_start().expect("sync_start failed");
+
+        for (obj, val) in [(&divergent, 5), (&corrupt, 6)] {
+            rcxl.write_object(obj, val).expect("Write should succeed");
+        }
+        rcxl.stop();
+
+        let report = rcxl.fsck().expect("fsck failed");
+        assert!(report.is_clean(), "Unexpected inconsistency: {:?}", report);
+
+        // the last node misses the write of object 1
+        let stale = ObjectMemoryEntry::new(request::Wid::new(0, 0), 0u64);
+        safe_memio::mem_writeone(divergent.info.offset, stale, &rcxl.view.memory_nodes[2])
+            .expect("Failed to write stale entry");
+        // the value of object 2 changes on node 1 without a new write
+        let mut entry: ObjectMemoryEntry<u64> = safe_memio::mem_readall(corrupt.info.offset, &rcxl.view.memory_nodes)
+            .expect("Read should succeed")[1];
+        entry.value = 7;
+        safe_memio::mem_writeone(corrupt.info.offset, entry, &rcxl.view.memory_nodes[1])
+            .expect("Failed to write corrupt entry");
+
+        let report = rcxl.fsck().expect("fsck failed");
+        assert!(!report.is_clean());
+        assert!(report.state_consistent());
+        assert!(report.overlapping_objects.is_empty());
+        let status = |id| report.objects.iter().find(|o| o.id == id).expect("Object missing from the report");
+        assert_eq!(status(1).status, ObjectStatus::Divergent);
+        assert_eq!(status(1).disagreeing_nodes, vec![2]);
+        assert_eq!(status(2).status, ObjectStatus::Corrupt);
+        assert_eq!(status(2).disagreeing_nodes, vec![1]);
+        assert_eq!(status(3).status, ObjectStatus::Consistent);
+        assert_eq!(report.disagreeing_nodes(), vec![1, 2]);
+    }
+
+    #[test]
+    fn test_two_phase_commit_reads_never_see_partial_write() {
+        let nodes = TestNodes::new("2pc", 3);
+
+        let config = RepCXLConfig {
+            round_time: 1_000_000, // 1 ms
+            pipeline: true,
+            two_phase_commit: true,
+            force_init: true,
+            ..nodes.config()
+        };
+        let mut rcxl = RepCXL::<u64>::new(config);
+        rcxl.init_state().expect("init_state failed");
+        let obj = rcxl.new_object(1).expect("failed to create object");
+        assert_eq!(obj.info.size, safe_memio::object_size::<u64>(true));
+        rcxl.sync_start().expect("sync_start failed");
+        assert!(matches!(rcxl.read_object(&obj), Ok(ReadReturn::Uninitialized)));
+
+        // writes replicate to the nodes one by one while clients read
+        let writes: u64 = 20_000;
+        let done = AtomicBool::new(false);
+        let view = &rcxl.view;
+        let offset = obj.info.offset;
+        let reader = || {
+            let mut last = 0;
+            while !done.load(Ordering::Relaxed) {
+                match obj.read().expect("Read should succeed") {
+                    ReadReturn::ReadSafe(v) => {
+                        assert!(v >= last, "Read {} after {}", v, last);
+                        last = v;
+                    }
+                    ReadReturn::Uninitialized => assert_eq!(last, 0),
+                    ReadReturn::ReadDirty(v) => panic!("Read a partially replicated write: {}", v),
+                    ReadReturn::TooStale => panic!("Read without freshness bound"),
+                    ReadReturn::ObjectRemoved => panic!("Read a removed object"),
+                }
+            }
+        };
+        std::thread::scope(|s| {
+            s.spawn(reader);
+            s.spawn(reader);
+            for i in 1..=writes {
+                let ome = ObjectMemoryEntry::new(request::Wid::new(i, 0), i);
+                safe_memio::mem_writeall(offset, ome, &view.memory_nodes).expect("Write should succeed");
+            }
+            done.store(true, Ordering::Relaxed);
+        });
+
+        assert!(matches!(rcxl.read_object(&obj), Ok(ReadReturn::ReadSafe(v)) if v == writes));
+        for ome in safe_memio::mem_readall::<u64>(offset, &rcxl.view.memory_nodes).expect("Read should succeed") {
+            assert_eq!(ome.value, writes);
+        }
+        rcxl.write_object(&obj, 1).expect("Write should succeed");
+        assert!(matches!(rcxl.read_object(&obj), Ok(ReadReturn::ReadSafe(1))));
+        assert!(rcxl.fsck().expect("fsck failed").is_clean());
+        rcxl.stop();
     }
 }
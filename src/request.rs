@@ -1,63 +1,455 @@
 use std::hash::Hash;
-use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::atomic::{AtomicU64, AtomicU8, AtomicUsize, Ordering};
+use std::collections::HashMap;
+use std::sync::{Arc, Condvar, Mutex};
 use std::time::Instant;
 use crate::shmem::object_index::ObjectInfo;
+use crate::ring::RingLayout;
 
 static WRITE_REQ_TRACE_ID: AtomicU64 = AtomicU64::new(1);
 
+// write request status, shared between the client and the write worker
+const WREQ_QUEUED: u8 = 0;
+const WREQ_PICKED: u8 = 1;
+const WREQ_CANCELLED: u8 = 2;
+
+/// Counts a write request as pending from its creation until it is dropped,
+/// i.e. until the write worker is done with it.
+pub(crate) struct PendingWrite(Arc<AtomicUsize>);
+
+impl PendingWrite {
+    fn new(pending_writes: &Arc<AtomicUsize>) -> Self {
+        pending_writes.fetch_add(1, Ordering::AcqRel);
+        PendingWrite(pending_writes.clone())
+    }
+}
+
+impl Drop for PendingWrite {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+/// Writes in flight per object of a process, issued with
+/// `RepCXLObject::try_write`. Capped to `max` per object: with 1, a write is
+/// only queued once the previous one of the object is done, keeping the
+/// per-object order, while writes to different objects still pipeline.
+#[derive(Debug)]
+pub(crate) struct InflightWrites {
+    max: usize,
+    counts: Mutex<HashMap<usize, usize>>,
+    released: Condvar,
+}
+
+impl InflightWrites {
+    pub(crate) fn new(max: usize) -> Self {
+        InflightWrites { max, counts: Mutex::new(HashMap::new()), released: Condvar::new() }
+    }
+
+    /// Block until object `id` has less than `max` writes in flight, then
+    /// count one more until the returned slot is dropped
+    pub(crate) fn acquire(self: &Arc<Self>, id: usize) -> InflightSlot {
+        let mut counts = self.counts.lock().unwrap();
+        while counts.get(&id).is_some_and(|&n| n >= self.max) {
+            counts = self.released.wait(counts).unwrap();
+        }
+        *counts.entry(id).or_insert(0) += 1;
+        InflightSlot { writes: self.clone(), id }
+    }
+}
+
+/// In-flight write slot of an object, released when the write request
+/// holding it is dropped, i.e. when the write worker is done with it
+#[derive(Debug)]
+pub(crate) struct InflightSlot {
+    writes: Arc<InflightWrites>,
+    id: usize,
+}
+
+impl Drop for InflightSlot {
+    fn drop(&mut self) {
+        let mut counts = self.writes.counts.lock().unwrap();
+        if let Some(n) = counts.get_mut(&self.id) {
+            *n -= 1;
+            if *n == 0 {
+                counts.remove(&self.id);
+            }
+        }
+        self.writes.released.notify_all();
+    }
+}
+
+/// Write acknowledgement sent by the write worker
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WriteAck {
+    pub success: bool,
+    /// no phase of the write overran its round
+    pub on_time: bool,
+    /// rounds from the first Try to the completion of the write, retries
+    /// included. 0 for algorithms without rounds
+    pub rounds_taken: u64,
+    /// set if the write lost a conflict, names the write that won the last
+    /// one. The application can use it to rebase and retry
+    pub conflict: Option<ConflictInfo>,
+    /// another process wrote the object in a round this write was tried in,
+    /// whether this write won or lost
+    pub conflicted: bool,
+    /// number of times the write was tried again after losing a conflict
+    pub retries: u32,
+    /// rounds the write waited in the queue before the worker picked it up,
+    /// i.e. behind the other writes of the process. Not included in
+    /// `rounds_taken`. 0 for algorithms without rounds
+    pub queue_wait_rounds: u64,
+    /// the write was abandoned unreplicated because its deadline passed, see
+    /// `RepCXLObject::write_deadline`
+    pub deadline_exceeded: bool,
+    /// id of the write on the memory nodes, see `RepCXLObject::flush`. None
+    /// for algorithms without write ids, which ack once every node holds
+    /// the write
+    pub wid: Option<Wid>,
+}
+
+impl WriteAck {
+    pub(crate) fn failed() -> Self {
+        WriteAck { success: false, on_time: false, rounds_taken: 0, conflict: None, conflicted: false, retries: 0, queue_wait_rounds: 0, deadline_exceeded: false, wid: None }
+    }
+
+    /// Ack of a write replicated with `wid` after `rounds_taken` rounds
+    pub(crate) fn succeeded(
+        on_time: bool,
+        rounds_taken: u64,
+        conflict: Option<ConflictInfo>,
+        conflicted: bool,
+        retries: u32,
+        wid: Wid,
+    ) -> Self {
+        WriteAck { success: true, on_time, rounds_taken, conflict, conflicted, retries, queue_wait_rounds: 0, deadline_exceeded: false, wid: Some(wid) }
+    }
+
+    /// Ack of a write without rounds or conflicts
+    pub(crate) fn uncontended(rounds_taken: u64) -> Self {
+        WriteAck { success: true, on_time: true, rounds_taken, conflict: None, conflicted: false, retries: 0, queue_wait_rounds: 0, deadline_exceeded: false, wid: None }
+    }
+}
+
+/// When the write worker acknowledges a write, see the `ack_policy` config
+/// and `RepCXLObject::write_with_ack`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AckPolicy {
+    /// once every memory node holds the write
+    All,
+    /// once a majority of the memory nodes hold the write. The worker
+    /// writes the other nodes before its next request
+    Quorum,
+}
+
+impl AckPolicy {
+    /// Policy of an `ack_policy` config value
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "all" => Some(AckPolicy::All),
+            "quorum" => Some(AckPolicy::Quorum),
+            _ => None,
+        }
+    }
+}
+
+/// Winner of a write conflict, as found in memory after the conflict
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConflictInfo {
+    pub winner_pid: usize,
+    pub winner_wid: Wid,
+}
+
 pub struct WriteRequest<T> {
     pub(crate) obj_info: ObjectInfo,
     pub data: T,
-    pub ack_tx: kanal::Sender<bool>,
+    pub ack_tx: kanal::Sender<WriteAck>,
     pub trace_id: u64,
     pub enqueue_at: Instant,
+    /// set for swaps: the worker sends the replaced value here before the ack
+    pub(crate) prev_tx: Option<kanal::Sender<T>>,
+    /// wall clock (ns since the epoch) when the client issued the write,
+    /// orders conflicting writes with the "timestamp" conflict order
+    pub timestamp: u64,
+    /// set for partial writes: ids of the only memory nodes to write
+    pub(crate) nodes: Option<Vec<usize>>,
+    /// set for ring pushes: the worker picks the entry to write
+    pub(crate) ring: Option<RingLayout>,
+    /// set for read-modify-writes (with `prev_tx`): the worker writes
+    /// `update(previous, data)`
+    pub(crate) update: Option<fn(T, T) -> T>,
+    /// client-side sequence number stored with the value, see
+    /// `RepCXLObject::check_continuity`
+    pub(crate) seq: u64,
+    /// overrides the `ack_policy` of the instance
+    pub(crate) ack_policy: Option<AckPolicy>,
+    /// abandon the write if it is not replicated by then
+    pub(crate) deadline: Option<Instant>,
+    status: Arc<AtomicU8>,
+    _pending: PendingWrite,
+    _inflight: Option<InflightSlot>,
 }
 
 impl<T> WriteRequest<T> {
-    pub(crate) fn new(obj_info: ObjectInfo, data: T, ack_tx: kanal::Sender<bool>) -> Self {
+    pub(crate) fn new(
+        obj_info: ObjectInfo,
+        data: T,
+        ack_tx: kanal::Sender<WriteAck>,
+        pending_writes: &Arc<AtomicUsize>,
+    ) -> Self {
         WriteRequest {
             obj_info,
             data,
             ack_tx,
             trace_id: WRITE_REQ_TRACE_ID.fetch_add(1, Ordering::Relaxed),
             enqueue_at: Instant::now(),
+            prev_tx: None,
+            timestamp: crate::timer::wall_clock_ns(),
+            nodes: None,
+            ring: None,
+            update: None,
+            seq: 0,
+            ack_policy: None,
+            deadline: None,
+            status: Arc::new(AtomicU8::new(WREQ_QUEUED)),
+            _pending: PendingWrite::new(pending_writes),
+            _inflight: None,
+        }
+    }
+
+    /// Write request that also returns the value it replaces on `prev_tx`
+    pub(crate) fn new_swap(
+        obj_info: ObjectInfo,
+        data: T,
+        ack_tx: kanal::Sender<WriteAck>,
+        prev_tx: kanal::Sender<T>,
+        pending_writes: &Arc<AtomicUsize>,
+    ) -> Self {
+        let mut req = Self::new(obj_info, data, ack_tx, pending_writes);
+        req.prev_tx = Some(prev_tx);
+        req
+    }
+
+    /// Swap writing `update(previous, data)`, see `RepCXLObject::fetch_update`
+    pub(crate) fn with_update(mut self, update: Option<fn(T, T) -> T>) -> Self {
+        self.update = update;
+        self
+    }
+
+    /// Use the timestamp given by the client instead of the wall clock
+    pub(crate) fn with_timestamp(mut self, timestamp: u64) -> Self {
+        self.timestamp = timestamp;
+        self
+    }
+
+    /// Write the given memory nodes only, see `RepCXLObject::write_to`
+    pub(crate) fn with_nodes(mut self, nodes: &[usize]) -> Self {
+        self.nodes = Some(nodes.to_vec());
+        self
+    }
+
+    /// Push to a ring, see `RingObject::push`
+    pub(crate) fn with_ring(mut self, layout: RingLayout) -> Self {
+        self.ring = Some(layout);
+        self
+    }
+
+    /// Store the client-side sequence number of the write
+    pub(crate) fn with_seq(mut self, seq: u64) -> Self {
+        self.seq = seq;
+        self
+    }
+
+    /// Acknowledge the write with `policy` instead of the instance policy
+    pub(crate) fn with_ack_policy(mut self, policy: AckPolicy) -> Self {
+        self.ack_policy = Some(policy);
+        self
+    }
+
+    /// Abandon the write if it is not replicated by `deadline`, see
+    /// `RepCXLObject::write_deadline`
+    pub(crate) fn with_deadline(mut self, deadline: Instant) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Hold an in-flight write slot of the object until the request is done
+    pub(crate) fn with_inflight_slot(mut self, slot: InflightSlot) -> Self {
+        self._inflight = Some(slot);
+        self
+    }
+
+    /// Called by the write worker before processing the request. Returns
+    /// false if the client cancelled the request while it was queued, in
+    /// which case it must be dropped without writing.
+    pub(crate) fn pick(&self) -> bool {
+        self.status
+            .compare_exchange(WREQ_QUEUED, WREQ_PICKED, Ordering::AcqRel, Ordering::Acquire)
+            .is_ok()
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum CancelError {
+    /// The write worker already picked up the request, it will be applied
+    AlreadyApplied,
+}
+
+/// Successful acks of the writes of a handle, see
+/// `RepCXLObject::check_continuity` and `RepCXLObject::flush`
+#[derive(Debug, Default)]
+pub(crate) struct AckCounter {
+    count: AtomicU64,
+    // latest write id among the acks
+    last_wid: std::sync::Mutex<Option<Wid>>,
+}
+
+impl AckCounter {
+    pub(crate) fn record(&self, ack: &WriteAck) {
+        self.count.fetch_add(1, Ordering::AcqRel);
+        if let Some(wid) = ack.wid {
+            let mut last_wid = self.last_wid.lock().unwrap();
+            if last_wid.is_none_or(|last| wid > last) {
+                *last_wid = Some(wid);
+            }
+        }
+    }
+
+    pub(crate) fn count(&self) -> u64 {
+        self.count.load(Ordering::Acquire)
+    }
+
+    pub(crate) fn last_wid(&self) -> Option<Wid> {
+        *self.last_wid.lock().unwrap()
+    }
+}
+
+/// Handle to a write request issued with `RepCXLObject::try_write`
+pub struct WriteToken {
+    status: Arc<AtomicU8>,
+    ack_rx: kanal::Receiver<WriteAck>,
+    // acks of the handle, see `RepCXLObject::check_continuity`
+    acked: Option<Arc<AckCounter>>,
+}
+
+impl WriteToken {
+    pub(crate) fn new<T>(req: &WriteRequest<T>, ack_rx: kanal::Receiver<WriteAck>) -> Self {
+        WriteToken {
+            status: req.status.clone(),
+            ack_rx,
+            acked: None,
         }
     }
 
-    pub(crate) fn to_tuple(self) -> (ObjectInfo, T, kanal::Sender<bool>) {
-        (self.obj_info, self.data, self.ack_tx)
+    /// Count the write in `acked` once it is acknowledged successfully
+    pub(crate) fn counting_acks(mut self, acked: Arc<AckCounter>) -> Self {
+        self.acked = Some(acked);
+        self
+    }
+
+    /// Remove the write request if it is still queued, i.e. the worker has
+    /// not picked it up yet in a Try round. A cancelled write is never
+    /// replicated.
+    pub fn cancel(&self) -> Result<(), CancelError> {
+        match self.status.compare_exchange(
+            WREQ_QUEUED,
+            WREQ_CANCELLED,
+            Ordering::AcqRel,
+            Ordering::Acquire,
+        ) {
+            Ok(_) | Err(WREQ_CANCELLED) => Ok(()),
+            Err(_) => Err(CancelError::AlreadyApplied),
+        }
+    }
+
+    /// Block until the write is acknowledged by the worker
+    pub fn wait(self) -> Result<(), String> {
+        match self.ack_rx.recv() {
+            Ok(ack) if ack.success => {
+                if let Some(acked) = &self.acked {
+                    acked.record(&ack);
+                }
+                Ok(())
+            }
+            Ok(_) => Err("Failed write operation".into()),
+            Err(e) => Err(format!("Failed to receive ack: {}", e)),
+        }
     }
 }
 
+/// Objects to read in a single read request
+enum ReadTargets {
+    One(ObjectInfo),
+    Many(Vec<ObjectInfo>),
+}
+
+/// Read request. The worker sends one ReadAck per object on `ack_tx`, in the
+/// same order as the objects in the request. A failed read gets its error and
+/// does not stop the reads of the next objects.
 pub struct ReadRequest<T> {
-    pub(crate) obj_info: ObjectInfo,
-    pub ack_tx: kanal::Sender<ReadReturn<T>>,
+    targets: ReadTargets,
+    pub ack_tx: kanal::Sender<ReadAck<T>>,
+    /// Freshness bound of the read in rounds, see `RepCXLObject::read_fresh`
+    pub max_stale_rounds: Option<u64>,
 }
 
 impl<T> ReadRequest<T> {
-    pub(crate) fn new(obj_info: ObjectInfo, ack_tx: kanal::Sender<ReadReturn<T>>) -> Self {
-        ReadRequest { obj_info, ack_tx }
+    pub(crate) fn new(obj_info: ObjectInfo, ack_tx: kanal::Sender<ReadAck<T>>) -> Self {
+        ReadRequest { targets: ReadTargets::One(obj_info), ack_tx, max_stale_rounds: None }
+    }
+
+    pub(crate) fn new_batch(obj_infos: Vec<ObjectInfo>, ack_tx: kanal::Sender<ReadAck<T>>) -> Self {
+        ReadRequest { targets: ReadTargets::Many(obj_infos), ack_tx, max_stale_rounds: None }
+    }
+
+    pub(crate) fn with_max_stale_rounds(mut self, max_stale_rounds: u64) -> Self {
+        self.max_stale_rounds = Some(max_stale_rounds);
+        self
+    }
+
+    pub(crate) fn objects(&self) -> &[ObjectInfo] {
+        match &self.targets {
+            ReadTargets::One(obj_info) => std::slice::from_ref(obj_info),
+            ReadTargets::Many(obj_infos) => obj_infos,
+        }
     }
 }
 
-#[derive(Debug)]
+/// Response of the read worker for one object
+pub type ReadAck<T> = Result<ReadReturn<T>, String>;
+
+#[derive(Debug, Clone)]
 pub enum ReadReturn<T> {
     ReadSafe(T),
     ReadDirty(T),
+    /// the object was created without a value and never written
+    Uninitialized,
+    /// the newest value is older than the freshness bound of the read, only
+    /// returned by `RepCXLObject::read_fresh`
+    TooStale,
+    /// the object was removed since the handle was created, its region might
+    /// now hold another object
+    ObjectRemoved,
 }
 
 /// RepCXL write request unique identifier. Stored next to every object
 /// Comparison checks for largest round number and smallest process ID if
 /// round numbers are equal.
+/// With the "timestamp" conflict order, the client timestamp is compared
+/// first. It is 0 otherwise.
 #[derive(Debug, Clone, Copy)]
 pub struct Wid {
     pub round_num: u64,
     pub process_id: usize,
+    pub timestamp: u64,
 }
 
 impl PartialEq for Wid {
     fn eq(&self, other: &Self) -> bool {
-        self.round_num == other.round_num && self.process_id == other.process_id
+        self.timestamp == other.timestamp
+            && self.round_num == other.round_num
+            && self.process_id == other.process_id
     }
 }
 impl Eq for Wid {}
@@ -70,6 +462,9 @@ impl PartialOrd for Wid {
 
 impl Ord for Wid {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        if self.timestamp != other.timestamp {
+            return self.timestamp.cmp(&other.timestamp);
+        }
         match self.round_num.cmp(&other.round_num) {
             std::cmp::Ordering::Greater => std::cmp::Ordering::Greater,
             std::cmp::Ordering::Less => std::cmp::Ordering::Less,
@@ -82,6 +477,7 @@ impl Hash for Wid {
     fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
         self.round_num.hash(state);
         self.process_id.hash(state);
+        self.timestamp.hash(state);
     }
 }
 
@@ -90,6 +486,16 @@ impl Wid {
         Wid {
             round_num,
             process_id,
+            timestamp: 0,
+        }
+    }
+
+    /// Write id ordered by `timestamp` first
+    pub fn with_timestamp(round_num: u64, process_id: usize, timestamp: u64) -> Self {
+        Wid {
+            round_num,
+            process_id,
+            timestamp,
         }
     }
 }
\ No newline at end of file
@@ -0,0 +1,93 @@
+//! Ring objects, append-only buffers of the last pushed values
+
+use crate::request::WriteRequest;
+use crate::safe_memio::{mem_readall, MemoryError, ObjectMemoryEntry};
+use crate::shmem::object_index::ObjectInfo;
+use crate::{GroupView, RepCXLObject};
+
+/// Layout of a ring allocation: `capacity` entries of `entry_size` bytes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct RingLayout {
+    pub capacity: usize,
+    pub entry_size: usize,
+}
+
+impl RingLayout {
+    /// Offset of the entry holding push `seq` (starting at 1)
+    pub(crate) fn slot_offset(&self, obj_info: &ObjectInfo, seq: u64) -> usize {
+        obj_info.offset + ((seq - 1) as usize % self.capacity) * self.entry_size
+    }
+}
+
+/// Replicated append-only ring of the last `capacity()` pushed values, e.g.
+/// for event streams. Each entry stores the sequence number of its push in
+/// the entry version, so the head of the ring is the largest sequence number
+/// and a push replaces the oldest entry once the ring is full. The slot of a
+/// push is picked by the MONSTER write worker in the Replicate phase, and a
+/// push losing a conflict is retried instead of being ordered before the
+/// winner: concurrent pushes are serialized by round and pid. Only supported
+/// by the MONSTER algorithms, in pipeline mode. See `RepCXL::new_ring`.
+#[derive(Debug)]
+pub struct RingObject<T: Copy> {
+    // the whole allocation, conflicts are detected on the ring id
+    pub(crate) obj: RepCXLObject<T>,
+    pub(crate) layout: RingLayout,
+    view: GroupView,
+}
+
+impl<T: Copy> RingObject<T> {
+    pub(crate) fn new(obj: RepCXLObject<T>, capacity: usize, entry_size: usize, view: GroupView) -> Self {
+        RingObject { obj, layout: RingLayout { capacity, entry_size }, view }
+    }
+
+    /// Maximum number of values kept
+    pub fn capacity(&self) -> usize {
+        self.layout.capacity
+    }
+
+    pub fn id(&self) -> usize {
+        self.obj.info.local_id()
+    }
+
+    /// Append a value, replacing the oldest one if the ring is full. Returns
+    /// once the value is replicated.
+    pub fn push(&self, value: T) -> Result<(), String> {
+        let (ack_tx, ack_rx) = kanal::unbounded();
+        let req = WriteRequest::new(self.obj.info, value, ack_tx, &self.obj.pending_writes)
+            .with_ring(self.layout);
+        match self.obj.send_write(req, ack_rx)? {
+            ack if ack.success => Ok(()),
+            _ => Err("Failed push operation".into()),
+        }
+    }
+
+    /// Values of the ring from the oldest to the newest push, by the newest
+    /// entry of each slot across the memory nodes
+    pub fn read_all(&self) -> Result<Vec<T>, String> {
+        let entries = newest_entries::<T>(&self.obj.info, self.layout, &self.view)
+            .map_err(|MemoryError(mnid)| format!("Memory node {} failed during read", mnid))?;
+        let mut pushed: Vec<ObjectMemoryEntry<T>> = entries.into_iter()
+            .filter(|ome| ome.written && ome.version > 0)
+            .collect();
+        pushed.sort_by_key(|ome| ome.version);
+        Ok(pushed.into_iter().map(|ome| ome.value).collect())
+    }
+}
+
+/// Newest entry of each slot of a ring across the memory nodes
+fn newest_entries<T: Copy>(
+    obj_info: &ObjectInfo,
+    layout: RingLayout,
+    view: &GroupView,
+) -> Result<Vec<ObjectMemoryEntry<T>>, MemoryError> {
+    (0..layout.capacity).map(|i| {
+        let omes: Vec<ObjectMemoryEntry<T>> = mem_readall(obj_info.offset + i * layout.entry_size, &view.memory_nodes)?;
+        Ok(omes.into_iter().max_by_key(|ome| (ome.version, ome.wid)).unwrap())
+    }).collect()
+}
+
+/// Sequence number of the next push, one past the largest one stored
+pub(crate) fn next_seq<T: Copy>(obj_info: &ObjectInfo, layout: RingLayout, view: &GroupView) -> Result<u64, MemoryError> {
+    let entries = newest_entries::<T>(obj_info, layout, view)?;
+    Ok(entries.iter().map(|ome| ome.version).max().unwrap_or(0) + 1)
+}
@@ -13,6 +13,9 @@ use crate::request::Wid;
 use crate::shmem::MemoryNode;
 use log::error;
 use core::arch::x86_64::{_mm_mfence, _mm_sfence};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
 
 const FAILURE_PROBABILITY: f32 = 0.0;
 pub const CACHE_LINE_SIZE: usize = 64;
@@ -65,29 +68,268 @@ pub(crate) fn mem_write_flush<T: Copy>(addr: *mut T, data: T) {
 #[derive(Debug)]
 pub struct MemoryError(pub usize);
 
+/// Failed `mem_writeall`, the entry is visible on the `succeeded` nodes only
+/// and the caller decides to roll them back or to go on without `failed`.
+#[derive(Debug)]
+pub struct PartialWrite {
+    /// IDs of the nodes holding the new entry, in node order
+    pub succeeded: Vec<usize>,
+    /// ID of the first node that failed
+    pub failed: usize,
+    pub cause: &'static str,
+}
+
+impl From<PartialWrite> for MemoryError {
+    fn from(e: PartialWrite) -> Self {
+        MemoryError(e.failed)
+    }
+}
+
 
 /// ObjectMemoryEntry. Stores the current write ID and the value of the object
-/// in memory.
+/// in memory. `written` is false in zeroed memory, i.e. for objects that were
+/// never written or initialized with a value.
+///
+/// `version` is the commit version of the object, bumped by every MONSTER
+/// replicate above the versions stored on the nodes. 0 for entries written
+/// without a wid.
+///
+/// `seq` is the client-side sequence number of the write, given by the
+/// object handle that issued it (see `RepCXLObject::check_continuity`). 0
+/// for entries not written by a handle.
 #[derive(Debug, Clone, Copy)]
 pub struct ObjectMemoryEntry<T> {
     pub wid: Wid,
+    pub written: bool,
+    pub version: u64,
+    pub seq: u64,
     pub value: T,
 }
 
 impl<T: Copy> ObjectMemoryEntry<T> {
     pub fn new(wid: Wid, value: T) -> Self {
-        ObjectMemoryEntry { wid, value }
+        ObjectMemoryEntry { wid, written: true, version: 0, seq: 0, value }
     }
 
     pub fn new_nowid(value: T) -> Self {
         ObjectMemoryEntry {
             wid: Wid::new(0, 0),
+            written: true,
+            version: 0,
+            seq: 0,
             value,
         }
     }
+
+    pub fn with_version(mut self, version: u64) -> Self {
+        self.version = version;
+        self
+    }
+
+    pub fn with_seq(mut self, seq: u64) -> Self {
+        self.seq = seq;
+        self
+    }
+}
+
+/// Two-phase commit layout of an object: a version word, then two entry
+/// slots. The committed entry is in slot `version % 2`, the other slot is the
+/// shadow slot written by the next write before it is committed.
+const TWO_PHASE_SLOTS_OFFSET: usize = CACHE_LINE_SIZE;
+
+/// Size of the memory region of an object of type T
+pub(crate) fn object_size<T>(two_phase_commit: bool) -> usize {
+    if two_phase_commit {
+        TWO_PHASE_SLOTS_OFFSET + 2 * size_of::<ObjectMemoryEntry<T>>()
+    } else {
+        size_of::<ObjectMemoryEntry<T>>()
+    }
+}
+
+fn version_word(node: &MemoryNode, offset: usize) -> &AtomicU64 {
+    unsafe { &*(node.addr_at(offset) as *const AtomicU64) }
+}
+
+fn read_version(node: &MemoryNode, offset: usize) -> u64 {
+    unsafe { cache_flush_read(node.addr_at(offset), size_of::<u64>()); }
+    version_word(node, offset).load(Ordering::Acquire)
+}
+
+fn slot_addr<T>(node: &MemoryNode, offset: usize, version: u64) -> *mut ObjectMemoryEntry<T> {
+    let slot = (version % 2) as usize;
+    node.addr_at(offset + TWO_PHASE_SLOTS_OFFSET + slot * size_of::<ObjectMemoryEntry<T>>()) as *mut ObjectMemoryEntry<T>
+}
+
+fn read_slot<T: Copy>(node: &MemoryNode, offset: usize, version: u64) -> Result<ObjectMemoryEntry<T>, &'static str> {
+    let addr = slot_addr::<T>(node, offset, version);
+    unsafe { cache_flush_read(addr as *const u8, size_of::<ObjectMemoryEntry<T>>()); }
+    safe_read(node, addr)
+}
+
+/// Read the committed entry of the object on `node`. With two-phase commit
+/// the slot is picked from the version of the node, and the read is retried
+/// if the version changed meanwhile, i.e. if the slot might have been
+/// overwritten by a later write or repair.
+fn read_entry<T: Copy>(node: &MemoryNode, offset: usize) -> Result<ObjectMemoryEntry<T>, &'static str> {
+    if !node.two_phase_commit() {
+        return safe_read(node, node.addr_at(offset) as *mut ObjectMemoryEntry<T>);
+    }
+    loop {
+        let version = read_version(node, offset);
+        let ome = read_slot(node, offset, version)?;
+        if read_version(node, offset) == version {
+            return Ok(ome);
+        }
+    }
+}
+
+type ReplicationJob = Box<dyn FnOnce() + Send>;
+
+/// Threads writing an entry to the memory nodes in parallel, see the
+/// `replication_threads` config. Shared by the nodes of an instance, the
+/// threads exit when the last node is dropped.
+pub(crate) struct ReplicationPool {
+    jobs: kanal::Sender<ReplicationJob>,
+    threads: usize,
+}
+
+// lets the jobs borrow from the caller of `ReplicationPool::run`, which waits
+// for them
+struct AssertSend<T>(T);
+unsafe impl<T> Send for AssertSend<T> {}
+unsafe impl<T> Sync for AssertSend<T> {}
+
+impl ReplicationPool {
+    pub(crate) fn new(threads: usize) -> Self {
+        let (jobs, rx) = kanal::unbounded::<ReplicationJob>();
+        for _ in 0..threads {
+            let rx = rx.clone();
+            std::thread::spawn(move || {
+                while let Ok(job) = rx.recv() {
+                    job();
+                }
+            });
+        }
+        ReplicationPool { jobs, threads }
+    }
+
+    /// Call `f` on `0..n`, spread over the threads of the pool, and wait
+    /// for all calls. Returns the errors of the failed indices, lowest first.
+    /// A call that panics is reported as an error of its index, so the pool
+    /// keeps its threads and never returns while a job can still call `f`.
+    fn run<F, E: From<&'static str> + Send + 'static>(&self, n: usize, f: F) -> Result<(), Vec<(usize, E)>>
+    where
+        F: Fn(usize) -> Result<(), E>,
+    {
+        let f: &dyn Fn(usize) -> Result<(), E> = &f;
+        // the jobs are done before `f` goes out of scope: `run` waits until
+        // every job sent its result or was dropped
+        let f_static: &'static dyn Fn(usize) -> Result<(), E> = unsafe { std::mem::transmute(f) };
+        let f_static = std::sync::Arc::new(AssertSend(f_static));
+
+        let (done_tx, done_rx) = kanal::unbounded();
+        let jobs = self.threads.min(n);
+        let mut errs = Vec::new();
+        let mut sent = 0;
+        for first in 0..jobs {
+            let (f_job, done_tx) = (f_static.clone(), done_tx.clone());
+            let job: ReplicationJob = Box::new(move || {
+                let errs = run_job(f_job.0, first, n, jobs);
+                // flushes of this thread
+                unsafe { _mm_mfence(); }
+                let _ = done_tx.send(errs);
+            });
+            if self.jobs.send(job).is_ok() {
+                sent += 1;
+            } else {
+                // the pool stopped, replicate in the calling thread
+                error!("Replication pool stopped, writing from the calling thread");
+                errs.extend(run_job(f, first, n, jobs));
+            }
+        }
+        // only the jobs hold a sender now, `recv` fails once they are all
+        // done or dropped
+        drop(done_tx);
+
+        for _ in 0..sent {
+            match done_rx.recv() {
+                Ok(job_errs) => errs.extend(job_errs),
+                Err(_) => break,
+            }
+        }
+        if errs.is_empty() {
+            return Ok(());
+        }
+        errs.sort_by_key(|&(i, _)| i);
+        Err(errs)
+    }
+}
+
+// Call `f` on the indices `first, first + step, ..` below `n`
+fn run_job<E: From<&'static str>>(
+    f: &dyn Fn(usize) -> Result<(), E>,
+    first: usize,
+    n: usize,
+    step: usize,
+) -> Vec<(usize, E)> {
+    (first..n).step_by(step)
+        .filter_map(|i| match std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| f(i))) {
+            Ok(res) => res.err().map(|e| (i, e)),
+            Err(_) => {
+                error!("Replication of index {} panicked", i);
+                Some((i, E::from("Replication panicked")))
+            }
+        })
+        .collect()
+}
+
+/// Scheduled failure of a memory node, see the `fault` config
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct FaultWindow {
+    pub(crate) start_round: u64,
+    pub(crate) end_round: u64, // exclusive
+    pub(crate) reads: bool,
+    pub(crate) writes: bool,
+}
+
+/// Scheduled failures of a memory node, in rounds. Inactive until the rounds
+/// start, see `start`
+#[derive(Debug, Default)]
+pub(crate) struct FaultSchedule {
+    windows: Vec<FaultWindow>,
+    clock: OnceLock<(Instant, Duration)>,
+}
+
+impl FaultSchedule {
+    pub(crate) fn new(windows: Vec<FaultWindow>) -> Self {
+        FaultSchedule { windows, clock: OnceLock::new() }
+    }
+
+    /// Start counting rounds from `start_instant`
+    pub(crate) fn start(&self, start_instant: Instant, round_time: Duration) {
+        let _ = self.clock.set((start_instant, round_time));
+    }
+
+    /// A scheduled failure of the reads (or writes) is active in the current
+    /// round
+    pub(crate) fn fails(&self, write: bool) -> bool {
+        if self.windows.is_empty() {
+            return false;
+        }
+        let Some(&(start_instant, round_time)) = self.clock.get() else {
+            return false;
+        };
+        let round = crate::timer::current_round(start_instant, round_time);
+        self.windows.iter().any(|w| {
+            (if write { w.writes } else { w.reads }) && w.start_round <= round && round < w.end_round
+        })
+    }
 }
 
-pub fn safe_write<T: Copy>(addr: *mut ObjectMemoryEntry<T>, data: ObjectMemoryEntry<T>) -> Result<(), &'static str> {
+pub fn safe_write<T: Copy>(node: &MemoryNode, addr: *mut ObjectMemoryEntry<T>, data: ObjectMemoryEntry<T>) -> Result<(), &'static str> {
+    if node.fault_schedule().fails(true) {
+        return Err("Scheduled write failure");
+    }
     if FAILURE_PROBABILITY > 0.0 {
         let mut rng = rand::rng();
         let roll: f32 = rng.random(); // random float between 0.0 and 1.0
@@ -96,12 +338,20 @@ pub fn safe_write<T: Copy>(addr: *mut ObjectMemoryEntry<T>, data: ObjectMemoryEn
         }
     }
 
+    if let Some(delay) = node.write_delay() {
+        std::thread::sleep(delay);
+    }
+
     // mechanism to handle segfault here, signal catch plus backup process
     unsafe { std::ptr::write_volatile(addr, data); }
+    node.count_written(size_of::<T>());
     Ok(())
 }
 
-pub fn safe_read<T: Copy>(addr: *mut ObjectMemoryEntry<T>) -> Result<ObjectMemoryEntry<T>, &'static str> {
+pub fn safe_read<T: Copy>(node: &MemoryNode, addr: *mut ObjectMemoryEntry<T>) -> Result<ObjectMemoryEntry<T>, &'static str> {
+    if node.fault_schedule().fails(false) {
+        return Err("Scheduled read failure");
+    }
     if FAILURE_PROBABILITY > 0.0 {
         let mut rng = rand::rng();
         let roll: f32 = rng.random(); // random float between 0.0 and 1.0
@@ -114,13 +364,24 @@ pub fn safe_read<T: Copy>(addr: *mut ObjectMemoryEntry<T>) -> Result<ObjectMemor
     Ok(unsafe { std::ptr::read_volatile(addr) })
 }
 
-/// Read the value from all memory nodes for the given object
-pub fn _mem_readone<T: Copy>(offset: usize, mem_nodes: &Vec<MemoryNode>) -> Result<ObjectMemoryEntry<T>, MemoryError> {
+/// Live memory node with the lowest latency hint, picked at random among
+/// the nodes with the same hint
+pub(crate) fn nearest_node(mem_nodes: &[MemoryNode]) -> Option<&MemoryNode> {
+    let live: Vec<&MemoryNode> = mem_nodes.iter().filter(|n| !n.is_failed()).collect();
+    let min_latency = live.iter().map(|n| n.latency()).min()?;
+    let nearest: Vec<&MemoryNode> = live.into_iter().filter(|n| n.latency() == min_latency).collect();
+    nearest.choose(&mut rand::rng()).copied()
+}
 
-    let node = mem_nodes.choose(&mut rand::rng()).unwrap();  // Returns Option<&T>
+/// Read the value of the given object from the nearest live memory node
+pub fn mem_readone<T: Copy>(offset: usize, mem_nodes: &[MemoryNode]) -> Result<ObjectMemoryEntry<T>, MemoryError> {
 
-    let addr = node.addr_at(offset) as *mut ObjectMemoryEntry<T>;
-    match safe_read(addr) {
+    let node = match nearest_node(mem_nodes) {
+        Some(node) => node,
+        None => return Err(MemoryError(mem_nodes.first().map_or(0, |n| n.id))),
+    };
+
+    match read_entry(node, offset) {
         Ok(ome) => return Ok(ome),
         Err(e) => {
             error!(
@@ -134,20 +395,142 @@ pub fn _mem_readone<T: Copy>(offset: usize, mem_nodes: &Vec<MemoryNode>) -> Resu
 
 /// Write the an ObjectMemoryEntry to all memory nodes at its given memory offset 
 /// Flush&fence to ensure visibility
-pub fn mem_writeall<T: Copy>(offset: usize, ome: ObjectMemoryEntry<T>, mem_nodes: &Vec<MemoryNode>) -> Result<(), MemoryError> {
+///
+/// Nodes are written one by one, so that a concurrent reader can observe the
+/// write on some nodes only. With two-phase commit the entry is first written
+/// to the shadow slot of every node, then committed by bumping the version of
+/// the object, and readers see the old entry until the first node commits.
+///
+/// On failure, nodes are written up to the first failed one, or all but the
+/// failed ones with a replication pool. A failed two-phase commit is never
+/// visible, it fails before committing on any node.
+pub fn mem_writeall<T: Copy>(offset: usize, ome: ObjectMemoryEntry<T>, mem_nodes: &[MemoryNode]) -> Result<(), PartialWrite> {
+    if mem_nodes.first().is_some_and(|n| n.two_phase_commit()) {
+        return mem_writeall_2pc(offset, ome, mem_nodes);
+    }
 
-    // write data to all memory nodes
-    for node in mem_nodes {
-        let addr = node.addr_at(offset) as *mut ObjectMemoryEntry<T>;
-        if let Err(e) = safe_write(addr, ome) {
+    // write data to all memory nodes, in parallel with a replication pool
+    if let Some(pool) = mem_nodes.first().and_then(|n| n.replication_pool()) {
+        let args = AssertSend((mem_nodes, ome));
+        return pool.run(mem_nodes.len(), |i| {
+            let (nodes, ome) = &args.0;
+            write_entry(&nodes[i], offset, *ome)
+        }).map_err(|errs| {
+            let (first, cause) = errs[0];
+            PartialWrite {
+                succeeded: (0..mem_nodes.len())
+                    .filter(|i| !errs.iter().any(|(j, _)| i == j))
+                    .map(|i| mem_nodes[i].id)
+                    .collect(),
+                failed: mem_nodes[first].id,
+                cause,
+            }
+        });
+    }
+    for (i, node) in mem_nodes.iter().enumerate() {
+        write_entry(node, offset, ome).map_err(|cause| PartialWrite {
+            succeeded: mem_nodes[..i].iter().map(|n| n.id).collect(),
+            failed: node.id,
+            cause,
+        })?;
+    }
+
+    // fence once only after all writes to all mem nodes are flushed
+    unsafe { _mm_mfence(); }
+
+    Ok(())
+}
+
+/// Same as `mem_writeall`, calling `on_quorum` as soon as a majority of the
+/// nodes (the first ones) hold the entry, then writing the other nodes. With
+/// two-phase commit, `on_quorum` is called once every node committed.
+pub fn mem_writeall_quorum<T: Copy>(
+    offset: usize,
+    ome: ObjectMemoryEntry<T>,
+    mem_nodes: &[MemoryNode],
+    on_quorum: impl FnOnce(),
+) -> Result<(), PartialWrite> {
+    if mem_nodes.is_empty() || mem_nodes[0].two_phase_commit() {
+        mem_writeall(offset, ome, mem_nodes)?;
+        on_quorum();
+        return Ok(());
+    }
+
+    let (quorum, rest) = mem_nodes.split_at(mem_nodes.len() / 2 + 1);
+    mem_writeall(offset, ome, quorum)?;
+    on_quorum();
+    mem_writeall(offset, ome, rest).map_err(|mut e| {
+        e.succeeded.splice(0..0, quorum.iter().map(|n| n.id));
+        e
+    })
+}
+
+// Write and flush the entry on one node, without fence
+fn write_entry<T: Copy>(node: &MemoryNode, offset: usize, ome: ObjectMemoryEntry<T>) -> Result<(), &'static str> {
+    let addr = node.addr_at(offset) as *mut ObjectMemoryEntry<T>;
+    if let Err(e) = safe_write(node, addr, ome) {
+        error!(
+            "Safe write failed at node {} offset {}: {}",
+            node.id, offset, e
+        );
+        return Err(e);
+    }
+    // flush
+    unsafe { clflushopt_range(addr as *const u8, size_of::<ObjectMemoryEntry<T>>()); }
+    Ok(())
+}
+
+/// Two-phase commit write. Writers of an object must be serialized by the
+/// algorithm, since the shadow slot is picked from the current version. Each
+/// node has its own version, a repair commits on the repaired node only.
+fn mem_writeall_2pc<T: Copy>(offset: usize, ome: ObjectMemoryEntry<T>, mem_nodes: &[MemoryNode]) -> Result<(), PartialWrite> {
+    let versions: Vec<u64> = mem_nodes.iter().map(|node| read_version(node, offset) + 1).collect();
+
+    // phase 1: write the shadow slot of every node
+    for (node, &version) in mem_nodes.iter().zip(&versions) {
+        let addr = slot_addr::<T>(node, offset, version);
+        if let Err(e) = safe_write(node, addr, ome) {
             error!(
                 "Safe write failed at node {} offset {}: {}",
                 node.id, offset, e
             );
-            return Err(MemoryError(node.id));
+            return Err(PartialWrite { succeeded: Vec::new(), failed: node.id, cause: e });
+        }
+        unsafe { clflushopt_range(addr as *const u8, size_of::<ObjectMemoryEntry<T>>()); }
+    }
+    unsafe { _mm_mfence(); }
+
+    // phase 2: commit, the first node decides what readers see
+    for (node, &version) in mem_nodes.iter().zip(&versions) {
+        version_word(node, offset).store(version, Ordering::Release);
+        unsafe { clflushopt_range(node.addr_at(offset), size_of::<u64>()); }
+    }
+    unsafe { _mm_mfence(); }
+
+    Ok(())
+}
+
+/// Write a batch of `(offset, entry)` to all memory nodes, node by node, with
+/// a single fence at the end. Entries of the same object are written in
+/// order, so the last one is kept. With two-phase commit each entry is
+/// committed on its own, see `mem_writeall`.
+pub fn mem_writebatch<T: Copy>(entries: &[(usize, ObjectMemoryEntry<T>)], mem_nodes: &Vec<MemoryNode>) -> Result<(), MemoryError> {
+    if mem_nodes.first().is_some_and(|n| n.two_phase_commit()) {
+        return entries.iter().try_for_each(|&(offset, ome)| Ok(mem_writeall_2pc(offset, ome, mem_nodes)?));
+    }
+
+    for node in mem_nodes {
+        for &(offset, ome) in entries {
+            let addr = node.addr_at(offset) as *mut ObjectMemoryEntry<T>;
+            if let Err(e) = safe_write(node, addr, ome) {
+                error!(
+                    "Safe write failed at node {} offset {}: {}",
+                    node.id, offset, e
+                );
+                return Err(MemoryError(node.id));
+            }
+            unsafe { clflushopt_range(addr as *const u8, size_of::<ObjectMemoryEntry<T>>()); }
         }
-        // flush
-        unsafe { clflushopt_range(addr  as *const u8, size_of::<ObjectMemoryEntry<T>>()); }
     }
 
     // fence once only after all writes to all mem nodes are flushed
@@ -155,14 +538,62 @@ pub fn mem_writeall<T: Copy>(offset: usize, ome: ObjectMemoryEntry<T>, mem_nodes
 
     Ok(())
 }
-    
+
+/// Write an ObjectMemoryEntry to a single memory node, e.g. to repair it.
+/// Flush&fence to ensure visibility. With two-phase commit the entry goes to
+/// the shadow slot and is committed on this node only.
+pub fn mem_writeone<T: Copy>(offset: usize, ome: ObjectMemoryEntry<T>, node: &MemoryNode) -> Result<(), MemoryError> {
+    if node.two_phase_commit() {
+        return mem_writeall_2pc(offset, ome, std::slice::from_ref(node)).map_err(|e| MemoryError(e.failed));
+    }
+    let addr = node.addr_at(offset) as *mut ObjectMemoryEntry<T>;
+    if let Err(e) = safe_write(node, addr, ome) {
+        error!(
+            "Safe write failed at node {} offset {}: {}",
+            node.id, offset, e
+        );
+        return Err(MemoryError(node.id));
+    }
+    unsafe {
+        clflushopt_range(addr as *const u8, size_of::<ObjectMemoryEntry<T>>());
+        _mm_mfence();
+    }
+    Ok(())
+}
+
+/// Write `ome` to a single memory node if the entry stored there is still
+/// older, to repair the node. The stored wid is read again right before the
+/// write, so that a write replicated since the caller read the node is not
+/// overwritten by an older entry. Writers do not lock entries: only a write
+/// landing between this check and the repair itself can still be lost on the
+/// node. Returns whether the node was written.
+pub fn mem_repairone<T: Copy>(offset: usize, ome: ObjectMemoryEntry<T>, node: &MemoryNode) -> Result<bool, MemoryError> {
+    if !node.two_phase_commit() {
+        unsafe { cache_flush_read(node.addr_at(offset), size_of::<ObjectMemoryEntry<T>>()); }
+    }
+    let current = read_entry::<T>(node, offset).map_err(|e| {
+        error!(
+            "Safe read failed. Node {}, offset {}: {}",
+            node.id, offset, e
+        );
+        MemoryError(node.id)
+    })?;
+    if current.written && current.wid >= ome.wid {
+        return Ok(false);
+    }
+    mem_writeone(offset, ome, node)?;
+    Ok(true)
+}
 
 /// Read the value from all memory nodes for the given object
 pub fn mem_readall<T: Copy>(offset: usize, mem_nodes: &Vec<MemoryNode>) -> Result<Vec<ObjectMemoryEntry<T>>, MemoryError> {
     let mut states = Vec::with_capacity(mem_nodes.len());
     for node in mem_nodes {
-        let addr = node.addr_at(offset) as *mut ObjectMemoryEntry<T>;
-        match safe_read(addr) {
+        if node.is_failed() {
+            error!("Read from failed node {}, offset {}", node.id, offset);
+            return Err(MemoryError(node.id));
+        }
+        match read_entry(node, offset) {
             Ok(data) => states.push(data),
             Err(e) => {
                 error!(
@@ -185,6 +616,15 @@ pub fn mem_readends<T: Copy>(offset: usize, mem_nodes: &Vec<MemoryNode>) -> Resu
     let first_node = &mem_nodes[0];
     let last_node = &mem_nodes[mem_nodes.len() - 1];
 
+    if let Some(node) = [first_node, last_node].into_iter().find(|n| n.is_failed()) {
+        error!("Read from failed node {}, offset {}", node.id, offset);
+        return Err(MemoryError(node.id));
+    }
+
+    if first_node.two_phase_commit() {
+        return mem_readends_2pc(offset, first_node, last_node);
+    }
+
     // flush nodes
     for node in [first_node, last_node] {
         let addr = node.addr_at(offset);
@@ -198,7 +638,7 @@ pub fn mem_readends<T: Copy>(offset: usize, mem_nodes: &Vec<MemoryNode>) -> Resu
     // now read both from memory    
     let mut addr = first_node.addr_at(offset) as *mut ObjectMemoryEntry<T>;
     let debug_step1 = start.elapsed().as_nanos(); // debug read times
-    let first = match safe_read(addr) {
+    let first = match safe_read(first_node, addr) {
         Ok(data) => data,
         Err(e) => {
             error!(
@@ -213,7 +653,7 @@ pub fn mem_readends<T: Copy>(offset: usize, mem_nodes: &Vec<MemoryNode>) -> Resu
     
     // read the last node
     addr = last_node.addr_at(offset) as *mut ObjectMemoryEntry<T>;
-    let last = match safe_read(addr) {
+    let last = match safe_read(last_node, addr) {
         Ok(data) => data,
         Err(e) => {
             error!(
@@ -235,3 +675,120 @@ pub fn mem_readends<T: Copy>(offset: usize, mem_nodes: &Vec<MemoryNode>) -> Resu
     Ok([first, last])
 }
 
+
+/// Read the committed entry of the first and last memory nodes, each with the
+/// version recheck of `read_entry`. Writes commit on the first node first: if
+/// the last node is behind and its shadow slot already holds the entry of the
+/// first one, the write is being committed and the shadow entry is returned,
+/// so that readers never see a write on the first node only.
+fn mem_readends_2pc<T: Copy>(offset: usize, first_node: &MemoryNode, last_node: &MemoryNode) -> Result<[ObjectMemoryEntry<T>; 2], MemoryError> {
+    let read = |node: &MemoryNode, version: u64| {
+        read_slot::<T>(node, offset, version).map_err(|e| {
+            error!(
+                "Safe read failed. Node {}, offset {}: {}",
+                node.id, offset, e
+            );
+            MemoryError(node.id)
+        })
+    };
+
+    loop {
+        let first_version = read_version(first_node, offset);
+        let last_version = read_version(last_node, offset);
+        let first = read(first_node, first_version)?;
+        let mut last = read(last_node, last_version)?;
+        if first.wid > last.wid {
+            let shadow = read(last_node, last_version + 1)?;
+            if shadow.written && shadow.wid == first.wid {
+                last = shadow;
+            }
+        }
+        if read_version(first_node, offset) == first_version && read_version(last_node, offset) == last_version {
+            return Ok([first, last]);
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::request::Wid;
+    use crate::shmem::HeapMemory;
+
+    fn two_phase_nodes(n: usize) -> Vec<MemoryNode> {
+        (0..n).map(|id| {
+            let mut node = MemoryNode::from_heap(id, HeapMemory::new(2 << 20));
+            node.set_two_phase_commit(true);
+            node
+        }).collect()
+    }
+
+    fn entry(round: u64) -> ObjectMemoryEntry<u64> {
+        ObjectMemoryEntry::new(Wid::new(round, 0), round)
+    }
+
+    #[test]
+    fn test_two_phase_repair_commits_through_shadow_slot() {
+        let nodes = two_phase_nodes(3);
+        let offset = 0;
+        mem_writeall(offset, entry(1), &nodes).expect("Write should succeed");
+
+        // repair the last node only, the committed slot stays untouched
+        assert!(mem_repairone(offset, entry(2), &nodes[2]).expect("Repair should succeed"));
+        assert_eq!(read_version(&nodes[2], offset), 2);
+        assert_eq!(read_version(&nodes[0], offset), 1);
+        assert_eq!(read_slot::<u64>(&nodes[2], offset, 1).expect("Read should succeed").value, 1);
+
+        // each node is read at its own version
+        let [first, last] = mem_readends::<u64>(offset, &nodes).expect("Read should succeed");
+        assert_eq!((first.value, last.value), (1, 2));
+        assert_eq!(mem_readall::<u64>(offset, &nodes).expect("Read should succeed").iter().map(|ome| ome.value).collect::<Vec<_>>(), vec![1, 1, 2]);
+
+        mem_writeall(offset, entry(3), &nodes).expect("Write should succeed");
+        for ome in mem_readall::<u64>(offset, &nodes).expect("Read should succeed") {
+            assert_eq!(ome.value, 3);
+        }
+    }
+
+    #[test]
+    fn test_two_phase_read_during_commit() {
+        let nodes = two_phase_nodes(2);
+        let offset = 0;
+        mem_writeall(offset, entry(1), &nodes).expect("Write should succeed");
+        // versions of different parity after a repair of the last node
+        mem_writeone(offset, entry(1), &nodes[1]).expect("Write should succeed");
+
+        // phase 1 done on both nodes, committed on the first one only
+        let versions: Vec<u64> = nodes.iter().map(|node| read_version(node, offset) + 1).collect();
+        for (node, &version) in nodes.iter().zip(&versions) {
+            safe_write(node, slot_addr::<u64>(node, offset, version), entry(2)).expect("Write should succeed");
+        }
+        version_word(&nodes[0], offset).store(versions[0], Ordering::Release);
+
+        let [first, last] = mem_readends::<u64>(offset, &nodes).expect("Read should succeed");
+        assert_eq!((first.value, last.value), (2, 2));
+    }
+
+    #[test]
+    fn test_replication_pool_survives_panicking_job() {
+        let pool = ReplicationPool::new(2);
+        let errs = pool.run(4, |i| -> Result<(), &'static str> {
+            if i == 1 {
+                panic!("Offset out of bounds");
+            }
+            Ok(())
+        }).expect_err("The panicking index should fail");
+        assert_eq!(errs.iter().map(|&(i, _)| i).collect::<Vec<_>>(), vec![1]);
+
+        // both threads are still there to run the next calls
+        for _ in 0..3 {
+            let calls = AtomicU64::new(0);
+            pool.run(4, |_| -> Result<(), &'static str> {
+                calls.fetch_add(1, Ordering::Relaxed);
+                Ok(())
+            }).expect("Calls should succeed");
+            assert_eq!(calls.load(Ordering::Relaxed), 4);
+        }
+    }
+}
@@ -1,23 +1,62 @@
-use libc::{mmap, munmap, MAP_SHARED, PROT_READ, PROT_WRITE};
+use libc::{mlock, mmap, munmap, MAP_SHARED, PROT_READ, PROT_WRITE};
+use std::alloc::{alloc_zeroed, dealloc, Layout};
 use std::fs::OpenOptions;
 use std::os::unix::fs::OpenOptionsExt;
 use std::os::unix::io::AsRawFd;
+use std::sync::atomic::{fence, AtomicBool, AtomicU32, AtomicU64, Ordering};
+use std::ptr::addr_of_mut;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use log::warn;
+
+use crate::safe_memio::{FaultSchedule, FaultWindow, ReplicationPool};
 
 
 
 // currently not used, requires the libnuma
 // mod numa_mem_node;
 
+mod checksum;
+use checksum::Crc32;
 pub mod object_index;
-use object_index::ObjectIndex;
+use object_index::{ObjectIndex, ObjectInfo};
 mod starting_block;
 use starting_block::StartingBlock;
 pub mod wcc;
 use wcc::{ObjectWCC, FastWCC};
+pub mod conflict_stats;
+use conflict_stats::ConflictStats;
 
 pub const MAX_OBJECTS: usize = 1000; // Maximum number of objects
 pub const MAX_PROCESSES: usize = 512; // Maximum number of processes
+const MAX_ALGORITHM_NAME: usize = 32; // Maximum length of the algorithm name
 const STATE_SIZE: usize = std::mem::size_of::<SharedState>();
+const HEADER_SIZE: usize = std::mem::size_of::<StateHeader>();
+const SEQLOCK_MAX_RETRIES: usize = 1000;
+/// Longest wait for the state lock before giving up, see `lock_state`
+pub(crate) const STATE_LOCK_TIMEOUT: Duration = Duration::from_secs(5);
+// the header and the shared state precede the object region
+const META_SIZE: usize = HEADER_SIZE + STATE_SIZE;
+
+/// Synchronization words at the start of every memory node, before the shared
+/// state. Kept out of SharedState so that `write_state` never overwrites them.
+#[repr(C, align(64))]
+pub(crate) struct StateHeader {
+    lock: AtomicU32, // pid of the holder, 0 if free
+    seq: AtomicU32, // seqlock of the shared state, odd while it is written
+}
+
+/// Holds the state lock of a memory node, released on drop
+pub(crate) struct StateLock<'a> {
+    header: &'a StateHeader,
+}
+
+impl Drop for StateLock<'_> {
+    fn drop(&mut self) {
+        self.header.lock.store(0, Ordering::Release);
+    }
+}
 
 #[derive(Debug, Clone, Copy)]
 pub(crate) struct SharedState {
@@ -25,18 +64,131 @@ pub(crate) struct SharedState {
     starting_block: StartingBlock,
     owcc: ObjectWCC,
     fwcc: FastWCC,
+    conflict_stats: ConflictStats,
+    algorithm: [u8; MAX_ALGORITHM_NAME], // published by the coordinator, zero-padded
+    round_time: u64, // ns, published by the coordinator
+    layout: u32, // layout_fingerprint() of the process that created the state
+    layout_probe: ObjectInfo, // LAYOUT_PROBE as encoded by that process
+    state_seq: u64, // bumped on every state update, highest is the newest
+    checksum: u32,
+}
+
+// Known object written in every shared state. A process decoding it to other
+// values does not compute the same offsets as the process that created it.
+const LAYOUT_PROBE: (usize, usize, usize) = (0x5eed_cafe, 4096, 64);
+
+/// Fingerprint of the shared state layout: the sizes and offsets every
+/// process must agree on to share the memory nodes, e.g. builds of different
+/// versions or for different targets disagree.
+pub(crate) fn layout_fingerprint() -> u32 {
+    let mut crc = Crc32::new();
+    for v in [
+        HEADER_SIZE,
+        STATE_SIZE,
+        MAX_OBJECTS,
+        MAX_PROCESSES,
+        std::mem::offset_of!(SharedState, object_index),
+        std::mem::offset_of!(SharedState, starting_block),
+        std::mem::offset_of!(SharedState, owcc),
+        std::mem::offset_of!(SharedState, fwcc),
+        std::mem::offset_of!(SharedState, conflict_stats),
+        std::mem::offset_of!(SharedState, algorithm),
+        std::mem::offset_of!(SharedState, round_time),
+        std::mem::offset_of!(SharedState, layout_probe),
+        std::mem::offset_of!(SharedState, state_seq),
+        std::mem::size_of::<ObjectInfo>(),
+    ] {
+        crc.update(&(v as u64).to_le_bytes());
+    }
+    crc.finish()
 }
 
 impl SharedState {
-    pub(crate) fn new(total_size: usize, chunk_size: usize) -> Self {
-        SharedState {
-            object_index: ObjectIndex::new(total_size, chunk_size),
-            starting_block: StartingBlock::new(),
-            owcc: ObjectWCC::new(),
-            fwcc: FastWCC::new(),
+    /// Fresh state, on the heap since it is too large for the stack. It is
+    /// built field by field in zeroed memory: a struct copied from the stack
+    /// carries stack garbage in its padding, which `find_pointer` can take
+    /// for an address once written to the memory nodes.
+    pub(crate) fn new(total_size: usize, chunk_size: usize) -> Box<Self> {
+        let mut state = Box::<SharedState>::new_zeroed();
+        let p = state.as_mut_ptr();
+        unsafe {
+            addr_of_mut!((*p).object_index).write(ObjectIndex::new(total_size, chunk_size));
+            addr_of_mut!((*p).starting_block).write(StartingBlock::new());
+            addr_of_mut!((*p).owcc).write(ObjectWCC::new());
+            addr_of_mut!((*p).fwcc).write(FastWCC::new());
+            addr_of_mut!((*p).conflict_stats).write(ConflictStats::new());
+            addr_of_mut!((*p).algorithm).write([0; MAX_ALGORITHM_NAME]);
+            addr_of_mut!((*p).round_time).write(0);
+            addr_of_mut!((*p).layout).write(layout_fingerprint());
+            addr_of_mut!((*p).layout_probe).write(ObjectInfo::new(LAYOUT_PROBE.0, LAYOUT_PROBE.1, LAYOUT_PROBE.2));
+            addr_of_mut!((*p).state_seq).write(0);
+            addr_of_mut!((*p).checksum).write(0); // set by MemoryNode::write_state
+            state.assume_init()
         }
     }
 
+    /// Checksum of the state written with `write_state`. The starting block,
+    /// the WCCs and the conflict stats are excluded since they are updated in place during the
+    /// rounds (see `get_state`).
+    fn compute_checksum(&self) -> u32 {
+        let mut crc = Crc32::new();
+        crc.update(&self.state_seq.to_le_bytes());
+        crc.update(&self.algorithm);
+        crc.update(&self.round_time.to_le_bytes());
+        crc.update(&self.layout.to_le_bytes());
+        self.object_index.digest(&mut crc);
+        crc.finish()
+    }
+
+    /// Publish the algorithm run by the group. Longer names are truncated.
+    pub(crate) fn set_algorithm(&mut self, name: &str) {
+        let len = name.len().min(MAX_ALGORITHM_NAME);
+        self.algorithm = [0; MAX_ALGORITHM_NAME];
+        self.algorithm[..len].copy_from_slice(&name.as_bytes()[..len]);
+    }
+
+    pub(crate) fn algorithm(&self) -> String {
+        let len = self.algorithm.iter().position(|&b| b == 0).unwrap_or(MAX_ALGORITHM_NAME);
+        String::from_utf8_lossy(&self.algorithm[..len]).into_owned()
+    }
+
+    /// Publish the round duration (ns) of the group
+    pub(crate) fn set_round_time(&mut self, round_time: u64) {
+        self.round_time = round_time;
+    }
+
+    pub(crate) fn round_time(&self) -> u64 {
+        self.round_time
+    }
+
+    pub(crate) fn seq(&self) -> u64 {
+        self.state_seq
+    }
+
+    /// Mark the state as newer than the one it was read from. Called once per
+    /// update, before writing the state to the memory nodes.
+    pub(crate) fn bump_seq(&mut self) {
+        self.state_seq += 1;
+    }
+
+    /// Store the checksum of the current state
+    pub(crate) fn seal(&mut self) {
+        self.checksum = self.compute_checksum();
+    }
+
+    /// Returns false if the stored checksum does not match the state, e.g.
+    /// because a process crashed in the middle of `write_state`.
+    pub(crate) fn checksum_ok(&self) -> bool {
+        self.checksum == self.compute_checksum()
+    }
+
+    /// Returns false if the state was created by a process with another
+    /// layout, or if the known probe object decodes to other values here.
+    pub(crate) fn layout_ok(&self) -> bool {
+        let probe = &self.layout_probe;
+        self.layout == layout_fingerprint() && (probe.id, probe.offset, probe.size) == LAYOUT_PROBE
+    }
+
     pub(crate) fn get_starting_block(&mut self) -> &mut StartingBlock {
         &mut self.starting_block
     }
@@ -48,16 +200,105 @@ impl SharedState {
     pub(crate) fn get_fwcc(&mut self) -> &mut FastWCC  {
         &mut self.fwcc
     }
+
+    pub(crate) fn get_conflict_stats(&mut self) -> &mut ConflictStats {
+        &mut self.conflict_stats
+    }
+}
+
+
+/// Zeroed heap region emulating a memory node without a file, e.g. for
+/// tests in sandboxes without tmpfs. Instances in the same process share the
+/// node by sharing the `Arc`.
+#[derive(Debug)]
+pub struct HeapMemory {
+    ptr: *mut u8,
+    layout: Layout,
+}
+
+unsafe impl Send for HeapMemory {} // the region is only accessed through MemoryNode
+unsafe impl Sync for HeapMemory {}
+
+impl HeapMemory {
+    pub fn new(size: usize) -> Arc<Self> {
+        if size <= META_SIZE {
+            panic!("Size must be greater than SharedState size ({})", META_SIZE);
+        }
+        let layout = Layout::from_size_align(size, HEAP_ALIGN).expect("Invalid heap memory size");
+        let ptr = unsafe { alloc_zeroed(layout) };
+        if ptr.is_null() {
+            panic!("Failed to allocate {} bytes of heap memory", size);
+        }
+        Arc::new(HeapMemory { ptr, layout })
+    }
+
+    pub fn size(&self) -> usize {
+        self.layout.size()
+    }
+}
+
+impl Drop for HeapMemory {
+    fn drop(&mut self) {
+        unsafe { dealloc(self.ptr, self.layout) };
+    }
 }
 
+// page alignment, like a mapped file
+const HEAP_ALIGN: usize = 4096;
+// alignment of mappings of DAX devices, also applied to files
+const DAX_ALIGN: usize = 2 * 1024 * 1024;
+
+/// Mapped node file, unmapped when the last clone of the node is dropped, so
+/// that a worker holding a clone never writes into unmapped memory
+struct FileMapping {
+    ptr: *mut u8,
+    len: usize,
+}
+
+unsafe impl Send for FileMapping {} // the region is only accessed through MemoryNode
+unsafe impl Sync for FileMapping {}
+
+impl Drop for FileMapping {
+    fn drop(&mut self) {
+        unsafe {
+            munmap(self.ptr as *mut libc::c_void, self.len);
+        }
+    }
+}
 
 // @TODO: add type for addr since repcxl is currently type-specific?
-#[derive(Hash, Clone)]
+#[derive(Clone)]
 pub(crate) struct MemoryNode {
     pub id: usize,
+    header_addr: *mut StateHeader,
     state_addr: *mut SharedState,
     obj_addr: *mut u8,
     size: usize,
+    // keeps the region alive for heap-backed nodes, None for mapped files
+    _heap: Option<Arc<HeapMemory>>,
+    // keeps the mapping alive for file-backed nodes, shared by the clones
+    _mapping: Option<Arc<FileMapping>>,
+    // fault injection: reads from a failed node return a MemoryError.
+    // Shared by the clones of the node
+    failed: Arc<AtomicBool>,
+    // fault injection: scheduled read/write failures, shared by the clones
+    faults: Arc<FaultSchedule>,
+    // value bytes of the object entries written to the node, shared by the
+    // clones, see `RepCXL::write_amplification`
+    bytes_written: Arc<AtomicU64>,
+    // reads of an entry zeroed under a written object, shared by the clones,
+    // see `RepCXL::zeroed_nodes`
+    zeroed_reads: Arc<AtomicU64>,
+    // parallel writes to the nodes, see safe_memio::mem_writeall
+    replication_pool: Option<Arc<ReplicationPool>>,
+    // emulated latency of every entry write, for tests
+    write_delay: Option<std::time::Duration>,
+    // objects are stored with a shadow slot, see safe_memio::mem_writeall
+    two_phase_commit: bool,
+    // latency hint in ns, see safe_memio::nearest_node
+    latency: u64,
+    // mapped file, None for heap-backed nodes
+    path: Option<String>,
 }
 
 impl MemoryNode {
@@ -67,7 +308,14 @@ impl MemoryNode {
     // across different hosts
     // assumes all processes/VMs use the same file path
     pub(crate) fn from_file(id: usize, path: &str, size: usize) -> Self {
-        if size <= STATE_SIZE {
+        Self::from_file_at(id, path, size, 0)
+    }
+
+    // Same as from_file but maps the region starting at `file_offset`, so that
+    // several regions can share one large device. The offset must be
+    // aligned to DAX_ALIGN
+    pub(crate) fn from_file_at(id: usize, path: &str, size: usize, file_offset: usize) -> Self {
+        if size <= META_SIZE {
             panic!("Size must be greater than SharedState size:\n\tObjectIndex: {}\n\tstarting_block: {}\n\towcc: {}\n\tfwcc: {}", 
                 std::mem::size_of::<ObjectIndex>(), 
                 std::mem::size_of::<StartingBlock>(), 
@@ -84,10 +332,13 @@ impl MemoryNode {
             .expect("Failed to open shared memory. Does the file exist?");
 
         /* DAX mapping requires a 2MiB alignment */
-        let page = 2 * 1024 * 1024;
+        let page = DAX_ALIGN;
         if size < page {
             panic!("Size must be at least 2 MiB for DAX mapping");
         }
+        if !file_offset.is_multiple_of(page) {
+            panic!("File offset {} must be 2 MiB aligned for DAX mapping", file_offset);
+        }
 
         let page_aligned_size = (size / page) * page;
 
@@ -98,7 +349,7 @@ impl MemoryNode {
                 PROT_READ | PROT_WRITE,
                 MAP_SHARED,
                 file.as_raw_fd(),
-                0,
+                file_offset as libc::off_t,
             )
         };
 
@@ -115,10 +366,271 @@ impl MemoryNode {
 
         MemoryNode {
             id,
-            state_addr: ptr as *mut SharedState,
-            obj_addr: unsafe { ptr.offset(STATE_SIZE as isize) },
+            header_addr: ptr as *mut StateHeader,
+            state_addr: unsafe { ptr.add(HEADER_SIZE) as *mut SharedState },
+            obj_addr: unsafe { ptr.add(META_SIZE) },
             size,
+            _heap: None,
+            _mapping: Some(Arc::new(FileMapping { ptr, len: page_aligned_size })),
+            failed: Arc::new(AtomicBool::new(false)),
+            faults: Arc::new(FaultSchedule::default()),
+            bytes_written: Arc::new(AtomicU64::new(0)),
+            zeroed_reads: Arc::new(AtomicU64::new(0)),
+            replication_pool: None,
+            write_delay: None,
+            two_phase_commit: false,
+            latency: 0,
+            path: Some(path.to_string()),
+        }
+    }
+
+    // Create a MemoryNode backed by a heap region instead of a file. The
+    // region is kept alive as long as a node uses it
+    pub(crate) fn from_heap(id: usize, heap: Arc<HeapMemory>) -> Self {
+        let ptr = heap.ptr;
+        MemoryNode {
+            id,
+            header_addr: ptr as *mut StateHeader,
+            state_addr: unsafe { ptr.add(HEADER_SIZE) as *mut SharedState },
+            obj_addr: unsafe { ptr.add(META_SIZE) },
+            size: heap.size(),
+            _heap: Some(heap),
+            _mapping: None,
+            failed: Arc::new(AtomicBool::new(false)),
+            faults: Arc::new(FaultSchedule::default()),
+            bytes_written: Arc::new(AtomicU64::new(0)),
+            zeroed_reads: Arc::new(AtomicU64::new(0)),
+            replication_pool: None,
+            write_delay: None,
+            two_phase_commit: false,
+            latency: 0,
+            path: None,
+        }
+    }
+
+    /// Reject a node file smaller than the region it would be mapped with,
+    /// starting at `file_offset`, or a misaligned offset.
+    /// Device files (e.g. DAX) report no size and are not checked
+    pub(crate) fn check_file_size(path: &str, file_offset: usize, size: usize) -> Result<(), String> {
+        if !file_offset.is_multiple_of(DAX_ALIGN) {
+            return Err(format!(
+                "Memory node {}: file offset {} is not aligned to {} bytes",
+                path, file_offset, DAX_ALIGN
+            ));
+        }
+        let meta = std::fs::metadata(path)
+            .map_err(|e| format!("Failed to open memory node {}: {}", path, e))?;
+        if meta.is_file() && (meta.len() as usize) < file_offset + size {
+            return Err(format!(
+                "Memory node {} is smaller ({} bytes) than the configured region ({} bytes at offset {})",
+                path, meta.len(), size, file_offset
+            ));
+        }
+        Ok(())
+    }
+
+    /// Reject a region that cannot be mapped from a file: it must hold the
+    /// header and the shared state, and be at least one DAX page
+    pub(crate) fn check_region_size(size: usize) -> Result<(), String> {
+        if size <= META_SIZE {
+            return Err(format!("Region of {} bytes cannot hold the shared state ({} bytes)", size, META_SIZE));
+        }
+        if size < DAX_ALIGN {
+            return Err(format!("Region of {} bytes is smaller than the {} bytes DAX alignment", size, DAX_ALIGN));
+        }
+        Ok(())
+    }
+
+    /// Create the node file if missing and grow it to `size` bytes. Larger
+    /// files are left untouched, and device files (e.g. DAX) only need to
+    /// exist
+    pub(crate) fn provision_file(path: &str, size: usize) -> Result<(), String> {
+        Self::check_region_size(size)?;
+        if let Ok(meta) = std::fs::metadata(path) {
+            if !meta.is_file() {
+                return Ok(());
+            }
+        }
+        let file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)
+            .map_err(|e| format!("Failed to create memory node {}: {}", path, e))?;
+        let len = file.metadata()
+            .map_err(|e| format!("Failed to open memory node {}: {}", path, e))?
+            .len();
+        if (len as usize) < size {
+            file.set_len(size as u64)
+                .map_err(|e| format!("Failed to resize memory node {} to {} bytes: {}", path, size, e))?;
+        }
+        Ok(())
+    }
+
+    /// Write the dirty pages of the object range back to the backing file
+    /// (msync with MS_SYNC). No-op for heap nodes
+    pub(crate) fn sync_range(&self, offset: usize, size: usize) -> Result<(), String> {
+        if offset + size > self.region_size() {
+            return Err(format!("Range {}+{} out of bounds of memory node {}", offset, size, self.id));
+        }
+        self.msync(unsafe { self.obj_addr.add(offset) }, size)
+    }
+
+    /// Same as `sync_range` for the header and the shared state
+    pub(crate) fn sync_state(&self) -> Result<(), String> {
+        self.msync(self.header_addr as *mut u8, META_SIZE)
+    }
+
+    fn msync(&self, addr: *mut u8, len: usize) -> Result<(), String> {
+        if self._heap.is_some() {
+            return Ok(());
+        }
+        // msync requires a page-aligned address, the mapping itself is
+        let page = unsafe { libc::sysconf(libc::_SC_PAGESIZE) } as usize;
+        let misalign = addr as usize % page;
+        let ret = unsafe {
+            libc::msync(addr.sub(misalign) as *mut libc::c_void, len + misalign, libc::MS_SYNC)
+        };
+        if ret != 0 {
+            return Err(format!(
+                "msync failed on memory node {}: {}",
+                self.id,
+                std::io::Error::last_os_error()
+            ));
+        }
+        Ok(())
+    }
+
+    /// Size of the object region, i.e. the node without the shared state
+    pub(crate) fn region_size(&self) -> usize {
+        self.size.saturating_sub(META_SIZE)
+    }
+
+    /// Addresses this node is mapped at in the current process
+    pub(crate) fn mapped_range(&self) -> std::ops::Range<usize> {
+        let base = self.header_addr as usize;
+        base..base + self.size
+    }
+
+    /// Offset in the shared state of the first word that is an address in
+    /// one of `ranges`. The shared state is mapped at different addresses by
+    /// each process, so it must only hold offsets.
+    pub(crate) fn find_pointer(&self, ranges: &[std::ops::Range<usize>]) -> Option<usize> {
+        let words = self.state_addr as *const u64;
+        (0..STATE_SIZE / 8).find_map(|i| {
+            let word = unsafe { std::ptr::read_volatile(words.add(i)) } as usize;
+            ranges.iter().any(|r| r.contains(&word)).then_some(i * 8)
+        })
+    }
+
+    // where the node is mapped from and at which address, for logging
+    pub(crate) fn describe(&self) -> String {
+        format!(
+            "{} ({} bytes at {:p})",
+            self.path.as_deref().unwrap_or("heap"),
+            self.size,
+            self.header_addr
+        )
+    }
+
+    /// Mark the node as failed (or recovered) to simulate a node failure
+    #[cfg(test)]
+    pub(crate) fn set_failed(&self, failed: bool) {
+        self.failed.store(failed, Ordering::Relaxed);
+    }
+
+    pub(crate) fn is_failed(&self) -> bool {
+        self.failed.load(Ordering::Relaxed)
+    }
+
+    /// Schedule failures of the node, see the `fault` config
+    pub(crate) fn set_fault_schedule(&mut self, windows: Vec<FaultWindow>) {
+        self.faults = Arc::new(FaultSchedule::new(windows));
+    }
+
+    pub(crate) fn fault_schedule(&self) -> &FaultSchedule {
+        &self.faults
+    }
+
+    pub(crate) fn set_replication_pool(&mut self, pool: Option<Arc<ReplicationPool>>) {
+        self.replication_pool = pool;
+    }
+
+    pub(crate) fn replication_pool(&self) -> Option<&ReplicationPool> {
+        self.replication_pool.as_deref()
+    }
+
+    pub(crate) fn shared_replication_pool(&self) -> Option<Arc<ReplicationPool>> {
+        self.replication_pool.clone()
+    }
+
+    #[cfg(test)]
+    pub(crate) fn set_write_delay(&mut self, delay: std::time::Duration) {
+        self.write_delay = Some(delay);
+    }
+
+    pub(crate) fn write_delay(&self) -> Option<std::time::Duration> {
+        self.write_delay
+    }
+
+    pub(crate) fn count_written(&self, bytes: usize) {
+        self.bytes_written.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    pub(crate) fn bytes_written(&self) -> u64 {
+        self.bytes_written.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn flag_zeroed(&self) {
+        self.zeroed_reads.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn zeroed_reads(&self) -> u64 {
+        self.zeroed_reads.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn set_two_phase_commit(&mut self, enabled: bool) {
+        self.two_phase_commit = enabled;
+    }
+
+    pub(crate) fn two_phase_commit(&self) -> bool {
+        self.two_phase_commit
+    }
+
+    /// Lock the whole node in RAM, faulting its pages in. The lock is
+    /// released when the node is unmapped. Fails e.g. when the region
+    /// exceeds RLIMIT_MEMLOCK
+    pub(crate) fn lock_memory(&self) -> Result<(), String> {
+        if unsafe { mlock(self.header_addr as *const libc::c_void, self.size) } != 0 {
+            return Err(format!(
+                "mlock failed on memory node {}: {}",
+                self.id,
+                std::io::Error::last_os_error()
+            ));
         }
+        Ok(())
+    }
+
+    /// Number of pages of the node that are not resident in RAM
+    #[cfg(test)]
+    pub(crate) fn non_resident_pages(&self) -> usize {
+        let page = unsafe { libc::sysconf(libc::_SC_PAGESIZE) } as usize;
+        let mut vec = vec![0u8; self.size.div_ceil(page)];
+        let ret = unsafe {
+            libc::mincore(self.header_addr as *mut libc::c_void, self.size, vec.as_mut_ptr())
+        };
+        if ret != 0 {
+            return vec.len();
+        }
+        vec.iter().filter(|&&v| v & 1 == 0).count()
+    }
+
+    pub(crate) fn set_latency(&mut self, latency: u64) {
+        self.latency = latency;
+    }
+
+    pub(crate) fn latency(&self) -> u64 {
+        self.latency
     }
 
     pub(crate) fn addr_at(&self, offset: usize) -> *mut u8 {
@@ -128,9 +640,41 @@ impl MemoryNode {
         unsafe { self.obj_addr.offset(offset as isize) }
     }
 
-    // copy of the shared state (which remains unchanged)
-    pub(crate) fn read_state(&self) -> SharedState {
-        unsafe { std::ptr::read(self.state_addr) } // WARNING: might want to read_unaligned
+    // zero `size` bytes of the object region starting at `offset`
+    pub(crate) fn zero_at(&self, offset: usize, size: usize) {
+        let addr = self.addr_at(offset);
+        unsafe {
+            std::ptr::write_bytes(addr, 0, size);
+            crate::safe_memio::cache_flush_write(addr, size);
+        }
+    }
+
+    /// Copy of the shared state (which remains unchanged). Retries while a
+    /// `write_state` is in progress or if one happened during the copy, so the
+    /// copy is not torn. Updates in place through `get_state` (starting
+    /// block, WCCs) are not covered.
+    /// A writer that crashed leaves the seqlock odd: after
+    /// `SEQLOCK_MAX_RETRIES` the copy is returned anyway and the state
+    /// checksum is left to detect it.
+    pub(crate) fn read_state(&self) -> Box<SharedState> {
+        let header = unsafe { &*self.header_addr };
+        let mut retries = 0;
+        loop {
+            let before = header.seq.load(Ordering::Acquire);
+            let give_up = retries == SEQLOCK_MAX_RETRIES;
+            retries += 1;
+            if before % 2 == 1 && !give_up {
+                std::thread::yield_now(); // let the writer finish
+                continue;
+            }
+            // copy straight to the heap, the state is too large for the stack
+            let mut state = Box::<SharedState>::new_uninit();
+            unsafe { std::ptr::copy_nonoverlapping(self.state_addr, state.as_mut_ptr(), 1) };
+            fence(Ordering::Acquire);
+            if give_up || header.seq.load(Ordering::Relaxed) == before {
+                return unsafe { state.assume_init() };
+            }
+        }
     }
 
     // mutable reference to the shared state
@@ -138,19 +682,65 @@ impl MemoryNode {
         unsafe { &mut *self.state_addr }
     }
 
-    pub(crate) fn write_state(&self, state: SharedState) {
-        unsafe {
-            std::ptr::write(self.state_addr, state); // WARNING: might want to write_unaligned
+    /// Spin until the state lock of this node is acquired. Processes updating
+    /// the shared state (e.g. the allocator) hold the lock of the master node
+    /// across their read-modify-write. Fails after `STATE_LOCK_TIMEOUT`, see
+    /// `lock_state_timeout`.
+    pub(crate) fn lock_state(&self) -> Result<StateLock<'_>, String> {
+        self.lock_state_timeout(STATE_LOCK_TIMEOUT)
+    }
+
+    /// Like `lock_state` but gives up after `timeout`. The lock word holds the
+    /// pid of the holder: a process that crashed while holding it leaves it
+    /// taken, and the error names that pid. `reset_header` frees it.
+    pub(crate) fn lock_state_timeout(&self, timeout: Duration) -> Result<StateLock<'_>, String> {
+        let header = unsafe { &*self.header_addr };
+        let pid = std::process::id();
+        let start = Instant::now();
+        loop {
+            match header.lock.compare_exchange_weak(0, pid, Ordering::Acquire, Ordering::Relaxed) {
+                Ok(_) => return Ok(StateLock { header }),
+                Err(holder) if holder != 0 && start.elapsed() >= timeout => {
+                    return Err(format!(
+                        "State lock of memory node {} still held by pid {} after {:?}, \
+                         the holder may have crashed: set force_init to reset it",
+                        self.id, holder, timeout
+                    ));
+                }
+                Err(_) => {
+                    std::hint::spin_loop();
+                    std::thread::yield_now(); // the holder might share the core
+                }
+            }
         }
     }
-}
 
-impl Drop for MemoryNode {
-    fn drop(&mut self) {
+    /// Free the state lock and end a torn seqlock write, both left behind by
+    /// a process that crashed while updating the state. Only for `force_init`:
+    /// a live holder would lose its lock.
+    pub(crate) fn reset_header(&self) {
+        let header = unsafe { &*self.header_addr };
+        let holder = header.lock.swap(0, Ordering::AcqRel);
+        if holder != 0 {
+            warn!("Released the state lock of memory node {} held by pid {}", self.id, holder);
+        }
+        let seq = header.seq.load(Ordering::Acquire);
+        if seq % 2 == 1 {
+            header.seq.store(seq.wrapping_add(1), Ordering::Release);
+        }
+    }
+
+    // seals the state with its checksum before writing it. The seqlock is
+    // odd during the write, writers are serialized by the state lock
+    pub(crate) fn write_state(&self, state: &mut SharedState) {
+        state.seal();
+        let header = unsafe { &*self.header_addr };
+        header.seq.fetch_add(1, Ordering::Relaxed);
+        fence(Ordering::Release);
         unsafe {
-            munmap(self.obj_addr as *mut libc::c_void, self.size);
+            std::ptr::copy_nonoverlapping(state, self.state_addr, 1);
         }
-        // File is automatically closed when it goes out of scope
+        header.seq.fetch_add(1, Ordering::Release);
     }
 }
 
@@ -180,9 +770,74 @@ mod tests {
         assert_eq!(node.id, mnid);
         assert!(!node.obj_addr.is_null());
         assert_eq!(node.size, size);
+        node.sync_state().expect("Failed to sync the state");
+        node.sync_range(100, 4096).expect("Failed to sync an unaligned range");
+        assert!(node.sync_range(node.region_size(), 1).is_err());
 
         // Clean up: remove the tmpfs file
         remove_file(path).expect("Failed to remove tmpfs file");
     }
 
+    #[test]
+    fn test_torn_state_write_detected() {
+        let path = "/dev/shm/repCXL_test_state_checksum";
+        let size: usize = 4 * 1024 * 1024;
+
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .open(path)
+            .expect("Failed to create/open file in tmpfs");
+        file.set_len(size as u64).expect("Failed to set file length");
+
+        let node = MemoryNode::from_file(0, path, size);
+        node.write_state(&mut SharedState::new(size / 2, 64));
+        assert!(node.read_state().checksum_ok());
+
+        // update the allocation table in place without re-sealing, i.e. a
+        // write_state interrupted before the checksum reached memory
+        node.get_state().object_index.alloc_object(3, 64).unwrap();
+        assert!(!node.read_state().checksum_ok());
+
+        remove_file(path).expect("Failed to remove tmpfs file");
+    }
+
+    // MemoryNode holds raw pointers, share it with the reader thread anyway
+    struct SharedNode(MemoryNode);
+    unsafe impl Sync for SharedNode {}
+
+    #[test]
+    fn test_no_torn_state_read() {
+        let size: usize = 4 * 1024 * 1024;
+        let heap = HeapMemory::new(size);
+        let writer = MemoryNode::from_heap(0, heap.clone());
+        let reader = SharedNode(MemoryNode::from_heap(0, heap));
+        writer.write_state(&mut SharedState::new(size / 2, 64));
+
+        let done = std::sync::atomic::AtomicBool::new(false);
+        std::thread::scope(|s| {
+            let (reader, done) = (&reader, &done);
+            let reads = s.spawn(move || {
+                let mut reads = 0;
+                while !done.load(Ordering::Relaxed) {
+                    let state = reader.0.read_state();
+                    assert!(state.checksum_ok(), "Torn state read after {} reads", reads);
+                    reads += 1;
+                }
+                reads
+            });
+
+            for i in 0..2000 {
+                let mut state = SharedState::new(size / 2, 64);
+                for id in 0..(i % 50) {
+                    state.object_index.alloc_object(id, 64).unwrap();
+                }
+                state.bump_seq();
+                writer.write_state(&mut state);
+            }
+            done.store(true, Ordering::Relaxed);
+            assert!(reads.join().unwrap() > 0);
+        });
+    }
 }
@@ -0,0 +1,58 @@
+/// CRC-32 (IEEE 802.3) used to detect torn writes of the shared state.
+/// Table-driven, the table is computed at compile time.
+const CRC32_POLY: u32 = 0xEDB8_8320;
+
+const CRC32_TABLE: [u32; 256] = {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ CRC32_POLY } else { crc >> 1 };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+/// Incremental CRC-32 hasher. Fields are fed one by one (instead of hashing
+/// the raw struct bytes) so that padding bytes never end up in the checksum.
+pub(crate) struct Crc32 {
+    crc: u32,
+}
+
+impl Crc32 {
+    pub(crate) fn new() -> Self {
+        Crc32 { crc: 0xFFFF_FFFF }
+    }
+
+    pub(crate) fn update(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.crc = CRC32_TABLE[((self.crc ^ b as u32) & 0xFF) as usize] ^ (self.crc >> 8);
+        }
+    }
+
+    pub(crate) fn update_usize(&mut self, val: usize) {
+        self.update(&(val as u64).to_le_bytes());
+    }
+
+    pub(crate) fn finish(&self) -> u32 {
+        self.crc ^ 0xFFFF_FFFF
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crc32_check_value() {
+        // standard CRC-32 check value
+        let mut crc = Crc32::new();
+        crc.update(b"123456789");
+        assert_eq!(crc.finish(), 0xCBF4_3926);
+    }
+}
@@ -0,0 +1,131 @@
+use super::MAX_PROCESSES;
+use super::object_index::ObjectInfo;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+
+/// Number of objects whose conflicts are tracked, bounds the size of the
+/// stats. Objects are tracked by id modulo this number, a conflict on an
+/// object replaces the stats of another object sharing its slot.
+pub const CONFLICT_STATS_OBJECTS: usize = 64;
+
+// id of the object tracked by a slot, empty slots have no id
+const NO_OBJECT: usize = usize::MAX;
+
+#[derive(Debug, Clone, Copy)]
+struct ObjectConflictSlot {
+    id: usize,
+    last_round: u64,
+    conflicts: u64,
+}
+
+/// Conflicts lost by the MONSTER writers of the group, shared so that any
+/// attached process can see the conflict hotspots. Updated in place by the
+/// writers (see `SharedState::get_conflict_stats`), complementing the per
+/// process `MonsterStats`. Recorded on every live node like the WCCs, so the
+/// stats outlive a master failover, minus the conflicts recorded while a node
+/// was failed.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ConflictStats {
+    // indexed by process ID
+    per_process: [u64; MAX_PROCESSES],
+    objects: [ObjectConflictSlot; CONFLICT_STATS_OBJECTS],
+}
+
+/// Conflicts on one object, see `ConflictReport`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ObjectConflicts {
+    /// local id of the object
+    pub id: usize,
+    pub namespace: u32,
+    /// round of the last conflict lost on the object
+    pub last_round: u64,
+    pub conflicts: u64,
+}
+
+/// Conflicts lost by the writers of the whole group, see
+/// `RepCXL::conflict_report`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConflictReport {
+    pub total: u64,
+    /// (process id, conflicts lost) of the processes that lost conflicts
+    pub per_process: Vec<(u32, u64)>,
+    /// tracked objects, most conflicted first
+    pub objects: Vec<ObjectConflicts>,
+}
+
+impl ConflictStats {
+    pub(crate) fn new() -> Self {
+        ConflictStats {
+            per_process: [0; MAX_PROCESSES],
+            objects: [ObjectConflictSlot { id: NO_OBJECT, last_round: 0, conflicts: 0 }; CONFLICT_STATS_OBJECTS],
+        }
+    }
+
+    /// Count a conflict lost by process `pid` on object `id` in `round`.
+    /// Writers record concurrently: the counters are atomic, the slot of an
+    /// object taken over by another object may mix their counts.
+    pub(crate) fn record(&mut self, pid: usize, id: usize, round: u64) {
+        if pid >= MAX_PROCESSES {
+            panic!("Process ID {} exceeds MAX_PROCESSES {}", pid, MAX_PROCESSES);
+        }
+        unsafe { AtomicU64::from_ptr(&mut self.per_process[pid]) }.fetch_add(1, Ordering::Relaxed);
+
+        let slot = &mut self.objects[id % CONFLICT_STATS_OBJECTS];
+        let slot_id = unsafe { AtomicUsize::from_ptr(&mut slot.id) };
+        let conflicts = unsafe { AtomicU64::from_ptr(&mut slot.conflicts) };
+        let last_round = unsafe { AtomicU64::from_ptr(&mut slot.last_round) };
+        if slot_id.swap(id, Ordering::AcqRel) != id {
+            conflicts.store(0, Ordering::Relaxed);
+            last_round.store(0, Ordering::Relaxed);
+        }
+        conflicts.fetch_add(1, Ordering::Relaxed);
+        last_round.fetch_max(round, Ordering::Relaxed);
+    }
+
+    pub(crate) fn report(&self) -> ConflictReport {
+        let per_process: Vec<(u32, u64)> = self.per_process.iter().enumerate()
+            .filter(|&(_, &n)| n > 0)
+            .map(|(pid, &n)| (pid as u32, n))
+            .collect();
+        let mut objects: Vec<ObjectConflicts> = self.objects.iter()
+            .filter(|slot| slot.id != NO_OBJECT)
+            .map(|slot| {
+                let info = ObjectInfo::new(slot.id, 0, 0);
+                ObjectConflicts {
+                    id: info.local_id(),
+                    namespace: info.namespace(),
+                    last_round: slot.last_round,
+                    conflicts: slot.conflicts,
+                }
+            })
+            .collect();
+        objects.sort_by(|a, b| b.conflicts.cmp(&a.conflicts).then(a.id.cmp(&b.id)));
+        ConflictReport {
+            total: per_process.iter().map(|&(_, n)| n).sum(),
+            per_process,
+            objects,
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_and_report() {
+        let mut stats = Box::new(ConflictStats::new());
+        stats.record(0, 3, 10);
+        stats.record(1, 3, 12);
+        stats.record(1, 5, 11);
+        // takes over the slot of object 5
+        stats.record(1, 5 + CONFLICT_STATS_OBJECTS, 8);
+
+        let report = stats.report();
+        assert_eq!(report.total, 4);
+        assert_eq!(report.per_process, vec![(0, 1), (1, 3)]);
+        assert_eq!(report.objects.len(), 2);
+        assert_eq!(report.objects[0], ObjectConflicts { id: 3, namespace: 0, last_round: 12, conflicts: 2 });
+        assert_eq!(report.objects[1], ObjectConflicts { id: 5 + CONFLICT_STATS_OBJECTS, namespace: 0, last_round: 8, conflicts: 1 });
+    }
+}
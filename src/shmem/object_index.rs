@@ -1,5 +1,5 @@
 use super::MAX_OBJECTS;
-use log::{info, warn};
+use super::checksum::Crc32;
 
 #[derive(Debug, Clone, Copy)]
 pub(crate) struct ObjectInfo {
@@ -12,6 +12,63 @@ impl ObjectInfo {
     pub(crate) fn new(id: usize, offset: usize, size: usize) -> Self {
         ObjectInfo { id, offset, size }
     }
+
+    /// Object id without the namespace bits
+    pub(crate) fn local_id(&self) -> usize {
+        self.id & ((1 << NAMESPACE_SHIFT) - 1)
+    }
+
+    pub(crate) fn namespace(&self) -> u32 {
+        (self.id >> NAMESPACE_SHIFT) as u32
+    }
+}
+
+/// The namespace of an object is stored in the high bits of its id in the
+/// index, so that instances with different namespaces can share a region.
+const NAMESPACE_SHIFT: usize = 32;
+
+/// Id of the object in the index, i.e. `id` scoped by `namespace`
+pub(crate) fn scoped_id(namespace: u32, id: usize) -> usize {
+    ((namespace as usize) << NAMESPACE_SHIFT) | id
+}
+
+/// Reason an object could not be created
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AllocError {
+    /// Not enough free space in the object region
+    OutOfSpace,
+    /// An object with the same id already exists
+    DuplicateId,
+    /// The maximum number of objects is reached
+    TooManyObjects,
+    /// The id is beyond the maximum object id
+    InvalidId,
+    /// Only the coordinator can create objects
+    NotCoordinator,
+    /// The object is larger than the whole object region, unlike
+    /// `OutOfSpace` it never fits
+    ObjectTooLarge { requested: usize, max: usize },
+    /// The state lock was not acquired in time, e.g. its holder crashed.
+    /// See `RepCXLConfig::force_init`
+    StateLocked,
+}
+
+/// Allocation statistics of the object region
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryStats {
+    pub total_size: usize,
+    pub allocated_size: usize,
+    pub num_objects: usize,
+}
+
+/// Contiguous range of the object region, either occupied by an object or
+/// free. See `RepCXL::layout_map`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LayoutSegment {
+    pub offset: usize,
+    pub size: usize,
+    /// Local id of the object occupying the segment, None if free
+    pub occupant: Option<usize>,
 }
 
 /// Memory allocation information. Process coordinator has write access
@@ -59,25 +116,26 @@ impl ObjectIndex {
     /// ## Arguments
     /// * 'id' - Unique identifier for the object.
     /// * `size` - Size of the memory to allocate.
-    pub(crate) fn alloc_object(&mut self, id: usize, size: usize) -> Option<usize> {
+    pub(crate) fn alloc_object(&mut self, id: usize, size: usize) -> Result<usize, AllocError> {
         let chunks = (size + self.chunk_size - 1) / self.chunk_size; // Round up to nearest chunk size
         let size = chunks * self.chunk_size;
 
         if self.allocated_size + size > self.total_size {
-            warn!("Not enough space");
-            return None;
+            return Err(AllocError::OutOfSpace);
         }
 
         if self.lookup_object(id).is_some() {
-            info!("Object with id {} already exists", id);
-            return None;
+            return Err(AllocError::DuplicateId);
         }
 
+        let mut free_slot = false;
+
         // suboptimal allocation algorithm
         // loses space when a smaller object takes the place of a larger one which was freed
         for i in 0..MAX_OBJECTS {
             let entry = self.object_index[i];
             if entry.is_none() {
+                free_slot = true;
 
                 let start = if i == 0 {
                     0
@@ -98,12 +156,101 @@ impl ObjectIndex {
                 if start + size <= end {
                     self.object_index[i] = Some(ObjectInfo::new(id, start, size));
                     self.allocated_size += size;
-                    return Some(start);
+                    return Ok(start);
                 }
             }
         }
-        warn!("Failed allocation: no free slot available");
-        None
+        // free slots but no gap large enough
+        if free_slot {
+            Err(AllocError::OutOfSpace)
+        } else {
+            Err(AllocError::TooManyObjects)
+        }
+    }
+
+    /// Simulate `alloc_object` for each `(id, size)` in order, without
+    /// changing the index. Returns the offset each object would get, so that
+    /// a later object sees the space taken by the previous ones.
+    pub(crate) fn plan(&self, objects: &[(usize, usize)]) -> Vec<Result<usize, AllocError>> {
+        let mut index = *self;
+        objects.iter().map(|&(id, size)| index.alloc_object(id, size)).collect()
+    }
+
+    /// Iterate over the allocated objects
+    pub(crate) fn objects(&self) -> impl Iterator<Item = ObjectInfo> + '_ {
+        self.object_index.iter().filter_map(|entry| *entry)
+    }
+
+    pub(crate) fn stats(&self) -> MemoryStats {
+        MemoryStats {
+            total_size: self.total_size,
+            allocated_size: self.allocated_size,
+            num_objects: self.objects().count(),
+        }
+    }
+
+    /// Segments covering the whole region in offset order, free gaps
+    /// included
+    pub(crate) fn layout(&self) -> Vec<LayoutSegment> {
+        let mut objects: Vec<ObjectInfo> = self.objects().collect();
+        objects.sort_by_key(|o| o.offset);
+
+        let mut segments = Vec::with_capacity(2 * objects.len() + 1);
+        let mut end = 0;
+        for obj in objects {
+            if obj.offset > end {
+                segments.push(LayoutSegment { offset: end, size: obj.offset - end, occupant: None });
+            }
+            segments.push(LayoutSegment { offset: obj.offset, size: obj.size, occupant: Some(obj.local_id()) });
+            end = obj.offset + obj.size;
+        }
+        if end < self.total_size {
+            segments.push(LayoutSegment { offset: end, size: self.total_size - end, occupant: None });
+        }
+        segments
+    }
+
+    /// Remove all objects
+    pub(crate) fn clear(&mut self) {
+        *self = ObjectIndex::new(self.total_size, self.chunk_size);
+    }
+
+    /// Feed the allocation table into a checksum, field by field
+    pub(crate) fn digest(&self, crc: &mut Crc32) {
+        crc.update_usize(self.total_size);
+        crc.update_usize(self.allocated_size);
+        crc.update_usize(self.chunk_size);
+        for entry in self.object_index.iter() {
+            match entry {
+                Some(obj) => {
+                    crc.update(&[1]);
+                    crc.update_usize(obj.id);
+                    crc.update_usize(obj.offset);
+                    crc.update_usize(obj.size);
+                }
+                None => crc.update(&[0]),
+            }
+        }
+    }
+
+    /// Moves object `id` to a new region of `size` bytes, freeing the
+    /// current one. The new region does not overlap the current one, so the
+    /// value can be copied before the index is updated.
+    /// Returns the offset of the new region.
+    pub(crate) fn realloc_object(&mut self, id: usize, size: usize) -> Result<usize, AllocError> {
+        // not a scoped id, see `scoped_id`
+        const MOVING_ID: usize = usize::MAX;
+
+        let mut index = *self;
+        let offset = index.alloc_object(MOVING_ID, size)?;
+        index.dealloc_object(id);
+        for obj in index.object_index.iter_mut().flatten() {
+            if obj.id == MOVING_ID {
+                obj.id = id;
+            }
+        }
+        *self = index;
+        Ok(offset)
     }
 
     /// Removes an object from the state by its id
@@ -118,3 +265,72 @@ impl ObjectIndex {
         });
     }
 }
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plan_matches_alloc() {
+        let mut index = ObjectIndex::new(1024, 64);
+        // a hole of 128 bytes at offset 64
+        for (id, size) in [(1, 64), (2, 128), (3, 64)] {
+            index.alloc_object(id, size).unwrap();
+        }
+        index.dealloc_object(2);
+
+        let objects = [(4, 200), (5, 64), (6, 100), (1, 64), (7, 64), (8, 1024)];
+        let planned = index.plan(&objects);
+        assert_eq!(index.stats().num_objects, 2, "plan must not change the index");
+
+        let allocated: Vec<_> = objects.iter().map(|&(id, size)| index.alloc_object(id, size)).collect();
+        assert_eq!(planned, allocated);
+        assert_eq!(planned[1], Ok(64)); // first fit in the hole
+        assert_eq!(planned[3], Err(AllocError::DuplicateId));
+        assert_eq!(planned[5], Err(AllocError::OutOfSpace));
+    }
+
+    #[test]
+    fn test_realloc_does_not_overlap() {
+        let mut index = ObjectIndex::new(1024, 64);
+        for (id, size) in [(1, 64), (2, 64)] {
+            index.alloc_object(id, size).unwrap();
+        }
+
+        // the current region of object 1 is still taken during the move
+        assert_eq!(index.realloc_object(1, 64), Ok(128));
+        assert_eq!(index.lookup_object(1).map(|o| (o.offset, o.size)), Some((128, 64)));
+        assert_eq!(index.stats().num_objects, 2);
+        assert_eq!(index.stats().allocated_size, 128);
+
+        // unchanged on failure
+        assert_eq!(index.realloc_object(2, 1024), Err(AllocError::OutOfSpace));
+        assert_eq!(index.lookup_object(2).map(|o| o.offset), Some(64));
+    }
+
+    #[test]
+    fn test_layout_tiles_region() {
+        let mut index = ObjectIndex::new(1024, 64);
+        assert_eq!(index.layout(), vec![LayoutSegment { offset: 0, size: 1024, occupant: None }]);
+
+        for (id, size) in [(1, 64), (2, 128), (3, 64), (4, 192)] {
+            index.alloc_object(id, size).unwrap();
+        }
+        index.dealloc_object(2);
+        index.dealloc_object(4);
+
+        let segments = index.layout();
+        let mut end = 0;
+        for seg in &segments {
+            assert_eq!(seg.offset, end, "gap or overlap at {}", end);
+            assert!(seg.size > 0);
+            end += seg.size;
+        }
+        assert_eq!(end, 1024);
+
+        let occupants: Vec<_> = segments.iter().map(|s| s.occupant).collect();
+        assert_eq!(occupants, vec![Some(1), None, Some(3), None]);
+        assert_eq!(segments[1], LayoutSegment { offset: 64, size: 128, occupant: None });
+    }
+}
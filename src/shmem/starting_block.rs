@@ -1,18 +1,23 @@
 use super::MAX_PROCESSES;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::SystemTime;
 
 /// Shared fixed-size array indexed by process ID
 #[derive(Debug, Clone, Copy)]
 pub(crate) struct StartingBlock {
     start_time: Option<SystemTime>,
-    ready_processes: [bool; MAX_PROCESSES],
+    // token of the instance that marked the process ready, 0 if not ready
+    ready_processes: [u64; MAX_PROCESSES],
+    // several instances tried to mark the process ready
+    collisions: [bool; MAX_PROCESSES],
 }
 
 impl StartingBlock {
     pub(crate) fn new() -> Self {
         StartingBlock {
             start_time: None,
-            ready_processes: [false; MAX_PROCESSES],
+            ready_processes: [0; MAX_PROCESSES],
+            collisions: [false; MAX_PROCESSES],
         }
     }
 
@@ -28,15 +33,40 @@ impl StartingBlock {
         self.start_time
     }
 
-    pub(crate) fn mark_ready(&mut self, pid: usize) {
-        if pid < MAX_PROCESSES {
-            self.ready_processes[pid] = true;
-        } else {
+    /// Mark `pid` ready on behalf of the instance holding `token` (non-zero).
+    /// Fails if another instance already marked `pid` ready, i.e. several
+    /// instances are configured with the same id. The collision is recorded
+    /// for the other processes to see.
+    pub(crate) fn mark_ready(&mut self, pid: usize, token: u64) -> Result<(), String> {
+        if pid >= MAX_PROCESSES {
             panic!("Process ID {} exceeds MAX_PROCESSES {}", pid, MAX_PROCESSES);
         }
+        // processes mark themselves concurrently
+        let slot = unsafe { AtomicU64::from_ptr(&mut self.ready_processes[pid]) };
+        match slot.compare_exchange(0, token, Ordering::AcqRel, Ordering::Acquire) {
+            Ok(_) => Ok(()),
+            Err(current) if current == token => Ok(()), // marked ready before
+            Err(_) => {
+                self.collisions[pid] = true;
+                Err(format!("Process id {} is already used by another instance", pid))
+            }
+        }
     }
 
     pub(crate) fn all_ready(&self, processes: Vec<u32>) -> bool {
-        processes.iter().all(|&pid| self.ready_processes[pid as usize])
+        processes.iter().all(|&pid| self.ready_processes[pid as usize] != 0)
+    }
+
+    pub(crate) fn ready_count(&self, processes: &[u32]) -> usize {
+        processes.iter().filter(|&&pid| self.ready_processes[pid as usize] != 0).count()
+    }
+
+    pub(crate) fn not_ready(&self, processes: &[u32]) -> Vec<u32> {
+        processes.iter().copied().filter(|&pid| self.ready_processes[pid as usize] == 0).collect()
+    }
+
+    /// Processes whose id is used by several instances
+    pub(crate) fn collisions(&self, processes: &[u32]) -> Vec<u32> {
+        processes.iter().copied().filter(|&pid| self.collisions[pid as usize]).collect()
     }
 }
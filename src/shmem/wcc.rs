@@ -88,38 +88,75 @@ impl WCCMultiObject {
 
 
 /// entry for ObjectWCC
-/// contains object ID, round
+/// contains object ID, round and the timestamp of the write (0 unless the
+/// conflict order is "timestamp")
 #[derive(Debug, Clone, Copy)]
 struct ObjectWCCEntry {
     oid: usize,
     round: u64,
+    timestamp: u64,
 }
 
 impl ObjectWCCEntry {
-    pub fn new(oid: usize, round: u64) -> Self {
-        ObjectWCCEntry { oid, round }
+    pub fn new(oid: usize, round: u64, timestamp: u64) -> Self {
+        ObjectWCCEntry { oid, round, timestamp }
     }
 }
 
-/// multi-object WCC with smaller memory footprint
+/// Bytes a process writes to each node to enter the ObjectWCC, counted in the
+/// write amplification
+pub(crate) const OWCC_ENTRY_SIZE: usize = std::mem::size_of::<ObjectWCCEntry>();
+
+/// Number of shards of the ObjectWCC, objects are assigned by ID
+pub(crate) const OWCC_SHARDS: usize = 8;
+
+/// multi-object WCC with smaller memory footprint. Sharded by object ID:
+/// writes to objects in different shards never touch the same entries, and a
+/// process keeps one entry per shard instead of one for all its objects.
+///
+/// Objects of the same shard share the entry of a process: a write must not
+/// replace the entry of another object while it can still be checked, see
+/// `is_pending`.
 #[derive(Debug, Clone, Copy)]
+#[repr(C, align(64))] // shards start on a cache line
 pub(crate) struct ObjectWCC {
-    p_round: [ObjectWCCEntry; MAX_PROCESSES] // array of ObjectWCCEntry indexed by process ID
+    // per shard, array of ObjectWCCEntry indexed by process ID
+    shards: [[ObjectWCCEntry; MAX_PROCESSES]; OWCC_SHARDS]
 }
 
 impl ObjectWCC {
     pub fn new() -> Self {
         ObjectWCC {
-            p_round: [ObjectWCCEntry::new(0, 0); MAX_PROCESSES],
+            shards: [[ObjectWCCEntry::new(0, 0, 0); MAX_PROCESSES]; OWCC_SHARDS],
         }
     }
 
-    pub fn write(&mut self, oid: usize, round: u64, pid: usize) {
+    fn shard(&mut self, oid: usize) -> &mut [ObjectWCCEntry; MAX_PROCESSES] {
+        &mut self.shards[oid % OWCC_SHARDS]
+    }
+
+    pub fn write(&mut self, oid: usize, round: u64, pid: usize, timestamp: u64) {
         if pid >= MAX_PROCESSES {  
             return; // invalid pid
         }
-        let entry = ObjectWCCEntry::new(oid, round);
-        safe_memio::mem_write_flush(&mut self.p_round[pid], entry);
+        let entry = ObjectWCCEntry::new(oid, round, timestamp);
+        safe_memio::mem_write_flush(&mut self.shard(oid)[pid], entry);
+    }
+
+    /// Check if the entry of `pid` in the shard of `oid` holds a write to
+    /// another object that the other processes might still check in `round`,
+    /// i.e. a write of the previous round or later. Writing `oid` now would
+    /// hide a conflict on that object.
+    pub fn is_pending(&mut self, oid: usize, round: u64, pid: usize) -> bool {
+        if pid >= MAX_PROCESSES {
+            return false; // invalid pid
+        }
+        let slot = &self.shard(oid)[pid];
+        unsafe {
+            safe_memio::cache_flush_read(slot as *const ObjectWCCEntry as *const u8, size_of::<ObjectWCCEntry>());
+        }
+        let entry = unsafe { std::ptr::read_volatile(slot) };
+        entry.oid != oid && entry.round > 0 && round <= entry.round + 1
     }
 
     /// Check if the given process is the last writer for the given object.
@@ -127,23 +164,25 @@ impl ObjectWCC {
     /// Last writer criteria: 
     /// - the winning process has written in the largest round smaller than
     /// the current round
-    /// - in case of conflicts, the larger pid wins
-    pub fn is_last(&mut self, oid_in: usize, current_round:u64, round_in: u64, pid_in: usize) -> bool {
+    /// - in case of conflicts, the larger timestamp wins, then the larger pid
+    pub fn is_last(&mut self, oid_in: usize, current_round:u64, round_in: u64, pid_in: usize, timestamp_in: u64) -> bool {
         if pid_in > MAX_PROCESSES {
             return false; // invalid pid
         }
 
-        // single bulk flush of the entire p_round array + mfence, then
-        // read_volatile per entry (avoids 128 individual flushes)
+        let p_round = self.shard(oid_in);
+
+        // single bulk flush of the shard + mfence, then read_volatile per
+        // entry (avoids 128 individual flushes)
         unsafe {
             safe_memio::cache_flush_read(
-                self.p_round.as_ptr() as *const u8,
+                p_round.as_ptr() as *const u8,
                 std::mem::size_of::<[ObjectWCCEntry; MAX_PROCESSES]>(),
             );
         }
 
-        for i in 0..MAX_PROCESSES {
-            let entry = unsafe { std::ptr::read_volatile(&self.p_round[i]) };
+        for (i, slot) in p_round.iter().enumerate() {
+            let entry = unsafe { std::ptr::read_volatile(slot) };
             // check only entries for the same object ID
             if entry.oid != oid_in {
                 continue;
@@ -153,12 +192,28 @@ impl ObjectWCC {
             if current_round > entry.round && entry.round > round_in {
                 return false; // another process has written in a larger round
             }
-            if entry.round == round_in && i > pid_in {
-                return false; // another process has larger pid
+            if entry.round == round_in && (entry.timestamp, i) > (timestamp_in, pid_in) {
+                return false; // another process has a later timestamp or larger pid
             }
         }
         true
     }
+
+    /// Check if another process tried to write the object in the same round,
+    /// i.e. the write of `pid_in` in `round_in` was in conflict
+    pub fn has_contender(&mut self, oid_in: usize, round_in: u64, pid_in: usize) -> bool {
+        let p_round = self.shard(oid_in);
+        unsafe {
+            safe_memio::cache_flush_read(
+                p_round.as_ptr() as *const u8,
+                std::mem::size_of::<[ObjectWCCEntry; MAX_PROCESSES]>(),
+            );
+        }
+        p_round.iter().enumerate().any(|(i, slot)| {
+            let entry = unsafe { std::ptr::read_volatile(slot) };
+            i != pid_in && entry.oid == oid_in && entry.round == round_in
+        })
+    }
 }
 
 /// Bitmap to track processes that have written to an object for FastWCC implementation.
@@ -217,6 +272,10 @@ impl ProcessBitmapUncached {
         (self.data[byte_index] & mask) == 0
     }
 
+    fn count(&self) -> u32 {
+        self.data.iter().map(|word| word.count_ones()).sum()
+    }
+
     fn smallest(&self) -> Option<usize> {
         for word_index in 0..self.data.len() {
             let word = self.data[word_index];
@@ -230,6 +289,10 @@ impl ProcessBitmapUncached {
 
 }
 
+/// Bytes a process writes to each node to enter the FastWCC, i.e. the bitmap
+/// of the object, counted in the write amplification
+pub(crate) const FWCC_ENTRY_SIZE: usize = std::mem::size_of::<ProcessBitmapUncached>();
+
 #[derive(Debug, Clone, Copy)]
 pub struct FastWCC {
     obm: [ProcessBitmapUncached; MAX_OBJECTS] // object bitmaps
@@ -289,4 +352,122 @@ impl FastWCC {
         unsafe { self.obm[oid].cfr(); }   
         self.obm[oid].smallest()
     }
+
+    /// Number of processes currently marked as writers of the object
+    pub fn writers(&mut self, oid: usize) -> u32 {
+        if oid >= MAX_OBJECTS {
+            panic!("Invalid object ID");
+        }
+
+        unsafe { self.obm[oid].cfr(); }
+        self.obm[oid].count()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_owcc_objects_do_not_interfere() {
+        let mut owcc = Box::new(ObjectWCC::new());
+        let (obj_a, obj_b) = (1, 2);
+
+        // conflict on object A in round 5, the larger pid wins
+        owcc.write(obj_a, 5, 1, 0);
+        owcc.write(obj_a, 5, 2, 0);
+        assert!(owcc.is_last(obj_a, 6, 5, 2, 0));
+        assert!(!owcc.is_last(obj_a, 6, 5, 1, 0));
+        assert!(owcc.has_contender(obj_a, 5, 2));
+
+        // the winner moves on to object B while A is still being checked
+        owcc.write(obj_b, 6, 2, 0);
+        assert!(!owcc.is_last(obj_a, 6, 5, 1, 0), "Write to object B hid the conflict on A");
+        assert!(owcc.is_last(obj_b, 7, 6, 2, 0));
+    }
+
+    #[test]
+    fn test_owcc_same_shard_write_waits_for_check() {
+        let mut owcc = Box::new(ObjectWCC::new());
+        let (obj_a, obj_b) = (1, 1 + OWCC_SHARDS);
+
+        // conflict on object A in round 5, the larger pid wins
+        owcc.write(obj_a, 5, 1, 0);
+        owcc.write(obj_a, 5, 2, 0);
+
+        // the winner cannot move on to object B, in the same shard, while
+        // the loser checks A in round 6
+        assert!(owcc.is_pending(obj_b, 6, 2));
+        assert!(!owcc.is_pending(obj_a, 6, 2), "Writes to the same object are not delayed");
+        assert!(!owcc.is_last(obj_a, 6, 5, 1, 0));
+
+        // A is no longer checked from round 7
+        assert!(!owcc.is_pending(obj_b, 7, 2));
+        owcc.write(obj_b, 7, 2, 0);
+        assert!(owcc.is_last(obj_b, 8, 7, 2, 0));
+    }
+
+    // ObjectWCC shared by the writer threads of a test, like the shared
+    // state of the memory nodes
+    struct SharedWCC(*mut ObjectWCC);
+    unsafe impl Send for SharedWCC {}
+    unsafe impl Sync for SharedWCC {}
+
+    // Time for two threads to write and check `ops` times one object each,
+    // serialized by a lock around the whole WCC or not
+    fn two_object_writes(ops: u64, whole_lock: bool) -> std::time::Duration {
+        let mut owcc = Box::new(ObjectWCC::new());
+        let wcc = SharedWCC(&mut *owcc);
+        let lock = std::sync::Mutex::new(());
+        let start = std::time::Instant::now();
+        std::thread::scope(|s| {
+            for pid in 0..2 {
+                let (wcc, lock) = (&wcc, &lock);
+                s.spawn(move || {
+                    let oid = pid + 1;
+                    for round in 1..=ops {
+                        let _guard = whole_lock.then(|| lock.lock().unwrap());
+                        let owcc = unsafe { &mut *wcc.0 };
+                        owcc.write(oid, round, pid, 0);
+                        assert!(owcc.is_last(oid, round + 1, round, pid, 0));
+                    }
+                });
+            }
+        });
+        start.elapsed()
+    }
+
+    #[test]
+    fn test_owcc_two_object_throughput() {
+        // nothing to scale on a single core
+        if std::thread::available_parallelism().map_or(1, |n| n.get()) < 2 {
+            return;
+        }
+        let ops = 20_000;
+        let locked = two_object_writes(ops, true);
+        let sharded = two_object_writes(ops, false);
+        assert!(
+            sharded < locked,
+            "Sharded WCC took {:?} for two objects, {:?} with a single lock",
+            sharded, locked
+        );
+    }
+
+    #[test]
+    fn test_owcc_later_timestamp_wins() {
+        let mut owcc = Box::new(ObjectWCC::new());
+
+        // the larger timestamp wins over the larger pid
+        owcc.write(1, 5, 1, 200);
+        owcc.write(1, 5, 2, 100);
+        assert!(owcc.is_last(1, 6, 5, 1, 200));
+        assert!(!owcc.is_last(1, 6, 5, 2, 100));
+
+        // equal timestamps: the larger pid wins
+        owcc.write(2, 5, 1, 100);
+        owcc.write(2, 5, 2, 100);
+        assert!(owcc.is_last(2, 6, 5, 2, 100));
+        assert!(!owcc.is_last(2, 6, 5, 1, 100));
+    }
 }
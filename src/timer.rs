@@ -1,8 +1,18 @@
 
 use core::panic;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::{Duration, Instant, SystemTime};
 
 pub(crate) const ROUND_SLEEP_RATIO: f64 = 0.0; // Percentage of round time to sleep before busy-waiting
+pub(crate) const WAIT_ROUND_SLEEP_RATIO: f64 = 0.9; // Percentage of the wait to sleep when waiting for a later round
+
+/// Nanoseconds since the Unix epoch
+pub fn wall_clock_ns() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or(0)
+}
 
 pub fn system_time_to_instant(start_time: SystemTime) -> Instant {
     let mut best_span = Duration::MAX;
@@ -129,6 +139,45 @@ pub fn wait_start_time(start_instant: Instant, sleep_ratio: f64) {
     }
 }
 
+/// Startup jitter of process `pid` in [0, max], see the `startup_jitter`
+/// config. Hashed from the pid and the start time of the rounds, so that the
+/// processes start apart and a process gets another jitter on every run
+pub(crate) fn startup_jitter(pid: u32, start_time: SystemTime, max: Duration) -> Duration {
+    if max.is_zero() {
+        return Duration::ZERO;
+    }
+    let start_ns = start_time.duration_since(SystemTime::UNIX_EPOCH).map_or(0, |d| d.as_nanos() as u64);
+    // splitmix64
+    let mut z = start_ns ^ (pid as u64).wrapping_mul(0x9E3779B97F4A7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+    z ^= z >> 31;
+    Duration::from_nanos(z % (max.as_nanos() as u64 + 1))
+}
+
+/// Number of the current round based on a monotonic start instant. Round 0
+/// until the start instant.
+pub fn current_round(start_instant: Instant, round_time: Duration) -> u64 {
+    round_at(start_instant, round_time, Instant::now())
+}
+
+/// Number of the round `at` falls in, see `current_round`
+pub fn round_at(start_instant: Instant, round_time: Duration, at: Instant) -> u64 {
+    let elapsed = at.saturating_duration_since(start_instant);
+    let round_time_ns = round_time.as_nanos();
+    if round_time_ns == 0 {
+        panic!("round_time must be greater than zero");
+    }
+    (elapsed.as_nanos() / round_time_ns) as u64
+}
+
+/// Wait until the start of round `round`, returns immediately if it already
+/// started. Sleeps for a portion of the time and busy-waits for the rest.
+pub fn wait_round(start_instant: Instant, round_time: Duration, round: u64, sleep_ratio: f64) {
+    let round_start = start_instant + Duration::from_nanos((round_time.as_nanos() * round as u128) as u64);
+    wait_start_time(round_start, sleep_ratio);
+}
+
 /// Wait for the next round based on a monotonic start instant. Returns its
 /// number and start instant.
 pub fn wait_next_round(
@@ -211,3 +260,202 @@ pub fn wait_round_progress(
         std::hint::spin_loop();
     }
 }
+
+/// Number of histogram buckets, bucket `i` counts the delays in
+/// [2^i, 2^(i+1)) ns (bucket 0 also counts 0 ns)
+const JITTER_BUCKETS: usize = 64;
+
+/// Distribution of the delays of one sign, see `JitterHistogram`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DelayStats {
+    pub count: u64,
+    pub sum_ns: u64,
+    pub max_ns: u64,
+    /// Log2 buckets of the delay in ns
+    pub buckets: [u64; JITTER_BUCKETS],
+}
+
+impl Default for DelayStats {
+    fn default() -> Self {
+        DelayStats { count: 0, sum_ns: 0, max_ns: 0, buckets: [0; JITTER_BUCKETS] }
+    }
+}
+
+impl DelayStats {
+    pub fn mean(&self) -> Duration {
+        if self.count == 0 {
+            return Duration::ZERO;
+        }
+        Duration::from_nanos(self.sum_ns / self.count)
+    }
+
+    pub fn max(&self) -> Duration {
+        Duration::from_nanos(self.max_ns)
+    }
+
+    /// Upper bound of the bucket holding the `q` quantile (0.0 to 1.0),
+    /// capped at the maximum delay
+    pub fn quantile(&self, q: f64) -> Duration {
+        if self.count == 0 {
+            return Duration::ZERO;
+        }
+        let rank = ((self.count as f64 * q).ceil() as u64).max(1);
+        let mut seen = 0;
+        for (i, &n) in self.buckets.iter().enumerate() {
+            seen += n;
+            if seen >= rank {
+                let upper = if i + 1 < JITTER_BUCKETS { (1u64 << (i + 1)) - 1 } else { u64::MAX };
+                return Duration::from_nanos(upper.min(self.max_ns));
+            }
+        }
+        self.max()
+    }
+
+    pub fn p99(&self) -> Duration {
+        self.quantile(0.99)
+    }
+}
+
+/// Delay between the start instant of a round and the moment the write
+/// worker actually started it, see `RepCXL::jitter_histogram`. Rounds are
+/// started late (positive delay) by scheduling and the spin loop, early
+/// (negative delay) only by clock adjustments.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct JitterHistogram {
+    pub late: DelayStats,
+    pub early: DelayStats,
+}
+
+impl JitterHistogram {
+    /// Number of recorded rounds
+    pub fn rounds(&self) -> u64 {
+        self.late.count + self.early.count
+    }
+}
+
+/// Lock-free accumulator of one sign of the round delays
+struct AtomicDelayStats {
+    count: AtomicU64,
+    sum_ns: AtomicU64,
+    max_ns: AtomicU64,
+    buckets: [AtomicU64; JITTER_BUCKETS],
+}
+
+impl AtomicDelayStats {
+    fn new() -> Self {
+        AtomicDelayStats {
+            count: AtomicU64::new(0),
+            sum_ns: AtomicU64::new(0),
+            max_ns: AtomicU64::new(0),
+            buckets: std::array::from_fn(|_| AtomicU64::new(0)),
+        }
+    }
+
+    fn add(&self, delay: Duration) {
+        let ns = delay.as_nanos().min(u64::MAX as u128) as u64;
+        let bucket = (u64::BITS - 1 - ns.max(1).leading_zeros()) as usize;
+        self.buckets[bucket].fetch_add(1, Ordering::Relaxed);
+        self.sum_ns.fetch_add(ns, Ordering::Relaxed);
+        self.max_ns.fetch_max(ns, Ordering::Relaxed);
+        self.count.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn snapshot(&self) -> DelayStats {
+        DelayStats {
+            count: self.count.load(Ordering::Relaxed),
+            sum_ns: self.sum_ns.load(Ordering::Relaxed),
+            max_ns: self.max_ns.load(Ordering::Relaxed),
+            buckets: std::array::from_fn(|i| self.buckets[i].load(Ordering::Relaxed)),
+        }
+    }
+}
+
+/// Round delays recorded by the write worker, shared with the instance
+pub(crate) struct JitterRecorder {
+    late: AtomicDelayStats,
+    early: AtomicDelayStats,
+}
+
+impl JitterRecorder {
+    pub(crate) fn new() -> Self {
+        JitterRecorder { late: AtomicDelayStats::new(), early: AtomicDelayStats::new() }
+    }
+
+    /// Record the delay of the round starting at `round_start`, now
+    pub(crate) fn record(&self, round_start: Instant) {
+        let now = Instant::now();
+        match now.checked_duration_since(round_start) {
+            Some(late) => self.late.add(late),
+            None => self.early.add(round_start - now),
+        }
+    }
+
+    pub(crate) fn snapshot(&self) -> JitterHistogram {
+        JitterHistogram { late: self.late.snapshot(), early: self.early.snapshot() }
+    }
+}
+
+/// Paces operations to a fixed rate, e.g. the write submissions of a
+/// process. Each operation gets the next free slot, `1 / rate` after the
+/// previous one, and waits for it: bursts are spread out and an idle period
+/// does not build up credit.
+#[derive(Debug)]
+pub(crate) struct RateLimiter {
+    interval: Duration,
+    next_slot: std::sync::Mutex<Instant>,
+}
+
+impl RateLimiter {
+    /// `rate` in operations per second
+    pub(crate) fn new(rate: f64) -> Self {
+        RateLimiter {
+            interval: Duration::from_secs_f64(1.0 / rate),
+            next_slot: std::sync::Mutex::new(Instant::now()),
+        }
+    }
+
+    /// Wait for the slot of the next operation
+    pub(crate) fn acquire(&self) {
+        let slot = {
+            let mut next_slot = self.next_slot.lock().unwrap();
+            let slot = (*next_slot).max(Instant::now());
+            *next_slot = slot + self.interval;
+            slot
+        };
+        wait_start_time(slot, WAIT_ROUND_SLEEP_RATIO);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_delay_stats_quantiles() {
+        let stats = AtomicDelayStats::new();
+        for ns in [0, 1, 3, 100, 1000] {
+            stats.add(Duration::from_nanos(ns));
+        }
+        let stats = stats.snapshot();
+        assert_eq!(stats.count, 5);
+        assert_eq!(stats.buckets[0], 2); // 0 and 1 ns
+        assert_eq!(stats.buckets[1], 1);
+        assert_eq!(stats.mean(), Duration::from_nanos(220));
+        assert_eq!(stats.quantile(0.5), Duration::from_nanos(3));
+        // capped at the maximum, not the end of the 512-1023 bucket
+        assert_eq!(stats.p99(), Duration::from_nanos(1000));
+        assert_eq!(DelayStats::default().p99(), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_startup_jitter_bounded() {
+        let max = Duration::from_millis(5);
+        let start = SystemTime::now();
+        let jitters: Vec<Duration> = (0..64).map(|pid| startup_jitter(pid, start, max)).collect();
+        assert!(jitters.iter().all(|&j| j <= max));
+        // spread over the range
+        assert!(jitters.iter().any(|&j| j < max / 2) && jitters.iter().any(|&j| j > max / 2));
+        assert_eq!(startup_jitter(3, start, max), jitters[3]);
+        assert_eq!(startup_jitter(3, start, Duration::ZERO), Duration::ZERO);
+    }
+}
@@ -2,6 +2,7 @@ use std::time::Duration;
 
 pub mod arg_parser;
 // pub mod mc_bench;
+pub mod bench;
 pub mod ycsb;
 pub mod ms_logger;
 
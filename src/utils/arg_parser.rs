@@ -1,6 +1,6 @@
 // Parse command line arguments for RepCXL binaries and benchmarks
 use clap::{Arg, value_parser};
-use crate::config::RepCXLConfig;
+use crate::config::{self, RepCXLConfig};
 use log::error;
 
 #[derive(Debug)]
@@ -60,8 +60,8 @@ impl ArgParser {
                 Arg::new("round_time")
                     .short('r')
                     .long("round")
-                    .help("Duration of the synchronous round of the replication protocol (in ns)")
-                    .value_parser(value_parser!(u64)),
+                    .help("Duration of the synchronous round of the replication protocol (ns, or with a unit e.g. 500us, 1ms, 2s)")
+                    .value_parser(config::parse_duration_ns),
             )
             .arg(
                 Arg::new("id")
@@ -91,6 +91,24 @@ impl ArgParser {
                     .help("Enable or disable pipelined write path")
                     .value_parser(value_parser!(bool)),
             )
+            .arg(
+                Arg::new("force_init")
+                    .long("force-init")
+                    .help("Overwrite a shared state already present on the memory nodes")
+                    .value_parser(value_parser!(bool)),
+            )
+            .arg(
+                Arg::new("two_phase_commit")
+                    .long("two-phase-commit")
+                    .help("Commit writes on all memory nodes at once, using a shadow slot per object")
+                    .value_parser(value_parser!(bool)),
+            )
+            .arg(
+                Arg::new("single_writer")
+                    .long("single-writer")
+                    .help("Skip the write conflict checker, only one process may write each object")
+                    .value_parser(value_parser!(bool)),
+            )
             .arg(
                 Arg::new("read_retries")
                     .short('R')
@@ -141,6 +159,15 @@ impl ArgParser {
         if let Some(pipeline) = matches.remove_one::<bool>("pipeline") {
             self.config.pipeline = pipeline;
         }
+        if let Some(force_init) = matches.remove_one::<bool>("force_init") {
+            self.config.force_init = force_init;
+        }
+        if let Some(two_phase_commit) = matches.remove_one::<bool>("two_phase_commit") {
+            self.config.two_phase_commit = two_phase_commit;
+        }
+        if let Some(single_writer) = matches.remove_one::<bool>("single_writer") {
+            self.config.single_writer = single_writer;
+        }
         if let Some(processes) = matches.remove_one::<u32>("processes") {
             self.config.processes = Vec::from_iter(0..processes);
         }
@@ -151,6 +178,12 @@ impl ArgParser {
             self.config.core_affinity = Some(core_affinity);
         }
 
+        // the binaries only use file-backed memory nodes
+        if self.config.mem_nodes.is_empty() {
+            error!("Invalid config: at least one memory node must be specified in the config\nHelp:\n{}", help_string);
+            std::process::exit(1);
+        }
+
         // validate config values
         match self.config.validate() {
             Ok(_) => matches,
@@ -0,0 +1,198 @@
+// Workload drivers shared by the benchmark binaries.
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use log::{debug, error};
+use rand::Rng;
+
+use crate::{RepCXL, RepCXLConfig, RepCXLObject};
+
+/// Human readable run parameters printed with the benchmark results, so that
+/// runs with different replication factors can be told apart
+pub fn run_summary(config: &RepCXLConfig) -> String {
+    format!(
+        "  Nodes: {}\n  Processes: {}\n  Algorithm: {}\n  Round time: {}ns",
+        config.mem_nodes.len(),
+        config.processes.len(),
+        config.algorithm,
+        config.round_time
+    )
+}
+
+/// Same parameters as `run_summary` on a single `key=value` line, for the
+/// analysis scripts
+pub fn run_params(config: &RepCXLConfig) -> String {
+    format!(
+        "Run params: nodes={} processes={} algorithm={} round_time={}",
+        config.mem_nodes.len(),
+        config.processes.len(),
+        config.algorithm,
+        config.round_time
+    )
+}
+
+/// Spawn `clients` threads, each issuing `attempts` blocking writes of `value`
+/// to random objects. Returns the latencies of all writes and the average
+/// per-client throughput (ops/sec).
+pub fn write_clients<T: Send + Copy + 'static>(
+    objects: Arc<Vec<RepCXLObject<T>>>,
+    clients: u32,
+    attempts: u32,
+    value: T,
+) -> (Vec<Duration>, f64) {
+    let mut client_handles = Vec::new();
+
+    for c in 0..clients {
+        let objects = Arc::clone(&objects);
+        let handle = std::thread::spawn(move || {
+            debug!("Starting client thread {}", c);
+
+            let mut lats = Vec::new();
+            let mut rng = rand::rng();
+
+            let total_start = Instant::now();
+            for _ in 0..attempts {
+                let id = rng.random_range(0..objects.len());
+                let obj = objects.get(id).unwrap();
+
+                let start = Instant::now();
+                // write is blocking
+                match obj.write(value) {
+                    Ok(()) => (),
+                    Err(e) => error!("{e}"),
+                }
+                lats.push(start.elapsed());
+            }
+            let total_elapsed_s = total_start.elapsed().as_secs_f64();
+            (lats, attempts as f64 / total_elapsed_s)
+        }); // end of thread body
+
+        client_handles.push(handle);
+    }
+
+    let mut lats = Vec::new();
+    let mut tputs = Vec::new();
+    for handle in client_handles {
+        let (mut l, t) = handle.join().unwrap();
+        lats.append(&mut l);
+        tputs.push(t);
+    }
+
+    (lats, tputs.iter().sum::<f64>() / tputs.len() as f64)
+}
+
+/// Writes of one second of a soak run, see `soak_clients`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SoakBucket {
+    /// seconds since the start of the run
+    pub second: u64,
+    pub writes: u64,
+    pub mean_latency: Duration,
+    pub max_latency: Duration,
+}
+
+/// Spawn `clients` threads writing `value` to random objects continuously
+/// for `duration`. Returns the writes bucketed per second of issue (one
+/// bucket per started second, empty ones included) and the sustained
+/// throughput of all clients (ops/sec), e.g. to spot latency drift or
+/// pauses over long runs.
+pub fn soak_clients<T: Send + Copy + 'static>(
+    objects: Arc<Vec<RepCXLObject<T>>>,
+    clients: u32,
+    duration: Duration,
+    value: T,
+) -> (Vec<SoakBucket>, f64) {
+    let run_start = Instant::now();
+    let mut client_handles = Vec::new();
+
+    for c in 0..clients {
+        let objects = Arc::clone(&objects);
+        let handle = std::thread::spawn(move || {
+            debug!("Starting soak client thread {}", c);
+
+            // (issue time since the start of the run, latency)
+            let mut lats = Vec::new();
+            let mut rng = rand::rng();
+
+            loop {
+                let issued = run_start.elapsed();
+                if issued >= duration {
+                    break;
+                }
+                let obj = &objects[rng.random_range(0..objects.len())];
+
+                let start = Instant::now();
+                if let Err(e) = obj.write(value) {
+                    error!("{e}");
+                }
+                lats.push((issued, start.elapsed()));
+            }
+            lats
+        });
+        client_handles.push(handle);
+    }
+
+    let mut lats = Vec::new();
+    for handle in client_handles {
+        lats.append(&mut handle.join().unwrap());
+    }
+    let elapsed_s = run_start.elapsed().as_secs_f64();
+
+    let seconds = duration.as_secs() + u64::from(duration.subsec_nanos() > 0);
+    let mut buckets: Vec<SoakBucket> = (0..seconds.max(1))
+        .map(|second| SoakBucket { second, ..Default::default() })
+        .collect();
+    let mut sums = vec![Duration::ZERO; buckets.len()];
+    for &(issued, lat) in &lats {
+        let i = issued.as_secs() as usize;
+        buckets[i].writes += 1;
+        buckets[i].max_latency = buckets[i].max_latency.max(lat);
+        sums[i] += lat;
+    }
+    for (bucket, sum) in buckets.iter_mut().zip(sums) {
+        if bucket.writes > 0 {
+            bucket.mean_latency = sum / bucket.writes as u32;
+        }
+    }
+
+    (buckets, lats.len() as f64 / elapsed_s)
+}
+
+/// Soak time series as CSV, one line per second, for the notebooks
+pub fn soak_csv(buckets: &[SoakBucket]) -> String {
+    let mut csv = String::from("second,writes,mean_latency_ns,max_latency_ns\n");
+    for b in buckets {
+        csv.push_str(&format!(
+            "{},{},{},{}\n",
+            b.second, b.writes, b.mean_latency.as_nanos(), b.max_latency.as_nanos()
+        ));
+    }
+    csv
+}
+
+/// Issue `attempts` writes of `value` to random objects one at a time from the
+/// calling thread, with no other outstanding operation. The latencies are the
+/// raw replication cost of a write, without queueing behind other clients.
+pub fn replication_floor<T: Send + Copy + PartialEq + std::fmt::Debug + 'static>(
+    rcxl: &mut RepCXL<T>,
+    objects: &[RepCXLObject<T>],
+    attempts: u32,
+    value: T,
+) -> Vec<Duration> {
+    let mut lats = Vec::with_capacity(attempts as usize);
+    let mut rng = rand::rng();
+
+    for _ in 0..attempts {
+        let obj = &objects[rng.random_range(0..objects.len())];
+
+        let start = Instant::now();
+        match rcxl.write_object(obj, value) {
+            Ok(()) => (),
+            Err(e) => error!("{e}"),
+        }
+        lats.push(start.elapsed());
+    }
+
+    lats
+}
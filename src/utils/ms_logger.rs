@@ -1,5 +1,5 @@
 use std::fs::{File, OpenOptions};
-use std::io::{BufRead, BufReader, Seek, SeekFrom, Write};
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom, Write};
 
 use crate::algorithms::monster::MonsterState;
 
@@ -14,6 +14,16 @@ pub struct MonsterStateLogEntry {
     pub object_id: usize,
 }
 
+/// A state log entry tagged with the process that logged it, used to merge
+/// the logs of several processes into a single timeline.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProcessLogEntry {
+    pub process_id: usize,
+    pub round_num: u64,
+    pub state: String,
+    pub object_id: usize,
+}
+
 // #[derive(Debug, Clone, PartialEq)]
 pub struct MonsterStateLogger {
     log: File,
@@ -47,25 +57,43 @@ impl MonsterStateLogger {
         let reader = BufReader::new(&self.log);
         reader
             .lines()
-            .filter_map(|line| {
-                let line = line.ok()?;
-                let line = line.trim();
-                if line.is_empty() {
-                    return None;
-                }
-                let parts: Vec<&str> = line.splitn(3, ',').collect();
-                if parts.len() != 3 {
-                    return None;
-                }
-                Some(MonsterStateLogEntry {
-                    round_num: parts[0].parse().ok()?,
-                    state: parts[1].to_string(),
-                    object_id: parts[2].parse().ok()?,
-                })
-            })
+            .filter_map(|line| parse_entry(&line.ok()?))
             .collect()
     }
 
+    /// Read the last `n` complete entries of the log, in log order. Only the
+    /// end of the file is read, growing the window from the end until it
+    /// holds `n` entries. A trailing line without newline (being written) is
+    /// skipped.
+    pub fn read_monster_log_tail(&mut self, n: usize) -> Vec<MonsterStateLogEntry> {
+        let len = self.log.seek(SeekFrom::End(0)).expect("Failed to seek to end of log file");
+        let mut window: u64 = 4096;
+        loop {
+            let start = len.saturating_sub(window);
+            self.log.seek(SeekFrom::Start(start)).expect("Failed to seek in log file");
+            let mut buf = Vec::with_capacity((len - start) as usize);
+            (&self.log).take(len - start).read_to_end(&mut buf).expect("Failed to read log file");
+
+            // only complete lines: drop the partial line at the end, and the
+            // one cut by the window start
+            let end = buf.iter().rposition(|&b| b == b'\n').map_or(0, |i| i + 1);
+            let begin = if start == 0 {
+                0
+            } else {
+                buf[..end].iter().position(|&b| b == b'\n').map_or(end, |i| i + 1)
+            };
+            let entries: Vec<MonsterStateLogEntry> = String::from_utf8_lossy(&buf[begin..end])
+                .lines()
+                .filter_map(parse_entry)
+                .collect();
+
+            if entries.len() >= n || start == 0 {
+                return entries[entries.len().saturating_sub(n)..].to_vec();
+            }
+            window *= 2;
+        }
+    }
+
     /// Read only the state names in order from the log file.
     pub fn read_monster_states(&mut self) -> Vec<String> {
         self.read_monster_log().into_iter().map(|e| e.state).collect()
@@ -77,7 +105,66 @@ impl MonsterStateLogger {
         self.log.seek(SeekFrom::Start(0)).expect("Failed to seek after truncation");
     }
 
+    /// Read the state logs of several processes, given as (process id, path)
+    /// pairs, and merge them in round order. Entries of the same round are
+    /// ordered by process id, entries of the same process keep the log order.
+    pub fn merge_logs(logs: &[(usize, &str)]) -> Vec<ProcessLogEntry> {
+        let mut merged: Vec<ProcessLogEntry> = logs
+            .iter()
+            .flat_map(|&(process_id, path)| {
+                MonsterStateLogger::new(path)
+                    .read_monster_log()
+                    .into_iter()
+                    .map(move |e| ProcessLogEntry {
+                        process_id,
+                        round_num: e.round_num,
+                        state: e.state,
+                        object_id: e.object_id,
+                    })
+            })
+            .collect();
+
+        // stable sort keeps the log order within a process and round
+        merged.sort_by_key(|e| (e.round_num, e.process_id));
+        merged
+    }
+}
 
+/// Parse a `<round_num>,<state>,<object_id>` log line
+fn parse_entry(line: &str) -> Option<MonsterStateLogEntry> {
+    let line = line.trim();
+    if line.is_empty() {
+        return None;
+    }
+    let parts: Vec<&str> = line.splitn(3, ',').collect();
+    if parts.len() != 3 {
+        return None;
+    }
+    Some(MonsterStateLogEntry {
+        round_num: parts[0].parse().ok()?,
+        state: parts[1].to_string(),
+        object_id: parts[2].parse().ok()?,
+    })
+}
+
+/// Timeline of a single object from merged state logs: the state transitions
+/// of every process that wrote to it, in round order.
+pub fn object_timeline(merged: &[ProcessLogEntry], object_id: usize) -> Vec<ProcessLogEntry> {
+    merged.iter().filter(|e| e.object_id == object_id).cloned().collect()
+}
+
+/// Human readable description of a MONSTER state in a timeline
+pub fn describe_state(state: &str) -> &'static str {
+    match state {
+        "Try" => "write attempt",
+        "Retry" => "retry",
+        "Check" => "conflict check",
+        "Replicate" => "write replicated",
+        "Wait" => "conflict lost, waiting",
+        "PostConflictCheck" => "post-conflict check",
+        "Backoff" => "backing off on hot object",
+        _ => "unknown state",
+    }
 }
 
 
@@ -0,0 +1,49 @@
+//! Versioned objects, keeping the last K writes of an object
+
+use crate::safe_memio::{mem_readall, MemoryError, ObjectMemoryEntry};
+use crate::shmem::object_index::ObjectInfo;
+use crate::{GroupView, RepCXLObject};
+
+/// Object keeping its last `versions()` values, e.g. for snapshot reads.
+/// The allocation holds one entry per version, used as a ring: a write
+/// replaces the entry holding the oldest version, ordered by write id. Each
+/// entry is replicated and read like a single object. See
+/// `RepCXL::write_version` and `RepCXL::read_version`.
+#[derive(Debug)]
+pub struct VersionedObject<T: Copy> {
+    // one object per entry, sharing the id of the versioned object
+    pub(crate) slots: Vec<RepCXLObject<T>>,
+}
+
+impl<T: Copy> VersionedObject<T> {
+    // split the allocation of `obj` into `versions` entries of `entry_size`
+    // bytes
+    pub(crate) fn new(obj: RepCXLObject<T>, versions: usize, entry_size: usize) -> Self {
+        let slots = (0..versions)
+            .map(|i| obj.with_info(ObjectInfo::new(obj.info.id, obj.info.offset + i * entry_size, entry_size)))
+            .collect();
+        VersionedObject { slots }
+    }
+
+    /// Number of versions kept
+    pub fn versions(&self) -> usize {
+        self.slots.len()
+    }
+
+    pub fn id(&self) -> usize {
+        self.slots[0].info.local_id()
+    }
+}
+
+/// Slot indexes from the newest to the oldest version, by the newest write
+/// id of each slot across the memory nodes. Slots never written come last.
+pub(crate) fn slots_by_age<T: Copy>(obj: &VersionedObject<T>, view: &GroupView) -> Result<Vec<usize>, MemoryError> {
+    let mut newest = Vec::with_capacity(obj.versions());
+    for slot in &obj.slots {
+        let omes: Vec<ObjectMemoryEntry<T>> = mem_readall(slot.info.offset, &view.memory_nodes)?;
+        newest.push(omes.iter().map(|ome| (ome.written, ome.wid)).max().unwrap());
+    }
+    let mut slots: Vec<usize> = (0..obj.versions()).collect();
+    slots.sort_by(|&a, &b| newest[b].cmp(&newest[a]));
+    Ok(slots)
+}
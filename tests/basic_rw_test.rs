@@ -1,5 +1,6 @@
 use std::time::Duration;
 use rep_cxl::request::ReadReturn;
+use rep_cxl::Blob;
 
 mod test_utils;
 use test_utils::*;
@@ -64,4 +65,311 @@ fn test_rw() {
     // write and read threads stop after the test ends and the repCXL instances 
     // are dropped. Cannot explicitly stop them here because threads own 
     // repcxl instances
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_read_many() {
+    let node_path = "/dev/shm/repCXL_test_read_many";
+    setup_tmpfs_file(node_path, TEST_MEMORY_SIZE);
+
+    let mut rcxl = single_rcxl(0, vec![node_path]);
+    rcxl.config.algorithm = ALGORITHM.to_string();
+    rcxl.config.pipeline = true;
+    rcxl.init_state().expect("init_state failed");
+
+    for (id, val) in [(1, 11), (2, 22), (3, 33)] {
+        rcxl.new_object_with_val(id, val).expect("failed to create object");
+    }
+    rcxl.start();
+
+    // a missing object is not part of the result
+    let results = rcxl.read_many(&[1, 2, 3, 4]);
+    assert_eq!(results.len(), 3);
+    for (id, val) in [(1, 11), (2, 22), (3, 33)] {
+        assert!(
+            matches!(results.get(&id), Some(ReadReturn::ReadSafe(v)) if *v == val),
+            "Object {} should read {}, got {:?}",
+            id, val, results.get(&id)
+        );
+    }
+    rcxl.stop();
+
+    cleanup_tmpfs_file(node_path);
+}
+
+#[test]
+fn test_read_direct() {
+    let node_paths = vec!["/dev/shm/repCXL_test_read_direct1", "/dev/shm/repCXL_test_read_direct2"];
+    for path in &node_paths {
+        setup_tmpfs_file(path, TEST_MEMORY_SIZE);
+    }
+
+    let mut rcxl = single_rcxl(0, node_paths.clone());
+    rcxl.config.algorithm = ALGORITHM.to_string();
+    rcxl.config.pipeline = true;
+    rcxl.init_state().expect("init_state failed");
+    let obj = rcxl.new_object(1).expect("failed to create object");
+    let _other = rcxl.new_object_with_val(2, 7).expect("failed to create object");
+    let other = rcxl.get_object(2).expect("failed to get object");
+    rcxl.start();
+
+    assert!(matches!(obj.read_direct(), Ok(ReadReturn::Uninitialized)));
+    for val in [5, 6] {
+        obj.write(val).expect("Write should succeed");
+        let direct = obj.read_direct().expect("Direct read should succeed");
+        let worker = obj.read().expect("Read should succeed");
+        assert!(matches!(direct, ReadReturn::ReadSafe(v) if v == val), "Unexpected direct read: {:?}", direct);
+        assert!(matches!(worker, ReadReturn::ReadSafe(v) if v == val), "Unexpected read: {:?}", worker);
+    }
+    assert!(matches!(other.read_direct(), Ok(ReadReturn::ReadSafe(7))));
+    rcxl.stop();
+
+    // not available for MONSTER, whose reads depend on the rounds
+    let mut rcxl = single_rcxl(0, node_paths.clone());
+    let obj = rcxl.get_object(1).expect("failed to get object");
+    let err = obj.read_direct().expect_err("Direct read should fail for monster");
+    assert!(err.contains("best-effort"), "Unexpected error: {}", err);
+
+    for path in &node_paths {
+        cleanup_tmpfs_file(path);
+    }
+}
+
+// Writes on a persistent (not tmpfs) backing file are synced by the msync
+// thread and found again after re-mapping the file
+#[test]
+fn test_msync_file_backed() {
+    let node_path = concat!(env!("CARGO_TARGET_TMPDIR"), "/repCXL_test_msync");
+    setup_tmpfs_file(node_path, TEST_MEMORY_SIZE);
+
+    {
+        let mut rcxl = single_rcxl(0, vec![node_path]);
+        rcxl.config.msync_interval_rounds = Some(2);
+        rcxl.init_state().expect("init_state failed");
+        let obj = rcxl.new_object(3).expect("failed to create object");
+        rcxl.sync_start().expect("sync_start failed");
+        rcxl.write_object(&obj, 99).expect("Write should succeed");
+        rcxl.wait_for_round(rcxl.current_round() + 3); // at least one sync
+        rcxl.stop();
+    }
+
+    let mut rcxl = single_rcxl(0, vec![node_path]);
+    let obj = rcxl.get_object(3).expect("Object should survive re-mapping");
+    match rcxl.read_object(&obj).expect("Read should succeed") {
+        ReadReturn::ReadSafe(v) => assert_eq!(v, 99),
+        other => panic!("Single node read should be safe: {:?}", other),
+    }
+
+    cleanup_tmpfs_file(node_path);
+}
+
+// sync_start takes the algorithm and round time from the config
+#[test]
+fn test_sync_start_from_config() {
+    let node_path = "/dev/shm/repCXL_test_sync_config";
+    setup_tmpfs_file(node_path, TEST_MEMORY_SIZE);
+
+    let mut config = test_config(vec![node_path]);
+    config.processes = vec![0];
+    config.algorithm = ALGORITHM.to_string();
+    config.round_time = 2 * ROUND_TIME.as_nanos() as u64;
+    let mut rcxl = rep_cxl::RepCXL::<u64>::new(config);
+    rcxl.init_state().expect("init_state failed");
+    let obj = rcxl.new_object(1).expect("failed to create object");
+    rcxl.sync_start().expect("sync_start failed");
+
+    let start_round = rcxl.current_round();
+    std::thread::sleep(10 * 2 * ROUND_TIME);
+    let rounds = rcxl.current_round() - start_round;
+    assert!((9..=12).contains(&rounds), "Rounds should follow the configured round time: {}", rounds);
+
+    rcxl.write_object(&obj, 42).expect("Write should succeed");
+    match rcxl.read_object(&obj).expect("Read should succeed") {
+        ReadReturn::ReadSafe(val) => assert_eq!(val, 42),
+        other => panic!("Single node read should be safe: {:?}", other),
+    }
+    rcxl.stop();
+
+    cleanup_tmpfs_file(node_path);
+}
+
+#[test]
+fn test_blob_value() {
+    let node_path = "/dev/shm/repCXL_test_blob";
+    setup_tmpfs_file(node_path, TEST_MEMORY_SIZE);
+
+    let mut config = test_config(vec![node_path]);
+    config.processes = vec![0];
+    let mut rcxl = rep_cxl::RepCXL::<Blob<128>>::new(config);
+    rcxl.init_state().expect("init_state failed");
+    let obj = rcxl.new_object(1).expect("failed to create object");
+    rcxl.sync_start().expect("sync_start failed");
+
+    // shorter than the blob: zero-padded
+    let payload = b"partial payload";
+    rcxl.write_object(&obj, Blob::from_slice(payload)).expect("Write should succeed");
+    match rcxl.read_object(&obj).expect("Read should succeed") {
+        ReadReturn::ReadSafe(blob) => {
+            assert_eq!(&blob.as_slice()[..payload.len()], payload);
+            assert!(blob.as_slice()[payload.len()..].iter().all(|&b| b == 0));
+        }
+        other => panic!("Single node read should be safe: {:?}", other),
+    }
+    rcxl.stop();
+
+    // longer than the blob: truncated
+    assert_eq!(Blob::<4>::from_slice(b"truncated").as_slice(), b"trun");
+
+    cleanup_tmpfs_file(node_path);
+}
+
+#[test]
+fn test_versioned_object() {
+    let node_paths = vec!["/dev/shm/repCXL_test_versioned0", "/dev/shm/repCXL_test_versioned1"];
+    for path in &node_paths {
+        setup_tmpfs_file(path, TEST_MEMORY_SIZE);
+    }
+
+    let mut rcxl = single_rcxl(0, node_paths.clone());
+    rcxl.init_state().expect("init_state failed");
+    let obj = rcxl.new_versioned_object(1, 3).expect("failed to create object");
+    assert_eq!(obj.versions(), 3);
+    assert!(rcxl.memory_stats().allocated_size >= 3 * rcxl.object_size());
+    rcxl.sync_start().expect("sync_start failed");
+
+    assert!(matches!(rcxl.read_version(&obj, 0), Ok(ReadReturn::Uninitialized)));
+    for val in [10, 20, 30] {
+        rcxl.write_version(&obj, val).expect("Write should succeed");
+    }
+    for (k, val) in [30, 20, 10].into_iter().enumerate() {
+        match rcxl.read_version(&obj, k).expect("Read should succeed") {
+            ReadReturn::ReadSafe(v) => assert_eq!(v, val, "Version {}", k),
+            other => panic!("Unexpected read of version {}: {:?}", k, other),
+        }
+    }
+    assert!(rcxl.read_version(&obj, 3).is_err());
+
+    // the oldest version is replaced
+    rcxl.write_version(&obj, 40).expect("Write should succeed");
+    let obj = rcxl.get_versioned_object(1, 3).expect("failed to get object");
+    for (k, val) in [40, 30, 20].into_iter().enumerate() {
+        assert!(matches!(rcxl.read_version(&obj, k), Ok(ReadReturn::ReadSafe(v)) if v == val), "Version {}", k);
+    }
+    assert!(rcxl.get_versioned_object(1, 1).is_none());
+    rcxl.stop();
+
+    for path in &node_paths {
+        cleanup_tmpfs_file(path);
+    }
+}
+
+#[test]
+fn test_batched_writes() {
+    let node_paths = vec!["/dev/shm/repCXL_test_write_batch0", "/dev/shm/repCXL_test_write_batch1"];
+    for path in &node_paths {
+        setup_tmpfs_file(path, TEST_MEMORY_SIZE);
+    }
+
+    let mut config = test_config(node_paths.clone());
+    config.processes = vec![0];
+    config.algorithm = ALGORITHM.to_string();
+    config.pipeline = true;
+    config.write_batch_size = 16;
+    config.max_inflight_writes = 3; // all the writes of an object are queued at once
+    let mut rcxl = rep_cxl::RepCXL::<u64>::new(config);
+    rcxl.init_state().expect("init_state failed");
+    let objs: Vec<_> = (0..4).map(|id| rcxl.new_object(id).expect("failed to create object")).collect();
+
+    // queued before the worker starts, taken in one batch
+    let mut tokens = Vec::new();
+    for round in 0..3 {
+        for (id, obj) in objs.iter().enumerate() {
+            tokens.push(obj.try_write((round * 10 + id) as u64).expect("Failed to enqueue write"));
+        }
+    }
+    rcxl.start();
+    for token in tokens {
+        token.wait().expect("Write should be acked");
+    }
+
+    // the last write of each object is kept
+    for (id, obj) in objs.iter().enumerate() {
+        let read = obj.read().expect("Read should succeed");
+        assert!(matches!(read, ReadReturn::ReadSafe(v) if v == (20 + id) as u64), "Object {}: {:?}", id, read);
+    }
+    rcxl.stop();
+
+    for path in &node_paths {
+        cleanup_tmpfs_file(path);
+    }
+}
+
+// A burst of writes is paced at the configured rate, through the write
+// worker or not
+#[test]
+fn test_write_rate_limit() {
+    for pipeline in [true, false] {
+        let node_path = "/dev/shm/repCXL_test_rate_limit";
+        setup_tmpfs_file(node_path, TEST_MEMORY_SIZE);
+
+        let mut config = test_config(vec![node_path]);
+        config.processes = vec![0];
+        config.algorithm = ALGORITHM.to_string();
+        config.pipeline = pipeline;
+        config.write_rate_limit = Some(1000.0);
+        let mut rcxl = rep_cxl::RepCXL::<u64>::new(config);
+        rcxl.init_state().expect("init_state failed");
+        let obj = rcxl.new_object(1).expect("failed to create object");
+        rcxl.sync_start().expect("sync_start failed");
+
+        let start = std::time::Instant::now();
+        for i in 0..2000 {
+            if pipeline {
+                obj.write(i).expect("Write should succeed");
+            } else {
+                rcxl.write_object(&obj, i).expect("Write should succeed");
+            }
+        }
+        // ~2 s, loose upper bound for a loaded machine
+        let elapsed = start.elapsed();
+        assert!(
+            elapsed >= Duration::from_millis(1900) && elapsed < Duration::from_secs(4),
+            "2000 writes at 1000 writes/s took {:?} (pipeline {})",
+            elapsed,
+            pipeline
+        );
+        rcxl.stop();
+
+        cleanup_tmpfs_file(node_path);
+    }
+}
+
+#[test]
+fn test_local_fence_read_your_writes() {
+    let node_paths = vec!["/dev/shm/repCXL_test_local_fence0", "/dev/shm/repCXL_test_local_fence1"];
+    for path in &node_paths {
+        setup_tmpfs_file(path, TEST_MEMORY_SIZE);
+    }
+
+    let mut rcxl = single_rcxl(0, node_paths.clone());
+    rcxl.config.algorithm = ALGORITHM.to_string();
+    rcxl.config.pipeline = true;
+    rcxl.config.write_batch_size = 16;
+    rcxl.init_state().expect("init_state failed");
+    let obj = rcxl.new_object(1).expect("failed to create object").with_local_fence();
+    rcxl.start();
+
+    for i in 0..1000u64 {
+        obj.write(i).expect("Write should succeed");
+        let read = if i % 2 == 0 { obj.read_direct() } else { rcxl.read_object(&obj) };
+        match read.expect("Read should succeed") {
+            ReadReturn::ReadSafe(v) | ReadReturn::ReadDirty(v) => assert_eq!(v, i),
+            ReadReturn::Uninitialized | ReadReturn::TooStale | ReadReturn::ObjectRemoved => panic!("Write {} not visible", i),
+        }
+    }
+    rcxl.stop();
+
+    for path in &node_paths {
+        cleanup_tmpfs_file(path);
+    }
+}
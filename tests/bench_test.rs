@@ -0,0 +1,96 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use rep_cxl::utils::{self, bench};
+
+mod test_utils;
+use test_utils::*;
+
+const ATTEMPTS: u32 = 10;
+const CLIENTS: u32 = 4;
+
+fn median(lats: &[Duration]) -> u64 {
+    let ns: Vec<u64> = lats.iter().map(|d| d.as_nanos() as u64).collect();
+    utils::percentile(&ns, 0.5)
+}
+
+/// The replication floor (single client, no outstanding operations) must be a
+/// lower bound on the write latency observed by concurrent clients
+#[test]
+fn test_replication_floor_lower_bound() {
+    let node_path = "/dev/shm/repCXL_test_floor";
+    setup_tmpfs_file(node_path, TEST_MEMORY_SIZE);
+
+    let mut rcxl = single_rcxl(0, vec![node_path]);
+    rcxl.config.pipeline = true;
+    rcxl.init_state().expect("init_state failed");
+
+    let objects: Vec<_> = (0..4)
+        .map(|i| rcxl.new_object(i).expect("failed to create object"))
+        .collect();
+
+    rcxl.sync_start().expect("sync_start failed");
+
+    let floor = bench::replication_floor(&mut rcxl, &objects, ATTEMPTS, 1);
+    let (multi, _) = bench::write_clients(Arc::new(objects), CLIENTS, ATTEMPTS, 2);
+    rcxl.stop();
+
+    assert_eq!(floor.len(), ATTEMPTS as usize);
+    assert_eq!(multi.len(), (ATTEMPTS * CLIENTS) as usize);
+    assert!(
+        median(&floor) <= median(&multi),
+        "replication floor {}ns should not exceed multi-client latency {}ns",
+        median(&floor),
+        median(&multi)
+    );
+
+    cleanup_tmpfs_file(node_path);
+}
+
+/// The run parameters include the replication factor
+#[test]
+fn test_run_summary_node_count() {
+    let config = test_config(vec!["/dev/shm/repCXL_a", "/dev/shm/repCXL_b", "/dev/shm/repCXL_c"]);
+
+    let summary = bench::run_summary(&config);
+    assert!(summary.contains("Nodes: 3"), "Summary without node count:\n{}", summary);
+    assert!(summary.contains(&format!("Algorithm: {}", TEST_ALGORITHM)));
+
+    let params = bench::run_params(&config);
+    assert!(params.contains("nodes=3"), "Params without node count: {}", params);
+    assert!(params.contains(&format!("round_time={}", TEST_ROUND_TIME)));
+}
+
+/// A soak run reports one time series bucket per second of the run
+#[test]
+fn test_soak_time_series() {
+    let node_path = "/dev/shm/repCXL_test_soak";
+    setup_tmpfs_file(node_path, TEST_MEMORY_SIZE);
+
+    let mut rcxl = single_rcxl(0, vec![node_path]);
+    rcxl.config.pipeline = true;
+    rcxl.init_state().expect("init_state failed");
+
+    let objects: Vec<_> = (0..4)
+        .map(|i| rcxl.new_object(i).expect("failed to create object"))
+        .collect();
+
+    rcxl.sync_start().expect("sync_start failed");
+
+    let (buckets, tput) = bench::soak_clients(Arc::new(objects), 2, Duration::from_secs(2), 1);
+    rcxl.stop();
+
+    assert_eq!(buckets.len(), 2);
+    for (i, bucket) in buckets.iter().enumerate() {
+        assert_eq!(bucket.second, i as u64);
+        assert!(bucket.writes > 0, "No write in second {}", i);
+        assert!(bucket.mean_latency <= bucket.max_latency);
+    }
+    assert!(tput > 0.0);
+
+    let csv = bench::soak_csv(&buckets);
+    assert_eq!(csv.lines().count(), 3);
+    assert!(csv.starts_with("second,writes,mean_latency_ns,max_latency_ns"));
+
+    cleanup_tmpfs_file(node_path);
+}
@@ -1,6 +1,8 @@
+use std::time::Duration;
 use std::vec;
 
-use rep_cxl::RepCXL;
+use rep_cxl::{AllocError, RepCXL};
+use rep_cxl::request::ReadReturn;
 
 mod test_utils;
 use test_utils::*;
@@ -23,6 +25,52 @@ fn test_repcxl_initialization() {
     cleanup_tmpfs_file(node_path);
 }
 
+// A second init_state would wipe the objects of the first one
+#[test]
+fn test_init_state_twice() {
+    let node_path = "/dev/shm/repCXL_test_init_twice";
+    setup_tmpfs_file(node_path, TEST_MEMORY_SIZE);
+
+    let mut rcxl = single_rcxl(0, vec![node_path]);
+    rcxl.init_state().expect("init_state failed");
+    rcxl.new_object(1).expect("Failed to create object");
+
+    let err = rcxl.init_state().expect_err("init_state should not overwrite a populated region");
+    assert!(err.contains("already holds a shared state"), "Unexpected error: {}", err);
+    assert_eq!(rcxl.list_objects(), vec![1], "Existing state was modified");
+
+    rcxl.config.force_init = true;
+    rcxl.init_state().expect("Forced init_state should overwrite the state");
+    assert!(rcxl.list_objects().is_empty());
+
+    cleanup_tmpfs_file(node_path);
+}
+
+#[test]
+fn test_replica_init_state_rejected() {
+    let node_path = "/dev/shm/repCXL_test_replica_init";
+    setup_tmpfs_file(node_path, TEST_MEMORY_SIZE);
+
+    let mut repcxls = multi_rcxl(2, vec![node_path]);
+    let mut replica = repcxls.remove(1);
+    let mut coordinator = repcxls.remove(0);
+    coordinator.new_object_with_val(1, 42).expect("Failed to create object");
+    let stats = coordinator.memory_stats();
+
+    // even when forced
+    replica.config.force_init = true;
+    let err = replica.init_state().expect_err("A replica should not initialize the state");
+    assert!(err.contains("Only the coordinator"), "Unexpected error: {}", err);
+
+    assert_eq!(coordinator.list_objects(), vec![1], "Existing state was modified");
+    assert_eq!(coordinator.memory_stats(), stats);
+    let report = coordinator.fsck().expect("fsck failed");
+    assert!(report.is_clean(), "Unexpected inconsistency: {:?}", report);
+    assert!(replica.get_object(1).is_some());
+
+    cleanup_tmpfs_file(node_path);
+}
+
 #[test]
 fn test_object_creation_and_allocation() {
     let node_path = "/dev/shm/repCXL_test_obj_create";
@@ -32,7 +80,7 @@ fn test_object_creation_and_allocation() {
     config.processes = vec![0];
 
     let mut rcxl = RepCXL::<u64>::new(config);
-    rcxl.init_state();
+    rcxl.init_state().expect("init_state failed");
     // Create multiple objects
     let obj1 = rcxl.new_object(1).expect("Failed to create object 1");
     let obj2 = rcxl.new_object(2).expect("Failed to create object 2");
@@ -81,15 +129,15 @@ fn test_object_lifecycle() {
     
 
     // only the coordinator (process with smallest ID) can create objects
-    assert!(repcxls[1].new_object(100).is_none(), 
+    assert_eq!(repcxls[1].new_object(100).unwrap_err(), AllocError::NotCoordinator,
         "Non-coordinator process should not be able to create objects");
 
     // Create object as coordinator
     let _obj = repcxls[0].new_object(100).expect("Failed to create object");
 
-    // re-creating object should return none
-    assert!(repcxls[0].new_object(100).is_none(), 
-        "Creating an object with an existing ID should return None");
+    // re-creating object should fail
+    assert_eq!(repcxls[0].new_object(100).unwrap_err(), AllocError::DuplicateId,
+        "Creating an object with an existing ID should fail");
     
     // Lookup object (simulates replica process)
     let found_obj = repcxls[1].get_object(100);
@@ -107,22 +155,828 @@ fn test_object_lifecycle() {
     cleanup_tmpfs_file(node_path);
 }
 
+#[test]
+fn test_read_removed_object() {
+    let node_path = "/dev/shm/repCXL_test_read_removed";
+    setup_tmpfs_file(node_path, TEST_MEMORY_SIZE);
+
+    let mut rcxl = single_rcxl(0, vec![node_path]);
+    rcxl.init_state().expect("init_state failed");
+    let removed = rcxl.new_object_with_val(1, 5).expect("Failed to create object");
+    // the allocations are cached until the state changes
+    assert!(matches!(rcxl.read_object(&removed), Ok(ReadReturn::ReadSafe(5))));
+    rcxl.remove_object(1);
+
+    // the new object takes the region of the removed one
+    let obj = rcxl.new_object_with_val(2, 9).expect("Failed to create object");
+    assert_eq!(obj.offset(), removed.offset());
+
+    assert!(matches!(rcxl.read_object(&removed), Ok(ReadReturn::ObjectRemoved)));
+    match rcxl.read_object(&obj).expect("Read should succeed") {
+        ReadReturn::ReadSafe(v) => assert_eq!(v, 9),
+        other => panic!("Single node read should be safe: {:?}", other),
+    }
+
+    cleanup_tmpfs_file(node_path);
+}
+
 #[test]
 fn test_object_limit() {
     let node_path = "/dev/shm/repCXL_test_limit";
     setup_tmpfs_file(node_path, TEST_MEMORY_SIZE);
 
     let mut rcxl = single_rcxl(0, vec![node_path]);
-    rcxl.init_state();
+    rcxl.init_state().expect("init_state failed");
 
     let max_objs = rep_cxl::shmem::MAX_OBJECTS;
 
     // Try to create more than MAX_OBJECTS (128)
     for i in 0..max_objs {
-        rcxl.new_object(i);
+        rcxl.new_object(i).expect("Failed to create object");
+    }
+
+    assert_eq!(rcxl.new_object(max_objs+1).unwrap_err(), AllocError::TooManyObjects,
+        "Creating an object beyond the maximum limit should fail");
+
+    cleanup_tmpfs_file(node_path);
+}
+
+#[test]
+fn test_alloc_errors() {
+    type Large = [u8; 512 * 1024];
+    let node_path = "/dev/shm/repCXL_test_alloc_errors";
+    setup_tmpfs_file(node_path, TEST_MEMORY_SIZE);
+
+    let mut config = test_config(vec![node_path]);
+    config.processes = vec![0];
+    let mut rcxl = RepCXL::<Large>::new(config);
+    rcxl.init_state().expect("init_state failed");
+
+    assert_eq!(rcxl.new_object(rep_cxl::shmem::MAX_OBJECTS).unwrap_err(), AllocError::InvalidId);
+
+    // the region only holds a few large objects
+    let mut id = 0;
+    let err = loop {
+        match rcxl.new_object(id) {
+            Ok(_) => id += 1,
+            Err(e) => break e,
+        }
+    };
+    assert!(id > 0, "No large object fits the region");
+    assert_eq!(err, AllocError::OutOfSpace);
+
+    cleanup_tmpfs_file(node_path);
+}
+
+#[test]
+fn test_state_checksum_fallback() {
+    let node_paths = vec![
+        "/dev/shm/repCXL_test_checksum1",
+        "/dev/shm/repCXL_test_checksum2",
+    ];
+    for path in &node_paths {
+        setup_tmpfs_file(path, TEST_MEMORY_SIZE);
+    }
+
+    let mut repcxls = multi_rcxl(2, node_paths.clone());
+    repcxls[0].new_object(7).expect("Failed to create object");
+
+    // tear the state on the first memory node
+    corrupt_tmpfs_file(node_paths[0], TEST_MEMORY_SIZE / 8);
+
+    // the replica detects the corrupted copy and reads the state from the
+    // second memory node
+    repcxls[1].config.state_checksum = true;
+    assert!(repcxls[1].get_object(7).is_some(), "Object should be found on the second node");
+
+    for path in &node_paths {
+        cleanup_tmpfs_file(path);
+    }
+}
+
+#[test]
+fn test_newest_state_is_read() {
+    let node_paths = vec![
+        "/dev/shm/repCXL_test_state_seq1",
+        "/dev/shm/repCXL_test_state_seq2",
+    ];
+    for path in &node_paths {
+        setup_tmpfs_file(path, TEST_MEMORY_SIZE);
+    }
+
+    let mut repcxls = multi_rcxl(2, node_paths.clone());
+    repcxls[0].new_object(7).expect("Failed to create object");
+
+    // a coordinator that only sees the second memory node creates a new
+    // object: the allocation table on the first node is now stale
+    let mut partial = single_rcxl(0, vec![node_paths[1]]);
+    partial.new_object(8).expect("Failed to create object");
+
+    // the replica must pick the newest allocation table regardless of
+    // the node order
+    assert!(repcxls[1].get_object(7).is_some(), "Object 7 should be found");
+    assert!(repcxls[1].get_object(8).is_some(), "Object 8 should be found in the newest state");
+
+    for path in &node_paths {
+        cleanup_tmpfs_file(path);
+    }
+}
+
+#[test]
+fn test_clear_objects() {
+    let node_path = "/dev/shm/repCXL_test_clear";
+    setup_tmpfs_file(node_path, TEST_MEMORY_SIZE);
+
+    let mut rcxl = single_rcxl(0, vec![node_path]);
+    rcxl.config.pipeline = true;
+    rcxl.init_state().expect("init_state failed");
+
+    let objs: Vec<_> = (0..3)
+        .map(|i| rcxl.new_object(i).expect("Failed to create object"))
+        .collect();
+    assert_eq!(rcxl.list_objects().len(), 3);
+    rcxl.sync_start().expect("sync_start failed");
+
+    // in-flight write is drained before clearing
+    let token = objs[1].try_write(42).expect("Failed to enqueue write");
+    rcxl.clear_objects(true).expect("clear_objects failed");
+    token.wait().expect("Write should complete before the clear");
+
+    assert!(rcxl.list_objects().is_empty(), "No object should be left");
+    let stats = rcxl.memory_stats();
+    assert_eq!(stats.allocated_size, 0);
+    assert_eq!(stats.num_objects, 0);
+
+    // the region can be reused and the old data was zeroed
+    let obj = rcxl.new_object(1).expect("Failed to create object after clear");
+    let read_val = rcxl.read_object(&obj).expect("Read should succeed");
+    assert!(matches!(read_val, ReadReturn::Uninitialized), "Read {:?}", read_val);
+    rcxl.stop();
+
+    cleanup_tmpfs_file(node_path);
+}
+
+#[test]
+fn test_algorithm_mismatch() {
+    let node_path = "/dev/shm/repCXL_test_algo_mismatch";
+    setup_tmpfs_file(node_path, TEST_MEMORY_SIZE);
+
+    // the coordinator publishes its algorithm when initializing the state
+    let mut repcxls = multi_rcxl(2, vec![node_path]);
+    let mut replica = repcxls.remove(1);
+    replica.config.algorithm = "fmonster".to_string();
+
+    let err = replica.sync_start().expect_err("sync_start should fail on algorithm mismatch");
+    assert!(err.contains("Algorithm mismatch"), "Unexpected error: {}", err);
+    assert!(err.contains("fmonster") && err.contains("monster"), "Error should name both algorithms: {}", err);
+
+    cleanup_tmpfs_file(node_path);
+}
+
+#[test]
+fn test_replica_adopts_round_time() {
+    let node_path = "/dev/shm/repCXL_test_round_time";
+    setup_tmpfs_file(node_path, TEST_MEMORY_SIZE);
+
+    // the coordinator publishes its round time when initializing the state
+    let mut repcxls = multi_rcxl(2, vec![node_path]);
+    let mut replica = repcxls.remove(1);
+    let mut coordinator = repcxls.remove(0);
+    replica.config.round_time = TEST_ROUND_TIME / 4;
+
+    std::thread::scope(|s| {
+        s.spawn(|| replica.sync_start().expect("replica sync_start failed"));
+        coordinator.sync_start().expect("coordinator sync_start failed");
+    });
+    assert_eq!(replica.config.round_time, TEST_ROUND_TIME);
+
+    // both processes count the same rounds
+    coordinator.wait_for_round(coordinator.current_round() + 10);
+    let (c, r) = (coordinator.current_round(), replica.current_round());
+    assert!(c.abs_diff(r) <= 1, "Rounds diverged: coordinator {}, replica {}", c, r);
+
+    coordinator.stop();
+    replica.stop();
+    cleanup_tmpfs_file(node_path);
+}
+
+#[test]
+fn test_plan_objects() {
+    let node_path = "/dev/shm/repCXL_test_plan";
+    setup_tmpfs_file(node_path, TEST_MEMORY_SIZE);
+
+    let mut rcxl = single_rcxl(0, vec![node_path]);
+    rcxl.init_state().expect("init_state failed");
+    for id in 0..4 {
+        rcxl.new_object(id).expect("failed to create object");
+    }
+    rcxl.remove_object(1);
+
+    let size = rcxl.object_size();
+    let ids = [1, 4, 2, 9999, 5];
+    let plan = rcxl.plan_objects(&ids.map(|id| (id, size))).expect("plan failed");
+    assert_eq!(rcxl.memory_stats().num_objects, 3, "plan must not create objects");
+
+    let created: Vec<_> = ids.iter().map(|&id| rcxl.new_object(id).map(|obj| obj.offset())).collect();
+    assert_eq!(plan, created);
+    assert_eq!(plan[2], Err(rep_cxl::AllocError::DuplicateId));
+    assert_eq!(plan[3], Err(rep_cxl::AllocError::InvalidId));
+
+    cleanup_tmpfs_file(node_path);
+}
+
+#[test]
+fn test_namespaces_share_region() {
+    let node_path = "/dev/shm/repCXL_test_namespace";
+    setup_tmpfs_file(node_path, TEST_MEMORY_SIZE);
+
+    let mut app_a = single_rcxl(0, vec![node_path]);
+    app_a.init_state().expect("init_state failed");
+    let obj_a = app_a.new_object_with_val(5, 11).expect("Failed to create object in default namespace");
+
+    // second application on the same region, does not re-init the state
+    let mut app_b = single_rcxl(0, vec![node_path]);
+    app_b.config.namespace = 1;
+    let obj_b = app_b.new_object_with_val(5, 22).expect("Same id in another namespace should not collide");
+
+    // but the id is still unique within a namespace
+    assert_eq!(app_b.new_object(5).unwrap_err(), AllocError::DuplicateId, "Duplicate id in the same namespace");
+
+    assert_eq!(app_a.list_objects(), vec![5]);
+    assert_eq!(app_b.list_objects(), vec![5]);
+    assert_eq!(app_a.memory_stats().num_objects, 2);
+
+    for (rcxl, obj, val) in [(&app_a, &obj_a, 11), (&app_b, &obj_b, 22)] {
+        match rcxl.read_object(obj).expect("Read should succeed") {
+            ReadReturn::ReadSafe(v) => assert_eq!(v, val),
+            other => panic!("Single node read should be safe: {:?}", other),
+        }
+    }
+
+    // get_object resolves the id in the instance's namespace
+    let obj_b2 = app_b.get_object(5).expect("Object should exist in namespace 1");
+    match app_b.read_object(&obj_b2).expect("Read should succeed") {
+        ReadReturn::ReadSafe(v) | ReadReturn::ReadDirty(v) => assert_eq!(v, 22),
+        ReadReturn::Uninitialized | ReadReturn::TooStale | ReadReturn::ObjectRemoved => panic!("Object was initialized with a value"),
+    }
+
+    cleanup_tmpfs_file(node_path);
+}
+
+#[test]
+fn test_migrate_object() {
+    let node_path = "/dev/shm/repCXL_test_migrate";
+    setup_tmpfs_file(node_path, TEST_MEMORY_SIZE);
+
+    let config = |id| {
+        let mut config = test_config(vec![node_path]);
+        config.id = id;
+        config.processes = vec![0, 1];
+        config
+    };
+    let mut old = RepCXL::<u32>::new(config(0));
+    old.init_state().expect("init_state failed");
+    old.new_object_with_val(3, 7).expect("Failed to create object");
+    let neighbor = old.new_object_with_val(4, 9).expect("Failed to create object");
+    old.new_object(5).expect("Failed to create object");
+
+    // only the coordinator migrates
+    let mut replica = RepCXL::<u32>::new(config(1));
+    replica.migrate_object(3, u64::from).expect_err("A replica should not migrate objects");
+
+    let convert = |v: u32| (v as u64) << 40;
+    old.migrate_object(3, convert).expect("Migration should succeed");
+    old.migrate_object(5, convert).expect("Migration should succeed");
+    let mut ids = old.list_objects();
+    ids.sort();
+    assert_eq!(ids, vec![3, 4, 5]);
+    old.migrate_object(6, convert).expect_err("Object 6 does not exist");
+
+    let mut new = RepCXL::<u64>::new(config(0));
+    let obj = new.get_object(3).expect("Migrated object should exist");
+    assert_eq!(obj.offset(), 3 * TEST_CHUNK_SIZE, "The new region should not overlap the old ones");
+    match new.read_object(&obj).expect("Read should succeed") {
+        ReadReturn::ReadSafe(v) => assert_eq!(v, 7 << 40),
+        other => panic!("Single node read should be safe: {:?}", other),
+    }
+    let never_written = new.get_object(5).expect("Migrated object should exist");
+    assert!(matches!(new.read_object(&never_written), Ok(ReadReturn::Uninitialized)));
+
+    match old.read_object(&neighbor).expect("Read should succeed") {
+        ReadReturn::ReadSafe(v) => assert_eq!(v, 9, "Neighboring object corrupted"),
+        other => panic!("Single node read should be safe: {:?}", other),
+    }
+
+    cleanup_tmpfs_file(node_path);
+}
+
+// A value filling exactly one chunk: the entry (value + wid) takes two chunks,
+// so the wid of a write must not spill into the neighboring object.
+#[test]
+fn test_chunk_sized_value_allocation() {
+    type ChunkValue = [u64; TEST_CHUNK_SIZE / 8];
+    let node_path = "/dev/shm/repCXL_test_chunk_sized";
+    setup_tmpfs_file(node_path, TEST_MEMORY_SIZE);
+
+    let mut config = test_config(vec![node_path]);
+    config.processes = vec![0];
+    let mut rcxl = RepCXL::<ChunkValue>::new(config);
+    rcxl.init_state().expect("init_state failed");
+
+    let obj_a = rcxl.new_object_with_val(0, [1; TEST_CHUNK_SIZE / 8]).expect("Failed to create object");
+    let obj_b = rcxl.new_object_with_val(1, [2; TEST_CHUNK_SIZE / 8]).expect("Failed to create object");
+    assert_eq!(rcxl.memory_stats().allocated_size, 2 * 2 * TEST_CHUNK_SIZE);
+
+    rcxl.sync_start().expect("sync_start failed");
+    rcxl.write_object(&obj_a, [3; TEST_CHUNK_SIZE / 8]).expect("Write should succeed");
+    rcxl.stop();
+
+    match rcxl.read_object(&obj_b).expect("Read should succeed") {
+        ReadReturn::ReadSafe(v) => assert_eq!(v, [2; TEST_CHUNK_SIZE / 8], "Neighboring object corrupted"),
+        other => panic!("Single node read should be safe: {:?}", other),
+    }
+    match rcxl.read_object(&obj_a).expect("Read should succeed") {
+        ReadReturn::ReadSafe(v) | ReadReturn::ReadDirty(v) => assert_eq!(v, [3; TEST_CHUNK_SIZE / 8]),
+        ReadReturn::Uninitialized | ReadReturn::TooStale | ReadReturn::ObjectRemoved => panic!("Object was written"),
+    }
+
+    cleanup_tmpfs_file(node_path);
+}
+
+#[test]
+fn test_sync_start_with_quorum() {
+    let node_path = "/dev/shm/repCXL_test_quorum";
+    setup_tmpfs_file(node_path, TEST_MEMORY_SIZE);
+
+    // process 2 is configured but absent at startup
+    let mut repcxls = multi_rcxl(3, vec![node_path]);
+    let mut late = repcxls.remove(2);
+    let mut replica = repcxls.remove(1);
+    let mut coordinator = repcxls.remove(0);
+    coordinator.config.min_ready = Some(2);
+    coordinator.config.ready_timeout = 200_000_000; // 200ms
+
+    let start = std::time::Instant::now();
+    std::thread::scope(|s| {
+        s.spawn(|| replica.sync_start().expect("replica sync_start failed"));
+        coordinator.sync_start().expect("Rounds should start with 2 out of 3 processes ready");
+    });
+    assert!(
+        start.elapsed() >= std::time::Duration::from_millis(200),
+        "Coordinator should wait for all processes until the timeout"
+    );
+
+    // the late process joins the running rounds
+    late.sync_start().expect("Late process should join");
+
+    coordinator.stop();
+    replica.stop();
+    late.stop();
+    cleanup_tmpfs_file(node_path);
+}
+
+#[test]
+fn test_sync_start_timeout() {
+    let node_path = "/dev/shm/repCXL_test_sync_timeout";
+    setup_tmpfs_file(node_path, TEST_MEMORY_SIZE);
+
+    // process 1 is configured but never becomes ready
+    let mut repcxls = multi_rcxl(2, vec![node_path]);
+    let mut coordinator = repcxls.remove(0);
+    coordinator.config.sync_timeout = 200_000_000; // 200ms
+
+    let err = coordinator.sync_start().expect_err("sync_start should time out");
+    assert!(err.contains("[1]"), "Missing process should be reported: {}", err);
+
+    cleanup_tmpfs_file(node_path);
+}
+
+#[test]
+fn test_reject_undersized_node() {
+    let node_path = "/dev/shm/repCXL_test_full_node";
+    let small_path = "/dev/shm/repCXL_test_small_node";
+    setup_tmpfs_file(node_path, TEST_MEMORY_SIZE);
+    setup_tmpfs_file(small_path, TEST_MEMORY_SIZE / 2);
+
+    let mut rcxl = single_rcxl(0, vec![node_path]);
+    let err = rcxl
+        .add_memory_node_from_file(small_path)
+        .expect_err("Undersized node should be rejected");
+    assert!(err.contains("smaller"), "Unexpected error: {}", err);
+
+    // a node of the configured size is accepted
+    let other_path = "/dev/shm/repCXL_test_other_node";
+    setup_tmpfs_file(other_path, TEST_MEMORY_SIZE);
+    rcxl.add_memory_node_from_file(other_path).expect("Node should be added");
+
+    cleanup_tmpfs_file(node_path);
+    cleanup_tmpfs_file(small_path);
+    cleanup_tmpfs_file(other_path);
+}
+
+// Two regions side by side in one backing file are independent nodes
+#[test]
+fn test_regions_in_one_file() {
+    let node_path = "/dev/shm/repCXL_test_file_regions";
+    setup_tmpfs_file(node_path, 2 * TEST_MEMORY_SIZE);
+
+    let mut regions = Vec::new();
+    for (i, file_offset) in [0, TEST_MEMORY_SIZE].into_iter().enumerate() {
+        let mut rcxl = single_rcxl(0, vec![]);
+        rcxl.add_memory_node_from_file_at(node_path, file_offset).expect("Region should be added");
+        rcxl.init_state().expect("init_state failed");
+        let obj = rcxl.new_object_with_val(3, 100 + i as u64).expect("Failed to create object");
+        regions.push((rcxl, obj));
     }
 
-    assert!(rcxl.new_object(max_objs+1).is_none(), "Creating an object beyond the maximum limit should return None");
+    for (i, (rcxl, obj)) in regions.iter().enumerate() {
+        assert_eq!(rcxl.memory_stats().num_objects, 1);
+        match rcxl.read_object(obj).expect("Read should succeed") {
+            ReadReturn::ReadSafe(v) => assert_eq!(v, 100 + i as u64, "Region {} overwritten", i),
+            other => panic!("Single node read should be safe: {:?}", other),
+        }
+    }
+
+    let mut rcxl = single_rcxl(0, vec![]);
+    let err = rcxl.add_memory_node_from_file_at(node_path, 4096).expect_err("Misaligned offset");
+    assert!(err.contains("aligned"), "Unexpected error: {}", err);
+    let err = rcxl
+        .add_memory_node_from_file_at(node_path, 2 * TEST_MEMORY_SIZE)
+        .expect_err("Region past the end of the file");
+    assert!(err.contains("smaller"), "Unexpected error: {}", err);
 
     cleanup_tmpfs_file(node_path);
 }
+
+// Two applications allocating on the same region at the same time: the state
+// lock must serialize the allocator updates so that none is lost.
+#[test]
+fn test_concurrent_object_creation() {
+    const OBJECTS: usize = 40;
+    let mut config = test_config(vec![]);
+    config.processes = vec![0];
+
+    let mut app_a = RepCXL::<u64>::new(config);
+    let heap = app_a.add_memory_node_heap();
+    app_a.init_state().expect("init_state failed");
+    let mut config = test_config(vec![]);
+    config.processes = vec![0];
+    config.namespace = 1;
+    let mut app_b = RepCXL::<u64>::new(config);
+    app_b.add_shared_memory_node_heap(heap).expect("failed to share heap node");
+
+    let create = |rcxl: &mut RepCXL<u64>, base: u64| {
+        (0..OBJECTS)
+            .map(|i| rcxl.new_object_with_val(i, base + i as u64).expect("Failed to create object"))
+            .collect::<Vec<_>>()
+    };
+    let (objs_a, objs_b) = std::thread::scope(|s| {
+        let a = s.spawn(|| create(&mut app_a, 1000));
+        let b = s.spawn(|| create(&mut app_b, 2000));
+        (a.join().unwrap(), b.join().unwrap())
+    });
+
+    assert_eq!(app_a.memory_stats().num_objects, 2 * OBJECTS, "Allocation lost");
+    for (rcxl, objs, base) in [(&app_a, &objs_a, 1000), (&app_b, &objs_b, 2000)] {
+        for (i, obj) in objs.iter().enumerate() {
+            match rcxl.read_object(obj).expect("Read should succeed") {
+                ReadReturn::ReadSafe(v) => assert_eq!(v, base + i as u64, "Object overwritten"),
+                other => panic!("Single node read should be safe: {:?}", other),
+            }
+        }
+    }
+}
+
+#[test]
+fn test_duplicate_process_id() {
+    let node_path = "/dev/shm/repCXL_test_duplicate_id";
+    setup_tmpfs_file(node_path, TEST_MEMORY_SIZE);
+
+    // two instances are configured with id 1
+    let mut repcxls = multi_rcxl(2, vec![node_path]);
+    let mut first = repcxls.remove(1);
+    let mut coordinator = repcxls.remove(0);
+    let mut second = single_rcxl(1, vec![node_path]);
+    second.register_process(0);
+    for rcxl in [&mut coordinator, &mut first, &mut second] {
+        rcxl.config.sync_timeout = 2_000_000_000; // 2s
+    }
+
+    let (first_res, second_res) = std::thread::scope(|s| {
+        let first = s.spawn(|| first.sync_start());
+        let second = s.spawn(|| second.sync_start());
+        (first.join().unwrap(), second.join().unwrap())
+    });
+    // the instance marked second fails, the other one sees the collision
+    for res in [first_res, second_res] {
+        let err = res.expect_err("Instances sharing an id should not start");
+        assert!(err.contains('1'), "Colliding id should be reported: {}", err);
+    }
+    let err = coordinator.sync_start().expect_err("Coordinator should detect the collision");
+    assert!(err.contains("[1]"), "Colliding id should be reported: {}", err);
+
+    cleanup_tmpfs_file(node_path);
+}
+
+#[test]
+fn test_states_report() {
+    let node_paths = vec!["/dev/shm/repCXL_test_states_report1", "/dev/shm/repCXL_test_states_report2"];
+    for path in &node_paths {
+        setup_tmpfs_file(path, TEST_MEMORY_SIZE);
+    }
+
+    let mut repcxls = multi_rcxl(2, node_paths.clone());
+    let before = repcxls[1].states_report();
+    assert_eq!(before.len(), 2);
+    assert!(before.iter().all(|r| r.objects.is_empty() && r.memory.num_objects == 0));
+
+    repcxls[0].new_object(7).expect("Failed to create object");
+
+    // the replica sees the object on every node
+    let report = repcxls[1].states_report();
+    assert_eq!(report.iter().map(|r| r.node_id).collect::<Vec<_>>(), vec![0, 1]);
+    for node in &report {
+        assert!(node.checksum_ok);
+        assert!(node.seq > before[node.node_id].seq, "seq not bumped on node {}", node.node_id);
+        assert_eq!(node.objects, vec![(0, 7)]);
+        assert_eq!(node.memory.num_objects, 1);
+        assert!(node.memory.allocated_size > 0);
+    }
+    assert_eq!(report[0].seq, report[1].seq);
+    repcxls[1].dump_states();
+
+    for path in &node_paths {
+        cleanup_tmpfs_file(path);
+    }
+}
+
+#[test]
+fn test_provision() {
+    let node_paths = vec!["/dev/shm/repCXL_test_provision1", "/dev/shm/repCXL_test_provision2"];
+    for path in &node_paths {
+        cleanup_tmpfs_file(path);
+    }
+    // a smaller leftover file is grown
+    setup_tmpfs_file(node_paths[1], 4096);
+
+    let mut config = test_config(node_paths.clone());
+    config.processes = vec![0];
+    RepCXL::<u64>::provision(&config).expect("provision failed");
+    let size = RepCXL::<u64>::required_node_size(&config);
+    for path in &node_paths {
+        assert_eq!(std::fs::metadata(path).unwrap().len() as usize, size);
+    }
+    // idempotent
+    RepCXL::<u64>::provision(&config).expect("second provision failed");
+
+    config.mem_nodes.truncate(1);
+    let mut rcxl = RepCXL::<u64>::new(config);
+    rcxl.add_memory_node_from_file(node_paths[1]).expect("Provisioned node should map");
+    rcxl.init_state().expect("init_state failed");
+    rcxl.new_object(1).expect("Failed to create object");
+
+    let mut bad = test_config(vec!["/dev/shm/repCXL_test_provision_missing/node"]);
+    assert!(RepCXL::<u64>::provision(&bad).is_err(), "Missing parent directory");
+    bad.mem_nodes = vec!["/dev/shm/repCXL_test_provision_small".into()];
+    bad.mem_size = 4096;
+    assert!(RepCXL::<u64>::provision(&bad).is_err(), "Region too small to map");
+
+    for path in &node_paths {
+        cleanup_tmpfs_file(path);
+    }
+}
+
+// The rep_init binary prepares the region for processes started afterwards
+#[test]
+fn test_rep_init_binary() {
+    let node_paths = vec!["/dev/shm/repCXL_test_rep_init0", "/dev/shm/repCXL_test_rep_init1"];
+    let config_path = "/tmp/repCXL_test_rep_init.toml";
+    for path in &node_paths {
+        cleanup_tmpfs_file(path);
+    }
+    let config = |id| {
+        let mut config = test_config(node_paths.clone());
+        config.id = id;
+        config.processes = vec![0, 1];
+        config
+    };
+    std::fs::write(config_path, config(1).to_toml()).expect("Failed to write config");
+    let rep_init = |force: bool| {
+        std::process::Command::new(env!("CARGO_BIN_EXE_rep_init"))
+            .args(["-c", config_path, "--force-init", &force.to_string()])
+            .output()
+            .expect("Failed to run rep_init")
+    };
+
+    // creates the node files
+    assert!(rep_init(false).status.success());
+    let mut coordinator = RepCXL::<u64>::new(config(0));
+    let obj = coordinator.new_object_with_val(1, 42).expect("Coordinator should start without init_state");
+
+    // a valid state is never overwritten by accident
+    let out = rep_init(false);
+    assert!(!out.status.success());
+    let log = String::from_utf8_lossy(&out.stdout) + String::from_utf8_lossy(&out.stderr);
+    assert!(log.contains("already holds a shared state"), "{}", log);
+
+    // a forced init drops the objects and their data
+    assert!(rep_init(true).status.success());
+    let mut replica = RepCXL::<u64>::new(config(1));
+    for report in replica.states_report() {
+        assert!(report.checksum_ok && report.seq > 0, "Node {} not initialized: {:?}", report.node_id, report);
+        assert_eq!(report.memory.num_objects, 0);
+    }
+    assert!(replica.get_object(1).is_none());
+    let recreated = coordinator.new_object(1).expect("Failed to create object");
+    assert_eq!(recreated.offset(), obj.offset());
+    assert!(matches!(coordinator.read_object(&recreated), Ok(ReadReturn::Uninitialized)));
+
+    for path in &node_paths {
+        cleanup_tmpfs_file(path);
+    }
+    let _ = std::fs::remove_file(config_path);
+}
+
+#[test]
+fn test_get_object_blocking() {
+    let node_path = "/dev/shm/repCXL_test_get_blocking";
+    setup_tmpfs_file(node_path, TEST_MEMORY_SIZE);
+
+    // the replica starts waiting before the coordinator initializes the state
+    let mut coordinator = single_rcxl(0, vec![node_path]);
+    let mut replica = single_rcxl(1, vec![node_path]);
+    let obj = std::thread::scope(|s| {
+        let waiter = s.spawn(|| replica.get_object_blocking(3, Duration::from_secs(5)));
+        std::thread::sleep(Duration::from_millis(50));
+        coordinator.init_state().expect("init_state failed");
+        coordinator.new_object_with_val(3, 9).expect("Failed to create object");
+        waiter.join().unwrap()
+    });
+    let obj = obj.expect("Object should be found once created");
+    assert!(matches!(replica.read_object(&obj), Ok(ReadReturn::ReadSafe(9))));
+
+    let err = replica.get_object_blocking(4, Duration::from_millis(50)).expect_err("Object 4 is never created");
+    assert!(err.contains("not created"), "Unexpected error: {}", err);
+
+    cleanup_tmpfs_file(node_path);
+}
+
+#[test]
+fn test_start_stop_stress() {
+    let node_path = "/dev/shm/repCXL_test_start_stop";
+    setup_tmpfs_file(node_path, TEST_MEMORY_SIZE);
+
+    for i in 0..20u64 {
+        let mut rcxl = single_rcxl(0, vec![node_path]);
+        rcxl.config.pipeline = true;
+        rcxl.config.startup_delay = 0;
+        rcxl.config.anti_entropy_interval = Some(1);
+        rcxl.config.audit_interval = Some(1);
+        rcxl.config.msync_interval_rounds = Some(1);
+        rcxl.config.force_init = true;
+        rcxl.init_state().expect("init_state failed");
+        let obj = rcxl.new_object(1).expect("failed to create object");
+        rcxl.sync_start().expect("sync_start failed");
+        obj.write(i).expect("Write should succeed");
+
+        // every worker, including the idle reader, acknowledges the stop
+        let start = std::time::Instant::now();
+        rcxl.stop();
+        assert!(start.elapsed() < Duration::from_secs(1), "Stop took {:?}", start.elapsed());
+        drop(obj);
+        drop(rcxl);
+    }
+
+    // the nodes were never written after being unmapped
+    let mut rcxl = single_rcxl(0, vec![node_path]);
+    let obj = rcxl.get_object(1).expect("Object should exist");
+    assert!(matches!(rcxl.read_object(&obj), Ok(ReadReturn::ReadSafe(19))));
+
+    cleanup_tmpfs_file(node_path);
+}
+
+#[test]
+fn test_object_too_large() {
+    type Huge = [u8; TEST_MEMORY_SIZE];
+    let node_path = "/dev/shm/repCXL_test_too_large";
+    setup_tmpfs_file(node_path, TEST_MEMORY_SIZE);
+
+    let mut config = test_config(vec![node_path]);
+    config.processes = vec![0];
+    let mut rcxl = RepCXL::<Huge>::new(config);
+    rcxl.init_state().expect("init_state failed");
+
+    // the region also holds the shared state, a node-sized object never fits
+    let err = rcxl.new_object(1).unwrap_err();
+    let AllocError::ObjectTooLarge { requested, max } = err else {
+        panic!("Unexpected error: {:?}", err);
+    };
+    assert_eq!(requested, rcxl.object_size());
+    assert!(max < TEST_MEMORY_SIZE, "The maximum should exclude the shared state");
+    assert_eq!(rcxl.memory_stats().num_objects, 0);
+
+    // planned objects are rejected the same way, without taking space
+    let plan = rcxl.plan_objects(&[(1, requested), (2, 64)]).expect("plan failed");
+    assert_eq!(plan[0], Err(err));
+    assert_eq!(plan[1], Ok(0));
+
+    cleanup_tmpfs_file(node_path);
+}
+
+// Each instance maps the node at its own address, the objects must still
+// resolve to the same offsets
+#[test]
+fn test_same_object_offsets_across_instances() {
+    let node_path = "/dev/shm/repCXL_test_offsets";
+    setup_tmpfs_file(node_path, TEST_MEMORY_SIZE);
+
+    let instance = |id: usize| {
+        let mut rcxl = single_rcxl(id, vec![node_path]);
+        rcxl.register_process((1 - id) as u32);
+        rcxl
+    };
+    let mut rcxl0 = instance(0);
+    rcxl0.init_state().expect("init_state failed");
+    let _ = rcxl0.new_object(1).expect("failed to create object");
+    let obj0 = rcxl0.new_object(2).expect("failed to create object");
+    let mut rcxl1 = instance(1);
+    let obj1 = rcxl1.get_object(2).expect("failed to get object");
+    assert_eq!(obj0.offset(), obj1.offset());
+    assert_ne!(obj0.offset(), 0, "Object 2 should follow object 1");
+
+    // both pass the layout self-check of sync_start
+    std::thread::scope(|s| {
+        s.spawn(|| rcxl0.sync_start().expect("sync_start failed"));
+        s.spawn(|| rcxl1.sync_start().expect("sync_start failed"));
+    });
+    rcxl0.write_object(&obj0, 7).expect("Write should succeed");
+    assert!(matches!(rcxl1.read_object(&obj1), Ok(ReadReturn::ReadSafe(7))));
+    rcxl0.stop();
+    rcxl1.stop();
+
+    cleanup_tmpfs_file(node_path);
+}
+
+#[test]
+fn test_new_object_auto_ids() {
+    let node_path = "/dev/shm/repCXL_test_auto_ids";
+    setup_tmpfs_file(node_path, TEST_MEMORY_SIZE);
+
+    let mut rcxl = single_rcxl(0, vec![node_path]);
+    rcxl.init_state().expect("init_state failed");
+    let ids: Vec<usize> = (0..3)
+        .map(|i| rcxl.new_object_auto(10 + i).expect("failed to create object").0)
+        .collect();
+    assert_eq!(ids, vec![0, 1, 2]);
+
+    // the smallest freed id is reused first
+    rcxl.remove_object(1);
+    let (id, obj) = rcxl.new_object_auto(42).expect("failed to create object");
+    assert_eq!(id, 1);
+    assert!(matches!(rcxl.read_object(&obj), Ok(ReadReturn::ReadSafe(42))));
+    assert_eq!(rcxl.new_object_auto(43).expect("failed to create object").0, 3);
+
+    // a replica finds the objects under the returned ids
+    let mut replica = single_rcxl(1, vec![node_path]);
+    let obj = replica.get_object(2).expect("Object 2 should exist");
+    assert!(matches!(replica.read_object(&obj), Ok(ReadReturn::ReadSafe(12))));
+
+    cleanup_tmpfs_file(node_path);
+}
+
+#[test]
+fn test_export_import_kv() {
+    let src_path = "/dev/shm/repCXL_test_export_kv";
+    let dst_path = "/dev/shm/repCXL_test_import_kv";
+    setup_tmpfs_file(src_path, TEST_MEMORY_SIZE);
+    setup_tmpfs_file(dst_path, TEST_MEMORY_SIZE);
+
+    let mut src = single_rcxl(0, vec![src_path]);
+    src.init_state().expect("init_state failed");
+    for (id, value) in [(3, 30), (5, 50), (9, 90)] {
+        src.new_object_with_val(id, value).expect("failed to create object");
+    }
+    let obj = src.get_object(5).expect("Object 5 should exist");
+    src.write_object(&obj, 55).expect("Write should succeed");
+    // never written, not exported
+    src.new_object(7).expect("failed to create object");
+
+    let mut dump = src.export_kv().expect("export failed");
+    dump.sort();
+    assert_eq!(dump.iter().map(|(id, _)| *id).collect::<Vec<_>>(), vec![3, 5, 9]);
+
+    let mut dst = single_rcxl(0, vec![dst_path]);
+    dst.init_state().expect("init_state failed");
+    dst.import_kv(&dump).expect("import failed");
+    for (id, value) in [(3, 30), (5, 55), (9, 90)] {
+        let obj = dst.get_object(id).expect("Imported object should exist");
+        assert!(matches!(dst.read_object(&obj), Ok(ReadReturn::ReadSafe(v)) if v == value), "Object {}", id);
+    }
+
+    // ids are not overwritten, values of another type are rejected
+    assert!(dst.import_kv(&dump[..1]).is_err());
+    assert!(dst.import_kv(&[(11, vec![0u8; 2])]).is_err());
+
+    cleanup_tmpfs_file(src_path);
+    cleanup_tmpfs_file(dst_path);
+}
@@ -2,16 +2,14 @@
 // causes e.g. expected conflict to not occur and similar unlucky events. Run with
 // at least 10ms round time or --test-threads=1 to reduce flakiness.
 use rep_cxl::request::ReadReturn;
+use rep_cxl::{AlgorithmInfo, ConflictOrder, RepCXL};
+use rep_cxl::config::FaultSpec;
 use rep_cxl::utils::ms_logger;
 use std::time::Duration;
 
 mod test_utils;
 use test_utils::*;
 
-fn wait_for_rounds(rounds: u32) {
-    std::thread::sleep(Duration::from_nanos(TEST_ROUND_TIME) * rounds);
-}
-
 // fn start_two_nodes_with
 /// Assert that a subsequence of states appears in order within the log file.
 pub fn check_state_transitions(actual: &Vec<String>, expected: &[&str]) -> bool {
@@ -52,16 +50,13 @@ fn test_rw_single_node() {
     let mut coordinator = repcxls.remove(0);
 
     std::thread::spawn(move || {
-        coordinator.sync_start();
+        coordinator.sync_start().expect("sync_start failed");
         let obj5 = coordinator.new_object(5).expect("failed to get obj with id 5");   
         let read_val = coordinator.read_object(&obj5).expect("Read should succeed");
         assert!(
-            matches!(read_val, ReadReturn::ReadSafe(_)),
-            "Read should return ReadSafe (single node)"
+            matches!(read_val, ReadReturn::Uninitialized),
+            "Read should return Uninitialized before the first write"
         );
-        if let ReadReturn::ReadSafe(v) = read_val {
-            assert_eq!(v, 0, "Initial value should be default (0)");
-        }    
     
         let result = coordinator.write_object(&obj5, val);
         assert!(result.is_ok(), "Write should succeed");
@@ -72,7 +67,7 @@ fn test_rw_single_node() {
 
     let mut replica = repcxls.remove(0);
     std::thread::spawn(move || {
-        replica.sync_start();
+        replica.sync_start().expect("sync_start failed");
 
         // wait for coordinator to finish
         std::thread::sleep(Duration::from_millis(100));
@@ -111,16 +106,13 @@ fn test_readsafe_multi_node() {
     let mut coordinator = repcxls.remove(0);
 
     std::thread::spawn(move || {
-        coordinator.sync_start();
+        coordinator.sync_start().expect("sync_start failed");
         let obj5 = coordinator.new_object(5).expect("failed to get obj with id 5");   
         let read_val = coordinator.read_object(&obj5).expect("Read should succeed");
         assert!(
-            matches!(read_val, ReadReturn::ReadSafe(_)),
-            "Read should return ReadSafe (single node)"
+            matches!(read_val, ReadReturn::Uninitialized),
+            "Read should return Uninitialized before the first write"
         );
-        if let ReadReturn::ReadSafe(v) = read_val {
-            assert_eq!(v, 0, "Initial value should be default (0)");
-        }    
     
         let result = coordinator.write_object(&obj5, val);
         assert!(result.is_ok(), "Write should succeed");
@@ -131,7 +123,7 @@ fn test_readsafe_multi_node() {
 
     let mut replica = repcxls.remove(0);
     std::thread::spawn(move || {
-        replica.sync_start();
+        replica.sync_start().expect("sync_start failed");
 
         // wait for coordinator to finish
         std::thread::sleep(Duration::from_millis(100));
@@ -172,7 +164,7 @@ fn test_readdirty() {
     // RepCXL instance 1: uses nodes 1 and 2
     let mut repcxl_a = single_rcxl(0, vec![node_paths[0], node_paths[1]]);
     repcxl_a.register_process(1);
-    repcxl_a.init_state();
+    repcxl_a.init_state().expect("init_state failed");
 
     // RepCXL instance 2: uses nodes 0 and 2
     let mut repcxl_b = single_rcxl(1, vec![node_paths[0], node_paths[2]]);
@@ -181,14 +173,14 @@ fn test_readdirty() {
 
 
     // a hacky way to simulate a write conflict using instances with different node sets.
-    // the current implementation, instance 2 reads the state from the first node only
-    // in the memorynode list, hence it is able to find the object. The
+    // the current implementation, instance 2 reads the newest state, which is
+    // on the first node in the memorynode list, hence it is able to find the object. The
     // object is not present in the second node, the process reads the initialized
     // value 0
 
     // Start both instances
     std::thread::spawn(move || {
-        repcxl_a.sync_start();
+        repcxl_a.sync_start().expect("sync_start failed");
         let obj_a = repcxl_a.new_object(7).expect("failed to create object");
 
         // Write from instance A (replicates to nodes 1 and 2)
@@ -198,7 +190,7 @@ fn test_readdirty() {
     });
 
     std::thread::spawn(move || {
-        repcxl_b.sync_start();
+        repcxl_b.sync_start().expect("sync_start failed");
 
         // wait for instance A to finish writing
         std::thread::sleep(Duration::from_millis(100));
@@ -235,28 +227,29 @@ fn test_states_single_write() {
 
 
     let mut rcxl = single_rcxl(0, vec![node_path]);
-    rcxl.init_state();
+    rcxl.init_state().expect("init_state failed");
     rcxl.enable_file_log(log_path);
 
     let obj = rcxl.new_object(1).expect("failed to create object");
     
 
-    rcxl.sync_start();
+    rcxl.sync_start().expect("sync_start failed");
 
     // Perform a single write and stop more than one round latency after to allow
     // the state machine to go back to the initial state (Try)
     let result = rcxl.write_object(&obj, 77);
-    wait_for_rounds(2);
+    rcxl.wait_for_round(rcxl.current_round() + 2);
     rcxl.stop();
 
 
     assert!(result.is_ok(), "Write should succeed");
 
-    // The log must contain the expected subsequence for a successful write:
-    //   Try (picks up the request)  →  Check  →  Replicate  →  Try (back to idle)
+    // The log must contain the expected subsequence for a successful write.
+    // Single process on a single node takes the fast path without Check:
+    //   Try (picks up the request)  →  Replicate  →  Try (back to idle)
     let states = ms_logger::MonsterStateLogger::new(log_path).read_monster_states();
     // println!("{:?}", states);
-    let correct_transition = check_state_transitions(&states, &["Try", "Check", "Replicate"]);
+    let correct_transition = check_state_transitions(&states, &["Try", "Replicate"]);
     // panic!();
     assert!(
         correct_transition,
@@ -285,7 +278,7 @@ fn test_states_write_conflict() {
     // init instance 1
     let mut rcxl0 = single_rcxl(0, vec![node_path]);
     rcxl0.register_process(1);
-    rcxl0.init_state();
+    rcxl0.init_state().expect("init_state failed");
     rcxl0.enable_file_log(log_path0);
 
     // init instance 2
@@ -297,14 +290,14 @@ fn test_states_write_conflict() {
 
     // conflicting writes from both instances
     std::thread::spawn(move || {
-        rcxl0.sync_start();
+        rcxl0.sync_start().expect("sync_start failed");
         let obj_coord = rcxl0.new_object(2).expect("failed to create object");
 
         let _ = rcxl0.write_object(&obj_coord, 88);
     });
 
     std::thread::spawn(move || {
-        rcxl1.sync_start();
+        rcxl1.sync_start().expect("sync_start failed");
         let obj_replica = rcxl1.get_object(2).expect("failed to get object");
         let _ = rcxl1.write_object(&obj_replica, 99);
         let replica_states = ms_logger::MonsterStateLogger::new(log_path1).read_monster_states();
@@ -347,7 +340,7 @@ fn test_states_write_conflict_then_error() {
     // init instance A (coordinator) with only the first memory node
     let mut rcxl0 = single_rcxl(0, vec![node_paths[0]]);
     rcxl0.register_process(1);
-    rcxl0.init_state();
+    rcxl0.init_state().expect("init_state failed");
     rcxl0.enable_file_log(log_path0);
 
     // init instance B (replica) with both memory nodes
@@ -358,14 +351,14 @@ fn test_states_write_conflict_then_error() {
 
     // conflicting writes from both instances
     std::thread::spawn(move || {
-        rcxl0.sync_start();
+        rcxl0.sync_start().expect("sync_start failed");
         let obj_coord = rcxl0.new_object(2).expect("failed to create object");
         let _ = rcxl0.write_object(&obj_coord, 88);
     });
 
     
     std::thread::spawn(move || {
-        rcxl1.sync_start();
+        rcxl1.sync_start().expect("sync_start failed");
     
         // sleep to make rcxl create the object but not too much to avoid missing
         // the conflict
@@ -412,3 +405,1079 @@ fn test_states_write_conflict_then_error() {
         cleanup_tmpfs_file(path);
     }
 }
+
+/// A write cancelled while still queued is never replicated, cancelling an
+/// acknowledged write fails
+#[test]
+fn test_cancel_queued_write() {
+    let node_path = "/dev/shm/repCXL_test_cancel";
+    setup_tmpfs_file(node_path, TEST_MEMORY_SIZE);
+
+    let mut rcxl = single_rcxl(0, vec![node_path]);
+    rcxl.config.pipeline = true;
+    rcxl.init_state().expect("init_state failed");
+    let obj = rcxl.new_object(1).expect("failed to create object");
+
+    // the worker is not running yet, the request stays queued
+    let token = obj.try_write(55).expect("failed to enqueue write");
+    assert_eq!(token.cancel(), Ok(()));
+
+    rcxl.sync_start().expect("sync_start failed");
+
+    // give the worker enough rounds to replicate the write if it was not
+    // skipped
+    rcxl.wait_for_round(rcxl.current_round() + 5);
+    let read_val = rcxl.read_object(&obj).expect("Read should succeed");
+    assert!(
+        matches!(read_val, ReadReturn::Uninitialized),
+        "Cancelled write should not be replicated, read {:?}",
+        read_val
+    );
+
+    let token = obj.try_write(77).expect("failed to enqueue write");
+    rcxl.wait_for_round(rcxl.current_round() + 5);
+    assert_eq!(token.cancel(), Err(rep_cxl::request::CancelError::AlreadyApplied));
+    token.wait().expect("Write should succeed");
+    rcxl.stop();
+
+    cleanup_tmpfs_file(node_path);
+}
+
+/// The write-complete callback is invoked once per acknowledged write
+#[test]
+fn test_write_complete_callback() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+
+    let node_path = "/dev/shm/repCXL_test_write_cb";
+    setup_tmpfs_file(node_path, TEST_MEMORY_SIZE);
+
+    let mut rcxl = single_rcxl(0, vec![node_path]);
+    rcxl.config.pipeline = true;
+    rcxl.init_state().expect("init_state failed");
+    let obj = rcxl.new_object(3).expect("failed to create object");
+
+    let calls = Arc::new(AtomicUsize::new(0));
+    let latencies = Arc::new(Mutex::new(Vec::new()));
+    let (c, l) = (calls.clone(), latencies.clone());
+    rcxl.on_write_complete(move |oid, lat| {
+        assert_eq!(oid, 3);
+        c.fetch_add(1, Ordering::Relaxed);
+        l.lock().unwrap().push(lat);
+    });
+
+    rcxl.sync_start().expect("sync_start failed");
+    for i in 0..5 {
+        obj.write(i).expect("Write should succeed");
+    }
+    rcxl.wait_for_round(rcxl.current_round() + 1); // the callback runs right after the ack
+    rcxl.stop();
+
+    assert_eq!(calls.load(Ordering::Relaxed), 5);
+    // a write takes at least one round (Try -> Check -> Replicate)
+    for lat in latencies.lock().unwrap().iter() {
+        assert!(*lat >= Duration::from_nanos(TEST_ROUND_TIME), "Implausible latency {:?}", lat);
+        assert!(*lat < Duration::from_secs(1), "Implausible latency {:?}", lat);
+    }
+
+    cleanup_tmpfs_file(node_path);
+}
+
+#[test]
+fn test_swap_returns_previous_value() {
+    let node_path = "/dev/shm/repCXL_test_swap";
+    setup_tmpfs_file(node_path, TEST_MEMORY_SIZE);
+
+    let mut rcxl = single_rcxl(0, vec![node_path]);
+    rcxl.config.pipeline = true;
+    rcxl.init_state().expect("init_state failed");
+    let obj = rcxl.new_object(1).expect("failed to create object");
+    rcxl.sync_start().expect("sync_start failed");
+
+    let first = obj.swap(5).expect("Swap should succeed");
+    assert_eq!(first, 0, "Fresh object should hold 0");
+    let second = obj.swap(10).expect("Swap should succeed");
+    assert_eq!(second, 5, "Swap should return the value written by the previous swap");
+
+    rcxl.stop();
+    cleanup_tmpfs_file(node_path);
+}
+
+// A single client finds the write worker idle, concurrent clients queue behind
+// each other's writes
+#[test]
+fn test_write_detailed_queue_wait_rounds() {
+    let node_path = "/dev/shm/repCXL_test_queue_wait";
+    setup_tmpfs_file(node_path, TEST_MEMORY_SIZE);
+    let clients = 8;
+
+    let mut rcxl = single_rcxl(0, vec![node_path]);
+    rcxl.config.pipeline = true;
+    rcxl.config.round_time = 50_000_000; // 50ms, longer than the scheduling delays
+    rcxl.init_state().expect("init_state failed");
+    let objects: Vec<_> = (0..clients)
+        .map(|i| rcxl.new_object(i).expect("failed to create object"))
+        .collect();
+    rcxl.sync_start().expect("sync_start failed");
+
+    // on a loaded machine the worker is descheduled once in a while
+    let mut single: Vec<u64> = (1..=5)
+        .map(|val| objects[0].write_detailed(val).expect("Write should succeed").queue_wait_rounds)
+        .collect();
+    single.sort();
+    assert!(single[2] <= 1, "Single client waited {:?} rounds", single);
+
+    let concurrent_max = std::thread::scope(|s| {
+        let handles: Vec<_> = objects.iter()
+            .map(|obj| s.spawn(move || {
+                (1..=3)
+                    .map(|val| obj.write_detailed(val).expect("Write should succeed").queue_wait_rounds)
+                    .max()
+                    .unwrap()
+            }))
+            .collect();
+        handles.into_iter().map(|h| h.join().unwrap()).max().unwrap()
+    });
+    // each write takes a few rounds, the last of 8 queued writes waits for 7
+    assert!(
+        concurrent_max >= clients as u64 && concurrent_max > single[4],
+        "Concurrent clients waited at most {} rounds, a single one {:?}", concurrent_max, single
+    );
+    rcxl.stop();
+
+    cleanup_tmpfs_file(node_path);
+}
+
+// Both instances enqueue a write to the same object before sync_start so that
+// the workers pick them up and Try in the first round. The loser waits for the
+// winner and takes more rounds.
+#[test]
+fn test_write_detailed_conflict_rounds() {
+    let node_path = "/dev/shm/repCXL_test_write_detailed";
+    setup_tmpfs_file(node_path, TEST_MEMORY_SIZE);
+
+    let mut rcxl0 = single_rcxl(0, vec![node_path]);
+    rcxl0.register_process(1);
+    rcxl0.config.pipeline = true;
+    rcxl0.init_state().expect("init_state failed");
+    let obj0 = rcxl0.new_object(4).expect("failed to create object");
+
+    let mut rcxl1 = single_rcxl(1, vec![node_path]);
+    rcxl1.register_process(0);
+    rcxl1.config.pipeline = true;
+    let obj1 = rcxl1.get_object(4).expect("failed to get object");
+
+    let (ack0, ack1) = std::thread::scope(|s| {
+        let h0 = s.spawn(|| obj0.write_detailed(1).expect("Write should succeed"));
+        let h1 = s.spawn(|| obj1.write_detailed(2).expect("Write should succeed"));
+        s.spawn(|| rcxl0.sync_start().expect("sync_start failed"));
+        s.spawn(|| rcxl1.sync_start().expect("sync_start failed"));
+        (h0.join().unwrap(), h1.join().unwrap())
+    });
+    rcxl0.stop();
+    rcxl1.stop();
+
+    assert!(ack0.success && ack1.success);
+    let loser = if ack0.rounds_taken > ack1.rounds_taken { ack0 } else { ack1 };
+    let winner = if ack0.rounds_taken > ack1.rounds_taken { ack1 } else { ack0 };
+    assert!(loser.rounds_taken > 1, "Conflicting write took {} rounds", loser.rounds_taken);
+    assert!(
+        loser.rounds_taken > winner.rounds_taken,
+        "No conflict: both writes took {} rounds",
+        winner.rounds_taken
+    );
+
+    cleanup_tmpfs_file(node_path);
+}
+
+// The loser of a conflict learns from its ack which process won it
+#[test]
+fn test_conflict_names_winner() {
+    let node_path = "/dev/shm/repCXL_test_conflict_winner";
+    setup_tmpfs_file(node_path, TEST_MEMORY_SIZE);
+
+    let mut rcxl0 = single_rcxl(0, vec![node_path]);
+    rcxl0.register_process(1);
+    rcxl0.config.pipeline = true;
+    rcxl0.init_state().expect("init_state failed");
+    let obj0 = rcxl0.new_object(4).expect("failed to create object");
+
+    let mut rcxl1 = single_rcxl(1, vec![node_path]);
+    rcxl1.register_process(0);
+    rcxl1.config.pipeline = true;
+    let obj1 = rcxl1.get_object(4).expect("failed to get object");
+
+    let (ack0, ack1) = std::thread::scope(|s| {
+        let h0 = s.spawn(|| obj0.write_detailed(1).expect("Write should succeed"));
+        let h1 = s.spawn(|| obj1.write_detailed(2).expect("Write should succeed"));
+        s.spawn(|| rcxl0.sync_start().expect("sync_start failed"));
+        s.spawn(|| rcxl1.sync_start().expect("sync_start failed"));
+        (h0.join().unwrap(), h1.join().unwrap())
+    });
+    rcxl0.stop();
+    rcxl1.stop();
+
+    let (loser_pid, loser, winner) = if ack0.conflict.is_some() { (0, ack0, ack1) } else { (1, ack1, ack0) };
+    let info = loser.conflict.expect("No conflict reported");
+    assert_eq!(info.winner_pid, 1 - loser_pid, "Conflict should name the other process");
+    assert_eq!(info.winner_wid.process_id, info.winner_pid);
+    assert!(winner.conflict.is_none(), "Winner reported a conflict: {:?}", winner.conflict);
+
+    cleanup_tmpfs_file(node_path);
+}
+
+// Both writes of a conflict report it, the winner included
+#[test]
+fn test_winner_reports_conflicted() {
+    let node_path = "/dev/shm/repCXL_test_conflicted";
+    setup_tmpfs_file(node_path, TEST_MEMORY_SIZE);
+
+    let mut rcxl0 = single_rcxl(0, vec![node_path]);
+    rcxl0.register_process(1);
+    rcxl0.config.pipeline = true;
+    rcxl0.init_state().expect("init_state failed");
+    let obj0 = rcxl0.new_object(4).expect("failed to create object");
+
+    let mut rcxl1 = single_rcxl(1, vec![node_path]);
+    rcxl1.register_process(0);
+    rcxl1.config.pipeline = true;
+    let obj1 = rcxl1.get_object(4).expect("failed to get object");
+
+    let (ack0, ack1) = std::thread::scope(|s| {
+        let h0 = s.spawn(|| obj0.write_detailed(1).expect("Write should succeed"));
+        let h1 = s.spawn(|| obj1.write_detailed(2).expect("Write should succeed"));
+        s.spawn(|| rcxl0.sync_start().expect("sync_start failed"));
+        s.spawn(|| rcxl1.sync_start().expect("sync_start failed"));
+        (h0.join().unwrap(), h1.join().unwrap())
+    });
+
+    let winner = if ack0.conflict.is_some() { ack1 } else { ack0 };
+    assert!(winner.conflict.is_none(), "Both writes lost the conflict");
+    assert!(winner.conflicted, "Winner did not report the conflict: {:?}", winner);
+    assert!(ack0.conflicted && ack1.conflicted);
+    assert_eq!(winner.retries, 0, "Winner retried");
+
+    // no contender anymore
+    let ack = obj0.write_detailed(3).expect("Write should succeed");
+    assert!(!ack.conflicted, "Uncontended write reported a conflict: {:?}", ack);
+    rcxl0.stop();
+    rcxl1.stop();
+
+    cleanup_tmpfs_file(node_path);
+}
+
+// A write to an object another process keeps writing needs at least a Try,
+// a Check and a Replicate round: with a deadline two rounds away the worker
+// abandons it before replicating, a write with a loose deadline goes through
+#[test]
+fn test_write_deadline_exceeded() {
+    let node_path = "/dev/shm/repCXL_test_write_deadline";
+    setup_tmpfs_file(node_path, TEST_MEMORY_SIZE);
+    let round_time = Duration::from_millis(50);
+
+    let mut rcxl0 = single_rcxl(0, vec![node_path]);
+    rcxl0.register_process(1);
+    rcxl0.config.pipeline = true;
+    rcxl0.config.round_time = round_time.as_nanos() as u64;
+    rcxl0.init_state().expect("init_state failed");
+    let obj0 = rcxl0.new_object(4).expect("failed to create object");
+
+    let mut rcxl1 = single_rcxl(1, vec![node_path]);
+    rcxl1.register_process(0);
+    rcxl1.config.pipeline = true;
+    rcxl1.config.round_time = round_time.as_nanos() as u64;
+    let obj1 = rcxl1.get_object(4).expect("failed to get object");
+
+    let done = std::sync::atomic::AtomicBool::new(false);
+    std::thread::scope(|s| {
+        s.spawn(|| rcxl0.sync_start().expect("sync_start failed"));
+        rcxl1.sync_start().expect("sync_start failed");
+        s.spawn(|| {
+            let mut val = 1;
+            while !done.load(std::sync::atomic::Ordering::Relaxed) {
+                obj1.write(val).expect("Write should succeed");
+                val += 1;
+            }
+        });
+
+        let start = std::time::Instant::now();
+        let err = obj0
+            .write_deadline(1000, start + 2 * round_time)
+            .expect_err("Write should miss its deadline");
+        assert!(err.starts_with("DeadlineExceeded"), "Unexpected error: {}", err);
+        // abandoned in the first round after the deadline
+        assert!(start.elapsed() < 4 * round_time, "Write abandoned after {:?}", start.elapsed());
+        obj0.write_deadline(2000, std::time::Instant::now() + 40 * round_time)
+            .expect("Write with a loose deadline should succeed");
+        done.store(true, std::sync::atomic::Ordering::Relaxed);
+    });
+    rcxl0.stop();
+    rcxl1.stop();
+
+    cleanup_tmpfs_file(node_path);
+}
+
+// Both processes switch from best-effort to MONSTER at the same round while
+// writes are queued, every write is applied
+#[test]
+fn test_switch_algorithm_keeps_queued_writes() {
+    let node_path = "/dev/shm/repCXL_test_switch_algorithm";
+    setup_tmpfs_file(node_path, TEST_MEMORY_SIZE);
+
+    let instance = |id: usize| {
+        let mut rcxl = single_rcxl(id, vec![node_path]);
+        rcxl.register_process((1 - id) as u32);
+        rcxl.config.algorithm = "async_best_effort".to_string();
+        rcxl.config.pipeline = true;
+        rcxl.config.max_inflight_writes = 64;
+        rcxl
+    };
+    let mut rcxl0 = instance(0);
+    rcxl0.init_state().expect("init_state failed");
+    let obj0 = rcxl0.new_object(1).expect("failed to create object");
+    let _ = rcxl0.new_object(2).expect("failed to create object");
+    let mut rcxl1 = instance(1);
+    let obj1 = rcxl1.get_object(2).expect("failed to get object");
+    std::thread::scope(|s| {
+        s.spawn(|| rcxl0.sync_start().expect("sync_start failed"));
+        s.spawn(|| rcxl1.sync_start().expect("sync_start failed"));
+    });
+
+    let writes = 40;
+    let at_round = rcxl0.current_round() + 5;
+    std::thread::scope(|s| {
+        for (rcxl, obj) in [(&mut rcxl0, &obj0), (&mut rcxl1, &obj1)] {
+            s.spawn(move || {
+                let mut tokens = Vec::new();
+                for val in 1..=writes / 2 {
+                    tokens.push(obj.try_write(val).expect("try_write failed"));
+                }
+                rcxl.switch_algorithm("monster", at_round).expect("switch_algorithm failed");
+                assert!(rcxl.current_round() >= at_round);
+                for val in writes / 2 + 1..=writes {
+                    tokens.push(obj.try_write(val).expect("try_write failed"));
+                }
+                for token in tokens {
+                    token.wait().expect("Write should succeed");
+                }
+            });
+        }
+    });
+
+    for (rcxl, obj) in [(&rcxl0, &obj0), (&rcxl1, &obj1)] {
+        assert!(matches!(rcxl.algorithm_info(), AlgorithmInfo::Monster { .. }));
+        assert!(matches!(rcxl.read_object(obj), Ok(ReadReturn::ReadSafe(v)) if v == writes));
+        assert_eq!(obj.check_continuity(), Ok(0), "Writes were lost");
+    }
+    // the workers run MONSTER, whose writes take several rounds
+    let ack = obj0.write_detailed(writes + 1).expect("Write should succeed");
+    assert!(ack.rounds_taken >= 2, "Write not applied by MONSTER: {:?}", ack);
+    assert!(rcxl1.switch_algorithm("monster", 0).is_err(), "Switched at a past round");
+    assert!(rcxl1.switch_algorithm("paxos", rcxl1.current_round() + 1).is_err());
+    rcxl0.stop();
+    rcxl1.stop();
+
+    cleanup_tmpfs_file(node_path);
+}
+
+// Two processes writing the same object as soon as the rounds start conflict
+// less often when their startup is jittered
+#[test]
+fn test_startup_jitter_spreads_first_writes() {
+    let node_path = "/dev/shm/repCXL_test_startup_jitter";
+    let runs = 6;
+
+    let first_round_conflicts = |jitter: u64| -> usize {
+        let mut conflicts = 0;
+        for _ in 0..runs {
+            setup_tmpfs_file(node_path, TEST_MEMORY_SIZE);
+            let instance = |id: usize| {
+                let mut rcxl = single_rcxl(id, vec![node_path]);
+                rcxl.register_process((1 - id) as u32);
+                rcxl.config.pipeline = true;
+                rcxl.config.startup_delay = 0;
+                rcxl.config.startup_jitter = jitter;
+                rcxl
+            };
+            let mut rcxl0 = instance(0);
+            rcxl0.init_state().expect("init_state failed");
+            let obj0 = rcxl0.new_object(1).expect("failed to create object");
+            let mut rcxl1 = instance(1);
+            let obj1 = rcxl1.get_object(1).expect("failed to get object");
+
+            let (ack0, ack1) = std::thread::scope(|s| {
+                let h0 = s.spawn(|| obj0.write_detailed(1).expect("Write should succeed"));
+                let h1 = s.spawn(|| obj1.write_detailed(2).expect("Write should succeed"));
+                s.spawn(|| rcxl0.sync_start().expect("sync_start failed"));
+                s.spawn(|| rcxl1.sync_start().expect("sync_start failed"));
+                (h0.join().unwrap(), h1.join().unwrap())
+            });
+            conflicts += ack0.conflicted as usize + ack1.conflicted as usize;
+            rcxl0.stop();
+            rcxl1.stop();
+            cleanup_tmpfs_file(node_path);
+        }
+        conflicts
+    };
+
+    let without = first_round_conflicts(0);
+    // up to 10 rounds late
+    let with = first_round_conflicts(10 * TEST_ROUND_TIME);
+    assert!(with < without, "Jitter did not reduce the conflicts: {} with, {} without", with, without);
+}
+
+// Conflicts lost by a writer are published in the shared state, visible to
+// any process attached to the memory nodes
+#[test]
+fn test_conflict_report_shared() {
+    let node_paths = vec!["/dev/shm/repCXL_test_conflict_report0", "/dev/shm/repCXL_test_conflict_report1"];
+    for path in &node_paths {
+        setup_tmpfs_file(path, TEST_MEMORY_SIZE);
+    }
+
+    let mut rcxl0 = single_rcxl(0, node_paths.clone());
+    rcxl0.register_process(1);
+    rcxl0.config.pipeline = true;
+    rcxl0.init_state().expect("init_state failed");
+    let obj0 = rcxl0.new_object(7).expect("failed to create object");
+
+    let mut rcxl1 = single_rcxl(1, node_paths.clone());
+    rcxl1.register_process(0);
+    rcxl1.config.pipeline = true;
+    let obj1 = rcxl1.get_object(7).expect("failed to get object");
+
+    let (ack0, ack1) = std::thread::scope(|s| {
+        let h0 = s.spawn(|| obj0.write_detailed(1).expect("Write should succeed"));
+        let h1 = s.spawn(|| obj1.write_detailed(2).expect("Write should succeed"));
+        s.spawn(|| rcxl0.sync_start().expect("sync_start failed"));
+        s.spawn(|| rcxl1.sync_start().expect("sync_start failed"));
+        (h0.join().unwrap(), h1.join().unwrap())
+    });
+    let loser = if ack0.conflict.is_some() { 0 } else { 1 };
+    assert!(ack0.conflict.is_some() || ack1.conflict.is_some(), "No write lost the conflict");
+    rcxl0.stop();
+    rcxl1.stop();
+
+    // a monitoring process that never wrote
+    let monitor = single_rcxl(2, node_paths.clone());
+    let report = monitor.conflict_report().expect("failed to read the conflict report");
+    assert!(report.total > 0, "No conflict published: {:?}", report);
+    assert!(report.per_process.iter().any(|&(pid, n)| pid == loser && n > 0), "{:?}", report);
+    let obj = report.objects.iter().find(|o| o.id == 7).expect("object not tracked");
+    assert!(obj.conflicts > 0 && obj.last_round > 0, "{:?}", obj);
+
+    // recorded on every node, the report survives the loss of the master
+    let backup = single_rcxl(2, vec![node_paths[1]]);
+    assert_eq!(backup.conflict_report().expect("failed to read the conflict report"), report);
+
+    for path in &node_paths {
+        cleanup_tmpfs_file(path);
+    }
+}
+
+// Pushing past the capacity of a ring replaces its oldest values, on every
+// node alike
+#[test]
+fn test_ring_overwrites_oldest() {
+    let node_paths = vec!["/dev/shm/repCXL_test_ring0", "/dev/shm/repCXL_test_ring1"];
+    for path in &node_paths {
+        setup_tmpfs_file(path, TEST_MEMORY_SIZE);
+    }
+    let capacity = 4;
+
+    let mut rcxl = single_rcxl(0, node_paths.clone());
+    rcxl.config.pipeline = true;
+    rcxl.init_state().expect("init_state failed");
+    let ring = rcxl.new_ring(1, capacity).expect("failed to create ring");
+    assert_eq!(ring.capacity(), capacity);
+    rcxl.sync_start().expect("sync_start failed");
+
+    assert!(ring.read_all().expect("Read should succeed").is_empty());
+    for val in 1..=capacity as u64 + 2 {
+        ring.push(val).expect("Push should succeed");
+    }
+    assert_eq!(ring.read_all().expect("Read should succeed"), vec![3, 4, 5, 6]);
+    assert!(rcxl.get_ring(1, capacity + 1).is_none());
+    rcxl.stop();
+
+    // each node on its own holds the same ring
+    for path in &node_paths {
+        let mut node = single_rcxl(0, vec![path]);
+        let ring = node.get_ring(1, capacity).expect("failed to get ring");
+        assert_eq!(ring.read_all().expect("Read should succeed"), vec![3, 4, 5, 6], "Node {}", path);
+    }
+
+    for path in &node_paths {
+        cleanup_tmpfs_file(path);
+    }
+}
+
+// Concurrent pushes conflict on the ring, the loser retries instead of
+// replacing the entry of the winner
+#[test]
+fn test_ring_concurrent_pushes() {
+    let node_path = "/dev/shm/repCXL_test_ring_conflict";
+    setup_tmpfs_file(node_path, TEST_MEMORY_SIZE);
+
+    let mut rcxl0 = single_rcxl(0, vec![node_path]);
+    rcxl0.register_process(1);
+    rcxl0.config.pipeline = true;
+    rcxl0.init_state().expect("init_state failed");
+    let ring0 = rcxl0.new_ring(2, 4).expect("failed to create ring");
+
+    let mut rcxl1 = single_rcxl(1, vec![node_path]);
+    rcxl1.register_process(0);
+    rcxl1.config.pipeline = true;
+    let ring1 = rcxl1.get_ring(2, 4).expect("failed to get ring");
+
+    std::thread::scope(|s| {
+        s.spawn(|| ring0.push(10).expect("Push should succeed"));
+        s.spawn(|| ring1.push(20).expect("Push should succeed"));
+        s.spawn(|| rcxl0.sync_start().expect("sync_start failed"));
+        s.spawn(|| rcxl1.sync_start().expect("sync_start failed"));
+    });
+    let mut values = ring0.read_all().expect("Read should succeed");
+    values.sort();
+    assert_eq!(values, vec![10, 20], "A push was lost");
+    rcxl0.stop();
+    rcxl1.stop();
+
+    cleanup_tmpfs_file(node_path);
+}
+
+// Concurrent increments of two processes all take effect
+#[test]
+fn test_counter_concurrent_increments() {
+    let node_path = "/dev/shm/repCXL_test_counter";
+    setup_tmpfs_file(node_path, TEST_MEMORY_SIZE);
+    let increments = 1000;
+
+    let instance = |id: usize| {
+        let mut config = test_config(vec![node_path]);
+        config.id = id as i32;
+        config.processes = vec![0, 1];
+        config.pipeline = true;
+        config.round_time = 1_000_000; // 1ms, thousands of increments
+        RepCXL::<u64>::new(config)
+    };
+    let mut rcxl0 = instance(0);
+    rcxl0.init_state().expect("init_state failed");
+    let counter0 = rcxl0.new_counter(3).expect("failed to create counter");
+    let mut rcxl1 = instance(1);
+    let counter1 = rcxl1.get_counter(3).expect("failed to get counter");
+
+    std::thread::scope(|s| {
+        s.spawn(|| rcxl0.sync_start().expect("sync_start failed"));
+        s.spawn(|| rcxl1.sync_start().expect("sync_start failed"));
+    });
+    std::thread::scope(|s| {
+        for counter in [&counter0, &counter1] {
+            s.spawn(move || {
+                for _ in 0..increments {
+                    counter.increment().expect("Increment should succeed");
+                }
+            });
+        }
+    });
+    assert_eq!(counter0.get().expect("Read should succeed"), 2 * increments);
+    assert_eq!(counter1.get().expect("Read should succeed"), 2 * increments);
+
+    // wraps around
+    assert_eq!(counter0.add(u64::MAX).expect("Add should succeed"), 2 * increments - 1);
+    rcxl0.stop();
+    rcxl1.stop();
+
+    cleanup_tmpfs_file(node_path);
+}
+
+// With the timestamp conflict order the write with the latest client
+// timestamp wins, whatever its round or pid
+#[test]
+fn test_timestamp_conflict_order() {
+    let node_path = "/dev/shm/repCXL_test_timestamp_order";
+    setup_tmpfs_file(node_path, TEST_MEMORY_SIZE);
+
+    let mut rcxl0 = single_rcxl(0, vec![node_path]);
+    rcxl0.register_process(1);
+    rcxl0.config.pipeline = true;
+    rcxl0.config.conflict_order = "timestamp".to_string();
+    rcxl0.init_state().expect("init_state failed");
+    let obj0 = rcxl0.new_object(4).expect("failed to create object");
+
+    let mut rcxl1 = single_rcxl(1, vec![node_path]);
+    rcxl1.register_process(0);
+    rcxl1.config.pipeline = true;
+    rcxl1.config.conflict_order = "timestamp".to_string();
+    let obj1 = rcxl1.get_object(4).expect("failed to get object");
+
+    // concurrent writes: the later timestamp of process 1 wins
+    std::thread::scope(|s| {
+        s.spawn(|| obj0.write_at(10, 100).expect("Write should succeed"));
+        s.spawn(|| obj1.write_at(11, 200).expect("Write should succeed"));
+        s.spawn(|| rcxl0.sync_start().expect("sync_start failed"));
+        s.spawn(|| rcxl1.sync_start().expect("sync_start failed"));
+    });
+    match rcxl0.read_object(&obj0).expect("Read should succeed") {
+        ReadReturn::ReadSafe(v) => assert_eq!(v, 11, "The earlier write won"),
+        other => panic!("Unexpected read: {:?}", other),
+    }
+
+    // a write in a later round with an earlier timestamp is ordered before
+    obj0.write_at(12, 150).expect("Write should succeed");
+    match rcxl1.read_object(&obj1).expect("Read should succeed") {
+        ReadReturn::ReadSafe(v) => assert_eq!(v, 11, "The earlier write won"),
+        other => panic!("Unexpected read: {:?}", other),
+    }
+    obj0.write_at(13, 300).expect("Write should succeed");
+    match rcxl1.read_object(&obj1).expect("Read should succeed") {
+        ReadReturn::ReadSafe(v) => assert_eq!(v, 13),
+        other => panic!("Unexpected read: {:?}", other),
+    }
+
+    rcxl0.stop();
+    rcxl1.stop();
+    cleanup_tmpfs_file(node_path);
+}
+
+// A single process on a single node cannot conflict: writes go straight from
+// Try to Replicate. A second process disables the fast path.
+#[test]
+fn test_single_writer_fast_path() {
+    let node_path = "/dev/shm/repCXL_test_fast_path";
+    let log_path = "/tmp/repcxl_fast_path.log";
+    setup_tmpfs_file(node_path, TEST_MEMORY_SIZE);
+
+    let mut rcxl = single_rcxl(0, vec![node_path]);
+    rcxl.init_state().expect("init_state failed");
+    rcxl.enable_file_log(log_path);
+    let obj = rcxl.new_object(1).expect("failed to create object");
+    rcxl.sync_start().expect("sync_start failed");
+
+    for i in 0..3 {
+        rcxl.write_object(&obj, i).expect("Write should succeed");
+        let states = ms_logger::MonsterStateLogger::new(log_path).read_monster_states();
+        assert!(
+            !states.iter().any(|s| s == "Check" || s == "Wait"),
+            "Single writer should skip conflict resolution: {}",
+            states.join(" -> ")
+        );
+    }
+    rcxl.stop();
+    drop(rcxl);
+
+    // same node, two processes
+    setup_tmpfs_file(node_path, TEST_MEMORY_SIZE);
+    let mut repcxls = multi_rcxl(2, vec![node_path]);
+    let mut replica = repcxls.remove(1);
+    let mut coordinator = repcxls.remove(0);
+    coordinator.enable_file_log(log_path);
+    let obj = coordinator.new_object(1).expect("failed to create object");
+
+    std::thread::scope(|s| {
+        s.spawn(|| replica.sync_start().expect("sync_start failed"));
+        coordinator.sync_start().expect("sync_start failed");
+    });
+    coordinator.write_object(&obj, 5).expect("Write should succeed");
+    coordinator.stop();
+    replica.stop();
+
+    let states = ms_logger::MonsterStateLogger::new(log_path).read_monster_states();
+    assert!(
+        check_state_transitions(&states, &["Try", "Check", "Replicate"]),
+        "Two processes should go through Check: {}",
+        states.join(" -> ")
+    );
+
+    cleanup_tmpfs_file(node_path);
+}
+
+// With single_writer, writes skip conflict resolution even with several
+// processes and memory nodes, but still reach every node
+#[test]
+fn test_single_writer_skips_check() {
+    let node_paths = vec!["/dev/shm/repCXL_test_single_writer_0", "/dev/shm/repCXL_test_single_writer_1"];
+    let log_path = "/tmp/repcxl_single_writer.log";
+    for path in &node_paths {
+        setup_tmpfs_file(path, TEST_MEMORY_SIZE);
+    }
+
+    let mut repcxls: Vec<RepCXL<u64>> = (0..2).map(|i| {
+        let mut config = test_config(node_paths.clone());
+        config.id = i;
+        config.processes = vec![0, 1];
+        config.single_writer = true;
+        RepCXL::new(config)
+    }).collect();
+    let mut replica = repcxls.remove(1);
+    let mut coordinator = repcxls.remove(0);
+    coordinator.init_state().expect("init_state failed");
+    coordinator.enable_file_log(log_path);
+    let obj = coordinator.new_object(1).expect("failed to create object");
+    let replica_obj = replica.get_object(1).expect("failed to get object");
+
+    std::thread::scope(|s| {
+        s.spawn(|| replica.sync_start().expect("sync_start failed"));
+        coordinator.sync_start().expect("sync_start failed");
+    });
+
+    for i in 0..3 {
+        coordinator.write_object(&obj, i).expect("Write should succeed");
+        let states = ms_logger::MonsterStateLogger::new(log_path).read_monster_states();
+        assert!(
+            !states.iter().any(|s| s == "Check" || s == "Wait"),
+            "Single writer should skip conflict resolution: {}",
+            states.join(" -> ")
+        );
+        match replica.read_object(&replica_obj).expect("Read should succeed") {
+            ReadReturn::ReadSafe(v) => assert_eq!(v, i),
+            other => panic!("Unexpected read: {:?}", other),
+        }
+    }
+    coordinator.stop();
+    replica.stop();
+
+    for path in &node_paths {
+        cleanup_tmpfs_file(path);
+    }
+}
+
+#[test]
+fn test_object_hwm() {
+    let node_paths = vec!["/dev/shm/repCXL_test_hwm_0", "/dev/shm/repCXL_test_hwm_1"];
+    for path in &node_paths {
+        setup_tmpfs_file(path, TEST_MEMORY_SIZE);
+    }
+
+    let mut rcxl = single_rcxl(0, node_paths.clone());
+    rcxl.init_state().expect("init_state failed");
+    let obj = rcxl.new_object(1).expect("failed to create object");
+    rcxl.sync_start().expect("sync_start failed");
+
+    assert_eq!(rcxl.object_hwm(1), Some(0), "Never written");
+    assert_eq!(rcxl.object_hwm(2), None, "No such object");
+
+    for i in 0..3 {
+        // the write starts in the round after the request
+        let before = rcxl.current_round();
+        rcxl.write_object(&obj, i).expect("Write should succeed");
+        let hwm = rcxl.object_hwm(1).expect("Object should exist");
+        assert!(hwm > before && hwm <= rcxl.current_round(), "HWM {} for a write after round {}", hwm, before);
+    }
+    rcxl.stop();
+
+    for path in &node_paths {
+        cleanup_tmpfs_file(path);
+    }
+}
+
+#[test]
+fn test_read_uninitialized() {
+    let node_path = "/dev/shm/repCXL_test_read_uninit";
+    setup_tmpfs_file(node_path, TEST_MEMORY_SIZE);
+
+    let mut rcxl = single_rcxl(0, vec![node_path]);
+    rcxl.init_state().expect("init_state failed");
+    let never_written = rcxl.new_object(1).expect("failed to create object");
+    let zero = rcxl.new_object_with_val(2, 0).expect("failed to create object");
+
+    rcxl.sync_start().expect("sync_start failed");
+
+    let read_val = rcxl.read_object(&never_written).expect("Read should succeed");
+    assert!(matches!(read_val, ReadReturn::Uninitialized), "Read {:?}", read_val);
+
+    // a real zero is not mistaken for a missing value
+    let read_val = rcxl.read_object(&zero).expect("Read should succeed");
+    assert!(matches!(read_val, ReadReturn::ReadSafe(0)), "Read {:?}", read_val);
+
+    rcxl.write_object(&never_written, 0).expect("Write should succeed");
+    let read_val = rcxl.read_object(&never_written).expect("Read should succeed");
+    assert!(matches!(read_val, ReadReturn::ReadSafe(0)), "Read {:?}", read_val);
+
+    rcxl.stop();
+    cleanup_tmpfs_file(node_path);
+}
+
+#[test]
+fn test_wait_for_round() {
+    let node_path = "/dev/shm/repCXL_test_wait_round";
+    setup_tmpfs_file(node_path, TEST_MEMORY_SIZE);
+
+    let mut rcxl = single_rcxl(0, vec![node_path]);
+    rcxl.init_state().expect("init_state failed");
+    rcxl.sync_start().expect("sync_start failed");
+
+    rcxl.wait_for_round(5);
+    assert!(rcxl.current_round() >= 5, "Returned at round {}", rcxl.current_round());
+
+    // a round that already passed does not block
+    let start = std::time::Instant::now();
+    rcxl.wait_for_round(1);
+    assert!(start.elapsed() < Duration::from_nanos(TEST_ROUND_TIME));
+
+    rcxl.stop();
+    cleanup_tmpfs_file(node_path);
+}
+
+#[test]
+fn test_rw_heap_nodes() {
+    let val = 77;
+
+    // no files: both processes share heap-backed memory nodes
+    let config = |id| rep_cxl::RepCXLConfig { id, processes: vec![0, 1], ..test_config(vec![]) };
+    let mut coordinator = rep_cxl::RepCXL::<u64>::new(config(0));
+    let mut replica = rep_cxl::RepCXL::<u64>::new(config(1));
+    for _ in 0..2 {
+        let heap = coordinator.add_memory_node_heap();
+        replica.add_shared_memory_node_heap(heap).expect("failed to share heap node");
+    }
+    coordinator.init_state().expect("init_state failed");
+
+    std::thread::scope(|s| {
+        s.spawn(|| {
+            coordinator.sync_start().expect("sync_start failed");
+            let obj = coordinator.new_object(5).expect("failed to create obj with id 5");
+            coordinator.write_object(&obj, val).expect("Write should succeed");
+        });
+        s.spawn(|| {
+            replica.sync_start().expect("sync_start failed");
+            std::thread::sleep(Duration::from_millis(100));
+            let obj = replica.get_object(5).expect("failed to get obj with id 5");
+            let read_val = replica.read_object(&obj).expect("Read should succeed");
+            assert!(
+                matches!(read_val, ReadReturn::ReadSafe(v) if v == val),
+                "Read should return the written value, got {:?}", read_val
+            );
+        });
+    });
+}
+
+#[test]
+fn test_jitter_histogram() {
+    let node_path = "/dev/shm/repCXL_test_jitter";
+    setup_tmpfs_file(node_path, TEST_MEMORY_SIZE);
+
+    let mut config = test_config(vec![node_path]);
+    config.id = 0;
+    config.processes = vec![0];
+    config.pipeline = true;
+    let mut rcxl = RepCXL::<u64>::new(config);
+    rcxl.init_state().expect("init_state failed");
+    let obj = rcxl.new_object(1).expect("failed to create object");
+    rcxl.sync_start().expect("sync_start failed");
+    assert_eq!(rcxl.jitter_histogram().rounds(), 0);
+
+    for i in 0..5 {
+        rcxl.write_object(&obj, i).expect("Write should succeed");
+    }
+    rcxl.stop();
+
+    // one round per write on a single node
+    let jitter = rcxl.jitter_histogram();
+    assert!(jitter.rounds() >= 5, "Only {} rounds recorded", jitter.rounds());
+    assert_eq!(jitter.late.buckets.iter().sum::<u64>(), jitter.late.count);
+    assert!(jitter.late.mean() < Duration::from_nanos(TEST_ROUND_TIME), "Mean delay {:?}", jitter.late.mean());
+    assert!(jitter.late.p99() <= jitter.late.max());
+
+    cleanup_tmpfs_file(node_path);
+}
+
+#[test]
+fn test_scheduled_node_fault() {
+    let node_paths = vec!["/dev/shm/repCXL_test_fault_0", "/dev/shm/repCXL_test_fault_1"];
+    for path in &node_paths {
+        setup_tmpfs_file(path, TEST_MEMORY_SIZE);
+    }
+
+    // writes to node 1 fail during rounds 30-59
+    let mut config = test_config(node_paths.clone());
+    config.id = 0;
+    config.processes = vec![0];
+    config.fault = vec![FaultSpec { node: 1, start_round: 30, fail_for_rounds: 30, mode: "write".into() }];
+    let mut rcxl = RepCXL::<u64>::new(config);
+    rcxl.init_state().expect("init_state failed");
+    let obj = rcxl.new_object(1).expect("failed to create object");
+    rcxl.sync_start().expect("sync_start failed");
+
+    let wait_round = |rcxl: &RepCXL<u64>, round: u64| {
+        while rcxl.current_round() < round {
+            std::thread::sleep(Duration::from_millis(1));
+        }
+    };
+
+    rcxl.write_object(&obj, 1).expect("Write before the fault should succeed");
+
+    wait_round(&rcxl, 32);
+    let err = rcxl.write_object(&obj, 2).expect_err("Write during the fault should fail");
+    assert!(err.contains("Memory node 1"), "Unexpected error: {}", err);
+    assert!(rcxl.current_round() < 60, "Write outlasted the fault window");
+    // reads are not affected
+    assert!(rcxl.read_object(&obj).is_ok());
+
+    wait_round(&rcxl, 61);
+    rcxl.write_object(&obj, 3).expect("Write after the fault should succeed");
+    assert!(matches!(rcxl.read_object(&obj), Ok(ReadReturn::ReadSafe(3))));
+    rcxl.stop();
+
+    for path in &node_paths {
+        cleanup_tmpfs_file(path);
+    }
+}
+
+#[test]
+fn test_enable_file_log_truncates() {
+    let node_path = "/dev/shm/repCXL_test_log_truncate";
+    let log_path = "/tmp/repCXL_test_log_truncate.log";
+    setup_tmpfs_file(node_path, TEST_MEMORY_SIZE);
+    // stale log of a previous run
+    std::fs::write(log_path, "10,Try,1\n11,Check,1\n12,Wait,1\n").unwrap();
+
+    let mut rcxl = single_rcxl(0, vec![node_path]);
+    rcxl.enable_file_log_with(log_path, false);
+    assert_eq!(ms_logger::MonsterStateLogger::new(log_path).read_monster_states().len(), 3);
+
+    rcxl.enable_file_log(log_path);
+    assert!(ms_logger::MonsterStateLogger::new(log_path).read_monster_log().is_empty());
+
+    // only the transitions of this run are logged
+    rcxl.init_state().expect("init_state failed");
+    let obj = rcxl.new_object(1).expect("failed to create object");
+    rcxl.sync_start().expect("sync_start failed");
+    rcxl.write_object(&obj, 1).expect("Write should succeed");
+    rcxl.stop();
+    let states = ms_logger::MonsterStateLogger::new(log_path).read_monster_states();
+    assert_eq!(states.first().map(String::as_str), Some("Try"));
+    assert!(!states.contains(&"Wait".to_string()), "Stale entries in the log: {:?}", states);
+
+    cleanup_tmpfs_file(node_path);
+    std::fs::remove_file(log_path).unwrap();
+}
+
+#[test]
+fn test_algorithm_info() {
+    let node_path = "/dev/shm/repCXL_test_algorithm_info";
+    setup_tmpfs_file(node_path, TEST_MEMORY_SIZE);
+
+    let mut config = test_config(vec![node_path]);
+    config.processes = vec![0];
+    config.conflict_order = "timestamp".to_string();
+    config.read_retries = 3;
+    let rcxl = RepCXL::<u64>::new(config);
+    let info = rcxl.algorithm_info();
+    assert_eq!(info.name(), TEST_ALGORITHM);
+    match info {
+        AlgorithmInfo::Monster { fast_wcc, round_time, conflict_order, single_writer, read_retries, .. } => {
+            assert!(!fast_wcc);
+            assert_eq!(round_time, Duration::from_nanos(TEST_ROUND_TIME));
+            assert_eq!(conflict_order, ConflictOrder::Timestamp);
+            assert!(!single_writer);
+            assert_eq!(read_retries, 3);
+        }
+        other => panic!("Expected the MONSTER algorithm: {:?}", other),
+    }
+
+    let mut best_effort = single_rcxl(0, vec![node_path]);
+    best_effort.config.algorithm = "async_best_effort".to_string();
+    assert_eq!(best_effort.algorithm_info().name(), "async_best_effort");
+
+    cleanup_tmpfs_file(node_path);
+}
+
+// try_write blocks while the object has max_inflight_writes writes in flight
+#[test]
+fn test_max_inflight_writes() {
+    let node_path = "/dev/shm/repCXL_test_inflight";
+
+    for max_inflight in [1, 2] {
+        setup_tmpfs_file(node_path, TEST_MEMORY_SIZE);
+        let mut config = test_config(vec![node_path]);
+        config.processes = vec![0];
+        config.pipeline = true;
+        config.max_inflight_writes = max_inflight;
+        let mut rcxl = RepCXL::<u64>::new(config);
+        rcxl.init_state().expect("init_state failed");
+        let obj = rcxl.new_object(1).expect("failed to create object");
+        let other = rcxl.new_object(2).expect("failed to create object");
+
+        let tokens = if max_inflight == 1 {
+            rcxl.sync_start().expect("sync_start failed");
+            let start = std::time::Instant::now();
+            let first = obj.try_write(1).expect("Failed to enqueue write");
+            // other objects are not limited
+            let unrelated = other.try_write(3).expect("Failed to enqueue write");
+            let second = obj.try_write(2).expect("Failed to enqueue write");
+            // queued once the first write is done, which takes several rounds
+            let enqueued = start.elapsed();
+            assert!(enqueued >= Duration::from_nanos(TEST_ROUND_TIME), "Second write queued after {:?}", enqueued);
+            [first, unrelated, second]
+        } else {
+            // both queued while the worker is not running yet
+            let first = obj.try_write(1).expect("Failed to enqueue write");
+            let unrelated = other.try_write(3).expect("Failed to enqueue write");
+            let second = obj.try_write(2).expect("Failed to enqueue write");
+            rcxl.sync_start().expect("sync_start failed");
+            [first, unrelated, second]
+        };
+        for token in tokens {
+            token.wait().expect("Write should be acked");
+        }
+
+        // applied in submission order
+        match rcxl.read_object(&obj).expect("Read should succeed") {
+            ReadReturn::ReadSafe(v) => assert_eq!(v, 2),
+            other => panic!("Single node read should be safe: {:?}", other),
+        }
+        rcxl.stop();
+        cleanup_tmpfs_file(node_path);
+    }
+}
+
+// Reads bounded by the age of the newest write, in rounds
+#[test]
+fn test_read_fresh() {
+    let node_paths = vec!["/dev/shm/repCXL_test_fresh0", "/dev/shm/repCXL_test_fresh1", "/dev/shm/repCXL_test_fresh2"];
+    for path in &node_paths {
+        setup_tmpfs_file(path, TEST_MEMORY_SIZE);
+    }
+
+    let mut rcxl = single_rcxl(0, node_paths.clone());
+    rcxl.config.pipeline = true;
+    rcxl.init_state().expect("init_state failed");
+    let obj = rcxl.new_object(1).expect("failed to create object");
+    rcxl.sync_start().expect("sync_start failed");
+
+    // bound on the age of a write issued at round `since`, with some slack
+    // for the read to be served
+    let slack = 10;
+    let age_bound = |since: u64| rcxl.current_round() - since + slack;
+    let idle = |rounds: u64| rcxl.wait_for_round(rcxl.current_round() + rounds);
+
+    assert!(matches!(obj.read_fresh(1), Ok(ReadReturn::Uninitialized)));
+    // the wid holds the Try round, the write commits two rounds later
+    let since = rcxl.current_round();
+    obj.write(5).expect("Write should succeed");
+    assert!(matches!(obj.read_fresh(age_bound(since)), Ok(ReadReturn::ReadSafe(5))));
+
+    idle(3);
+    assert!(matches!(obj.read_fresh(1), Ok(ReadReturn::TooStale)));
+    assert!(matches!(obj.read_fresh(1000), Ok(ReadReturn::ReadSafe(5))));
+    assert!(matches!(obj.read(), Ok(ReadReturn::ReadSafe(5))));
+
+    // a newer write on the middle node only is found by the retry, the end
+    // nodes hold a value older than the bound
+    idle(3 * slack);
+    let since = rcxl.current_round();
+    obj.write_to(7, &[1]).expect("Partial write should succeed");
+    assert!(matches!(obj.read_fresh(age_bound(since)), Ok(ReadReturn::ReadDirty(7))));
+
+    // a full write refreshes the object
+    idle(3);
+    assert!(matches!(obj.read_fresh(1), Ok(ReadReturn::TooStale)));
+    let since = rcxl.current_round();
+    obj.write(9).expect("Write should succeed");
+    assert!(matches!(obj.read_fresh(age_bound(since)), Ok(ReadReturn::ReadSafe(9))));
+    rcxl.stop();
+
+    for path in &node_paths {
+        cleanup_tmpfs_file(path);
+    }
+}
@@ -0,0 +1,73 @@
+use rep_cxl::utils::ms_logger::{self, MonsterStateLogger};
+
+// Synthetic logs of a write conflict on object 3: process 0 wins, process 1
+// waits, finds a stale node and retries. Object 8 is unrelated traffic.
+#[test]
+fn test_replay_object_timeline() {
+    let log0 = "/tmp/repCXL_replay0.log";
+    let log1 = "/tmp/repCXL_replay1.log";
+    std::fs::write(log0, "10,Try,3\n11,Check,3\n12,Replicate,3\n13,Try,8\n").unwrap();
+    std::fs::write(
+        log1,
+        "10,Try,3\n11,Check,3\n12,Wait,3\n13,PostConflictCheck,3\n14,Retry,3\n15,Check,3\n16,Replicate,3\n",
+    )
+    .unwrap();
+
+    let merged = MonsterStateLogger::merge_logs(&[(0, log0), (1, log1)]);
+    assert_eq!(merged.len(), 11);
+
+    let timeline: Vec<(u64, usize, String)> = ms_logger::object_timeline(&merged, 3)
+        .into_iter()
+        .map(|e| (e.round_num, e.process_id, e.state))
+        .collect();
+    let expected = [
+        (10, 0, "Try"),
+        (10, 1, "Try"),
+        (11, 0, "Check"),
+        (11, 1, "Check"),
+        (12, 0, "Replicate"),
+        (12, 1, "Wait"),
+        (13, 1, "PostConflictCheck"),
+        (14, 1, "Retry"),
+        (15, 1, "Check"),
+        (16, 1, "Replicate"),
+    ];
+    let expected: Vec<(u64, usize, String)> =
+        expected.iter().map(|&(r, p, s)| (r, p, s.to_string())).collect();
+    assert_eq!(timeline, expected);
+
+    let other = ms_logger::object_timeline(&merged, 8);
+    assert_eq!(other.len(), 1);
+    assert_eq!(other[0].process_id, 0);
+
+    let _ = std::fs::remove_file(log0);
+    let _ = std::fs::remove_file(log1);
+}
+
+#[test]
+fn test_read_log_tail() {
+    let path = "/tmp/repCXL_log_tail.log";
+    let states = ["Try", "Check", "Replicate", "Wait", "PostConflictCheck"];
+    let mut log = String::new();
+    for round in 0..10_000u64 {
+        log += &format!("{},{},{}\n", round, states[round as usize % states.len()], round % 7);
+    }
+    // a line being written
+    log += "10000,Tr";
+    std::fs::write(path, log).unwrap();
+
+    let mut logger = MonsterStateLogger::new(path);
+    let tail = logger.read_monster_log_tail(5);
+    let rounds: Vec<u64> = tail.iter().map(|e| e.round_num).collect();
+    assert_eq!(rounds, vec![9995, 9996, 9997, 9998, 9999]);
+    let tail_states: Vec<&str> = tail.iter().map(|e| e.state.as_str()).collect();
+    assert_eq!(tail_states, states);
+    assert_eq!(tail[4].object_id, 9999 % 7);
+
+    // more than a window, and more than the whole log
+    assert_eq!(logger.read_monster_log_tail(2000), logger.read_monster_log()[8000..10_000]);
+    assert_eq!(logger.read_monster_log_tail(20_000).len(), 10_000);
+    assert!(logger.read_monster_log_tail(0).is_empty());
+
+    std::fs::remove_file(path).unwrap();
+}
@@ -10,13 +10,15 @@ pub const TEST_ROUND_TIME: u64 = 10_000_000; // 10 ms
 pub fn test_config(node_paths: Vec<&'static str>) -> RepCXLConfig {
     RepCXLConfig {
         id: 0,
-        mem_nodes: node_paths.into_iter().map(|s| s.to_string()).collect(),
+        mem_nodes: node_paths.iter().map(|s| s.to_string()).collect(),
         mem_size: TEST_MEMORY_SIZE,
         chunk_size: TEST_CHUNK_SIZE,
         processes: vec![], 
         algorithm: TEST_ALGORITHM.to_string(),
         round_time: TEST_ROUND_TIME,
         pipeline: false, // no threads
+        // no node files: the test adds the nodes after `RepCXL::new`
+        deferred_nodes: node_paths.is_empty(),
         ..Default::default()
     }
 }
@@ -30,6 +32,18 @@ pub fn cleanup_tmpfs_file(path: &str) {
     let _ = std::fs::remove_file(path);
 }
 
+/// Overwrite `len` bytes at the start of a tmpfs file with garbage, e.g. to
+/// simulate a torn write of the shared state
+#[allow(dead_code)]
+pub fn corrupt_tmpfs_file(path: &str, len: usize) {
+    use std::io::Write;
+    let mut file = std::fs::OpenOptions::new()
+        .write(true)
+        .open(path)
+        .expect("Failed to open tmpfs file");
+    file.write_all(&vec![0xAB; len]).expect("Failed to corrupt tmpfs file");
+}
+
 
 pub fn single_rcxl(id: usize, node_paths: Vec<&'static str>) -> RepCXL<u64> {
     let mut config = test_config(node_paths);
@@ -53,12 +67,13 @@ pub fn single_rcxl(id: usize, node_paths: Vec<&'static str>) -> RepCXL<u64> {
 // }
 
 
+#[allow(dead_code)]
 pub fn multi_rcxl(num: usize, node_paths: Vec<&'static str>) -> Vec<RepCXL<u64>> {
     let mut processes = Vec::new();
     for i in 0..num {
         let mut rcxl = single_rcxl(i, node_paths.clone());
         if i == 0 {
-            rcxl.init_state(); // coordinator inits state
+            rcxl.init_state().expect("init_state failed"); // coordinator inits state
         }
 
         // register processes